@@ -13,7 +13,6 @@
 //
 
 use async_std::{fs::File, io::WriteExt, sync::Mutex};
-use prost::Message as pMessage;
 use zenoh_flow_nodes::prelude::*;
 
 #[export_sink]
@@ -25,7 +24,9 @@ pub struct FileWriter {
 #[async_trait::async_trait]
 impl Node for FileWriter {
     async fn iteration(&self) -> Result<()> {
-        let (greeting, _) = self.input.recv().await?;
+        let TypedMessage::Data(greeting, _) = self.input.recv().await? else {
+            return Ok(());
+        };
 
         let mut file = self.file.lock().await;
         file.write_all(greeting.as_bytes())
@@ -60,7 +61,7 @@ impl Sink for FileWriter {
             input: inputs
                 .take("in")
                 .expect("No Input called 'in' found")
-                .typed(|bytes| String::decode(bytes).map_err(|e| anyhow!(e))),
+                .typed_with::<String, codecs::Protobuf>(),
         })
     }
 }
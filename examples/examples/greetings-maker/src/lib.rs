@@ -12,7 +12,6 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use prost::Message as pMessage;
 use zenoh_flow_nodes::prelude::*;
 
 #[export_operator]
@@ -37,7 +36,7 @@ impl Operator for GreetingsMaker {
             output: outputs
                 .take("greeting")
                 .expect("No output 'greeting' found")
-                .typed(|buffer, data: &String| data.encode(buffer).map_err(|e| anyhow!(e))),
+                .typed_with::<String, codecs::Protobuf>(),
         })
     }
 }
@@ -45,7 +44,9 @@ impl Operator for GreetingsMaker {
 #[async_trait::async_trait]
 impl Node for GreetingsMaker {
     async fn iteration(&self) -> Result<()> {
-        let (characters, _) = self.input.recv().await?;
+        let TypedMessage::Data(characters, _) = self.input.recv().await? else {
+            return Ok(());
+        };
         let name = characters.trim_end();
 
         let greetings = match name {
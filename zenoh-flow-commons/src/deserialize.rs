@@ -12,14 +12,14 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-//! This module exposes the functions [deserialize_size] and [deserialize_time] that are used
+//! This module exposes the functions [deserialize_size], [deserialize_time] and [deserialize_period] that are used
 //! throughout Zenoh-Flow to "parse" values used to express time or size.
 //!
 //! The external crates [bytesize] and [humantime] are leveraged for these purposes.
 
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 
-use serde::Deserializer;
+use serde::{Deserializer, Serializer};
 use zenoh_keyexpr::OwnedKeyExpr;
 
 /// Deserialise, from a String, an `Arc<str>` that is guaranteed to be a valid Zenoh-Flow [NodeId](crate::NodeId) or
@@ -97,6 +97,25 @@ where
     )))
 }
 
+/// Deserialise the capacity of a queue, ensuring that it is strictly positive.
+///
+/// # Errors
+///
+/// An error is returned if the capacity is not a positive integer or if it is equal to 0.
+pub fn deserialize_capacity<'de, D>(deserializer: D) -> std::result::Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let capacity: usize = serde::de::Deserialize::deserialize(deserializer)?;
+    if capacity == 0 {
+        return Err(serde::de::Error::custom(
+            "The capacity of a queue must be strictly positive.",
+        ));
+    }
+
+    Ok(capacity)
+}
+
 /// Deserialise a duration in *microseconds* leveraging the [humantime] crate.
 ///
 /// This allows parsing, for instance, "1ms" as 1000 microseconds.
@@ -122,11 +141,87 @@ where
     })
 }
 
+/// Deserialise an optional, strictly positive, period leveraging the [humantime] crate.
+///
+/// This allows parsing, for instance, "100ms" or "1s 500ms". The field must also be annotated with
+/// `#[serde(default)]` for its absence to be accepted.
+///
+/// # Errors
+///
+/// An error is returned if the period cannot be parsed by [humantime] or if it is equal to 0.
+pub fn deserialize_period<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let period: Option<String> = serde::de::Deserialize::deserialize(deserializer)?;
+    let Some(period) = period else {
+        return Ok(None);
+    };
+
+    let period: Duration = period
+        .parse::<humantime::Duration>()
+        .map_err(serde::de::Error::custom)?
+        .into();
+
+    if period.is_zero() {
+        return Err(serde::de::Error::custom(
+            "A period must be strictly positive.",
+        ));
+    }
+
+    Ok(Some(period))
+}
+
+/// Serialise an optional period such that it can be deserialised by [deserialize_period].
+pub fn serialize_period<S>(
+    period: &Option<Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match period {
+        Some(period) => serializer.serialize_some(&humantime::format_duration(*period).to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserialise a duration leveraging the [humantime] crate.
+///
+/// # Errors
+///
+/// See the [humantime] documentation.
+pub fn deserialize_duration<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let duration: String = serde::de::Deserialize::deserialize(deserializer)?;
+    Ok(duration
+        .parse::<humantime::Duration>()
+        .map_err(serde::de::Error::custom)?
+        .into())
+}
+
+/// Serialise a duration such that it can be deserialised by [deserialize_duration].
+pub fn serialize_duration<S>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&humantime::format_duration(*duration).to_string())
+}
+
 #[cfg(test)]
 mod tests {
-    use serde::Deserialize;
+    use std::time::Duration;
 
-    use crate::NodeId;
+    use serde::{Deserialize, Serialize};
+
+    use crate::{NodeId, QueueConfiguration, QueuePolicy, RestartPolicy};
 
     #[derive(Deserialize, Debug)]
     pub struct TestStruct {
@@ -184,4 +279,138 @@ mod tests {
 "#;
         assert!(serde_json::from_str::<TestStruct>(json_str).is_ok());
     }
+
+    #[test]
+    fn test_deserialize_queue() {
+        let json_str = r#"
+{
+  "capacity": 0
+}
+"#;
+        assert!(serde_json::from_str::<QueueConfiguration>(json_str).is_err());
+
+        let json_str = r#"
+{
+  "capacity": 16
+}
+"#;
+        let queue = serde_json::from_str::<QueueConfiguration>(json_str).unwrap();
+        assert_eq!(QueuePolicy::Block, queue.policy);
+
+        let json_str = r#"
+{
+  "capacity": 16,
+  "policy": "drop-oldest"
+}
+"#;
+        let queue = serde_json::from_str::<QueueConfiguration>(json_str).unwrap();
+        assert_eq!(QueuePolicy::DropOldest, queue.policy);
+    }
+
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    pub struct TestPeriod {
+        #[serde(
+            default,
+            deserialize_with = "super::deserialize_period",
+            serialize_with = "super::serialize_period"
+        )]
+        pub period: Option<Duration>,
+    }
+
+    #[test]
+    fn test_deserialize_period() {
+        let period = serde_yaml::from_str::<TestPeriod>("period: 1s 500ms")
+            .expect("Failed to deserialise a period");
+        assert_eq!(Some(Duration::from_millis(1500)), period.period);
+
+        let serialized = serde_json::to_string(&period).expect("Failed to serialise a period");
+        assert_eq!(
+            period,
+            serde_json::from_str::<TestPeriod>(&serialized)
+                .expect("Failed to deserialise a period")
+        );
+
+        assert_eq!(
+            None,
+            serde_yaml::from_str::<TestPeriod>("{}")
+                .expect("Failed to deserialise a missing period")
+                .period
+        );
+        assert!(serde_yaml::from_str::<TestPeriod>("period: 0s").is_err());
+        assert!(serde_yaml::from_str::<TestPeriod>("period: 10").is_err());
+    }
+
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    pub struct TestRestart {
+        #[serde(default, with = "serde_yaml::with::singleton_map")]
+        pub restart: RestartPolicy,
+    }
+
+    #[test]
+    fn test_deserialize_restart_policy() {
+        assert_eq!(
+            RestartPolicy::Always,
+            serde_yaml::from_str::<TestRestart>("restart: always")
+                .unwrap()
+                .restart
+        );
+        assert_eq!(
+            RestartPolicy::Always,
+            serde_yaml::from_str::<TestRestart>("{}").unwrap().restart
+        );
+
+        let policy = serde_yaml::from_str::<TestRestart>(
+            r#"
+restart:
+  on-failure:
+    max-retries: 4
+    backoff: 1s
+    max-backoff: 5s
+"#,
+        )
+        .expect("Failed to deserialise a restart policy")
+        .restart;
+        assert_eq!(
+            RestartPolicy::OnFailure {
+                max_retries: 4,
+                backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(5),
+            },
+            policy
+        );
+
+        let test_restart = TestRestart { restart: policy };
+        let serialized = serde_json::to_string(&test_restart).expect("Failed to serialise");
+        assert_eq!(
+            test_restart,
+            serde_json::from_str::<TestRestart>(&serialized).expect("Failed to deserialise")
+        );
+        let serialized = serde_yaml::to_string(&test_restart).expect("Failed to serialise");
+        assert_eq!(
+            test_restart,
+            serde_yaml::from_str::<TestRestart>(&serialized).expect("Failed to deserialise")
+        );
+
+        // Exponential backoff, capped by `max-backoff` and limited to `max-retries` consecutive failures.
+        assert_eq!(Some(Duration::from_secs(1)), policy.backoff(1));
+        assert_eq!(Some(Duration::from_secs(2)), policy.backoff(2));
+        assert_eq!(Some(Duration::from_secs(4)), policy.backoff(3));
+        assert_eq!(Some(Duration::from_secs(5)), policy.backoff(4));
+        assert_eq!(None, policy.backoff(5));
+
+        assert_eq!(None, RestartPolicy::Never.backoff(1));
+        assert_eq!(Some(Duration::ZERO), RestartPolicy::Always.backoff(100));
+
+        let policy = serde_yaml::from_str::<TestRestart>("restart: { on-failure: {} }")
+            .expect("Failed to deserialise a restart policy with default values")
+            .restart;
+        assert_eq!(Some(Duration::from_millis(100)), policy.backoff(1));
+        assert_eq!(None, policy.backoff(4));
+
+        assert!(serde_yaml::from_str::<TestRestart>("restart: sometimes").is_err());
+        assert!(
+            serde_yaml::from_str::<TestRestart>("restart: { on-failure: { retries: 2 } }").is_err()
+        );
+        assert!(serde_yaml::from_str::<TestRestart>("restart: { always: {} }").is_err());
+    }
 }
@@ -0,0 +1,60 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// The encoding of the data going through a port.
+///
+/// Each encoding corresponds to a codec provided by Zenoh-Flow. Declaring the encoding of a port allows nodes written
+/// by different teams to interoperate without agreeing on ad-hoc serialisation functions.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_commons::Encoding;
+/// # let encoding = r#"
+/// msgpack
+/// # "#;
+/// # assert_eq!(Encoding::MessagePack, serde_yaml::from_str::<Encoding>(encoding).unwrap());
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// [JSON](https://www.json.org/), through `serde_json`.
+    Json,
+    /// [CBOR](https://cbor.io/), through `serde_cbor`.
+    Cbor,
+    /// [bincode](https://github.com/bincode-org/bincode).
+    Bincode,
+    /// [MessagePack](https://msgpack.org/), through `rmp-serde`.
+    #[serde(rename = "msgpack", alias = "messagepack")]
+    MessagePack,
+    /// [Protocol Buffers](https://protobuf.dev/), through `prost`.
+    #[serde(alias = "prost")]
+    Protobuf,
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Json => write!(f, "json"),
+            Encoding::Cbor => write!(f, "cbor"),
+            Encoding::Bincode => write!(f, "bincode"),
+            Encoding::MessagePack => write!(f, "msgpack"),
+            Encoding::Protobuf => write!(f, "protobuf"),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{collections::BTreeMap, fmt::Display, ops::Deref, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+/// `Labels` are pairs of `(key, value)` describing a Zenoh-Flow runtime, for instance its architecture, the site where
+/// it is deployed or the devices it has access to.
+///
+/// A Zenoh-Flow runtime advertises its labels. The same structure is used in a descriptor to select, among the
+/// Zenoh-Flow runtimes, the ones on which a node can run: a runtime [matches](Labels::matches()) a selector if it has
+/// all the labels of the selector, with the same value.
+///
+/// # Example (YAML)
+///
+/// ```
+/// # use zenoh_flow_commons::Labels;
+/// # let labels = r#"
+/// arch: aarch64
+/// site: lab
+/// camera: true
+/// # "#;
+/// # let labels = serde_yaml::from_str::<Labels>(labels).unwrap();
+/// # assert_eq!(Some(&"true".into()), labels.get("camera"));
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Labels(BTreeMap<Arc<str>, Arc<str>>);
+
+impl Deref for Labels {
+    type Target = BTreeMap<Arc<str>, Arc<str>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K: Into<Arc<str>>, V: Into<Arc<str>>> FromIterator<(K, V)> for Labels {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: Into<Arc<str>>, V: Into<Arc<str>>, const N: usize> From<[(K, V); N]> for Labels {
+    fn from(value: [(K, V); N]) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl Display for Labels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Labels {
+    /// Returns `true` if these labels contain all the labels of the `selector`, with the same value.
+    ///
+    /// An empty selector matches any set of labels.
+    pub fn matches(&self, selector: &Labels) -> bool {
+        selector
+            .iter()
+            .all(|(key, value)| self.0.get(key) == Some(value))
+    }
+
+    /// Inserts the label `(key, value)`, returning the previous value of `key`, if any.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Arc<str>>,
+        value: impl Into<Arc<str>>,
+    ) -> Option<Arc<str>> {
+        self.0.insert(key.into(), value.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_matches() {
+        let labels = Labels::from([("arch", "aarch64"), ("site", "lab"), ("camera", "true")]);
+
+        assert!(labels.matches(&Labels::default()));
+        assert!(labels.matches(&Labels::from([("arch", "aarch64")])));
+        assert!(labels.matches(&Labels::from([("site", "lab"), ("camera", "true")])));
+        assert!(!labels.matches(&Labels::from([("arch", "x86_64")])));
+        assert!(!labels.matches(&Labels::from([("arch", "aarch64"), ("gpu", "true")])));
+        assert_eq!("arch=aarch64, camera=true, site=lab", labels.to_string());
+    }
+}
@@ -21,14 +21,28 @@ mod configuration;
 pub use configuration::Configuration;
 
 mod deserialize;
-pub use deserialize::deserialize_id;
+pub use deserialize::{
+    deserialize_duration, deserialize_id, deserialize_period, serialize_duration, serialize_period,
+};
+
+mod encoding;
+pub use encoding::Encoding;
 
 mod identifiers;
 pub use identifiers::{InstanceId, NodeId, PortId, RuntimeId};
 
+mod labels;
+pub use labels::Labels;
+
 mod merge;
 pub use merge::IMergeOverwrite;
 
+mod queue;
+pub use queue::{QueueConfiguration, QueuePolicy};
+
+mod restart;
+pub use restart::RestartPolicy;
+
 mod shared_memory;
 pub use shared_memory::SharedMemoryConfiguration;
 
@@ -0,0 +1,74 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::deserialize::deserialize_capacity;
+
+/// Structure to configure the queue of a link, i.e. the channel connecting an Output to an Input.
+///
+/// This configuration is applied on a link basis. If a link does not specify a queue, its channel is *unbounded*.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_commons::QueueConfiguration;
+/// # let queue = r#"
+/// capacity: 256
+/// policy: drop-oldest
+/// # "#;
+/// # serde_yaml::from_str::<QueueConfiguration>(queue).unwrap();
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QueueConfiguration {
+    /// The maximum number of messages the queue can hold. It must be strictly positive.
+    #[serde(deserialize_with = "deserialize_capacity")]
+    pub capacity: usize,
+    /// What to do when a message is sent on a full queue. Defaults to [QueuePolicy::Block].
+    #[serde(default)]
+    pub policy: QueuePolicy,
+}
+
+/// The behaviour of a link when a message is sent while its queue is full.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueuePolicy {
+    /// The sender waits until there is enough space in the queue.
+    ///
+    /// Note that the synchronous `try_send` methods cannot wait: they return an error instead.
+    #[default]
+    Block,
+    /// The oldest message in the queue is discarded to make room for the new one.
+    DropOldest,
+    /// The new message is discarded, the content of the queue is left untouched.
+    DropNewest,
+}
+
+impl Display for QueuePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueuePolicy::Block => write!(f, "block"),
+            QueuePolicy::DropOldest => write!(f, "drop-oldest"),
+            QueuePolicy::DropNewest => write!(f, "drop-newest"),
+        }
+    }
+}
+
+impl Display for QueueConfiguration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "capacity: {}, policy: {}", self.capacity, self.policy)
+    }
+}
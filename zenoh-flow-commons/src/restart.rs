@@ -0,0 +1,149 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::deserialize::{deserialize_duration, serialize_duration};
+
+/// What the Zenoh-Flow runtime should do when the `iteration` of a node fails, i.e. when it returns an error or when
+/// it panics.
+///
+/// A node that is not restarted is stopped and the data flow instance it belongs to is reported as degraded, with the
+/// reason of the failure.
+///
+/// In a descriptor, the `on-failure` variant is written as a map with a single key: the fields holding a
+/// `RestartPolicy` are (de)serialised with [serde_yaml::with::singleton_map].
+///
+/// # Examples
+///
+/// ```
+/// # use zenoh_flow_commons::RestartPolicy;
+/// # let restart = r#"
+/// never
+/// # "#;
+/// # let deserializer = serde_yaml::Deserializer::from_str(restart);
+/// # assert_eq!(
+/// #     RestartPolicy::Never,
+/// #     serde_yaml::with::singleton_map::deserialize::<RestartPolicy, _>(deserializer).unwrap()
+/// # );
+/// ```
+///
+/// ```
+/// # use zenoh_flow_commons::RestartPolicy;
+/// # let restart = r#"
+/// on-failure:
+///   max-retries: 5
+///   backoff: 100ms
+///   max-backoff: 10s
+/// # "#;
+/// # let deserializer = serde_yaml::Deserializer::from_str(restart);
+/// # serde_yaml::with::singleton_map::deserialize::<RestartPolicy, _>(deserializer).unwrap();
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum RestartPolicy {
+    /// The node is stopped after its first failure.
+    Never,
+    /// The `iteration` of the node is immediately called again after a failure, indefinitely.
+    #[default]
+    Always,
+    /// The `iteration` of the node is called again after a failure, at most `max-retries` times in a row.
+    ///
+    /// Before each retry, the runtime waits for a delay that starts at `backoff` and doubles after each consecutive
+    /// failure, up to `max-backoff`. A successful `iteration` resets the count.
+    #[serde(rename_all = "kebab-case")]
+    OnFailure {
+        /// The maximum number of consecutive failures after which the node is stopped. Defaults to 3.
+        #[serde(default = "default_max_retries")]
+        max_retries: u32,
+        /// The delay before the first retry. Defaults to 100ms.
+        #[serde(
+            default = "default_backoff",
+            deserialize_with = "deserialize_duration",
+            serialize_with = "serialize_duration"
+        )]
+        backoff: Duration,
+        /// The maximum delay between two retries. Defaults to 10s.
+        #[serde(
+            default = "default_max_backoff",
+            deserialize_with = "deserialize_duration",
+            serialize_with = "serialize_duration"
+        )]
+        max_backoff: Duration,
+    },
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_backoff() -> Duration {
+    Duration::from_millis(100)
+}
+
+fn default_max_backoff() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl RestartPolicy {
+    /// Returns how long to wait before calling the `iteration` of a node again, after `failures` consecutive failures,
+    /// or `None` if the node should be stopped.
+    pub fn backoff(&self, failures: u32) -> Option<Duration> {
+        match self {
+            RestartPolicy::Never => None,
+            RestartPolicy::Always => Some(Duration::ZERO),
+            RestartPolicy::OnFailure {
+                max_retries,
+                backoff,
+                max_backoff,
+            } => {
+                if failures > *max_retries {
+                    return None;
+                }
+
+                let factor = 1u32
+                    .checked_shl(failures.saturating_sub(1))
+                    .unwrap_or(u32::MAX);
+                Some(
+                    backoff
+                        .checked_mul(factor)
+                        .unwrap_or(*max_backoff)
+                        .min(*max_backoff),
+                )
+            }
+        }
+    }
+}
+
+impl Display for RestartPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestartPolicy::Never => write!(f, "never"),
+            RestartPolicy::Always => write!(f, "always"),
+            RestartPolicy::OnFailure {
+                max_retries,
+                backoff,
+                max_backoff,
+            } => write!(
+                f,
+                "on-failure (max-retries: {}, backoff: {}, max-backoff: {})",
+                max_retries,
+                humantime::format_duration(*backoff),
+                humantime::format_duration(*max_backoff)
+            ),
+        }
+    }
+}
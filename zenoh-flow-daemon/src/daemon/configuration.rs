@@ -12,7 +12,14 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
+#[cfg(feature = "prometheus")]
+use std::net::SocketAddr;
+use std::{path::PathBuf, time::Duration};
+
 use serde::Deserialize;
+#[cfg(feature = "otlp")]
+use url::Url;
+use zenoh_flow_commons::{deserialize_duration, Labels};
 use zenoh_flow_runtime::Extensions;
 
 /// The configuration of a Zenoh-Flow Daemon.
@@ -20,6 +27,195 @@ use zenoh_flow_runtime::Extensions;
 pub struct ZenohFlowConfiguration {
     /// A human-readable name for this Daemon and its embedded Runtime.
     pub name: String,
+    /// The [Labels] describing this Daemon, advertised to the other Daemons such that the nodes of a data flow can be
+    /// placed according to their constraints.
+    ///
+    /// For instance:
+    ///
+    /// ```yaml
+    /// labels:
+    ///   arch: aarch64
+    ///   site: lab
+    ///   camera: true
+    /// ```
+    #[serde(default)]
+    pub labels: Labels,
+    /// The maximum number of nodes this Daemon should manage, across all the data flow instances. No node is placed
+    /// on this Daemon, through constraints, once it is reached. Unlimited if it is not provided.
+    #[serde(default)]
+    pub capacity: Option<usize>,
     /// Additionally supported [Extensions].
     pub extensions: Option<Extensions>,
+    /// The address on which the metrics of this Daemon are served, in the Prometheus text format, at the path
+    /// `/metrics`. No metrics are served if it is not provided.
+    ///
+    /// For instance: `metrics_address: 127.0.0.1:9464`.
+    #[cfg(feature = "prometheus")]
+    #[serde(default)]
+    pub metrics_address: Option<SocketAddr>,
+    /// Whether the messages sent by the nodes start a new trace when they do not already carry a trace context. The
+    /// trace contexts carried by the messages are always propagated.
+    ///
+    /// See [trace_messages](zenoh_flow_runtime::RuntimeBuilder::trace_messages).
+    #[serde(default)]
+    pub trace_messages: bool,
+    /// The endpoint of the OpenTelemetry collector to which the traces are exported, using OTLP over HTTP. No traces
+    /// are exported if it is not provided.
+    ///
+    /// For instance: `otlp_endpoint: http://127.0.0.1:4318`.
+    #[cfg(feature = "otlp")]
+    #[serde(default)]
+    pub otlp_endpoint: Option<Url>,
+    /// The local store in which the data flow instances managed by this Daemon are persisted, such that they are
+    /// loaded again when it restarts. The instances are not persisted if it is not provided.
+    ///
+    /// See [StateStoreConfiguration].
+    #[serde(default)]
+    pub state_store: Option<StateStoreConfiguration>,
+    /// How long this Daemon waits for the other Daemons to answer its queries and how many times it retries, for each
+    /// operation.
+    ///
+    /// See [QueriesConfiguration].
+    #[serde(default)]
+    pub queries: QueriesConfiguration,
+    /// Whether this Daemon aborts its part of a data flow instance when another runtime involved is lost, i.e. when its
+    /// liveliness token disappears. Defaults to `false`: the instance is only reported as
+    /// [Disconnected](zenoh_flow_runtime::InstanceState::Disconnected).
+    ///
+    /// An instance is not aborted if some nodes of the lost runtime have a
+    /// [failover](zenoh_flow_descriptors::FailoverPolicy) policy, unless they could not be re-placed.
+    #[serde(default)]
+    pub abort_on_lost_runtime: bool,
+}
+
+/// The configuration of the local store in which a Zenoh-Flow Daemon persists its data flow instances.
+///
+/// The [record](zenoh_flow_records::DataFlowRecord) and the last known [state](zenoh_flow_runtime::InstanceState) of
+/// each instance are written in the `directory`, one file per instance. When the Daemon starts, the instances found in
+/// the `directory` are loaded again, on this Daemon and on the other Daemons involved in their execution.
+///
+/// ⚠️ An instance can only be restored if the identifier of the Daemon did not change: as it is the identifier of its
+/// Zenoh session, the `id` should be set in the configuration of Zenoh.
+///
+/// For instance:
+///
+/// ```yaml
+/// state_store:
+///   directory: /var/lib/zenoh-flow
+///   restart_instances: true
+/// ```
+#[derive(Deserialize, Debug)]
+pub struct StateStoreConfiguration {
+    /// The directory in which the instances are persisted. It is created if it does not exist.
+    pub directory: PathBuf,
+    /// Whether the instances that were running when the Daemon stopped are started once they are loaded again.
+    /// Defaults to `false`: they are only loaded.
+    #[serde(default)]
+    pub restart_instances: bool,
+}
+
+/// The [QueryPolicy] of each operation a Zenoh-Flow Daemon performs on the other Daemons involved in a data flow
+/// instance. The operations that are not listed -- for instance, reconfiguring a node -- follow the default policy.
+///
+/// For instance:
+///
+/// ```yaml
+/// queries:
+///   load:
+///     timeout: 30s
+///   start:
+///     retries: 5
+///     backoff: 1s
+/// ```
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct QueriesConfiguration {
+    /// The policy of the queries loading a data flow instance, when it is created or updated.
+    #[serde(default)]
+    pub load: QueryPolicy,
+    /// The policy of the queries starting a data flow instance.
+    #[serde(default)]
+    pub start: QueryPolicy,
+    /// The policy of the queries aborting a data flow instance.
+    #[serde(default)]
+    pub abort: QueryPolicy,
+    /// The policy of the queries deleting a data flow instance.
+    #[serde(default)]
+    pub delete: QueryPolicy,
+}
+
+/// How long a Zenoh-Flow Daemon waits for another Daemon to answer a query and how many times it retries.
+///
+/// A query is only retried if the other Daemon did not answer before the `timeout`: a Daemon that answers with an
+/// error is not queried again. Before each retry, the Daemon waits for a delay that starts at `backoff` and doubles
+/// after each attempt.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryPolicy {
+    /// How long to wait for an answer. Defaults to 10s.
+    #[serde(default = "default_timeout", deserialize_with = "deserialize_duration")]
+    pub timeout: Duration,
+    /// How many times the query is sent again if no answer was received. Defaults to 2.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// The delay before the first retry. Defaults to 500ms.
+    #[serde(default = "default_backoff", deserialize_with = "deserialize_duration")]
+    pub backoff: Duration,
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+fn default_backoff() -> Duration {
+    Duration::from_millis(500)
+}
+
+impl Default for QueryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: default_timeout(),
+            retries: default_retries(),
+            backoff: default_backoff(),
+        }
+    }
+}
+
+impl QueryPolicy {
+    /// Returns how long to wait before sending a query again, after `attempts` attempts that received no answer.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempts.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.backoff.saturating_mul(factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_configuration() {
+        let queries = r#"
+load:
+  timeout: 30s
+start:
+  retries: 5
+  backoff: 1s
+"#;
+        let queries = serde_yaml::from_str::<QueriesConfiguration>(queries)
+            .expect("Failed to deserialize the queries configuration");
+
+        assert_eq!(Duration::from_secs(30), queries.load.timeout);
+        assert_eq!(default_retries(), queries.load.retries);
+        assert_eq!(5, queries.start.retries);
+        assert_eq!(QueryPolicy::default(), queries.abort);
+
+        assert_eq!(Duration::from_secs(1), queries.start.backoff(1));
+        assert_eq!(Duration::from_secs(2), queries.start.backoff(2));
+        assert_eq!(Duration::from_secs(4), queries.start.backoff(3));
+    }
 }
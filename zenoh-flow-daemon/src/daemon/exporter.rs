@@ -0,0 +1,474 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! A minimal HTTP endpoint serving the metrics of a Zenoh-Flow Daemon in the Prometheus text format.
+//!
+//! Only `GET /metrics` is answered, every connection is closed after the response is sent.
+
+use std::{collections::HashMap, fmt::Write as _, net::SocketAddr, sync::Arc};
+
+use anyhow::Context;
+use async_std::{
+    io::{prelude::BufReadExt, BufReader, WriteExt},
+    net::{TcpListener, TcpStream},
+    stream::StreamExt,
+    task::JoinHandle,
+};
+use zenoh_flow_commons::{InstanceId, Result};
+use zenoh_flow_runtime::{InstanceMetrics, InstanceState, Runtime};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The labels of the different [InstanceState], all of them are always exported.
+const STATES: [&str; 7] = [
+    "creating",
+    "loaded",
+    "running",
+    "degraded",
+    "disconnected",
+    "aborted",
+    "failed",
+];
+
+fn state_label(state: &InstanceState) -> &'static str {
+    match state {
+        InstanceState::Creating(_) => "creating",
+        InstanceState::Loaded(_) => "loaded",
+        InstanceState::Running(_) => "running",
+        InstanceState::Degraded(_) => "degraded",
+        InstanceState::Disconnected(_) => "disconnected",
+        InstanceState::Aborted(_) => "aborted",
+        InstanceState::Failed(_) => "failed",
+    }
+}
+
+/// Binds the provided `address` and spawns a task serving the metrics of the `runtime`.
+///
+/// Returns the address effectively bound (which differs from the one provided if its port is 0) and the handle of the
+/// task.
+///
+/// # Errors
+///
+/// This function will fail if the address could not be bound.
+pub(crate) async fn spawn(
+    address: SocketAddr,
+    runtime: Arc<Runtime>,
+) -> Result<(SocketAddr, JoinHandle<()>)> {
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Failed to bind the metrics endpoint on < {address} >"))?;
+    let local_address = listener
+        .local_addr()
+        .context("Failed to retrieve the address of the metrics endpoint")?;
+    tracing::info!("Serving metrics on http://{}/metrics", local_address);
+
+    let handle = async_std::task::spawn(async move {
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            match stream {
+                Ok(stream) => {
+                    let runtime = runtime.clone();
+                    async_std::task::spawn(async move {
+                        if let Err(e) = serve(stream, &runtime).await {
+                            tracing::debug!("Failed to serve metrics: {:?}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to accept connection on metrics endpoint: {:?}", e)
+                }
+            }
+        }
+    });
+
+    Ok((local_address, handle))
+}
+
+/// Answers the HTTP request received on the `stream`.
+async fn serve(stream: TcpStream, runtime: &Runtime) -> Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    // The headers are irrelevant, they are consumed until the empty line that ends them.
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut request = request_line.split_whitespace();
+    let (status, content_type, body) = match (request.next(), request.next()) {
+        (Some("GET"), Some(target)) if target.split('?').next() == Some("/metrics") => {
+            ("200 OK", CONTENT_TYPE, render(runtime).await)
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method Not Allowed\n".to_string(),
+        ),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+
+    let mut writer = &stream;
+    writer.write_all(response.as_bytes()).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Gathers the metrics of the `runtime` and encodes them in the Prometheus text format.
+async fn render(runtime: &Runtime) -> String {
+    let states = runtime.instances_state().await;
+    let mut metrics = Vec::with_capacity(states.len());
+    for instance_id in states.keys() {
+        if let Some(instance_metrics) = runtime.get_instance_metrics(instance_id).await {
+            metrics.push((instance_id.clone(), instance_metrics));
+        }
+    }
+
+    encode(
+        states.values().map(|(_, state)| state),
+        runtime.loaded_libraries().await,
+        &metrics,
+    )
+}
+
+/// A metric family of the Prometheus text format: its description and its samples.
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    samples: String,
+}
+
+impl Family {
+    fn new(name: &'static str, kind: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            help,
+            samples: String::default(),
+        }
+    }
+
+    /// Adds a sample to this family, `suffix` is appended to its name (e.g. `_bucket` for a histogram).
+    fn sample(&mut self, suffix: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+        // NOTE: Writing in a `String` cannot fail.
+        let _ = write!(self.samples, "{}{}", self.name, suffix);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            let _ = write!(self.samples, "{{{labels}}}");
+        }
+        let _ = writeln!(self.samples, " {value}");
+    }
+
+    fn write_into(&self, output: &mut String) {
+        let _ = writeln!(output, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(output, "# TYPE {} {}", self.name, self.kind);
+        output.push_str(&self.samples);
+    }
+}
+
+/// Escapes a label value as mandated by the Prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// Encodes, in the Prometheus text format, the `states` of the instances, the number of loaded `libraries` and the
+/// `metrics` of the nodes of each instance.
+fn encode<'a>(
+    states: impl Iterator<Item = &'a InstanceState>,
+    libraries: usize,
+    metrics: &[(InstanceId, InstanceMetrics)],
+) -> String {
+    let mut instances = Family::new(
+        "zenoh_flow_instances",
+        "gauge",
+        "Number of data flow instances managed by the runtime, by state.",
+    );
+    let mut count_per_state = STATES
+        .iter()
+        .map(|state| (*state, 0usize))
+        .collect::<HashMap<_, _>>();
+    for state in states {
+        *count_per_state.entry(state_label(state)).or_default() += 1;
+    }
+    for state in STATES {
+        instances.sample("", &[("state", state)], count_per_state[state]);
+    }
+
+    let mut loaded_libraries = Family::new(
+        "zenoh_flow_loaded_libraries",
+        "gauge",
+        "Number of shared libraries loaded by the runtime.",
+    );
+    loaded_libraries.sample("", &[], libraries);
+
+    let mut iterations = Family::new(
+        "zenoh_flow_node_iterations_total",
+        "counter",
+        "Number of iterations of the node.",
+    );
+    let mut errors = Family::new(
+        "zenoh_flow_node_errors_total",
+        "counter",
+        "Number of iterations of the node that returned an error.",
+    );
+    let mut panics = Family::new(
+        "zenoh_flow_node_panics_total",
+        "counter",
+        "Number of iterations of the node that panicked.",
+    );
+    let mut durations = Family::new(
+        "zenoh_flow_node_iteration_duration_seconds",
+        "histogram",
+        "Duration of the iterations of the node.",
+    );
+    let mut received = Family::new(
+        "zenoh_flow_input_messages_total",
+        "counter",
+        "Number of messages received on the input.",
+    );
+    let mut received_bytes = Family::new(
+        "zenoh_flow_input_bytes_total",
+        "counter",
+        "Number of bytes received on the input, absent if it received typed payloads.",
+    );
+    let mut lost = Family::new(
+        "zenoh_flow_input_lost_total",
+        "counter",
+        "Number of messages that never reached the input.",
+    );
+    let mut duplicates = Family::new(
+        "zenoh_flow_input_duplicates_total",
+        "counter",
+        "Number of messages received more than once, or out of order, on the input.",
+    );
+    let mut pending = Family::new(
+        "zenoh_flow_input_pending",
+        "gauge",
+        "Number of messages waiting in the queue of the input.",
+    );
+    let mut sent = Family::new(
+        "zenoh_flow_output_messages_total",
+        "counter",
+        "Number of messages sent on the output.",
+    );
+    let mut sent_bytes = Family::new(
+        "zenoh_flow_output_bytes_total",
+        "counter",
+        "Number of bytes sent on the output, absent if it sent typed payloads.",
+    );
+    let mut dropped = Family::new(
+        "zenoh_flow_output_dropped_total",
+        "counter",
+        "Number of messages discarded because a queue was full.",
+    );
+
+    for (instance_id, instance_metrics) in metrics {
+        let instance_id = instance_id.to_string();
+        for (node_id, node) in instance_metrics.nodes.iter() {
+            let labels: [(&str, &str); 2] = [
+                ("instance_id", instance_id.as_str()),
+                ("node", node_id.as_ref()),
+            ];
+            iterations.sample("", &labels, node.iterations);
+            errors.sample("", &labels, node.errors);
+            panics.sample("", &labels, node.panics);
+
+            let mut cumulated = 0;
+            for (bound, count) in node.latency.bounds_us.iter().zip(&node.latency.counts) {
+                cumulated += count;
+                let le = (*bound as f64 / 1_000_000.0).to_string();
+                durations.sample(
+                    "_bucket",
+                    &[labels[0], labels[1], ("le", le.as_str())],
+                    cumulated,
+                );
+            }
+            durations.sample(
+                "_bucket",
+                &[labels[0], labels[1], ("le", "+Inf")],
+                node.latency.count(),
+            );
+            durations.sample("_sum", &labels, node.latency.sum_us as f64 / 1_000_000.0);
+            durations.sample("_count", &labels, node.latency.count());
+
+            for (port_id, input) in node.ports.inputs.iter() {
+                let labels = [labels[0], labels[1], ("port", port_id.as_ref())];
+                received.sample("", &labels, input.received);
+                if let Some(bytes) = input.bytes {
+                    received_bytes.sample("", &labels, bytes);
+                }
+                lost.sample("", &labels, input.lost);
+                duplicates.sample("", &labels, input.duplicates);
+                pending.sample("", &labels, input.pending);
+            }
+
+            for (port_id, output) in node.ports.outputs.iter() {
+                let labels = [labels[0], labels[1], ("port", port_id.as_ref())];
+                sent.sample("", &labels, output.sent);
+                if let Some(bytes) = output.bytes {
+                    sent_bytes.sample("", &labels, bytes);
+                }
+                dropped.sample("", &labels, output.dropped);
+            }
+        }
+    }
+
+    let mut output = String::default();
+    for family in [
+        instances,
+        loaded_libraries,
+        iterations,
+        errors,
+        panics,
+        durations,
+        received,
+        received_bytes,
+        lost,
+        duplicates,
+        pending,
+        sent,
+        sent_bytes,
+        dropped,
+    ] {
+        family.write_into(&mut output);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::io::ReadExt;
+    use uhlc::HLC;
+    use zenoh_flow_commons::{NodeId, RuntimeId};
+    use zenoh_flow_runtime::{LatencyHistogram, NodeMetrics};
+
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let hlc = HLC::default();
+        let instance_id: InstanceId = uuid::Uuid::new_v4().into();
+        let mut node = NodeMetrics {
+            iterations: 3,
+            errors: 1,
+            latency: LatencyHistogram {
+                bounds_us: vec![10, 1_000],
+                counts: vec![1, 1, 1],
+                sum_us: 2_500_005,
+            },
+            ..Default::default()
+        };
+        node.ports.inputs.insert("in".into(), Default::default());
+
+        let output = encode(
+            [
+                InstanceState::Running(hlc.new_timestamp()),
+                InstanceState::Running(hlc.new_timestamp()),
+                InstanceState::Failed((hlc.new_timestamp(), "bad \"node\"".into())),
+            ]
+            .iter(),
+            2,
+            &[(
+                instance_id.clone(),
+                InstanceMetrics {
+                    runtime_id: RuntimeId::rand(),
+                    nodes: HashMap::from([(NodeId::from("node-1"), node)]),
+                },
+            )],
+        );
+
+        assert!(output.contains("# TYPE zenoh_flow_instances gauge\n"));
+        assert!(output.contains("zenoh_flow_instances{state=\"running\"} 2\n"));
+        assert!(output.contains("zenoh_flow_instances{state=\"failed\"} 1\n"));
+        assert!(output.contains("zenoh_flow_instances{state=\"loaded\"} 0\n"));
+        assert!(output.contains("zenoh_flow_loaded_libraries 2\n"));
+
+        let labels = format!("instance_id=\"{instance_id}\",node=\"node-1\"");
+        assert!(output.contains(&format!("zenoh_flow_node_iterations_total{{{labels}}} 3\n")));
+        assert!(output.contains(&format!("zenoh_flow_node_errors_total{{{labels}}} 1\n")));
+        // The buckets are cumulative and expressed in seconds.
+        assert!(output.contains(&format!(
+            "zenoh_flow_node_iteration_duration_seconds_bucket{{{labels},le=\"0.00001\"}} 1\n"
+        )));
+        assert!(output.contains(&format!(
+            "zenoh_flow_node_iteration_duration_seconds_bucket{{{labels},le=\"0.001\"}} 2\n"
+        )));
+        assert!(output.contains(&format!(
+            "zenoh_flow_node_iteration_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3\n"
+        )));
+        assert!(output.contains(&format!(
+            "zenoh_flow_node_iteration_duration_seconds_sum{{{labels}}} 2.500005\n"
+        )));
+        assert!(output.contains(&format!(
+            "zenoh_flow_input_pending{{{labels},port=\"in\"}} 0\n"
+        )));
+
+        assert_eq!(r#"bad \"node\"\n"#, escape("bad \"node\"\n"));
+    }
+
+    #[async_std::test]
+    async fn test_endpoint() {
+        let runtime = Runtime::builder("test-metrics-endpoint")
+            .build()
+            .await
+            .expect("Failed to build the Zenoh-Flow runtime");
+        let (address, handle) = spawn("127.0.0.1:0".parse().unwrap(), Arc::new(runtime))
+            .await
+            .expect("Failed to spawn the metrics endpoint");
+
+        let get = |target: &'static str| async move {
+            let mut stream = TcpStream::connect(address)
+                .await
+                .expect("Failed to connect to the metrics endpoint");
+            stream
+                .write_all(format!("GET {target} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+                .await
+                .expect("Failed to send the request");
+            let mut response = String::default();
+            stream
+                .read_to_string(&mut response)
+                .await
+                .expect("Failed to read the response");
+            response
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(CONTENT_TYPE));
+        assert!(response.contains("zenoh_flow_instances{state=\"running\"} 0\n"));
+        assert!(response.contains("zenoh_flow_loaded_libraries 0\n"));
+
+        assert!(get("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        handle.cancel().await;
+    }
+}
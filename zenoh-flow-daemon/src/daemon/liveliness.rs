@@ -0,0 +1,324 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The detection of the Zenoh-Flow runtimes that were lost.
+//!
+//! Each Daemon declares a liveliness token, on behalf of its runtime, and watches the tokens of the other runtimes. As
+//! soon as the token of a runtime disappears -- because it stopped or because it is no longer reachable -- the running
+//! data flow instances it is involved in are reported as
+//! [Disconnected](zenoh_flow_runtime::InstanceState::Disconnected), until its token appears again.
+//!
+//! If some nodes of the lost runtime have a [failover](zenoh_flow_descriptors::FailoverPolicy) policy, they are
+//! re-placed on another runtime by a single Daemon: among the runtimes involved in the instance that are still alive,
+//! the one with the smallest identifier. The runtimes involved are then updated, the ones that become involved load
+//! the instance and, if the instance was running, start it.
+//!
+//! ⚠️ A runtime that was not stopped but only unreachable keeps its nodes: once it is reachable again, the nodes that
+//! were re-placed run twice until the instance is deleted from that runtime.
+
+use std::{str::FromStr, sync::Arc};
+
+use anyhow::anyhow;
+use async_std::task::JoinHandle;
+use zenoh::{key_expr::KeyExpr, liveliness::LivelinessToken, sample::SampleKind};
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
+use zenoh_flow_records::DataFlowRecord;
+use zenoh_flow_runtime::Runtime;
+
+use super::QueriesConfiguration;
+use crate::queries::{instances::update::update_all, selectors};
+
+/// Declares the liveliness token of the `runtime` and spawns a task watching the tokens of the other runtimes.
+///
+/// If `abort_instances` is set, the instances a lost runtime is involved in are additionally aborted on the `runtime`,
+/// unless some of the nodes of the lost runtime can be re-placed.
+///
+/// The token is undeclared once dropped.
+///
+/// # Errors
+///
+/// This function will fail if the liveliness token or the liveliness subscriber could not be declared.
+pub(crate) async fn spawn(
+    runtime: Arc<Runtime>,
+    abort_instances: bool,
+    queries: QueriesConfiguration,
+) -> Result<(LivelinessToken, JoinHandle<()>)> {
+    let key_expr = selectors::selector_liveliness(runtime.id());
+    let token = runtime
+        .session()
+        .liveliness()
+        .declare_token(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to declare liveliness token on < {key_expr} >: {e:?}"))?;
+
+    let key_expr = selectors::selector_all_liveliness();
+    let subscriber = runtime
+        .session()
+        .liveliness()
+        .declare_subscriber(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to declare liveliness subscriber on < {key_expr} >: {e:?}"))?;
+
+    let handle = async_std::task::spawn(async move {
+        while let Ok(sample) = subscriber.recv_async().await {
+            let Some(runtime_id) = runtime_id_of(sample.key_expr()) else {
+                tracing::warn!("Unexpected liveliness token < {} >", sample.key_expr());
+                continue;
+            };
+
+            if &runtime_id == runtime.id() {
+                continue;
+            }
+
+            match sample.kind() {
+                SampleKind::Put => {
+                    for instance_id in runtime.mark_runtime_recovered(&runtime_id).await {
+                        tracing::info!(
+                            "Runtime < {} > involved in instance < {} > is back",
+                            runtime_id,
+                            instance_id
+                        );
+                    }
+                }
+                SampleKind::Delete => {
+                    for instance_id in runtime.mark_runtime_lost(&runtime_id).await {
+                        tracing::warn!(
+                            "Runtime < {} > involved in instance < {} > was lost",
+                            runtime_id,
+                            instance_id
+                        );
+
+                        match runtime.try_get_record(&instance_id).await {
+                            Ok(record) if has_failover(&record, &runtime_id) => {
+                                async_std::task::spawn(failover(
+                                    runtime.clone(),
+                                    record,
+                                    runtime_id.clone(),
+                                    abort_instances,
+                                    queries,
+                                ));
+                            }
+                            _ => {
+                                if abort_instances {
+                                    abort(&runtime, &instance_id).await;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    Ok((token, handle))
+}
+
+/// Returns the identifier of the runtime that declared the liveliness token `key_expr`.
+fn runtime_id_of(key_expr: &KeyExpr) -> Option<RuntimeId> {
+    key_expr
+        .as_str()
+        .split('/')
+        .nth(1)
+        .and_then(|runtime_id| RuntimeId::from_str(runtime_id).ok())
+}
+
+/// Returns `true` if at least one node of the `lost_runtime` has a failover policy.
+fn has_failover(record: &DataFlowRecord, lost_runtime: &RuntimeId) -> bool {
+    record.mapping().get(lost_runtime).is_some_and(|nodes| {
+        nodes
+            .iter()
+            .any(|node_id| record.failover().contains_key(node_id))
+    })
+}
+
+/// Aborts the instance `instance_id` on the `runtime`, logging the error if it failed.
+async fn abort(runtime: &Runtime, instance_id: &InstanceId) {
+    if let Err(e) = runtime.try_abort_instance(instance_id, None).await {
+        tracing::error!("Failed to abort instance < {} >: {:?}", instance_id, e);
+    }
+}
+
+/// Re-places the nodes of the `lost_runtime`, if the `runtime` is the one elected to do so.
+///
+/// If the nodes could not be re-placed and `abort_instances` is set, the instance is aborted on the `runtime`.
+async fn failover(
+    runtime: Arc<Runtime>,
+    record: DataFlowRecord,
+    lost_runtime: RuntimeId,
+    abort_instances: bool,
+    queries: QueriesConfiguration,
+) {
+    let instance_id = record.instance_id().clone();
+    match try_failover(&runtime, record, &lost_runtime, &queries).await {
+        Ok(true) => tracing::info!(
+            "Re-placed the nodes of lost runtime < {} > involved in instance < {} >",
+            lost_runtime,
+            instance_id
+        ),
+        Ok(false) => tracing::debug!(
+            "Another runtime is re-placing the nodes of lost runtime < {} > involved in instance < {} >",
+            lost_runtime,
+            instance_id
+        ),
+        Err(e) => {
+            tracing::error!(
+                "Failed to re-place the nodes of lost runtime < {} > involved in instance < {} >: {:?}",
+                lost_runtime,
+                instance_id,
+                e
+            );
+            if abort_instances {
+                abort(&runtime, &instance_id).await;
+            }
+        }
+    }
+}
+
+/// Returns `false` if the `runtime` is not the one elected to re-place the nodes of the `lost_runtime`.
+///
+/// The runtimes that are alive are retrieved through their liveliness tokens. The elected runtime is, among the
+/// runtimes involved in the instance that are alive, the one with the smallest identifier.
+async fn try_failover(
+    runtime: &Runtime,
+    record: DataFlowRecord,
+    lost_runtime: &RuntimeId,
+    queries: &QueriesConfiguration,
+) -> Result<bool> {
+    let key_expr = selectors::selector_all_liveliness();
+    let replies = runtime
+        .session()
+        .liveliness()
+        .get(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to query the liveliness tokens on < {key_expr} >: {e:?}"))?;
+
+    // NOTE: The runtime comes first such that, with the `any` policy, it is preferred to re-place the nodes.
+    let mut alive_runtimes = vec![runtime.id().clone()];
+    while let Ok(reply) = replies.recv_async().await {
+        if let Some(runtime_id) = reply
+            .result()
+            .ok()
+            .and_then(|sample| runtime_id_of(sample.key_expr()))
+        {
+            if runtime_id != *runtime.id()
+                && runtime_id != *lost_runtime
+                && !alive_runtimes.contains(&runtime_id)
+            {
+                alive_runtimes.push(runtime_id);
+            }
+        }
+    }
+    alive_runtimes[1..].sort_by_key(|runtime_id| runtime_id.to_string());
+
+    let leader = record
+        .mapping()
+        .keys()
+        .filter(|&runtime_id| alive_runtimes.contains(runtime_id))
+        .min_by_key(|runtime_id| runtime_id.to_string());
+    if leader != Some(runtime.id()) {
+        return Ok(false);
+    }
+
+    let update = record.try_failover(lost_runtime, &alive_runtimes)?;
+    let unreachable_runtimes = record
+        .mapping()
+        .keys()
+        .filter(|&runtime_id| !alive_runtimes.contains(runtime_id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    update_all(
+        runtime,
+        record.instance_id(),
+        &update,
+        runtime.id(),
+        queries,
+        &unreachable_runtimes,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_flow_runtime::InstanceState;
+
+    use super::*;
+    use crate::test_utils::{self, TestDataFlow};
+
+    #[test]
+    fn test_runtime_id_of() {
+        let runtime_id = RuntimeId::rand();
+        let key_expr = selectors::selector_liveliness(&runtime_id);
+        assert_eq!(Some(runtime_id), runtime_id_of(&key_expr.into()));
+        assert_eq!(
+            None,
+            runtime_id_of(&KeyExpr::try_from("zenoh-flow").unwrap())
+        );
+    }
+
+    #[async_std::test]
+    async fn test_lost_runtime() {
+        let runtime = Arc::new(
+            Runtime::builder("test-liveliness")
+                .build()
+                .await
+                .expect("Failed to build the Zenoh-Flow runtime"),
+        );
+        let remote = RuntimeId::rand();
+
+        let record = TestDataFlow::new(Some(&remote)).record(runtime.id());
+        assert!(!has_failover(&record, &remote));
+        let instance_id = test_utils::run(&runtime, record).await;
+
+        let (_token, handle) = spawn(runtime.clone(), false, QueriesConfiguration::default())
+            .await
+            .expect("Failed to spawn the liveliness task");
+
+        // The liveliness token of the remote runtime is declared on behalf of it.
+        let remote_token = runtime
+            .session()
+            .liveliness()
+            .declare_token(selectors::selector_liveliness(&remote))
+            .await
+            .unwrap();
+        test_utils::wait_for_state(&runtime, &instance_id, |state| {
+            matches!(state, InstanceState::Running(_))
+        })
+        .await;
+
+        // 1. Its token disappears: the instance is disconnected.
+        drop(remote_token);
+        test_utils::wait_for_state(&runtime, &instance_id, |state| {
+            matches!(state, InstanceState::Disconnected(_))
+        })
+        .await;
+
+        // 2. Its token appears again: the instance is running again.
+        let _remote_token = runtime
+            .session()
+            .liveliness()
+            .declare_token(selectors::selector_liveliness(&remote))
+            .await
+            .unwrap();
+        test_utils::wait_for_state(&runtime, &instance_id, |state| {
+            matches!(state, InstanceState::Running(_))
+        })
+        .await;
+
+        handle.cancel().await;
+        test_utils::delete(&runtime, &instance_id).await;
+    }
+}
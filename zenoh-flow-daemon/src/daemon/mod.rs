@@ -20,7 +20,11 @@
 //! [runtime]: zenoh_flow_runtime::Runtime
 
 mod configuration;
+#[cfg(feature = "prometheus")]
+mod exporter;
+mod liveliness;
 mod queryables;
+mod store;
 
 use std::sync::Arc;
 
@@ -29,7 +33,10 @@ use zenoh::Session;
 use zenoh_flow_commons::Result;
 pub use zenoh_flow_runtime::{Extension, Extensions, Runtime};
 
-pub use self::configuration::ZenohFlowConfiguration;
+pub use self::configuration::{
+    QueriesConfiguration, QueryPolicy, StateStoreConfiguration, ZenohFlowConfiguration,
+};
+use self::store::StateStore;
 use crate::queries::{instances::delete::delete_instance, Origin};
 
 /// A Zenoh-Flow daemon declares 2 queryables:
@@ -44,6 +51,16 @@ pub struct Daemon {
     abort_tx: Sender<()>,
     abort_ack_rx: Receiver<()>,
     runtime: Arc<Runtime>,
+    queries: QueriesConfiguration,
+    #[cfg(feature = "prometheus")]
+    exporter: async_std::sync::Mutex<Option<async_std::task::JoinHandle<()>>>,
+    store: async_std::sync::Mutex<Option<async_std::task::JoinHandle<()>>>,
+    liveliness: async_std::sync::Mutex<
+        Option<(
+            zenoh::liveliness::LivelinessToken,
+            async_std::task::JoinHandle<()>,
+        )>,
+    >,
 }
 
 impl Daemon {
@@ -64,7 +81,10 @@ impl Daemon {
     /// - the [extensions] could not be added to the Runtime (see the list of potential reasons
     ///   [here](zenoh_flow_runtime::RuntimeBuilder::add_extensions())),
     /// - the Zenoh queryables -- one to manage the `instances` and another to manage the `runtime` itself -- could not
-    ///   be created.
+    ///   be created,
+    /// - if the feature `prometheus` is enabled, the address on which the metrics should be served could not be bound,
+    /// - if the feature `otlp` is enabled, the endpoint of the OpenTelemetry collector is not a valid `http` URL,
+    /// - a [state store](StateStoreConfiguration) is configured and its directory could not be created.
     ///
     /// [extensions]: Extensions
     /// [runtime]: Runtime
@@ -74,14 +94,56 @@ impl Daemon {
         configuration: ZenohFlowConfiguration,
     ) -> Result<Self> {
         let extensions = configuration.extensions.unwrap_or_default();
+        let state_store = configuration
+            .state_store
+            .map(|state_store| {
+                StateStore::try_new(state_store.directory)
+                    .map(|store| (store, state_store.restart_instances))
+            })
+            .transpose()?;
 
-        let runtime = Runtime::builder(configuration.name)
+        let mut builder = Runtime::builder(configuration.name)
             .add_extensions(extensions)?
             .session(zenoh_session)
-            .build()
-            .await?;
+            .labels(configuration.labels)
+            .trace_messages(configuration.trace_messages);
 
-        Daemon::spawn(runtime).await
+        if let Some(capacity) = configuration.capacity {
+            builder = builder.capacity(capacity);
+        }
+
+        #[cfg(feature = "otlp")]
+        if let Some(endpoint) = configuration.otlp_endpoint {
+            builder = builder.otlp_endpoint(endpoint);
+        }
+
+        let runtime = builder.build().await?;
+        let daemon = Daemon::try_spawn(
+            runtime,
+            configuration.queries,
+            configuration.abort_on_lost_runtime,
+        )
+        .await?;
+
+        #[cfg(feature = "prometheus")]
+        if let Some(address) = configuration.metrics_address {
+            let (_, handle) = exporter::spawn(address, daemon.runtime.clone()).await?;
+            *daemon.exporter.lock().await = Some(handle);
+        }
+
+        // NOTE: The instances are restored once the queryables are declared, as the other Daemons involved may query
+        // this one.
+        if let Some((store, restart_instances)) = state_store {
+            let handle = store::spawn(
+                store,
+                daemon.runtime.clone(),
+                daemon.queries,
+                restart_instances,
+            );
+            *daemon.store.lock().await = Some(handle);
+        }
+
+        Ok(daemon)
     }
 
     /// Spawn a new Zenoh-Flow Daemon wrapping the provided [runtime].
@@ -89,10 +151,47 @@ impl Daemon {
     /// # Errors
     ///
     /// This function will fail if the Zenoh queryables -- one to manage the `instances` and another to manage the
-    /// `runtime` itself -- could not be created.
+    /// `runtime` itself -- could not be created, or if the liveliness token of the runtime could not be declared.
     ///
     /// [runtime]: Runtime
     pub async fn spawn(runtime: Runtime) -> Result<Self> {
+        Daemon::spawn_with_queries(runtime, QueriesConfiguration::default()).await
+    }
+
+    /// Spawn a new Zenoh-Flow Daemon wrapping the provided [runtime], its queries to the other Daemons following the
+    /// provided [QueriesConfiguration].
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the Zenoh queryables -- one to manage the `instances` and another to manage the
+    /// `runtime` itself -- could not be created, or if the liveliness token of the runtime could not be declared.
+    ///
+    /// [runtime]: Runtime
+    pub async fn spawn_with_queries(
+        runtime: Runtime,
+        queries: QueriesConfiguration,
+    ) -> Result<Self> {
+        Daemon::try_spawn(runtime, queries, false).await
+    }
+
+    /// Spawn a new Zenoh-Flow Daemon wrapping the provided [runtime].
+    ///
+    /// The Daemon declares the liveliness token of its runtime and watches the tokens of the other runtimes: the
+    /// running data flow instances a lost runtime is involved in are reported as
+    /// [Disconnected](zenoh_flow_runtime::InstanceState::Disconnected), the nodes of the lost runtime that have a
+    /// [failover](zenoh_flow_descriptors::FailoverPolicy) policy are re-placed and, if `abort_on_lost_runtime` is set,
+    /// the instances without nodes to re-place are aborted.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the liveliness token or the liveliness subscriber could not be declared.
+    ///
+    /// [runtime]: Runtime
+    async fn try_spawn(
+        runtime: Runtime,
+        queries: QueriesConfiguration,
+        abort_on_lost_runtime: bool,
+    ) -> Result<Self> {
         // Channels to gracefully stop the Zenoh-Flow daemon:
         // - `abort_?x` to tell the queryables that they have to stop,
         // - `abort_ack_?x` for the queryables to inform the runtime that they did stop.
@@ -119,6 +218,7 @@ impl Daemon {
         if let Err(e) = queryables::spawn_instances_queryable(
             session.clone(),
             runtime.clone(),
+            queries,
             abort_rx,
             abort_ack_tx,
         )
@@ -131,10 +231,17 @@ impl Daemon {
             // TODO: Clean everything up before aborting.
         }
 
+        let liveliness = liveliness::spawn(runtime.clone(), abort_on_lost_runtime, queries).await?;
+
         Ok(Daemon {
             abort_tx,
             abort_ack_rx,
             runtime,
+            queries,
+            #[cfg(feature = "prometheus")]
+            exporter: Default::default(),
+            store: Default::default(),
+            liveliness: async_std::sync::Mutex::new(Some(liveliness)),
         })
     }
 
@@ -143,9 +250,21 @@ impl Daemon {
     /// This method will first stop the queryables this daemon declared (to not process new requests) and then delete
     /// all the data flow instances it manages.
     ///
+    /// If a [state store](StateStoreConfiguration) is configured, it stops being synchronised before the instances are
+    /// deleted: they are thus restored the next time the Daemon starts.
+    ///
     /// ⚠️ If a data flow is spanning over multiple Daemons, stopping a single Daemon will delete the data flow instance
     /// on all the Daemons.
     pub async fn stop(&self) {
+        #[cfg(feature = "prometheus")]
+        if let Some(exporter) = self.exporter.lock().await.take() {
+            exporter.cancel().await;
+        }
+
+        if let Some(store) = self.store.lock().await.take() {
+            store.cancel().await;
+        }
+
         for iteration in 0..NUMBER_QUERYABLES {
             tracing::trace!(
                 "Sending abort signal to queryable ({}/{})",
@@ -162,12 +281,26 @@ impl Daemon {
             });
         }
 
-        let data_flows = self.runtime.instances_state().await;
-        let delete_requests = data_flows
+        let data_flows = self
+            .runtime
+            .instances_state()
+            .await
             .into_keys()
-            .map(|instance_id| delete_instance(self.runtime.clone(), Origin::Client, instance_id));
+            .collect::<Vec<_>>();
+        let delete_requests = data_flows.iter().map(|instance_id| {
+            delete_instance(
+                &self.runtime,
+                Origin::Client,
+                instance_id,
+                &self.queries.delete,
+            )
+        });
 
-        futures::future::join_all(delete_requests).await;
+        for deleted in futures::future::join_all(delete_requests).await {
+            if let Err(e) = deleted {
+                tracing::error!("{:?}", e);
+            }
+        }
 
         // TODO Introduce a timer: if, for whatever reason, a queryable fails to send an acknowledgement we should not
         // block the stopping procedure.
@@ -188,5 +321,10 @@ impl Daemon {
                 NUMBER_QUERYABLES
             );
         }
+
+        // NOTE: Dropping the token undeclares it, the other Daemons are thus informed that this one is gone.
+        if let Some((_token, watcher)) = self.liveliness.lock().await.take() {
+            watcher.cancel().await;
+        }
     }
 }
@@ -21,14 +21,18 @@ use zenoh::Session;
 use zenoh_flow_commons::Result;
 use zenoh_flow_runtime::Runtime;
 
+use super::QueriesConfiguration;
 use crate::queries::{
     instances::InstancesQuery, runtime::RuntimesQuery, selectors, validate_query,
 };
 
 /// Spawns an async task to answer queries received on `zenoh-flow/{runtime_id}/instances`.
+///
+/// The queries this Daemon sends to the other Daemons, while answering, follow the provided [QueriesConfiguration].
 pub(crate) async fn spawn_instances_queryable(
     zenoh_session: Session,
     runtime: Arc<Runtime>,
+    queries: QueriesConfiguration,
     abort_rx: Receiver<()>,
     abort_ack_tx: Sender<()>,
 ) -> Result<()> {
@@ -64,7 +68,7 @@ pub(crate) async fn spawn_instances_queryable(
 
                             let runtime = runtime.clone();
                             async_std::task::spawn(async move {
-                                instance_query.process(query, runtime).await;
+                                instance_query.process(query, runtime, queries).await;
                             });
                         }
                         Err(e) => {
@@ -0,0 +1,445 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The local store in which a Zenoh-Flow Daemon persists the data flow instances it manages.
+//!
+//! Each instance is persisted in its own file, named after its identifier, holding its record and its last known
+//! state. A task periodically compares what the runtime manages with what was last persisted and only writes the
+//! instances that changed.
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use async_std::task::JoinHandle;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
+use zenoh_flow_records::DataFlowRecord;
+use zenoh_flow_runtime::{InstanceState, Runtime};
+
+use super::{QueriesConfiguration, QueryPolicy};
+use crate::queries::instances::{delete, query_runtime, start, InstancesQuery};
+
+const INSTANCE_EXTENSION: &str = "json";
+
+/// The interval at which the instances managed by the runtime are compared with the ones persisted.
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A data flow instance, as persisted in the [StateStore].
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct PersistedInstance {
+    pub(crate) record: DataFlowRecord,
+    pub(crate) state: InstanceState,
+}
+
+/// The `StateStore` persists data flow instances in a directory, one file per instance.
+pub(crate) struct StateStore {
+    directory: PathBuf,
+}
+
+impl StateStore {
+    /// Attempts to create a `StateStore` persisting the instances in the provided `directory`.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the directory does not exist and could not be created.
+    pub(crate) fn try_new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).with_context(|| {
+            format!(
+                "Failed to create the state store directory < {} >",
+                directory.display()
+            )
+        })?;
+
+        Ok(Self { directory })
+    }
+
+    fn path(&self, instance_id: &InstanceId) -> PathBuf {
+        self.directory
+            .join(format!("{instance_id}.{INSTANCE_EXTENSION}"))
+    }
+
+    /// Returns all the instances persisted in this store.
+    ///
+    /// The files that could not be read or deserialized are skipped, with an error logged.
+    pub(crate) async fn load_all(&self) -> Vec<PersistedInstance> {
+        let mut entries = match async_std::fs::read_dir(&self.directory).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to read the state store directory < {} >: {:?}",
+                    self.directory.display(),
+                    e
+                );
+                return Vec::default();
+            }
+        };
+
+        let mut instances = Vec::default();
+        while let Some(entry) = entries.next().await {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let path = entry.path();
+            if !path
+                .extension()
+                .is_some_and(|extension| extension == INSTANCE_EXTENSION)
+            {
+                continue;
+            }
+
+            match try_load(path.as_ref()).await {
+                Ok(instance) => instances.push(instance),
+                Err(e) => tracing::error!("{:?}", e),
+            }
+        }
+
+        instances
+    }
+
+    /// Attempts to persist the (serialized) instance `instance_id`.
+    ///
+    /// The file is first written under a temporary name and then renamed such that a partially written instance is
+    /// never restored.
+    pub(crate) async fn try_save(&self, instance_id: &InstanceId, bytes: &[u8]) -> Result<()> {
+        let path = self.path(instance_id);
+        let tmp_path = path.with_extension("tmp");
+
+        async_std::fs::write(&tmp_path, bytes)
+            .await
+            .with_context(|| format!("Failed to write < {} >", tmp_path.display()))?;
+        async_std::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("Failed to rename < {} >", tmp_path.display()))
+    }
+
+    /// Attempts to remove the instance `instance_id` from this store. Removing an instance that is not persisted does
+    /// nothing.
+    pub(crate) async fn try_remove(&self, instance_id: &InstanceId) -> Result<()> {
+        let path = self.path(instance_id);
+        match async_std::fs::remove_file(&path).await {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            removed => removed.with_context(|| format!("Failed to remove < {} >", path.display())),
+        }
+    }
+}
+
+async fn try_load(path: &Path) -> Result<PersistedInstance> {
+    let bytes = async_std::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read < {} >", path.display()))?;
+    serde_json::from_slice(&bytes)
+        .with_context(|| format!("Failed to deserialize instance < {} >", path.display()))
+}
+
+/// Spawns a task that first restores the instances persisted in the `store` and then keeps it synchronised with the
+/// instances managed by the `runtime`.
+///
+/// An instance is persisted as soon as its record or its state changes and removed once the runtime no longer manages
+/// it. The file of an instance that failed is left untouched, as the runtime no longer exposes its record. The file of
+/// an instance that could not be restored is also left untouched: it will be restored again the next time the Daemon
+/// starts.
+///
+/// Cancelling the returned task stops the synchronisation: the instances deleted afterwards -- for instance, when the
+/// Daemon is stopped -- remain in the store.
+pub(crate) fn spawn(
+    store: StateStore,
+    runtime: Arc<Runtime>,
+    queries: QueriesConfiguration,
+    restart_instances: bool,
+) -> JoinHandle<()> {
+    async_std::task::spawn(async move {
+        for instance in store.load_all().await {
+            let instance_id = instance.record.instance_id().clone();
+            if let Err(e) = restore(&runtime, instance, &queries, restart_instances).await {
+                tracing::error!("Failed to restore instance < {} >: {:?}", instance_id, e);
+            }
+        }
+
+        let mut persisted: HashMap<InstanceId, Vec<u8>> = HashMap::default();
+        loop {
+            async_std::task::sleep(SYNC_INTERVAL).await;
+
+            let states = runtime.instances_state().await;
+            for (instance_id, (_, state)) in states.iter() {
+                // NOTE: The record of a failed instance is not accessible, we keep the last one persisted.
+                let Ok(record) = runtime.try_get_record(instance_id).await else {
+                    continue;
+                };
+
+                let instance = PersistedInstance {
+                    record,
+                    state: state.clone(),
+                };
+                let bytes = match serde_json::to_vec(&instance) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to serialize instance < {} >: {:?}",
+                            instance_id,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                if persisted.get(instance_id) == Some(&bytes) {
+                    continue;
+                }
+
+                match store.try_save(instance_id, &bytes).await {
+                    Ok(()) => {
+                        persisted.insert(instance_id.clone(), bytes);
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to persist instance < {} >: {:?}", instance_id, e)
+                    }
+                }
+            }
+
+            let removed = persisted
+                .keys()
+                .filter(|&instance_id| !states.contains_key(instance_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            for instance_id in removed {
+                match store.try_remove(&instance_id).await {
+                    Ok(()) => {
+                        persisted.remove(&instance_id);
+                    }
+                    Err(e) => tracing::error!("{:?}", e),
+                }
+            }
+        }
+    })
+}
+
+/// Restores the persisted `instance`: it is loaded on this runtime and on the runtimes involved in its execution that
+/// do not manage it, and, if `restart_instance` is set and it was running, started on all of them.
+///
+/// The runtimes that still manage the instance -- because they did not restart -- are left untouched.
+///
+/// # Errors
+///
+/// This function will fail if:
+/// - the instance is not mapped on this runtime, which happens if its identifier changed,
+/// - this runtime or one of the runtimes involved failed to load the instance. The instance is then deleted from this
+///   runtime and from the runtimes that loaded it,
+/// - this runtime failed to start the instance.
+async fn restore(
+    runtime: &Runtime,
+    instance: PersistedInstance,
+    queries: &QueriesConfiguration,
+    restart_instance: bool,
+) -> Result<()> {
+    let PersistedInstance { record, state } = instance;
+    let instance_id = record.instance_id().clone();
+
+    if !record.mapping().contains_key(runtime.id()) {
+        bail!(
+            "The instance is not mapped on this runtime < {} >, was its identifier changed?",
+            runtime.id()
+        );
+    }
+
+    runtime
+        .try_load_data_flow(record.clone())
+        .await
+        .context("Failed to load the instance")?;
+
+    let load_query = InstancesQuery::Load(Box::new(record.clone()));
+    let status_query = InstancesQuery::Status(instance_id.clone());
+    let mut loaded_runtimes: Vec<RuntimeId> = Vec::default();
+
+    for runtime_id in record
+        .mapping()
+        .keys()
+        .filter(|&runtime_id| runtime_id != runtime.id())
+    {
+        // NOTE: A runtime that knows the instance replies to the `Status` query, otherwise it replies with an error.
+        if query_runtime(
+            runtime.session(),
+            runtime_id,
+            &status_query,
+            &QueryPolicy::default(),
+        )
+        .await
+        .is_ok()
+        {
+            continue;
+        }
+
+        if let Err(e) =
+            query_runtime(runtime.session(), runtime_id, &load_query, &queries.load).await
+        {
+            let failures = delete::query_delete(
+                runtime.session(),
+                loaded_runtimes.iter(),
+                &instance_id,
+                &queries.delete,
+            )
+            .await;
+            for (runtime_id, e) in failures {
+                tracing::error!(
+                    "Runtime < {} > failed to delete instance < {} > while cleaning up after failed restoration: {:?}",
+                    runtime_id,
+                    instance_id,
+                    e
+                );
+            }
+            if let Err(e) = runtime.try_delete_instance(&instance_id).await {
+                tracing::error!(
+                    "Failed to delete instance < {} > while cleaning up after failed restoration: {:?}",
+                    instance_id,
+                    e
+                );
+            }
+
+            return Err(e.context(format!(
+                "Runtime < {runtime_id} > failed to load the instance"
+            )));
+        }
+
+        loaded_runtimes.push(runtime_id.clone());
+    }
+
+    if restart_instance
+        && matches!(
+            state,
+            InstanceState::Running(_) | InstanceState::Degraded(_) | InstanceState::Disconnected(_)
+        )
+    {
+        start::query_start(
+            runtime.session(),
+            loaded_runtimes.iter(),
+            &instance_id,
+            queries,
+        )
+        .await?;
+        runtime
+            .try_start_instance(&instance_id)
+            .await
+            .context("Failed to start the instance")?;
+    }
+
+    tracing::info!("Restored instance < {} >", instance_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::test_utils::{self, TestDataFlow};
+
+    #[async_std::test]
+    async fn test_save_load_remove() {
+        let directory = std::env::temp_dir().join(format!("zenoh-flow-store-{}", Uuid::new_v4()));
+        let store = StateStore::try_new(&directory).expect("Failed to create the state store");
+
+        let record = TestDataFlow::new(None).record(&RuntimeId::rand());
+        let instance_id = record.instance_id().clone();
+        let instance = PersistedInstance {
+            record: record.clone(),
+            state: InstanceState::Running(uhlc::HLC::default().new_timestamp()),
+        };
+        store
+            .try_save(&instance_id, &serde_json::to_vec(&instance).unwrap())
+            .await
+            .expect("Failed to persist the instance");
+
+        // Invalid files and files with another extension are skipped.
+        std::fs::write(directory.join("invalid.json"), b"not an instance").unwrap();
+        std::fs::write(directory.join("ignored.txt"), b"not an instance").unwrap();
+
+        let instances = store.load_all().await;
+        assert_eq!(1, instances.len());
+        assert_eq!(record, instances[0].record);
+        assert!(matches!(instances[0].state, InstanceState::Running(_)));
+
+        store
+            .try_remove(&instance_id)
+            .await
+            .expect("Failed to remove the instance");
+        assert!(store.load_all().await.is_empty());
+        // Removing an instance that is not persisted does nothing.
+        assert!(store.try_remove(&instance_id).await.is_ok());
+
+        std::fs::remove_dir_all(directory).expect("Failed to clean up");
+    }
+
+    #[async_std::test]
+    async fn test_persist_restore() {
+        let directory = std::env::temp_dir().join(format!("zenoh-flow-store-{}", Uuid::new_v4()));
+        let runtime = Arc::new(
+            Runtime::builder("test-store")
+                .build()
+                .await
+                .expect("Failed to build the Zenoh-Flow runtime"),
+        );
+
+        let record = TestDataFlow::new(None).record(runtime.id());
+        let instance_id = test_utils::run(&runtime, record.clone()).await;
+
+        // 1. The running instance is persisted.
+        let handle = spawn(
+            StateStore::try_new(&directory).unwrap(),
+            runtime.clone(),
+            QueriesConfiguration::default(),
+            true,
+        );
+        let store = StateStore::try_new(&directory).unwrap();
+        let store = &store;
+        test_utils::wait_until(
+            || async move { !store.load_all().await.is_empty() },
+            "The instance was not persisted",
+        )
+        .await;
+        handle.cancel().await;
+
+        let instances = store.load_all().await;
+        assert_eq!(1, instances.len());
+        assert_eq!(record, instances[0].record);
+        assert!(matches!(instances[0].state, InstanceState::Running(_)));
+
+        // 2. Once the synchronisation is stopped, the instances deleted remain in the store and are restored -- and
+        //    restarted -- the next time it is spawned.
+        test_utils::delete(&runtime, &instance_id).await;
+        assert_eq!(1, store.load_all().await.len());
+
+        let handle = spawn(
+            StateStore::try_new(&directory).unwrap(),
+            runtime.clone(),
+            QueriesConfiguration::default(),
+            true,
+        );
+        test_utils::wait_for_state(&runtime, &instance_id, |state| {
+            matches!(state, InstanceState::Running(_))
+        })
+        .await;
+        handle.cancel().await;
+        assert_eq!(record, runtime.try_get_record(&instance_id).await.unwrap());
+
+        test_utils::delete(&runtime, &instance_id).await;
+        std::fs::remove_dir_all(directory).expect("Failed to clean up");
+    }
+}
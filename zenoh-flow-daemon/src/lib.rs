@@ -31,3 +31,6 @@
 
 pub mod daemon;
 pub mod queries;
+
+#[cfg(test)]
+pub(crate) mod test_utils;
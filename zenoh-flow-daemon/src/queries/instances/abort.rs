@@ -12,75 +12,100 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use zenoh::Session;
-use zenoh_flow_commons::{InstanceId, RuntimeId};
+use anyhow::Context;
+use zenoh::{query::Query, Session};
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
 use zenoh_flow_runtime::Runtime;
 
-use super::{InstancesQuery, Origin};
-use crate::queries::selectors;
+use super::{ensure_acknowledged, query_runtimes, reply, InstancesQuery, Origin};
+use crate::daemon::QueryPolicy;
 
-pub(crate) fn abort(runtime: Arc<Runtime>, origin: Origin, instance_id: InstanceId) {
+/// Aborts the data flow instance identified by `instance_id`, replying once it is aborted.
+///
+/// If this query originates from a [Client](Origin::Client) then this function also queries the other runtimes to abort
+/// the same data flow instance.
+///
+/// # Consistency
+///
+/// Aborting a data flow instance cannot be rolled back: the instance is aborted on all the runtimes that could be
+/// reached. The runtimes that failed to abort it, or that did not acknowledge it, are listed in the error.
+pub(crate) fn abort(
+    runtime: Arc<Runtime>,
+    query: Query,
+    origin: Origin,
+    instance_id: InstanceId,
+    graceful: Option<Duration>,
+    policy: QueryPolicy,
+) {
     async_std::task::spawn(async move {
-        if matches!(origin, Origin::Client) {
-            match runtime.try_get_record(&instance_id).await {
-                Ok(record) => {
-                    query_abort(
-                        runtime.session(),
-                        record
-                            .mapping()
-                            .keys()
-                            .filter(|&runtime_id| runtime_id != runtime.id()),
-                        &instance_id,
-                    )
-                    .await
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Could not get record of data flow < {} >: {e:?}",
-                        instance_id
-                    );
-                    return;
-                }
-            }
+        let aborted = abort_instance(&runtime, origin, &instance_id, graceful, &policy).await;
+        if let Err(e) = &aborted {
+            tracing::error!("{:?}", e);
         }
 
-        if let Err(e) = runtime.try_abort_instance(&instance_id).await {
-            tracing::error!("Failed to abort instance < {} >: {:?}", instance_id, e);
+        if let Err(e) = reply(query, aborted).await {
+            tracing::error!("Failed to reply to 'Abort' query: {:?}", e);
         }
     });
 }
 
+async fn abort_instance(
+    runtime: &Runtime,
+    origin: Origin,
+    instance_id: &InstanceId,
+    graceful: Option<Duration>,
+    policy: &QueryPolicy,
+) -> Result<()> {
+    let mut failures = Vec::default();
+
+    if matches!(origin, Origin::Client) {
+        let record = runtime
+            .try_get_record(instance_id)
+            .await
+            .with_context(|| format!("Could not get record of data flow < {instance_id} >"))?;
+
+        failures = query_abort(
+            runtime.session(),
+            record
+                .mapping()
+                .keys()
+                .filter(|&runtime_id| runtime_id != runtime.id()),
+            instance_id,
+            graceful,
+            policy,
+        )
+        .await;
+    }
+
+    if let Err(e) = runtime.try_abort_instance(instance_id, graceful).await {
+        failures.push((runtime.id().clone(), e));
+    }
+
+    ensure_acknowledged("abort", instance_id, &failures)
+}
+
+/// Queries, concurrently, the `runtimes` to abort the data flow instance identified by `instance_id`.
+///
+/// Returns the runtimes that failed to abort it or that did not acknowledge it. If the instance is gracefully aborted,
+/// the timeout of the `policy` is extended by the `graceful` duration.
 pub(crate) async fn query_abort(
     session: &Session,
     runtimes: impl Iterator<Item = &RuntimeId>,
     instance_id: &InstanceId,
-) {
-    let abort_query = match serde_json::to_vec(&InstancesQuery::Abort {
+    graceful: Option<Duration>,
+    policy: &QueryPolicy,
+) -> Vec<(RuntimeId, anyhow::Error)> {
+    let abort_query = InstancesQuery::Abort {
         origin: Origin::Daemon,
         instance_id: instance_id.clone(),
-    }) {
-        Ok(query) => query,
-        Err(e) => {
-            tracing::error!(
-                "serde_json failed to serialize InstancesQuery::Abort: {:?}",
-                e
-            );
-            return;
-        }
+        graceful,
+    };
+    let policy = QueryPolicy {
+        timeout: policy.timeout + graceful.unwrap_or_default(),
+        ..*policy
     };
 
-    for runtime_id in runtimes {
-        let selector = selectors::selector_instances(runtime_id);
-
-        if let Err(e) = session.get(selector).payload(abort_query.clone()).await {
-            tracing::error!(
-                "Sending abort query to runtime < {} > failed with error: {:?}",
-                runtime_id,
-                e
-            );
-        }
-        tracing::trace!("Sent abort query to runtime < {} >", runtime_id);
-    }
+    query_runtimes(session, runtimes, &abort_query, &policy).await
 }
@@ -12,16 +12,71 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::sync::Arc;
+use std::{collections::HashMap, fmt::Display, sync::Arc};
 
 use anyhow::Context;
-use zenoh_flow_commons::{InstanceId, Result};
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
 use zenoh_flow_descriptors::FlattenedDataFlowDescriptor;
 use zenoh_flow_records::DataFlowRecord;
 use zenoh_flow_runtime::Runtime;
 
-use super::InstancesQuery;
-use crate::queries::selectors;
+use super::{delete, query_runtime, InstancesQuery};
+use crate::{daemon::QueriesConfiguration, queries::runtime::query_candidates};
+
+/// The outcome, for a single runtime, of the creation of a data flow instance.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum LoadOutcome {
+    /// The runtime loaded the nodes it manages.
+    Loaded,
+    /// The runtime failed to load the nodes it manages, or could not be contacted.
+    Failed,
+    /// The runtime loaded the nodes it manages but was then requested to delete the instance, another runtime having
+    /// failed.
+    RolledBack,
+    /// The runtime was not contacted, another runtime having failed before.
+    Skipped,
+}
+
+impl Display for LoadOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadOutcome::Loaded => write!(f, "Loaded"),
+            LoadOutcome::Failed => write!(f, "Failed"),
+            LoadOutcome::RolledBack => write!(f, "Rolled back"),
+            LoadOutcome::Skipped => write!(f, "Skipped"),
+        }
+    }
+}
+
+/// The [LoadOutcome] of a runtime involved in the creation of a data flow instance.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RuntimeLoad {
+    pub runtime_id: RuntimeId,
+    pub outcome: LoadOutcome,
+    /// The chain of errors, from the outermost to the root cause, if the runtime [Failed](LoadOutcome::Failed) or if
+    /// it failed to delete the instance when it was [RolledBack](LoadOutcome::RolledBack).
+    pub errors: Vec<String>,
+}
+
+/// The answer to a [Create] query waiting for the data flow instance to be loaded.
+///
+/// [Create]: InstancesQuery::Create
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreationReport {
+    pub instance_id: InstanceId,
+    /// The outcome of each runtime involved, in the order in which they were contacted.
+    pub runtimes: Vec<RuntimeLoad>,
+}
+
+impl CreationReport {
+    /// Returns `true` if all the runtimes involved loaded the data flow instance.
+    pub fn is_success(&self) -> bool {
+        self.runtimes
+            .iter()
+            .all(|runtime| runtime.outcome == LoadOutcome::Loaded)
+    }
+}
 
 /// Create a new instance of the data flow described by the provided (flattened) descriptor.
 ///
@@ -40,121 +95,298 @@ use crate::queries::selectors;
 ///
 /// If a Zenoh-Flow runtime fails to load the node(s) it is responsible for, all other involved runtime will *delete*
 /// that same instance.
-pub(crate) fn create_instance(
+pub(crate) async fn create_instance(
     runtime: Arc<Runtime>,
     data_flow: &FlattenedDataFlowDescriptor,
+    queries: QueriesConfiguration,
 ) -> Result<InstanceId> {
-    let record =
-        DataFlowRecord::try_new(data_flow, runtime.id()).context("Failed to create Record")?;
+    let record = try_create_record(&runtime, data_flow, &queries).await?;
     let instance_id = record.instance_id().clone();
 
     // Spawn a new task to handle the query to minimize the amount of time the Zenoh-Flow runtime is blocked.
     async_std::task::spawn(async move {
-        let instance_id = record.instance_id().clone();
-        let current_runtime_id = runtime.id().clone();
-        let involved_runtimes = record
+        load_instance(runtime, record, queries).await;
+    });
+
+    Ok(instance_id)
+}
+
+/// Create a new instance of the data flow described by the provided (flattened) descriptor and wait until all the
+/// involved Zenoh-Flow runtimes loaded it -- or until it was deleted everywhere, if one of them failed.
+///
+/// The [CreationReport] lists the outcome of each runtime. Note that a failure to load the instance is *not* an error:
+/// it is reported.
+///
+/// # Error
+///
+/// This function can return an error if the Zenoh-Flow runtime failed to create an instance based on the flattened
+/// descriptor.
+///
+/// # Consistency
+///
+/// See [create_instance].
+pub(crate) async fn create_instance_sync(
+    runtime: Arc<Runtime>,
+    data_flow: &FlattenedDataFlowDescriptor,
+    queries: QueriesConfiguration,
+) -> Result<CreationReport> {
+    let record = try_create_record(&runtime, data_flow, &queries).await?;
+
+    Ok(load_instance(runtime, record, queries).await)
+}
+
+/// Creates the record of the data flow, the nodes without a mapping being assigned to the `runtime`.
+///
+/// If some nodes have [placement](zenoh_flow_descriptors::PlacementDescriptor) constraints, they are first resolved
+/// against the Zenoh-Flow runtimes that are reachable.
+async fn try_create_record(
+    runtime: &Runtime,
+    data_flow: &FlattenedDataFlowDescriptor,
+    queries: &QueriesConfiguration,
+) -> Result<DataFlowRecord> {
+    if data_flow.placement.is_empty() {
+        return DataFlowRecord::try_new(data_flow, runtime.id()).context("Failed to create Record");
+    }
+
+    let candidates = query_candidates(runtime, &queries.load).await?;
+    let mut data_flow = data_flow.clone();
+    data_flow
+        .try_place(&candidates, runtime.id())
+        .context("Failed to place the nodes of the data flow")?;
+
+    DataFlowRecord::try_new(&data_flow, runtime.id()).context("Failed to create Record")
+}
+
+/// Loads the data flow instance described by the `record` on all the involved Zenoh-Flow runtimes -- rollback if
+/// needed.
+///
+/// We start with the current Zenoh-Flow runtime such that, if there is an error, we don't need to go through the
+/// network to rollback everything. If a runtime fails, the instance is deleted from the runtimes that loaded it -- and
+/// from the one that failed, as it may have loaded the instance after its timeout -- and the remaining runtimes are not
+/// contacted.
+async fn load_instance(
+    runtime: Arc<Runtime>,
+    record: DataFlowRecord,
+    queries: QueriesConfiguration,
+) -> CreationReport {
+    let instance_id = record.instance_id().clone();
+    let current_runtime_id = runtime.id().clone();
+
+    let mut involved_runtimes = Vec::with_capacity(record.mapping().len());
+    if record.mapping().contains_key(&current_runtime_id) {
+        involved_runtimes.push(current_runtime_id.clone());
+    }
+    involved_runtimes.extend(
+        record
             .mapping()
             .keys()
             .filter(|&runtime_id| runtime_id != &current_runtime_id)
-            .cloned()
-            .collect::<Vec<_>>();
+            .cloned(),
+    );
+
+    let mut report = CreationReport {
+        instance_id: instance_id.clone(),
+        runtimes: involved_runtimes
+            .into_iter()
+            .map(|runtime_id| RuntimeLoad {
+                runtime_id,
+                outcome: LoadOutcome::Skipped,
+                errors: Vec::default(),
+            })
+            .collect(),
+    };
+
+    let load_query = InstancesQuery::Load(Box::new(record.clone()));
+
+    for index in 0..report.runtimes.len() {
+        let runtime_id = &report.runtimes[index].runtime_id;
+        let loaded = if runtime_id == &current_runtime_id {
+            runtime.try_load_data_flow(record.clone()).await
+        } else {
+            query_runtime(runtime.session(), runtime_id, &load_query, &queries.load).await
+        }
+        .with_context(|| {
+            format!("Runtime < {runtime_id} > failed to load data flow instance < {instance_id} >")
+        });
 
-        // We start with the current Zenoh-Flow runtime such that, if there is an error, we don't need to go through
-        // the network to rollback everything.
-        if record.mapping().contains_key(&current_runtime_id) {
-            if let Err(e) = runtime.try_load_data_flow(record.clone()).await {
-                tracing::error!("Failed to load data flow < {} >: {:?}", instance_id, e);
-                return;
+        match loaded {
+            Ok(()) => report.runtimes[index].outcome = LoadOutcome::Loaded,
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                report.runtimes[index].outcome = LoadOutcome::Failed;
+                report.runtimes[index].errors = e.chain().map(|err| err.to_string()).collect();
+                let (loaded, failed) = report.runtimes.split_at_mut(index);
+                rollback(
+                    &runtime,
+                    loaded,
+                    &failed[0].runtime_id,
+                    &instance_id,
+                    &queries,
+                )
+                .await;
+                break;
             }
         }
+    }
+
+    report
+}
+
+/// Deletes the data flow instance from the `loaded` runtimes, marking them as [RolledBack](LoadOutcome::RolledBack),
+/// and from the `failed` runtime, as it may have loaded the instance after its timeout.
+///
+/// The errors of the `loaded` runtimes that failed to delete the instance, or that did not acknowledge it, are
+/// reported. The `failed` runtime most likely did not load the instance: its errors are only logged.
+async fn rollback(
+    runtime: &Runtime,
+    loaded: &mut [RuntimeLoad],
+    failed: &RuntimeId,
+    instance_id: &InstanceId,
+    queries: &QueriesConfiguration,
+) {
+    let mut failures = delete::query_delete(
+        runtime.session(),
+        loaded
+            .iter()
+            .map(|load| &load.runtime_id)
+            .chain(std::iter::once(failed))
+            .filter(|&runtime_id| runtime_id != runtime.id()),
+        instance_id,
+        &queries.delete,
+    )
+    .await;
 
-        let mut contacted_runtimes = Vec::with_capacity(involved_runtimes.len());
-        let load_query = InstancesQuery::Load(Box::new(record.clone()));
-
-        // -------------------------------------------------------------------------------------------------------------
-        // NOTE: If at any step of the creation of the data flow something fails, we have to rollback: we have to
-        // contact each remote Zenoh-Flow runtime and request it to delete the instance.
-        //
-        // The following macro let's us not repeat this rollback code.
-        //
-        // Defining the macro inside the function allows "capturing the context" and reduces the amount of parameters we
-        // have to pass to a strict minimum.
-        macro_rules! rollback_if_err {
-            (
-                $faillible: expr,
-                $message: expr,
-                $( $item: expr ),*
-            ) => {
-                match $faillible {
-                    Ok(result) => result,
-                    Err(e) => {
-                        let error_message = format!($message, $( $item ),*);
-                        tracing::error!(r#"{}
-Caused by:
-{:?}"#,
-                            error_message, e
-                        );
-                        super::delete::query_delete(&runtime.session(),
-                            contacted_runtimes.iter(),
-                            &instance_id
-                        ).await;
-
-                        if record.mapping().contains_key(&current_runtime_id) {
-                            if let Err(e) = runtime.try_delete_instance(&instance_id).await {
-                                tracing::error!(
-                                    "Failed to delete instance < {} > while cleaning up after failed creation
-Caused by:
-{:?}",
-                                    &instance_id,
-                                    e
-                                );
-                            }
-                        }
-
-                        return;
-                    }
-                }
-            };
+    if failed == runtime.id() || loaded.iter().any(|load| &load.runtime_id == runtime.id()) {
+        if let Err(e) = runtime.try_delete_instance(instance_id).await {
+            failures.push((runtime.id().clone(), e));
         }
-        // -------------------------------------------------------------------------------------------------------------
-
-        let payload = rollback_if_err!(
-            serde_json::to_vec(&load_query),
-            r#"`serde_json` failed to serialize the query:
-Query:
-{:?},"#,
-            load_query
+    }
+
+    let mut failures = failures.into_iter().collect::<HashMap<_, _>>();
+    if let Some(e) = failures.remove(failed) {
+        tracing::debug!(
+            "Runtime < {} > did not delete instance < {} > while cleaning up after failed creation: {:?}",
+            failed,
+            instance_id,
+            e
         );
+    }
+    for load in loaded.iter_mut() {
+        load.outcome = LoadOutcome::RolledBack;
+        if let Some(e) = failures.remove(&load.runtime_id) {
+            let e = e.context(format!(
+                "Runtime < {} > failed to delete instance < {} > while cleaning up after failed creation",
+                load.runtime_id, instance_id
+            ));
+            tracing::error!("{:?}", e);
+            load.errors = e.chain().map(|err| err.to_string()).collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
 
-        for runtime_id in involved_runtimes {
-            let selector = selectors::selector_instances(&runtime_id);
-
-            let receiver_reply = rollback_if_err!(
-                runtime
-                    .session()
-                    .get(&selector)
-                    .payload(payload.clone())
-                    .await,
-                r#"Zenoh query on < {} > failed"#,
-                selector
-            );
-
-            let reply = rollback_if_err!(
-                receiver_reply.recv_async().await,
-                "Failed to receive acknowledgment from runtime < {} >",
-                &runtime_id
-            );
-
-            rollback_if_err!(
-                reply.result(),
-                "Runtime < {} > failed to load data flow instance < {} >",
-                &runtime_id,
-                &instance_id
-            );
-
-            contacted_runtimes.push(runtime_id);
+    use super::*;
+    use crate::{
+        daemon::QueryPolicy,
+        queries::selectors,
+        test_utils::{self, TestDataFlow},
+    };
+
+    fn queries() -> QueriesConfiguration {
+        let policy = QueryPolicy {
+            timeout: Duration::from_millis(200),
+            retries: 0,
+            backoff: Duration::from_millis(10),
+        };
+
+        QueriesConfiguration {
+            load: policy,
+            start: policy,
+            abort: policy,
+            delete: policy,
         }
-    });
+    }
 
-    Ok(instance_id)
+    #[async_std::test]
+    async fn test_create_instance_sync() {
+        let runtime = Arc::new(
+            Runtime::builder("test-create")
+                .build()
+                .await
+                .expect("Failed to build the Zenoh-Flow runtime"),
+        );
+
+        let report = create_instance_sync(
+            runtime.clone(),
+            &TestDataFlow::new(None).descriptor,
+            queries(),
+        )
+        .await
+        .expect("Failed to create the instance");
+
+        assert!(report.is_success());
+        assert_eq!(1, report.runtimes.len());
+        assert_eq!(runtime.id(), &report.runtimes[0].runtime_id);
+        assert!(report.runtimes[0].errors.is_empty());
+        assert!(runtime.try_get_record(&report.instance_id).await.is_ok());
+
+        test_utils::delete(&runtime, &report.instance_id).await;
+    }
+
+    #[async_std::test]
+    async fn test_create_instance_sync_failure() {
+        let runtime = Arc::new(
+            Runtime::builder("test-create-failure")
+                .build()
+                .await
+                .expect("Failed to build the Zenoh-Flow runtime"),
+        );
+        // No Daemon answers on behalf of this runtime: it cannot load the Sink. The queries it receives are kept.
+        let unreachable = RuntimeId::rand();
+        let queryable = runtime
+            .session()
+            .declare_queryable(selectors::selector_instances(&unreachable))
+            .await
+            .unwrap();
+
+        let report = create_instance_sync(
+            runtime.clone(),
+            &TestDataFlow::new(Some(&unreachable)).descriptor,
+            queries(),
+        )
+        .await
+        .expect("A failure to load the instance should be reported, not returned");
+
+        assert!(!report.is_success());
+        assert_eq!(2, report.runtimes.len());
+
+        // The current runtime is contacted first and then rolled back.
+        let current = &report.runtimes[0];
+        assert_eq!(runtime.id(), &current.runtime_id);
+        assert_eq!(LoadOutcome::RolledBack, current.outcome);
+        assert!(current.errors.is_empty());
+
+        let failed = &report.runtimes[1];
+        assert_eq!(unreachable, failed.runtime_id);
+        assert_eq!(LoadOutcome::Failed, failed.outcome);
+        assert!(failed.errors[0].contains(&unreachable.to_string()));
+
+        assert!(runtime.try_get_record(&report.instance_id).await.is_err());
+
+        // The failed runtime is also requested to delete the instance, as it may have loaded it after its timeout.
+        let queries = queryable
+            .drain()
+            .filter_map(|query| {
+                serde_json::from_slice::<InstancesQuery>(&query.payload()?.to_bytes()).ok()
+            })
+            .collect::<Vec<_>>();
+        assert!(matches!(queries[0], InstancesQuery::Load(_)));
+        assert!(matches!(
+            &queries[1],
+            InstancesQuery::Delete { instance_id, .. } if *instance_id == report.instance_id
+        ));
+    }
 }
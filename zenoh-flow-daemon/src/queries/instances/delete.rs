@@ -12,76 +12,73 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::sync::Arc;
-
-use async_std::task::JoinHandle;
 use zenoh::Session;
-use zenoh_flow_commons::{InstanceId, RuntimeId};
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
 use zenoh_flow_runtime::{DataFlowErr, Runtime};
 
-use super::{InstancesQuery, Origin};
-use crate::queries::selectors;
+use super::{ensure_acknowledged, query_runtimes, InstancesQuery, Origin};
+use crate::daemon::QueryPolicy;
 
-/// Query all the runtimes to delete the provided data flow instance.
+/// Queries, concurrently, the `runtimes` to delete the provided data flow instance.
+///
+/// Returns the runtimes that failed to delete it or that did not acknowledge it.
 pub(crate) async fn query_delete(
     session: &Session,
     runtimes: impl Iterator<Item = &RuntimeId>,
     instance_id: &InstanceId,
-) {
-    let delete_query = serde_json::to_vec(&InstancesQuery::Delete {
+    policy: &QueryPolicy,
+) -> Vec<(RuntimeId, anyhow::Error)> {
+    let delete_query = InstancesQuery::Delete {
         origin: Origin::Daemon,
         instance_id: instance_id.clone(),
-    })
-    .expect("serde_json failed to serialize InstancesQuery::Delete");
+    };
 
-    for runtime_id in runtimes {
-        let selector = selectors::selector_instances(runtime_id);
-
-        // NOTE: No need to process the request, as, even if the query failed, this is not something we want to recover
-        // from.
-        if let Err(e) = session.get(selector).payload(delete_query.clone()).await {
-            tracing::error!(
-                "Sending delete query to runtime < {} > failed with error: {:?}",
-                runtime_id,
-                e
-            );
-        }
-        tracing::trace!("Sent delete query to runtime < {} >", runtime_id);
-    }
+    query_runtimes(session, runtimes, &delete_query, policy).await
 }
 
 /// Deletes the data flow instance.
 ///
 /// If the query comes from a [Client](Origin::Client) then this daemon will query all the runtimes involved in this
 /// instance to make them also delete the data flow instance.
-pub(crate) fn delete_instance(
-    runtime: Arc<Runtime>,
+///
+/// # Consistency
+///
+/// Deleting a data flow instance cannot be rolled back: the instance is deleted from all the runtimes that could be
+/// reached. The runtimes that failed to delete it, or that did not acknowledge it, are listed in the error.
+pub(crate) async fn delete_instance(
+    runtime: &Runtime,
     origin: Origin,
-    instance_id: InstanceId,
-) -> JoinHandle<()> {
-    async_std::task::spawn(async move {
-        if matches!(origin, Origin::Client) {
-            match runtime.try_get_record(&instance_id).await {
-                Ok(record) => {
-                    query_delete(
-                        runtime.session(),
-                        record
-                            .mapping()
-                            .keys()
-                            .filter(|&runtime_id| runtime_id != runtime.id()),
-                        &instance_id,
-                    )
-                    .await
-                }
-                Err(DataFlowErr::NotFound) => return,
-                // NOTE: If the data flow is in a failed state we still want to process the delete request but only on
-                // this runtime.
-                Err(DataFlowErr::FailedState) => {}
+    instance_id: &InstanceId,
+    policy: &QueryPolicy,
+) -> Result<()> {
+    let mut failures = Vec::default();
+
+    if matches!(origin, Origin::Client) {
+        match runtime.try_get_record(instance_id).await {
+            Ok(record) => {
+                failures = query_delete(
+                    runtime.session(),
+                    record
+                        .mapping()
+                        .keys()
+                        .filter(|&runtime_id| runtime_id != runtime.id()),
+                    instance_id,
+                    policy,
+                )
+                .await
             }
+            Err(DataFlowErr::NotFound) => {
+                anyhow::bail!("Found no data flow with instance id < {} >", instance_id)
+            }
+            // NOTE: If the data flow is in a failed state we still want to process the delete request but only on
+            // this runtime.
+            Err(DataFlowErr::FailedState) => {}
         }
+    }
 
-        if let Err(e) = runtime.try_delete_instance(&instance_id).await {
-            tracing::error!("Failed to delete instance < {} >: {:?}", instance_id, e);
-        }
-    })
+    if let Err(e) = runtime.try_delete_instance(instance_id).await {
+        failures.push((runtime.id().clone(), e));
+    }
+
+    ensure_acknowledged("delete", instance_id, &failures)
 }
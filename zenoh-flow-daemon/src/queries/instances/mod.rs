@@ -15,17 +15,28 @@
 pub(crate) mod abort;
 pub(crate) mod create;
 pub(crate) mod delete;
+pub(crate) mod reconfigure;
 pub(crate) mod start;
+pub(crate) mod tap;
+pub(crate) mod update;
 
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, path::PathBuf, sync::Arc, time::Duration};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use serde::{Deserialize, Serialize};
-use zenoh::query::Query;
-use zenoh_flow_commons::{InstanceId, Result};
-use zenoh_flow_descriptors::FlattenedDataFlowDescriptor;
+use uuid::Uuid;
+use zenoh::{query::Query, Session};
+use zenoh_flow_commons::{Configuration, InstanceId, NodeId, Result, RuntimeId};
+use zenoh_flow_descriptors::{
+    FlattenedDataFlowDescriptor, FlattenedDataFlowUpdate, InputDescriptor, OutputDescriptor,
+};
 use zenoh_flow_records::DataFlowRecord;
-use zenoh_flow_runtime::Runtime;
+use zenoh_flow_runtime::{Recording, ReplayMode, Runtime};
+
+use crate::{
+    daemon::{QueriesConfiguration, QueryPolicy},
+    queries::selectors,
+};
 
 /// Where the query originated.
 ///
@@ -53,11 +64,130 @@ async fn reply<T: Serialize + Debug>(query: Query, data: Result<T>) -> Result<()
             Ok(payload) => query.reply(query.key_expr(), payload).await,
             Err(e) => query.reply_err(e.to_string()).await,
         },
-        Err(e) => query.reply_err(e.to_string()).await,
+        // NOTE: The `Debug` representation includes the chain of errors, such that the cause is not lost.
+        Err(e) => query.reply_err(format!("{e:?}")).await,
     }
     .map_err(|e| anyhow!("Failed to send reply: {e:?}"))
 }
 
+/// Sends the `instances_query` to the runtime `runtime_id`, following the `policy`, returning an error if it failed to
+/// process it or if it did not answer.
+///
+/// The query is sent again, after a backoff, as long as the runtime does not answer before the timeout and the retries
+/// of the `policy` are not exhausted.
+pub(crate) async fn query_runtime(
+    session: &Session,
+    runtime_id: &RuntimeId,
+    instances_query: &InstancesQuery,
+    policy: &QueryPolicy,
+) -> Result<()> {
+    query_runtime_reply(session, runtime_id, instances_query, policy)
+        .await
+        .map(|_| ())
+}
+
+/// Sends the `instances_query` to the runtime `runtime_id`, following the `policy`, returning the payload of its
+/// reply.
+///
+/// See [query_runtime] for the errors and the retries.
+pub(crate) async fn query_runtime_reply(
+    session: &Session,
+    runtime_id: &RuntimeId,
+    instances_query: &InstancesQuery,
+    policy: &QueryPolicy,
+) -> Result<Vec<u8>> {
+    let payload = serde_json::to_vec(instances_query)
+        .map_err(|e| anyhow!("serde_json failed to serialize the query: {e:?}"))?;
+
+    let selector = selectors::selector_instances(runtime_id);
+    let mut attempts = 0;
+    loop {
+        let replies = session
+            .get(&selector)
+            .payload(payload.clone())
+            .timeout(policy.timeout)
+            .await
+            .map_err(|e| anyhow!("Query on runtime < {runtime_id} > failed: {e:?}"))?;
+
+        let reply = match replies.recv_async().await {
+            Ok(reply) => reply,
+            Err(e) => {
+                attempts += 1;
+                if attempts > policy.retries {
+                    bail!(
+                        "Runtime < {runtime_id} > did not reply after {attempts} attempt(s): {e:?}"
+                    );
+                }
+
+                let backoff = policy.backoff(attempts);
+                tracing::debug!(
+                    "Runtime < {} > did not reply, retrying in {:?}",
+                    runtime_id,
+                    backoff
+                );
+                async_std::task::sleep(backoff).await;
+                continue;
+            }
+        };
+
+        return match reply.result() {
+            Ok(sample) => Ok(sample.payload().to_bytes().to_vec()),
+            Err(err) => bail!(
+                "{}",
+                err.payload()
+                    .try_to_string()
+                    .unwrap_or_else(|e| e.to_string().into())
+            ),
+        };
+    }
+}
+
+/// Sends, concurrently, the `instances_query` to all the `runtimes`, following the `policy`.
+///
+/// Returns the runtimes that failed to process it or that did not answer, alongside the reason.
+pub(crate) async fn query_runtimes(
+    session: &Session,
+    runtimes: impl Iterator<Item = &RuntimeId>,
+    instances_query: &InstancesQuery,
+    policy: &QueryPolicy,
+) -> Vec<(RuntimeId, anyhow::Error)> {
+    let queries = runtimes.map(|runtime_id| async move {
+        query_runtime(session, runtime_id, instances_query, policy)
+            .await
+            .err()
+            .map(|e| (runtime_id.clone(), e))
+    });
+
+    futures::future::join_all(queries)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Returns an error listing the runtimes that failed to perform the `operation`, if there are any.
+pub(crate) fn ensure_acknowledged(
+    operation: &str,
+    instance_id: &InstanceId,
+    failures: &[(RuntimeId, anyhow::Error)],
+) -> Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    bail!(
+        "Failed to {} data flow < {} > on {} runtime(s):\n{}",
+        operation,
+        instance_id,
+        failures.len(),
+        failures
+            .iter()
+            .map(|(runtime_id, e)| format!("- < {runtime_id} >: {e:?}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
 /// The available interactions to manipulate a data flow instance.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum InstancesQuery {
@@ -65,7 +195,15 @@ pub enum InstancesQuery {
     ///
     /// This query returns the unique identifier, [InstanceId], associated with the instance as soon as a
     /// [DataFlowRecord] is generated but *before* the instance is loaded (i.e. ready to be started).
-    Create(Box<FlattenedDataFlowDescriptor>),
+    ///
+    /// If `wait` is set, this query is only answered once all the runtimes involved loaded the instance -- or once it
+    /// was deleted everywhere, if one of them failed. It returns a [CreationReport](create::CreationReport) listing the
+    /// outcome of each runtime.
+    Create {
+        data_flow: Box<FlattenedDataFlowDescriptor>,
+        #[serde(default)]
+        wait: bool,
+    },
     /// Requests the runtime to load the provided [DataFlowRecord].
     Load(Box<DataFlowRecord>),
     /// Requests the runtime to start the data flow instance identified by the provided [InstanceId].
@@ -80,9 +218,15 @@ pub enum InstancesQuery {
     ///
     /// If the [Origin] of the query is [Client](Origin::Client) then the Daemon will query all the other runtimes
     /// involved in the execution of the data flow to also abort it.
+    ///
+    /// If a `graceful` timeout is provided, the data flow instance is gracefully stopped: the Sources are stopped first
+    /// and the messages in flight are processed before the other nodes are stopped. The nodes still processing
+    /// messages after the timeout are aborted.
     Abort {
         origin: Origin,
         instance_id: InstanceId,
+        #[serde(default)]
+        graceful: Option<Duration>,
     },
     /// Requests the runtime to delete the instance.
     Delete {
@@ -93,16 +237,116 @@ pub enum InstancesQuery {
     ///
     /// A Daemon that answers this query will only provide its *local view* of the data flow instance.
     Status(InstanceId),
+    /// Requests the metrics of the nodes of the data flow instance identified by the provided [InstanceId]: their
+    /// throughput, the latency of their iterations, their errors and the messages and bytes exchanged on their ports.
+    ///
+    /// A Daemon that answers this query will only provide the metrics of the nodes it manages.
+    Metrics(InstanceId),
+    /// Requests the runtime to record, in the file at `path`, the messages sent on the `outputs` of the data flow
+    /// instance identified by the provided [InstanceId].
+    ///
+    /// A Daemon that answers this query only records the Outputs of the nodes it manages, in a file on its own
+    /// filesystem, and replies with the number of Outputs it records.
+    Record {
+        instance_id: InstanceId,
+        path: PathBuf,
+        outputs: Vec<OutputDescriptor>,
+    },
+    /// Requests the runtime to stop the recording of the data flow instance identified by the provided [InstanceId].
+    ///
+    /// A Daemon that answers this query replies with the
+    /// [summary](zenoh_flow_runtime::RecordingSummary) of its recording, if it was recording.
+    StopRecording(InstanceId),
+    /// Requests the runtime to start the data flow instance identified by the provided [InstanceId], replacing its
+    /// Sources by the recording stored in the file at `path`.
+    ///
+    /// A Daemon that answers this query replays the messages, found in the file on its own filesystem, that were
+    /// recorded on the Outputs of the Sources it manages. If there is no such file, its Sources are simply not
+    /// started. It replies with the number of messages it replays.
+    Replay {
+        instance_id: InstanceId,
+        path: PathBuf,
+        #[serde(default)]
+        mode: ReplayMode,
+    },
+    /// Requests the runtime to tap the `output` of the data flow instance identified by the provided [InstanceId]: a
+    /// copy of every message sent on it is published on the key expression generated by
+    /// [selector_tap](crate::queries::selector_tap()) with the `tap_id`.
+    ///
+    /// The client must declare a liveliness token on that key expression *before* sending this query: the tap is
+    /// removed as soon as the token disappears (for instance, when the client disconnects).
+    ///
+    /// A Daemon that answers this query replies `true` if it manages the node of the `output`, and thus publishes its
+    /// messages, `false` otherwise.
+    Tap {
+        instance_id: InstanceId,
+        output: OutputDescriptor,
+        tap_id: Uuid,
+    },
+    /// Requests the runtime to reconfigure the node `node_id` of the data flow instance identified by the provided
+    /// [InstanceId], merging the `patch` into its configuration.
+    ///
+    /// If the [Origin] of the query is [Client](Origin::Client) then the Daemon first queries the runtime managing the
+    /// node and, if the node accepted its new configuration, the other runtimes involved in the execution of the data
+    /// flow, such that all the records of the instance are updated. It replies with the updated configuration.
+    Reconfigure {
+        origin: Origin,
+        instance_id: InstanceId,
+        node_id: NodeId,
+        patch: Configuration,
+    },
+    /// Requests the runtime to inject, into the `input` of the data flow instance identified by the provided
+    /// [InstanceId], a message carrying the `payload`.
+    ///
+    /// The `input` must be declared injectable in the descriptor of its node. A Daemon that answers this query replies
+    /// `true` if it manages the node of the `input`, and thus injected the message, `false` otherwise.
+    Inject {
+        instance_id: InstanceId,
+        input: InputDescriptor,
+        payload: Vec<u8>,
+    },
+    /// Requests the runtime to apply the [update](FlattenedDataFlowUpdate) to the graph of the data flow instance
+    /// identified by the provided [InstanceId], without tearing it down. The nodes added without a mapping are
+    /// assigned to the `default_runtime`, which should be the runtime receiving the query from the client.
+    ///
+    /// If the [Origin] of the query is [Client](Origin::Client) then the Daemon also queries the other runtimes
+    /// involved: the ones already involved update their part of the instance, the ones that become involved load it.
+    /// It replies with the updated [DataFlowRecord].
+    Update {
+        origin: Origin,
+        instance_id: InstanceId,
+        update: Box<FlattenedDataFlowUpdate>,
+        default_runtime: RuntimeId,
+    },
     /// Requests the list of data flow instances currently running on the runtime.
     List,
 }
 
 impl InstancesQuery {
     #[tracing::instrument(level = "trace", skip(self))]
-    pub(crate) async fn process(self, query: Query, runtime: Arc<Runtime>) {
+    pub(crate) async fn process(
+        self,
+        query: Query,
+        runtime: Arc<Runtime>,
+        queries: QueriesConfiguration,
+    ) {
         match self {
-            InstancesQuery::Create(data_flow) => {
-                if let Err(e) = reply(query, create::create_instance(runtime, &data_flow)).await {
+            InstancesQuery::Create { data_flow, wait } => {
+                let replied = if wait {
+                    reply(
+                        query,
+                        create::create_instance_sync(runtime, &data_flow, queries).await,
+                    )
+                    .await
+                } else {
+                    reply(
+                        query,
+                        create::create_instance(runtime, &data_flow, queries).await,
+                    )
+                    .await
+                };
+
+                if let Err(e) = replied {
                     tracing::error!("Failed to reply to 'create' query: {:?}", e);
                 }
             }
@@ -118,20 +362,27 @@ impl InstancesQuery {
             InstancesQuery::Start {
                 origin,
                 instance_id,
-            } => start::start(runtime, query, origin, instance_id),
+            } => start::start(runtime, query, origin, instance_id, queries),
 
             InstancesQuery::Abort {
                 origin,
                 instance_id,
-            } => {
-                abort::abort(runtime, origin, instance_id);
-            }
+                graceful,
+            } => abort::abort(runtime, query, origin, instance_id, graceful, queries.abort),
 
             InstancesQuery::Delete {
                 origin,
                 instance_id,
             } => {
-                delete::delete_instance(runtime, origin, instance_id);
+                let deleted =
+                    delete::delete_instance(&runtime, origin, &instance_id, &queries.delete).await;
+                if let Err(e) = &deleted {
+                    tracing::error!("{:?}", e);
+                }
+
+                if let Err(e) = reply(query, deleted).await {
+                    tracing::error!("Failed to reply to 'Delete' query: {:?}", e);
+                }
             }
 
             InstancesQuery::Status(instance_id) => {
@@ -150,6 +401,116 @@ impl InstancesQuery {
                 }
             }
 
+            InstancesQuery::Metrics(instance_id) => {
+                if let Err(e) = reply(
+                    query,
+                    runtime
+                        .get_instance_metrics(&instance_id)
+                        .await
+                        .ok_or_else(|| {
+                            anyhow!("Found no data flow with instance id < {} >", instance_id)
+                        }),
+                )
+                .await
+                {
+                    tracing::error!("Failed to reply to 'Metrics' query: {:?}", e);
+                }
+            }
+
+            InstancesQuery::Record {
+                instance_id,
+                path,
+                outputs,
+            } => {
+                if let Err(e) = reply(
+                    query,
+                    runtime
+                        .try_start_recording(&instance_id, &outputs, path)
+                        .await,
+                )
+                .await
+                {
+                    tracing::error!("Failed to reply to 'Record' query: {:?}", e);
+                }
+            }
+
+            InstancesQuery::StopRecording(instance_id) => {
+                if let Err(e) = reply(query, runtime.try_stop_recording(&instance_id).await).await {
+                    tracing::error!("Failed to reply to 'StopRecording' query: {:?}", e);
+                }
+            }
+
+            InstancesQuery::Replay {
+                instance_id,
+                path,
+                mode,
+            } => {
+                let recording = if async_std::path::Path::new(&path).exists().await {
+                    Recording::try_load(&path).await
+                } else {
+                    Ok(Recording::empty(instance_id.clone()))
+                };
+
+                let replayed = match recording {
+                    Ok(recording) => {
+                        runtime
+                            .try_replay_instance(&instance_id, recording, mode)
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+
+                if let Err(e) = reply(query, replayed).await {
+                    tracing::error!("Failed to reply to 'Replay' query: {:?}", e);
+                }
+            }
+
+            InstancesQuery::Tap {
+                instance_id,
+                output,
+                tap_id,
+            } => tap::tap(runtime, query, instance_id, output, tap_id),
+
+            InstancesQuery::Reconfigure {
+                origin,
+                instance_id,
+                node_id,
+                patch,
+            } => reconfigure::reconfigure(runtime, query, origin, instance_id, node_id, patch),
+
+            InstancesQuery::Inject {
+                instance_id,
+                input,
+                payload,
+            } => {
+                // NOTE: This query is sent to all the Daemons, the ones not involved in the data flow instance simply
+                // reply that they did not inject the message.
+                let injected = if runtime.get_instance_status(&instance_id).await.is_none() {
+                    Ok(false)
+                } else {
+                    runtime.try_inject(&instance_id, &input, payload).await
+                };
+
+                if let Err(e) = reply(query, injected).await {
+                    tracing::error!("Failed to reply to 'Inject' query: {:?}", e);
+                }
+            }
+
+            InstancesQuery::Update {
+                origin,
+                instance_id,
+                update,
+                default_runtime,
+            } => update::update(
+                runtime,
+                query,
+                origin,
+                instance_id,
+                *update,
+                default_runtime,
+                queries,
+            ),
+
             InstancesQuery::List => {
                 if let Err(e) = reply(query, Ok(runtime.instances_state().await)).await {
                     tracing::error!("Failed to reply to 'List' query: {:?}", e);
@@ -0,0 +1,143 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use zenoh::{query::Query, Session};
+use zenoh_flow_commons::{Configuration, InstanceId, NodeId, Result, RuntimeId};
+use zenoh_flow_runtime::Runtime;
+
+use super::{query_runtime_reply, reply, InstancesQuery, Origin};
+use crate::daemon::QueryPolicy;
+
+/// Reconfigures the node `node_id` of the data flow instance identified by `instance_id`, replying with its updated
+/// configuration.
+///
+/// If this query originates from a [Client](Origin::Client) then this function first queries the runtime managing the
+/// node and, once the node accepted its new configuration, the other runtimes involved.
+///
+/// # Consistency
+///
+/// If the node refuses its new configuration, no runtime updates its record. If a runtime that does not manage the node
+/// fails to update its record, the error is logged but the reconfiguration is not rolled back: the node already runs
+/// with its new configuration.
+pub(crate) fn reconfigure(
+    runtime: Arc<Runtime>,
+    query: Query,
+    origin: Origin,
+    instance_id: InstanceId,
+    node_id: NodeId,
+    patch: Configuration,
+) {
+    async_std::task::spawn(async move {
+        let configuration = match origin {
+            Origin::Client => reconfigure_all(&runtime, &instance_id, &node_id, patch).await,
+            Origin::Daemon => {
+                runtime
+                    .try_reconfigure_node(&instance_id, &node_id, patch)
+                    .await
+            }
+        };
+
+        if let Err(e) = reply(query, configuration).await {
+            tracing::error!("Failed to reply to 'Reconfigure' query: {:?}", e);
+        }
+    });
+}
+
+/// Reconfigures the node on the runtime that manages it and then updates the records of all the other runtimes
+/// involved.
+async fn reconfigure_all(
+    runtime: &Runtime,
+    instance_id: &InstanceId,
+    node_id: &NodeId,
+    patch: Configuration,
+) -> Result<Configuration> {
+    let record = runtime.try_get_record(instance_id).await?;
+    let owner = record
+        .mapping()
+        .iter()
+        .find(|(_, nodes)| nodes.contains(node_id))
+        .map(|(runtime_id, _)| runtime_id)
+        .ok_or_else(|| {
+            anyhow!(
+                "Found no node < {} > in data flow < {} >",
+                node_id,
+                instance_id
+            )
+        })?;
+
+    let configuration = if owner == runtime.id() {
+        runtime
+            .try_reconfigure_node(instance_id, node_id, patch.clone())
+            .await?
+    } else {
+        let configuration =
+            query_reconfigure(runtime.session(), owner, instance_id, node_id, &patch).await?;
+        runtime
+            .try_reconfigure_node(instance_id, node_id, patch.clone())
+            .await?;
+        configuration
+    };
+
+    for runtime_id in record
+        .mapping()
+        .keys()
+        .filter(|&runtime_id| runtime_id != owner && runtime_id != runtime.id())
+    {
+        if let Err(e) =
+            query_reconfigure(runtime.session(), runtime_id, instance_id, node_id, &patch).await
+        {
+            tracing::error!(
+                "Runtime < {} > failed to update the configuration of < {} >: {:?}",
+                runtime_id,
+                node_id,
+                e
+            );
+        }
+    }
+
+    Ok(configuration)
+}
+
+/// Queries the runtime `runtime_id` to reconfigure the node `node_id`, returning the updated configuration.
+///
+/// The query follows the default [QueryPolicy].
+async fn query_reconfigure(
+    session: &Session,
+    runtime_id: &RuntimeId,
+    instance_id: &InstanceId,
+    node_id: &NodeId,
+    patch: &Configuration,
+) -> Result<Configuration> {
+    let reconfigure_query = InstancesQuery::Reconfigure {
+        origin: Origin::Daemon,
+        instance_id: instance_id.clone(),
+        node_id: node_id.clone(),
+        patch: patch.clone(),
+    };
+
+    let payload = query_runtime_reply(
+        session,
+        runtime_id,
+        &reconfigure_query,
+        &QueryPolicy::default(),
+    )
+    .await
+    .with_context(|| format!("Runtime < {runtime_id} > failed to reconfigure < {node_id} >"))?;
+
+    serde_json::from_slice::<Configuration>(&payload)
+        .map_err(|e| anyhow!("Failed to parse reply of runtime < {runtime_id} >: {e:?}"))
+}
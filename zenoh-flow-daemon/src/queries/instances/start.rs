@@ -14,15 +14,15 @@
 
 use std::sync::Arc;
 
-use anyhow::bail;
-use zenoh::{bytes::ZBytes, query::Query, Session};
+use anyhow::Context;
+use zenoh::{query::Query, Session};
 use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
 use zenoh_flow_runtime::Runtime;
 
-use super::{abort, InstancesQuery, Origin};
-use crate::queries::selectors;
+use super::{abort, ensure_acknowledged, query_runtime, reply, InstancesQuery, Origin};
+use crate::daemon::QueriesConfiguration;
 
-/// Starts the data flow instance identified by `instance_id`.
+/// Starts the data flow instance identified by `instance_id`, replying once it is started.
 ///
 /// If this query originates from a [Client](Origin::Client) then this function also queries the other runtimes to start
 /// the same data flow instance.
@@ -30,153 +30,133 @@ use crate::queries::selectors;
 /// # Consistency
 ///
 /// If a Zenoh-Flow runtime fails to start the data flow instance, all other involved runtimes will abort that instance.
-pub(crate) fn start(runtime: Arc<Runtime>, query: Query, origin: Origin, instance_id: InstanceId) {
+pub(crate) fn start(
+    runtime: Arc<Runtime>,
+    query: Query,
+    origin: Origin,
+    instance_id: InstanceId,
+    queries: QueriesConfiguration,
+) {
     async_std::task::spawn(async move {
-        // -------------------------------------------------------------------------------------------------------------
-        // NOTE: If, at any step, starting the data flow instance fails, we have to stop and inform the client / daemon
-        // that initiated this process that it failed.
-        macro_rules! return_if_err {
-            (
-                $faillible: expr,
-                $message: expr,
-                $( $item: expr ),*
-            ) => {
-                match $faillible {
-                    Ok(result) => result,
-                    Err(e) => {
-                        let message = format!($message, $( $item, )*);
-                        tracing::error!(r#"{}
-Caused by:
-{:?}"#,
-                            message,
-                            e
-                        );
-                        if let Err(e) = query.reply_err(message).await {
-                            tracing::error!(
-                                "Failed to reply (error) to query on < {} >: {:?}",
-                                query.key_expr(),
-                                e
-                            );
-                        }
-                        return;
-                    }
-                }
-            };
+        let started = start_instance(&runtime, origin, &instance_id, &queries).await;
+        match &started {
+            Ok(()) => tracing::trace!("Successfully started instance < {} >", instance_id),
+            Err(e) => tracing::error!("{:?}", e),
         }
-        // -------------------------------------------------------------------------------------------------------------
 
-        let record = return_if_err!(
-            runtime.try_get_record(&instance_id).await,
-            "Could not get record associated with < {} >",
-            instance_id
-        );
+        if let Err(e) = reply(query, started).await {
+            tracing::error!("Failed to reply to 'Start' query: {:?}", e);
+        }
+    });
+}
 
-        if matches!(origin, Origin::Client) {
-            return_if_err!(
-                query_start(
+async fn start_instance(
+    runtime: &Runtime,
+    origin: Origin,
+    instance_id: &InstanceId,
+    queries: &QueriesConfiguration,
+) -> Result<()> {
+    let record = runtime
+        .try_get_record(instance_id)
+        .await
+        .with_context(|| format!("Could not get record associated with < {instance_id} >"))?;
+    let other_runtimes = record
+        .mapping()
+        .keys()
+        .filter(|&runtime_id| runtime_id != runtime.id());
+
+    if matches!(origin, Origin::Client) {
+        query_start(
+            runtime.session(),
+            other_runtimes.clone(),
+            instance_id,
+            queries,
+        )
+        .await
+        .with_context(|| {
+            format!("Failed to query other runtime(s) to start instance < {instance_id} >")
+        })?;
+    }
+
+    if record.mapping().contains_key(runtime.id()) {
+        if let Err(e) = runtime.try_start_instance(instance_id).await {
+            let mut e = e.context(format!("Failed to start instance < {instance_id} >"));
+            if matches!(origin, Origin::Client) {
+                let failures = abort::query_abort(
                     runtime.session(),
-                    record
-                        .mapping()
-                        .keys()
-                        .filter(|&runtime_id| runtime_id != runtime.id()),
-                    &instance_id,
+                    other_runtimes,
+                    instance_id,
+                    None,
+                    &queries.abort,
                 )
-                .await,
-                "Failed to query other runtime(s) to start instance < {} >",
-                instance_id
-            );
-        }
+                .await;
+                if let Err(rollback) = ensure_acknowledged("abort", instance_id, &failures) {
+                    e = e.context(format!("Rollback failed: {rollback:?}"));
+                }
+            }
 
-        if record.mapping().contains_key(runtime.id()) {
-            return_if_err!(
-                runtime.try_start_instance(&instance_id).await,
-                "Failed to start instance < {} >",
-                instance_id
-            );
+            return Err(e);
         }
+    }
 
-        tracing::trace!("Successfully started instance < {} >", instance_id);
-        return_if_err!(
-            query.reply(query.key_expr(), ZBytes::default()).await,
-            "Failed to reply (success) to query on < {} >",
-            query.key_expr()
-        );
-    });
+    Ok(())
 }
 
 /// Queries the `runtimes` to start the data flow instance identified by `instance_id` — rollback if needed.
 ///
 /// This function is intended to only be called by the runtime that received the query from a [Client](Origin::Client).
 ///
-/// If a runtime fails to start the data flow instance, all the previously contacted runtimes are queried again to
-/// abort the instance.
+/// The runtimes are queried one after the other, following the `start` [policy](QueriesConfiguration::start).
 ///
 /// # Rollback
 ///
 /// This function will rollback the `start` request if a single Zenoh-Flow runtime that is involved fails to start the
-/// data flow instance.
+/// data flow instance, or does not acknowledge it.
 ///
-/// This rollback consists in querying all the previously contacted Zenoh-Flow runtime to abort the execution of the
-/// data flow instance.
-async fn query_start(
+/// This rollback consists in querying all the previously contacted Zenoh-Flow runtimes -- including the one that
+/// failed, as it may have started the instance after its timeout -- to abort the execution of the data flow instance.
+/// The runtimes that did not acknowledge the rollback are listed in the error.
+pub(crate) async fn query_start(
     session: &Session,
     runtimes: impl Iterator<Item = &RuntimeId>,
     instance_id: &InstanceId,
+    queries: &QueriesConfiguration,
 ) -> Result<()> {
-    let start_query = match serde_json::to_vec(&InstancesQuery::Start {
+    let start_query = InstancesQuery::Start {
         origin: Origin::Daemon,
         instance_id: instance_id.clone(),
-    }) {
-        Ok(query) => query,
-        Err(e) => {
-            bail!("serde_json failed to serialize `start query`: {:?}", e)
-        }
     };
 
     let mut contacted_runtimes = Vec::new();
 
-    // -----------------------------------------------------------------------------------------------------------------
-    // NOTE: We are using a labelled block to handle the errors and avoid code repetition.
-    //
-    // We should be sure that for each "error" case there is a `break 'happy_path;` line.
-    macro_rules! rollback_if_err {
-        (
-            $faillible: expr,
-            $message: expr,
-            $( $item: expr ),*
-        ) => {
-            match $faillible {
-                Ok(result) => result,
-                Err(e) => {
-                    let message = format!($message, $( $item, )*);
-                    abort::query_abort(session, contacted_runtimes.iter(), instance_id).await;
-                    anyhow::bail!(r#"{}
-Caused by:
-{:?}"#,
-                        message,
-                        e
-                    );
-                }
-            }
-        };
-    }
-    // -----------------------------------------------------------------------------------------------------------------
-
     for runtime_id in runtimes {
-        let selector = selectors::selector_instances(runtime_id);
+        contacted_runtimes.push(runtime_id.clone());
 
-        rollback_if_err!(
-            session.get(selector).payload(start_query.clone()).await,
-            "Query `start` on runtime < {} > failed",
-            runtime_id
-        );
+        if let Err(e) = query_runtime(session, runtime_id, &start_query, &queries.start).await {
+            let mut e = e.context(format!(
+                "Runtime < {runtime_id} > failed to start instance < {instance_id} >"
+            ));
+
+            let failures = abort::query_abort(
+                session,
+                contacted_runtimes.iter(),
+                instance_id,
+                None,
+                &queries.abort,
+            )
+            .await;
+            if let Err(rollback) = ensure_acknowledged("abort", instance_id, &failures) {
+                e = e.context(format!("Rollback failed: {rollback:?}"));
+            }
+
+            return Err(e);
+        }
 
         tracing::trace!(
-            "Queried runtime < {} > to start instance < {} >",
+            "Runtime < {} > started instance < {} >",
             runtime_id,
             instance_id
         );
-        contacted_runtimes.push(runtime_id.clone());
     }
 
     Ok(())
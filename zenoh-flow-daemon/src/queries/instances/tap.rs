@@ -0,0 +1,264 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use uhlc::Timestamp;
+use uuid::Uuid;
+use zenoh::{
+    key_expr::OwnedKeyExpr,
+    pubsub::{Publisher, Subscriber},
+    query::Query,
+    sample::SampleKind,
+};
+use zenoh_flow_commons::{InstanceId, Result};
+use zenoh_flow_descriptors::OutputDescriptor;
+use zenoh_flow_nodes::prelude::Message;
+use zenoh_flow_runtime::Runtime;
+
+use super::reply;
+use crate::queries::selectors;
+
+/// The number of messages a tap holds, waiting to be published. The messages sent while it is full are not published.
+const TAP_CAPACITY: usize = 256;
+
+/// The metadata of a message published by a tap.
+///
+/// It is sent, serialised in JSON, as the attachment of the Zenoh sample whose payload is the (serialised) payload of
+/// the message.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TapMetadata {
+    /// The timestamp of the message.
+    pub timestamp: Timestamp,
+}
+
+/// A tap publishing the messages of an Output until its client disappears.
+struct Tap {
+    key_expr: OwnedKeyExpr,
+    messages: flume::Receiver<Message>,
+    publisher: Publisher<'static>,
+    disconnected: flume::Receiver<()>,
+    _liveliness: Subscriber<()>,
+}
+
+pub(crate) fn tap(
+    runtime: Arc<Runtime>,
+    query: Query,
+    instance_id: InstanceId,
+    output: OutputDescriptor,
+    tap_id: Uuid,
+) {
+    async_std::task::spawn(async move {
+        let (tap, tapping) = match try_declare_tap(&runtime, &instance_id, &output, &tap_id).await {
+            Ok(tap) => {
+                let tapping = tap.is_some();
+                (tap, Ok(tapping))
+            }
+            Err(e) => (None, Err(e)),
+        };
+
+        if let Err(e) = reply(query, tapping).await {
+            tracing::error!("Failed to reply to 'Tap' query: {:?}", e);
+        }
+
+        if let Some(tap) = tap {
+            tracing::info!(
+                "Tapping < {} > of data flow < {} > on < {} >",
+                output,
+                instance_id,
+                tap.key_expr
+            );
+            tap.run().await;
+        }
+    });
+}
+
+/// Attaches a mirror to the `output` and declares the Zenoh entities of the tap, returning [None] if the node of the
+/// `output` is not managed by this runtime.
+async fn try_declare_tap(
+    runtime: &Runtime,
+    instance_id: &InstanceId,
+    output: &OutputDescriptor,
+    tap_id: &Uuid,
+) -> Result<Option<Tap>> {
+    let Some(messages) = runtime
+        .try_tap_output(instance_id, output, TAP_CAPACITY)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let key_expr = selectors::selector_tap(instance_id, tap_id);
+    let session = runtime.session();
+
+    // NOTE: The liveliness subscriber is declared before checking that the token of the client exists, such that its
+    // disappearance cannot be missed in between.
+    let (disconnect, disconnected) = flume::bounded(1);
+    let liveliness = session
+        .liveliness()
+        .declare_subscriber(key_expr.clone())
+        .callback(move |sample| {
+            if sample.kind() == SampleKind::Delete {
+                let _ = disconnect.try_send(());
+            }
+        })
+        .await
+        .map_err(|e| anyhow!("Failed to declare liveliness subscriber on < {key_expr} >: {e:?}"))?;
+
+    let client = session
+        .liveliness()
+        .get(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to query liveliness tokens on < {key_expr} >: {e:?}"))?;
+    if !matches!(client.recv_async().await, Ok(reply) if reply.result().is_ok()) {
+        bail!("Found no liveliness token on < {key_expr} >, the client of the tap is gone");
+    }
+
+    let publisher = session
+        .declare_publisher(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to declare publisher on < {key_expr} >: {e:?}"))?;
+
+    Ok(Some(Tap {
+        key_expr,
+        messages,
+        publisher,
+        disconnected,
+        _liveliness: liveliness,
+    }))
+}
+
+impl Tap {
+    /// Publishes the messages sent on the Output until the client disappears or the Output is dropped.
+    ///
+    /// Watermarks are not published.
+    async fn run(self) {
+        let Tap {
+            key_expr,
+            messages,
+            publisher,
+            disconnected,
+            _liveliness,
+        } = self;
+
+        let mut messages = futures::stream::select(
+            messages.into_stream().map(Some),
+            disconnected.into_stream().map(|_| None),
+        );
+
+        while let Some(Some(message)) = messages.next().await {
+            let Message::Data(data) = message else {
+                continue;
+            };
+
+            let payload = match data.payload().try_as_bytes() {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!("Failed to serialise a message, skipping it: {:?}", e);
+                    continue;
+                }
+            };
+
+            let metadata = match serde_json::to_vec(&TapMetadata {
+                timestamp: *data.timestamp(),
+            }) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    tracing::warn!("Failed to serialise the metadata of a message: {:?}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = publisher.put(payload.to_vec()).attachment(metadata).await {
+                tracing::warn!("Failed to publish on < {} >: {:?}", key_expr, e);
+            }
+        }
+
+        tracing::info!("Tap < {} > removed", key_expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_utils::{self, TestDataFlow};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[async_std::test]
+    async fn test_tap() {
+        let runtime = Runtime::builder("test-tap")
+            .build()
+            .await
+            .expect("Failed to build the Zenoh-Flow runtime");
+        let data_flow = TestDataFlow::new(None);
+        let instance_id = test_utils::run(&runtime, data_flow.record(runtime.id())).await;
+
+        let output = OutputDescriptor::new("source", "out");
+        let tap_id = Uuid::new_v4();
+        let tap_key_expr = selectors::selector_tap(&instance_id, &tap_id);
+
+        // Without the liveliness token of its client, the tap is not declared.
+        assert!(try_declare_tap(&runtime, &instance_id, &output, &tap_id)
+            .await
+            .is_err());
+
+        let token = runtime
+            .session()
+            .liveliness()
+            .declare_token(tap_key_expr.clone())
+            .await
+            .unwrap();
+        let subscriber = runtime
+            .session()
+            .declare_subscriber(tap_key_expr.clone())
+            .await
+            .unwrap();
+
+        let tap = try_declare_tap(&runtime, &instance_id, &output, &tap_id)
+            .await
+            .expect("Failed to declare the tap")
+            .expect("The node of the output is managed by the runtime");
+        let handle = async_std::task::spawn(tap.run());
+
+        runtime
+            .session()
+            .put(format!("{}/in", data_flow.key_expr), b"tapped".to_vec())
+            .await
+            .unwrap();
+
+        let sample = async_std::future::timeout(TIMEOUT, subscriber.recv_async())
+            .await
+            .expect("No message was tapped")
+            .unwrap();
+        assert_eq!(b"tapped".to_vec(), sample.payload().to_bytes().to_vec());
+        let metadata = sample
+            .attachment()
+            .map(|attachment| serde_json::from_slice::<TapMetadata>(&attachment.to_bytes()));
+        assert!(matches!(metadata, Some(Ok(_))));
+
+        // The tap is removed once its client disappears.
+        drop(token);
+        async_std::future::timeout(TIMEOUT, handle)
+            .await
+            .expect("The tap was not removed");
+
+        test_utils::delete(&runtime, &instance_id).await;
+    }
+}
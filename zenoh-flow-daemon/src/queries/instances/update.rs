@@ -0,0 +1,158 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::sync::Arc;
+
+use anyhow::bail;
+use zenoh::query::Query;
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
+use zenoh_flow_descriptors::FlattenedDataFlowUpdate;
+use zenoh_flow_records::DataFlowRecord;
+use zenoh_flow_runtime::{InstanceState, Runtime};
+
+use super::{query_runtime, reply, start, InstancesQuery, Origin};
+use crate::daemon::QueriesConfiguration;
+
+/// Updates the graph of the data flow instance identified by `instance_id`, replying with its updated record.
+///
+/// If this query originates from a [Client](Origin::Client) then this function also queries the other runtimes
+/// involved: the runtimes that already manage a part of the instance update it, the runtimes that become involved load
+/// it (and start it, if the instance is running).
+///
+/// # Consistency
+///
+/// The update is validated before any runtime is contacted and a runtime that fails to load the nodes it is given
+/// leaves its part of the instance untouched. There is however no rollback: if a runtime fails, the error is reported
+/// but the runtimes that succeeded keep the updated graph.
+pub(crate) fn update(
+    runtime: Arc<Runtime>,
+    query: Query,
+    origin: Origin,
+    instance_id: InstanceId,
+    update: FlattenedDataFlowUpdate,
+    default_runtime: RuntimeId,
+    queries: QueriesConfiguration,
+) {
+    async_std::task::spawn(async move {
+        let record = match origin {
+            Origin::Client => {
+                update_all(
+                    &runtime,
+                    &instance_id,
+                    &update,
+                    &default_runtime,
+                    &queries,
+                    &[],
+                )
+                .await
+            }
+            Origin::Daemon => {
+                runtime
+                    .try_update_instance(&instance_id, &update, &default_runtime)
+                    .await
+            }
+        };
+
+        if let Err(e) = reply(query, record).await {
+            tracing::error!("Failed to reply to 'Update' query: {:?}", e);
+        }
+    });
+}
+
+/// Updates the graph of the data flow instance on all the runtimes involved, before and after the update.
+///
+/// The `unreachable_runtimes` are not contacted.
+pub(crate) async fn update_all(
+    runtime: &Runtime,
+    instance_id: &InstanceId,
+    update: &FlattenedDataFlowUpdate,
+    default_runtime: &RuntimeId,
+    queries: &QueriesConfiguration,
+    unreachable_runtimes: &[RuntimeId],
+) -> Result<DataFlowRecord> {
+    let record = runtime.try_get_record(instance_id).await?;
+    // NOTE: The update is applied once here, without side effects, such that an invalid update is refused before any
+    // runtime is contacted.
+    let updated_record = record.try_update(update, default_runtime)?;
+    let is_running = matches!(
+        runtime
+            .get_instance_status(instance_id)
+            .await
+            .map(|status| status.state),
+        Some(
+            InstanceState::Running(_) | InstanceState::Degraded(_) | InstanceState::Disconnected(_)
+        )
+    );
+
+    let mut failures = Vec::default();
+    if record.mapping().contains_key(runtime.id()) {
+        runtime
+            .try_update_instance(instance_id, update, default_runtime)
+            .await?;
+    }
+
+    for runtime_id in record.mapping().keys().filter(|&runtime_id| {
+        runtime_id != runtime.id() && !unreachable_runtimes.contains(runtime_id)
+    }) {
+        let update_query = InstancesQuery::Update {
+            origin: Origin::Daemon,
+            instance_id: instance_id.clone(),
+            update: Box::new(update.clone()),
+            default_runtime: default_runtime.clone(),
+        };
+
+        if let Err(e) =
+            query_runtime(runtime.session(), runtime_id, &update_query, &queries.load).await
+        {
+            failures.push(format!("- < {runtime_id} >: {e:?}"));
+        }
+    }
+
+    let new_runtimes = updated_record
+        .mapping()
+        .keys()
+        .filter(|&runtime_id| !record.mapping().contains_key(runtime_id))
+        .collect::<Vec<_>>();
+    for &runtime_id in new_runtimes.iter() {
+        let load_query = InstancesQuery::Load(Box::new(updated_record.clone()));
+        if let Err(e) =
+            query_runtime(runtime.session(), runtime_id, &load_query, &queries.load).await
+        {
+            failures.push(format!("- < {runtime_id} >: {e:?}"));
+        }
+    }
+
+    if is_running && !new_runtimes.is_empty() {
+        if let Err(e) = start::query_start(
+            runtime.session(),
+            new_runtimes.into_iter(),
+            instance_id,
+            queries,
+        )
+        .await
+        {
+            failures.push(format!("- {e:?}"));
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "The graph of data flow < {} > was only partially updated:\n{}",
+            instance_id,
+            failures.join("\n")
+        );
+    }
+
+    Ok(updated_record)
+}
@@ -29,10 +29,14 @@ use anyhow::{anyhow, bail};
 use serde::Deserialize;
 use zenoh::query::Query;
 use zenoh_flow_commons::Result;
-pub use zenoh_flow_runtime::InstanceStatus;
+pub use zenoh_flow_runtime::{InstanceMetrics, InstanceStatus, RecordingSummary, ReplayMode};
 
 pub use self::{
-    instances::{InstancesQuery, Origin},
+    instances::{
+        create::{CreationReport, LoadOutcome, RuntimeLoad},
+        tap::TapMetadata,
+        InstancesQuery, Origin,
+    },
     runtime::{RuntimeInfo, RuntimeStatus, RuntimesQuery},
     selectors::*,
 };
@@ -14,19 +14,24 @@
 
 use std::{collections::HashMap, sync::Arc};
 
+use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind};
-use zenoh::query::Query;
-use zenoh_flow_commons::{InstanceId, RuntimeId};
+use zenoh::query::{ConsolidationMode, Query};
+use zenoh_flow_commons::{InstanceId, Labels, Result, RuntimeId};
+use zenoh_flow_descriptors::RuntimeCandidate;
 use zenoh_flow_runtime::{InstanceState, Runtime};
 
+use super::selectors;
+use crate::daemon::QueryPolicy;
+
 /// The available interactions with Zenoh-Flow Daemon(s).
 #[derive(Debug, Deserialize, Serialize)]
 pub enum RuntimesQuery {
     /// To list all the reachable Zenoh-Flow Daemon(s).
     ///
-    /// This query will display the name and [unique identifier](RuntimeId) of each Zenoh-Flow Daemon. See the
-    /// corresponding structure, [RuntimeInfo], for usage within your code.
+    /// This query will display the name, [unique identifier](RuntimeId), [Labels] and capacity of each Zenoh-Flow
+    /// Daemon. See the corresponding structure, [RuntimeInfo], for usage within your code.
     List,
     /// To obtain detailed information about a Zenoh-Flow Daemon and its host.
     ///
@@ -50,6 +55,78 @@ pub enum RuntimesQuery {
 pub struct RuntimeInfo {
     pub id: RuntimeId,
     pub name: Arc<str>,
+    /// The labels describing the runtime.
+    #[serde(default)]
+    pub labels: Labels,
+    /// The maximum number of nodes the runtime should manage, if it was set.
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    /// The number of nodes the runtime currently manages, connectors excluded.
+    #[serde(default)]
+    pub nodes: usize,
+}
+
+impl From<RuntimeInfo> for RuntimeCandidate {
+    fn from(value: RuntimeInfo) -> Self {
+        Self {
+            id: value.id,
+            labels: value.labels,
+            available: value
+                .capacity
+                .map(|capacity| capacity.saturating_sub(value.nodes)),
+        }
+    }
+}
+
+/// Returns the Zenoh-Flow runtimes that answered a [List](RuntimesQuery::List) query before the timeout of the
+/// `policy`, as candidates on which nodes can be placed. The `runtime` is always part of the candidates.
+///
+/// # Errors
+///
+/// This function will fail if the query could not be sent.
+pub(crate) async fn query_candidates(
+    runtime: &Runtime,
+    policy: &QueryPolicy,
+) -> Result<Vec<RuntimeCandidate>> {
+    let payload = serde_json::to_vec(&RuntimesQuery::List)
+        .map_err(|e| anyhow!("serde_json failed to serialize the query: {e:?}"))?;
+
+    let replies = runtime
+        .session()
+        .get(selectors::selector_all_runtimes())
+        .payload(payload)
+        // We want to address all the Zenoh-Flow daemons that are reachable on the Zenoh network.
+        .consolidation(ConsolidationMode::None)
+        .timeout(policy.timeout)
+        .await
+        .map_err(|e| anyhow!("Failed to query the reachable runtimes: {e:?}"))?;
+
+    let mut candidates = vec![RuntimeCandidate::from(runtime_info(runtime).await)];
+    while let Ok(reply) = replies.recv_async().await {
+        let Ok(sample) = reply.result() else {
+            continue;
+        };
+
+        match serde_json::from_slice::<RuntimeInfo>(&sample.payload().to_bytes()) {
+            Ok(runtime_info) if runtime_info.id != *runtime.id() => {
+                candidates.push(runtime_info.into())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to parse a reply as a `RuntimeInfo`: {:?}", e),
+        }
+    }
+
+    Ok(candidates)
+}
+
+async fn runtime_info(runtime: &Runtime) -> RuntimeInfo {
+    RuntimeInfo {
+        id: runtime.id().clone(),
+        name: runtime.name(),
+        labels: runtime.labels().clone(),
+        capacity: runtime.capacity(),
+        nodes: runtime.assigned_nodes().await,
+    }
 }
 
 /// The answer to a [Status] query.
@@ -69,16 +146,7 @@ pub struct RuntimeStatus {
 impl RuntimesQuery {
     pub(crate) async fn process(self, query: Query, runtime: Arc<Runtime>) {
         let payload = match self {
-            RuntimesQuery::List => {
-                // TODO We could probably try to generate that structure the moment we create the daemon, I don't see
-                // these values changing at runtime.
-                let runtime_info = RuntimeInfo {
-                    id: runtime.id().clone(),
-                    name: runtime.name(),
-                };
-
-                serde_json::to_vec(&runtime_info)
-            }
+            RuntimesQuery::List => serde_json::to_vec(&runtime_info(&runtime).await),
 
             RuntimesQuery::Status => {
                 let data_flows_status = runtime.instances_state().await;
@@ -12,12 +12,15 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
+use uuid::Uuid;
 use zenoh::key_expr::OwnedKeyExpr;
-use zenoh_flow_commons::RuntimeId;
+use zenoh_flow_commons::{InstanceId, RuntimeId};
 
 const ZENOH_FLOW: &str = "zenoh-flow";
 const INSTANCES: &str = "instances";
 const RUNTIMES: &str = "runtimes";
+const TAPS: &str = "taps";
+const ALIVE: &str = "alive";
 
 /// This function generates an [OwnedKeyExpr] from the provided String.
 ///
@@ -29,7 +32,8 @@ const RUNTIMES: &str = "runtimes";
 ///
 /// Although panicking seems like a strong posture, we believe this choice strikes a correct balance: we, the Zenoh-Flow
 /// team, know the internals of Zenoh and can guarantee the validity of the key expressions we are building. Plus, all
-/// the exposed API leveraging this function only accepts [RuntimeId] which is a thin wrapper over ZenohId.
+/// the exposed API leveraging this function only accepts identifiers, such as [RuntimeId] which is a thin wrapper over
+/// ZenohId, that are valid chunks of key expression.
 fn autocanonize(maybe_ke: String) -> OwnedKeyExpr {
     OwnedKeyExpr::autocanonize(maybe_ke.clone()).unwrap_or_else(|e| {
         panic!(
@@ -99,3 +103,44 @@ pub fn selector_runtimes(runtime_id: &RuntimeId) -> OwnedKeyExpr {
 pub fn selector_all_runtimes() -> OwnedKeyExpr {
     autocanonize(format!("{ZENOH_FLOW}/*/{RUNTIMES}"))
 }
+
+/// Helper function to generate an [OwnedKeyExpr] on which a Zenoh-Flow runtime declares its liveliness token.
+///
+/// The generated key expression has the following structure: `zenoh-flow/<runtime id>/alive`
+///
+/// where `<runtime id>` corresponds to the unique identifier of the runtime. The token disappears as soon as the runtime
+/// stops or is disconnected.
+///
+/// # Panic
+///
+/// This function will panic in the impossible scenario where the provided [RuntimeId] would make the key expression not
+/// valid or not canonical.
+pub fn selector_liveliness(runtime_id: &RuntimeId) -> OwnedKeyExpr {
+    autocanonize(format!("{ZENOH_FLOW}/{runtime_id}/{ALIVE}"))
+}
+
+/// Helper function to generate an [OwnedKeyExpr] matching the liveliness tokens of all the Zenoh-Flow runtimes.
+///
+/// # Panic
+///
+/// This function will panic in the impossible scenario where the key expression we internally rely on is no longer
+/// valid or canonical.
+pub fn selector_all_liveliness() -> OwnedKeyExpr {
+    autocanonize(format!("{ZENOH_FLOW}/*/{ALIVE}"))
+}
+
+/// Helper function to generate an [OwnedKeyExpr] on which the messages of a tap of a data flow instance are published.
+///
+/// The generated key expression has the following structure: `zenoh-flow/taps/<instance id>/<tap id>`
+///
+/// where `<instance id>` corresponds to the unique identifier of the data flow instance and `<tap id>` to the unique
+/// identifier of the tap, chosen by the client. The client declares a liveliness token on the same key expression: the
+/// tap is removed as soon as this token disappears.
+///
+/// # Panic
+///
+/// This function will panic in the impossible scenario where the provided identifiers would make the key expression not
+/// valid or not canonical.
+pub fn selector_tap(instance_id: &InstanceId, tap_id: &Uuid) -> OwnedKeyExpr {
+    autocanonize(format!("{ZENOH_FLOW}/{TAPS}/{instance_id}/{tap_id}"))
+}
@@ -0,0 +1,144 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The fixtures shared by the tests of the Zenoh-Flow Daemon.
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+use zenoh_flow_commons::{InstanceId, RuntimeId, Vars};
+use zenoh_flow_descriptors::{DataFlowDescriptor, FlattenedDataFlowDescriptor};
+use zenoh_flow_records::DataFlowRecord;
+use zenoh_flow_runtime::{InstanceState, Runtime};
+
+/// How long [wait_until] waits for a condition to be satisfied.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A data flow made of a built-in Zenoh Source, with the Output `out`, linked to a built-in Zenoh Sink, with the Input
+/// `in`. As they do not rely on shared libraries, it can be loaded by any runtime.
+pub(crate) struct TestDataFlow {
+    /// The key expression prefix, unique to this data flow: the Source subscribes to `<key_expr>/in` and the Sink
+    /// publishes on `<key_expr>/out`.
+    pub(crate) key_expr: String,
+    pub(crate) descriptor: FlattenedDataFlowDescriptor,
+}
+
+impl TestDataFlow {
+    /// Creates the data flow, mapping the Sink on `sink_runtime` if one is provided.
+    pub(crate) fn new(sink_runtime: Option<&RuntimeId>) -> Self {
+        let key_expr = format!("test/zenoh-flow/{}", Uuid::new_v4());
+        let mapping = match sink_runtime {
+            Some(runtime_id) => format!("mapping:\n  {runtime_id}:\n    - sink\n"),
+            None => String::default(),
+        };
+        let descriptor = format!(
+            r#"
+name: test-data-flow
+
+sources:
+  - id: source
+    zenoh-subscribers:
+      out: "{key_expr}/in"
+
+sinks:
+  - id: sink
+    zenoh-publishers:
+      in: "{key_expr}/out"
+
+links:
+  - from:
+      node: source
+      output: out
+    to:
+      node: sink
+      input: in
+
+{mapping}"#
+        );
+
+        let descriptor = FlattenedDataFlowDescriptor::try_flatten(
+            serde_yaml::from_str::<DataFlowDescriptor>(&descriptor).unwrap(),
+            Vars::default(),
+        )
+        .unwrap();
+
+        Self {
+            key_expr,
+            descriptor,
+        }
+    }
+
+    /// Returns the record of this data flow, the nodes without a mapping being assigned to the `default_runtime`.
+    pub(crate) fn record(&self, default_runtime: &RuntimeId) -> DataFlowRecord {
+        DataFlowRecord::try_new(&self.descriptor, default_runtime).unwrap()
+    }
+}
+
+/// Loads and starts, on the `runtime`, the data flow instance described by the `record`.
+pub(crate) async fn run(runtime: &Runtime, record: DataFlowRecord) -> InstanceId {
+    let instance_id = record.instance_id().clone();
+    runtime
+        .try_load_data_flow(record)
+        .await
+        .expect("Failed to load the data flow");
+    runtime
+        .try_start_instance(&instance_id)
+        .await
+        .expect("Failed to start the data flow");
+
+    instance_id
+}
+
+/// Deletes, from the `runtime`, the data flow instance `instance_id`.
+pub(crate) async fn delete(runtime: &Runtime, instance_id: &InstanceId) {
+    runtime
+        .try_delete_instance(instance_id)
+        .await
+        .expect("Failed to delete the data flow");
+}
+
+/// Polls the `condition` until it is satisfied, panicking with the `message` if it is not after 10 seconds.
+pub(crate) async fn wait_until<F, Fut>(condition: F, message: &str)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let instant = Instant::now();
+    while !condition().await {
+        assert!(instant.elapsed() < TIMEOUT, "{message}");
+        async_std::task::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Waits until the state of the data flow instance `instance_id`, on the `runtime`, satisfies the `expected` predicate.
+pub(crate) async fn wait_for_state(
+    runtime: &Runtime,
+    instance_id: &InstanceId,
+    expected: fn(&InstanceState) -> bool,
+) {
+    wait_until(
+        || async move {
+            runtime
+                .instances_state()
+                .await
+                .get(instance_id)
+                .is_some_and(|(_, state)| expected(state))
+        },
+        "The state of the instance did not change",
+    )
+    .await
+}
@@ -21,8 +21,9 @@ use serde::{Deserialize, Serialize};
 use zenoh_flow_commons::{Configuration, InstanceId, NodeId, RuntimeId};
 
 use crate::{
+    mapping::MappingEntry,
     nodes::{operator::OperatorDescriptor, sink::SinkDescriptor, source::SourceDescriptor},
-    LinkDescriptor,
+    LinkDescriptor, PlacementDescriptor,
 };
 
 /// A `DataFlowDescriptor` describes an entire Zenoh-Flow application and is obtained after a parsing step.
@@ -69,6 +70,12 @@ use crate::{
 /// - `mapping`: To force the deployment of a node on a Zenoh-Flow runtime. Note that it is not mandatory to assign all
 ///   nodes to Zenoh-Flow runtimes. The runtime that instantiates the data flow will self-assign all unassigned nodes.
 ///
+///   A node can be mapped alongside a [failover](crate::FailoverPolicy) policy, written `{ node: <id>, failover:
+///   <policy> }`, to have it re-placed on another Zenoh-Flow runtime if the one it runs on is lost.
+///
+/// - `placement`: To constrain, per node, the Zenoh-Flow runtimes on which it can run through the labels they advertise
+///   and affinity / anti-affinity rules. See [PlacementDescriptor](crate::PlacementDescriptor) for more details.
+///
 /// # Node descriptor structure
 ///
 /// The three types of nodes -- Sources, Sinks and Operators -- share a similar structure.
@@ -229,7 +236,19 @@ use crate::{
 ///     - Remote-Operator
 ///   d8c50f6160154e409c77b61866c5cb47:
 ///     - Zenoh-Sink
-///     - Sink
+///     - node: Sink
+///       failover: any
+///
+/// placement:
+///   Source:
+///     labels:
+///       site: lab
+///   Operator:
+///     affinity:
+///       - Source
+///   Remote-Sink:
+///     anti-affinity:
+///       - Remote-Source
 /// # "#;
 /// # let data_flow_yaml = serde_yaml::from_str::<DataFlowDescriptor>(yaml).unwrap();
 /// ```
@@ -266,7 +285,10 @@ pub struct DataFlowDescriptor {
     /// Note that, if this field is omitted or only covers a part of the nodes, Zenoh-Flow will assign the nodes without
     /// a mapping to the Zenoh-Flow runtime that was requested to instantiate the data flow.
     #[serde(default)]
-    pub(crate) mapping: HashMap<RuntimeId, HashSet<NodeId>>,
+    pub(crate) mapping: HashMap<RuntimeId, HashSet<MappingEntry>>,
+    /// *(optional)* The constraints on the Zenoh-Flow runtimes on which the nodes can run.
+    #[serde(default)]
+    pub(crate) placement: HashMap<NodeId, PlacementDescriptor>,
 }
 
 #[cfg(test)]
@@ -18,14 +18,16 @@ use std::{
     sync::Arc,
 };
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 use zenoh_flow_commons::{Configuration, InstanceId, NodeId, Result, RuntimeId, Vars};
 
 use super::validator::Validator;
 use crate::{
-    DataFlowDescriptor, FlattenedOperatorDescriptor, FlattenedSinkDescriptor,
-    FlattenedSourceDescriptor, LinkDescriptor,
+    mapping::split_mapping,
+    nodes::{operator::OperatorDescriptor, sink::SinkDescriptor, source::SourceDescriptor},
+    DataFlowDescriptor, FailoverPolicy, FlattenedDataFlowUpdate, FlattenedOperatorDescriptor,
+    FlattenedSinkDescriptor, FlattenedSourceDescriptor, LinkDescriptor, PlacementDescriptor,
 };
 
 /// A `FlattenedDataFlowDescriptor` is a self-contained description of a data flow.
@@ -66,6 +68,16 @@ pub struct FlattenedDataFlowDescriptor {
     /// a mapping to the Zenoh-Flow runtime that instantiates the data flow.
     #[serde(default)]
     pub mapping: HashMap<RuntimeId, HashSet<NodeId>>,
+    /// *(optional)* The [FailoverPolicy] of the nodes that should be re-placed if the Zenoh-Flow runtime they run on is
+    /// lost.
+    ///
+    /// A node that does not appear in this list is never re-placed.
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub failover: HashMap<NodeId, FailoverPolicy>,
+    /// *(optional)* The [placement](PlacementDescriptor) constraints of the nodes, resolved by
+    /// [try_place](FlattenedDataFlowDescriptor::try_place()).
+    #[serde(default)]
+    pub placement: HashMap<NodeId, PlacementDescriptor>,
 }
 
 impl Display for FlattenedDataFlowDescriptor {
@@ -109,63 +121,32 @@ impl FlattenedDataFlowDescriptor {
     /// - The flattening of a Sink failed.
     /// - The flattened data flow is not valid.
     pub fn try_flatten(mut data_flow: DataFlowDescriptor, vars: Vars) -> Result<Self> {
-        let mut flattened_operators = Vec::with_capacity(data_flow.operators.len());
-        for operator_desc in data_flow.operators {
-            let operator_id = operator_desc.id.clone();
-            let (mut flat_ops, mut flat_links, patch) = FlattenedOperatorDescriptor::try_flatten(
-                operator_desc,
-                data_flow.configuration.clone(),
-                Configuration::default(),
-                vars.clone(),
-                &mut HashSet::default(),
-            )?;
-
-            // Update the mapping: removing the id of the composite node & adding the "leaves".
-            let flattened_ids: Vec<_> = flat_ops.iter().map(|op| op.id.clone()).collect();
-            for nodes in data_flow.mapping.values_mut() {
-                if nodes.remove(&operator_id) {
-                    nodes.extend(flattened_ids.clone().into_iter());
-                }
-            }
-
-            // NOTE: This `append` has to be done after updating the mapping as it drains the content of the vector.
-            flattened_operators.append(&mut flat_ops);
-            patch.apply(&mut data_flow.links);
-            data_flow.links.append(&mut flat_links);
-        }
-
-        let sources = data_flow
-            .sources
-            .into_iter()
-            .map(|source_desc| {
-                FlattenedSourceDescriptor::try_flatten(
-                    source_desc,
-                    vars.clone(),
-                    data_flow.configuration.clone(),
-                )
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        let sinks = data_flow
-            .sinks
-            .into_iter()
-            .map(|sink_desc| {
-                FlattenedSinkDescriptor::try_flatten(
-                    sink_desc,
-                    vars.clone(),
-                    data_flow.configuration.clone(),
-                )
-            })
-            .collect::<Result<Vec<_>>>()?;
+        let (mapping, failover) = split_mapping(data_flow.mapping);
+        let mut tables = NodeTables {
+            mapping,
+            failover,
+            placement: data_flow.placement,
+        };
+        let (sources, operators, sinks) = try_flatten_nodes(
+            &data_flow.configuration,
+            data_flow.sources,
+            data_flow.operators,
+            data_flow.sinks,
+            &mut data_flow.links,
+            &mut tables,
+            &vars,
+        )?;
 
         let flattened_data_flow = Self {
             id: data_flow.id,
             name: data_flow.name,
             sources,
-            operators: flattened_operators,
+            operators,
             sinks,
             links: data_flow.links,
-            mapping: data_flow.mapping,
+            mapping: tables.mapping,
+            failover: tables.failover,
+            placement: tables.placement,
         };
 
         Validator::validate(&flattened_data_flow)
@@ -174,6 +155,110 @@ impl FlattenedDataFlowDescriptor {
         Ok(flattened_data_flow)
     }
 
+    /// Applies the [update](FlattenedDataFlowUpdate) to this data flow, returning the updated data flow.
+    ///
+    /// The nodes and links are first removed, the nodes and links of the update are then added: a node whose
+    /// identifier is already used replaces the existing node, keeping its links and, if the update does not map it,
+    /// its mapping and failover policy. A node mapped by the update takes the failover policy of the update, if any.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if:
+    /// - a node or a link to remove does not exist,
+    /// - the updated data flow is not valid (see [try_flatten](FlattenedDataFlowDescriptor::try_flatten())).
+    pub fn try_update(&self, update: &FlattenedDataFlowUpdate) -> Result<Self> {
+        let mut data_flow = self.clone();
+
+        for node_id in update.removed_nodes.iter() {
+            if !data_flow.remove_node(node_id) {
+                bail!(
+                    "Found no node < {} > to remove from data flow < {} >",
+                    node_id,
+                    self.name
+                );
+            }
+
+            data_flow
+                .links
+                .retain(|link| link.from.node != *node_id && link.to.node != *node_id);
+            data_flow.mapping.values_mut().for_each(|nodes| {
+                nodes.remove(node_id);
+            });
+            data_flow.failover.remove(node_id);
+            data_flow.placement.remove(node_id);
+            data_flow.placement.values_mut().for_each(|placement| {
+                placement.affinity.retain(|id| id != node_id);
+                placement.anti_affinity.retain(|id| id != node_id);
+            });
+        }
+
+        for removed_link in update.removed_links.iter() {
+            let count = data_flow.links.len();
+            data_flow
+                .links
+                .retain(|link| link.from != removed_link.from || link.to != removed_link.to);
+            if count == data_flow.links.len() {
+                bail!(
+                    "Found no link < {} > to remove from data flow < {} >",
+                    removed_link,
+                    self.name
+                );
+            }
+        }
+
+        for source in update.sources.iter() {
+            data_flow.remove_node(&source.id);
+            data_flow.sources.push(source.clone());
+        }
+
+        for operator in update.operators.iter() {
+            data_flow.remove_node(&operator.id);
+            data_flow.operators.push(operator.clone());
+        }
+
+        for sink in update.sinks.iter() {
+            data_flow.remove_node(&sink.id);
+            data_flow.sinks.push(sink.clone());
+        }
+
+        data_flow.links.extend(update.links.iter().cloned());
+
+        for (runtime_id, nodes) in update.mapping.iter() {
+            data_flow.mapping.values_mut().for_each(|mapped_nodes| {
+                mapped_nodes.retain(|node_id| !nodes.contains(node_id));
+            });
+            data_flow
+                .mapping
+                .entry(runtime_id.clone())
+                .or_default()
+                .extend(nodes.iter().cloned());
+
+            for node_id in nodes.iter() {
+                match update.failover.get(node_id) {
+                    Some(policy) => data_flow.failover.insert(node_id.clone(), policy.clone()),
+                    None => data_flow.failover.remove(node_id),
+                };
+            }
+        }
+        data_flow.mapping.retain(|_, nodes| !nodes.is_empty());
+
+        Validator::validate(&data_flow)
+            .context("The updated data flow does not appear to be valid")?;
+
+        Ok(data_flow)
+    }
+
+    /// Removes the Source, Operator or Sink `node_id`, leaving its links and mapping untouched. Returns `true` if a
+    /// node was removed.
+    fn remove_node(&mut self, node_id: &NodeId) -> bool {
+        let count = self.sources.len() + self.operators.len() + self.sinks.len();
+        self.sources.retain(|source| source.id != *node_id);
+        self.operators.retain(|operator| operator.id != *node_id);
+        self.sinks.retain(|sink| sink.id != *node_id);
+
+        count != self.sources.len() + self.operators.len() + self.sinks.len()
+    }
+
     /// Returns the unique identifier of the Zenoh-Flow runtime on which the node is configured to run.
     ///
     /// If there is no mapping entry for this specific node, `None` is returned.
@@ -188,6 +273,101 @@ impl FlattenedDataFlowDescriptor {
     }
 }
 
+/// The tables of a data flow that are indexed by the identifiers of its nodes.
+///
+/// The entries of a Composite Operator are replaced by entries for the Operators it contains when it is flattened.
+pub(crate) struct NodeTables {
+    /// On which Zenoh-Flow runtime the nodes should run.
+    pub(crate) mapping: HashMap<RuntimeId, HashSet<NodeId>>,
+    /// The failover policies of the nodes.
+    pub(crate) failover: HashMap<NodeId, FailoverPolicy>,
+    /// The placement constraints of the nodes.
+    pub(crate) placement: HashMap<NodeId, PlacementDescriptor>,
+}
+
+/// Flattens the provided Sources, Operators and Sinks, updating the `links` and the node `tables` that reference
+/// Composite Operators such that they point to the Operators they contain.
+///
+/// The `configuration` is propagated (possibly extended) to each node.
+pub(crate) fn try_flatten_nodes(
+    configuration: &Configuration,
+    sources: Vec<SourceDescriptor>,
+    operators: Vec<OperatorDescriptor>,
+    sinks: Vec<SinkDescriptor>,
+    links: &mut Vec<LinkDescriptor>,
+    tables: &mut NodeTables,
+    vars: &Vars,
+) -> Result<(
+    Vec<FlattenedSourceDescriptor>,
+    Vec<FlattenedOperatorDescriptor>,
+    Vec<FlattenedSinkDescriptor>,
+)> {
+    let mut flattened_operators = Vec::with_capacity(operators.len());
+    for operator_desc in operators {
+        let operator_id = operator_desc.id.clone();
+        let (mut flat_ops, mut flat_links, patch) = FlattenedOperatorDescriptor::try_flatten(
+            operator_desc,
+            configuration.clone(),
+            Configuration::default(),
+            vars.clone(),
+            &mut HashSet::default(),
+        )?;
+
+        // Update the mapping: removing the id of the composite node & adding the "leaves".
+        let flattened_ids: Vec<_> = flat_ops.iter().map(|op| op.id.clone()).collect();
+        for nodes in tables.mapping.values_mut() {
+            if nodes.remove(&operator_id) {
+                nodes.extend(flattened_ids.clone().into_iter());
+            }
+        }
+        if let Some(policy) = tables.failover.remove(&operator_id) {
+            tables
+                .failover
+                .extend(flattened_ids.iter().map(|id| (id.clone(), policy.clone())));
+        }
+        // The Operators contained in a Composite Operator inherit its placement and replace it in the affinity rules.
+        for node_placement in tables.placement.values_mut() {
+            for nodes in [
+                &mut node_placement.affinity,
+                &mut node_placement.anti_affinity,
+            ] {
+                if nodes.contains(&operator_id) {
+                    nodes.retain(|id| *id != operator_id);
+                    nodes.extend(flattened_ids.iter().cloned());
+                }
+            }
+        }
+        if let Some(composite_placement) = tables.placement.remove(&operator_id) {
+            tables.placement.extend(
+                flattened_ids
+                    .iter()
+                    .map(|id| (id.clone(), composite_placement.clone())),
+            );
+        }
+
+        // NOTE: This `append` has to be done after updating the mapping as it drains the content of the vector.
+        flattened_operators.append(&mut flat_ops);
+        patch.apply(links);
+        links.append(&mut flat_links);
+    }
+
+    let sources = sources
+        .into_iter()
+        .map(|source_desc| {
+            FlattenedSourceDescriptor::try_flatten(source_desc, vars.clone(), configuration.clone())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let sinks = sinks
+        .into_iter()
+        .map(|sink_desc| {
+            FlattenedSinkDescriptor::try_flatten(sink_desc, vars.clone(), configuration.clone())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((sources, flattened_operators, sinks))
+}
+
 #[cfg(test)]
 #[path = "./tests.rs"]
 mod tests;
@@ -14,6 +14,7 @@
 
 pub(crate) mod dataflow;
 pub(crate) mod nodes;
+pub(crate) mod update;
 pub(crate) mod validator;
 
 use std::{
@@ -16,12 +16,16 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 use url::Url;
-use zenoh_flow_commons::{Configuration, IMergeOverwrite, NodeId, PortId, Result, Vars};
+use zenoh_flow_commons::{
+    deserialize_period, serialize_period, Configuration, IMergeOverwrite, NodeId, RestartPolicy,
+    Result, Vars,
+};
 
 use crate::{
     flattened::{Patch, Substitutions},
@@ -29,7 +33,7 @@ use crate::{
         composite::CompositeOperatorDescriptor, CustomOperatorDescriptor, OperatorDescriptor,
         OperatorVariants,
     },
-    uri, InputDescriptor, LinkDescriptor, OutputDescriptor,
+    uri, InputDescriptor, LinkDescriptor, OutputDescriptor, PortDescriptor,
 };
 
 /// A `FlattenedOperatorDescriptor` is a self-contained description of an Operator node.
@@ -43,9 +47,20 @@ pub struct FlattenedOperatorDescriptor {
     #[serde(alias = "Library")]
     pub library: Url,
     /// The identifiers of the inputs the Operator uses.
-    pub inputs: Vec<PortId>,
+    pub inputs: Vec<PortDescriptor>,
     /// The identifiers of the outputs the Operator uses.
-    pub outputs: Vec<PortId>,
+    pub outputs: Vec<PortDescriptor>,
+    /// The period at which the `iteration` of the Operator is called, if it is periodic.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_period",
+        serialize_with = "serialize_period",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub period: Option<Duration>,
+    /// What the Zenoh-Flow runtime should do when the `iteration` of the Operator fails.
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub restart: RestartPolicy,
     /// Pairs of `(key, value)` to change the behaviour of the Operator without altering its implementation.
     #[serde(default)]
     pub configuration: Configuration,
@@ -142,6 +157,8 @@ Possible infinite recursion detected, the following descriptor appears to includ
                     library: custom_desc.library,
                     inputs: custom_desc.inputs,
                     outputs: custom_desc.outputs,
+                    period: custom_desc.period,
+                    restart: custom_desc.restart,
                     // An inline operator's configuration has higher priority than the outer configuration. In turn, the
                     // overwriting configuration has the highest priority.
                     configuration: overwritting_configuration.merge_overwrite(
@@ -231,6 +248,8 @@ Possible infinite recursion detected, the following descriptor appears to includ
 
 #[cfg(test)]
 mod tests {
+    use zenoh_flow_commons::Encoding;
+
     use super::*;
 
     #[test]
@@ -268,4 +287,47 @@ configuration:
             serde_yaml::from_str(yaml_str).expect("Failed to deserialise");
         assert!(serde_yaml::to_string(&flat_operator).is_ok());
     }
+
+    #[test]
+    fn test_serialize_port_encoding() {
+        let yaml_str = r#"
+id: operator-1
+description: operator-1
+library: file:///home/zenoh-flow/nodes/liboperator_1.so
+inputs:
+  - in-0
+  - id: in-1
+    type: sensor_msgs/Image
+    encoding: cbor
+outputs:
+  - id: out-0
+    encoding: json
+"#;
+
+        let flat_operator: FlattenedOperatorDescriptor =
+            serde_yaml::from_str(yaml_str).expect("Failed to deserialise");
+        assert_eq!(
+            vec![
+                PortDescriptor::from("in-0"),
+                PortDescriptor {
+                    id: "in-1".into(),
+                    schema: Some("sensor_msgs/Image".into()),
+                    encoding: Some(Encoding::Cbor),
+                    injectable: false,
+                },
+            ],
+            flat_operator.inputs
+        );
+
+        // The serialised descriptor is sent to other Zenoh-Flow runtimes, as JSON, in the record of a data flow.
+        let json = serde_json::to_string(&flat_operator).expect("Failed to serialise");
+        assert_eq!(
+            flat_operator,
+            serde_json::from_str::<FlattenedOperatorDescriptor>(&json)
+                .expect("Failed to deserialise")
+        );
+
+        let wrong_id = yaml_str.replace("in-1", "in-1/*");
+        assert!(serde_yaml::from_str::<FlattenedOperatorDescriptor>(&wrong_id).is_err());
+    }
 }
@@ -12,12 +12,15 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{collections::HashMap, fmt::Display, sync::Arc};
+use std::{collections::HashMap, fmt::Display, sync::Arc, time::Duration};
 
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use url::Url;
-use zenoh_flow_commons::{Configuration, IMergeOverwrite, NodeId, PortId, Result, Vars};
+use zenoh_flow_commons::{
+    deserialize_period, serialize_period, Configuration, IMergeOverwrite, NodeId, PortId,
+    RestartPolicy, Result, Vars,
+};
 use zenoh_keyexpr::OwnedKeyExpr;
 
 use crate::{
@@ -25,7 +28,7 @@ use crate::{
         builtin::zenoh::ZenohSinkDescriptor,
         sink::{CustomSinkDescriptor, SinkDescriptor, SinkVariants},
     },
-    uri,
+    uri, PortDescriptor,
 };
 
 /// A `FlattenedSinkDescriptor` is a self-contained description of a Sink node.
@@ -39,7 +42,18 @@ pub struct FlattenedSinkDescriptor {
     #[serde(flatten)]
     pub sink: SinkVariant,
     /// The identifiers of the inputs the Sink uses.
-    pub inputs: Vec<PortId>,
+    pub inputs: Vec<PortDescriptor>,
+    /// The period at which the `iteration` of the Sink is called, if it is periodic.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_period",
+        serialize_with = "serialize_period",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub period: Option<Duration>,
+    /// What the Zenoh-Flow runtime should do when the `iteration` of the Sink fails.
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub restart: RestartPolicy,
     /// Pairs of `(key, value)` to change the behaviour of the Sink without altering its implementation.
     #[serde(default)]
     pub configuration: Configuration,
@@ -124,14 +138,23 @@ impl FlattenedSinkDescriptor {
                 description: custom_sink.description,
                 sink: SinkVariant::Library(custom_sink.library),
                 inputs: custom_sink.inputs,
+                period: custom_sink.period,
+                restart: custom_sink.restart,
                 configuration: overwritting_configuration
                     .merge_overwrite(custom_sink.configuration),
             }),
             LocalSinkVariants::Zenoh(zenoh_desc) => Ok(Self {
                 id: sink_desc.id,
                 description: zenoh_desc.description,
-                inputs: zenoh_desc.publishers.keys().cloned().collect(),
+                inputs: zenoh_desc
+                    .publishers
+                    .keys()
+                    .cloned()
+                    .map(PortDescriptor::from)
+                    .collect(),
                 sink: SinkVariant::Zenoh(zenoh_desc.publishers),
+                period: None,
+                restart: RestartPolicy::default(),
                 configuration: Configuration::default(),
             }),
         }
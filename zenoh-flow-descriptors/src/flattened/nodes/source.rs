@@ -12,12 +12,15 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{collections::HashMap, fmt::Display, sync::Arc};
+use std::{collections::HashMap, fmt::Display, sync::Arc, time::Duration};
 
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use url::Url;
-use zenoh_flow_commons::{Configuration, IMergeOverwrite, NodeId, PortId, Result, Vars};
+use zenoh_flow_commons::{
+    deserialize_period, serialize_period, Configuration, IMergeOverwrite, NodeId, PortId,
+    RestartPolicy, Result, Vars,
+};
 use zenoh_keyexpr::OwnedKeyExpr;
 
 use crate::{
@@ -25,7 +28,7 @@ use crate::{
         builtin::zenoh::ZenohSourceDescriptor,
         source::{CustomSourceDescriptor, SourceDescriptor, SourceVariants},
     },
-    uri,
+    uri, PortDescriptor,
 };
 
 /// A `FlattenedSourceDescriptor` is a self-contained description of a Source node.
@@ -39,7 +42,18 @@ pub struct FlattenedSourceDescriptor {
     #[serde(flatten)]
     pub source: SourceVariant,
     /// The identifiers of the outputs the Source uses.
-    pub outputs: Vec<PortId>,
+    pub outputs: Vec<PortDescriptor>,
+    /// The period at which the `iteration` of the Source is called, if it is periodic.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_period",
+        serialize_with = "serialize_period",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub period: Option<Duration>,
+    /// What the Zenoh-Flow runtime should do when the `iteration` of the Source fails.
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub restart: RestartPolicy,
     /// Pairs of `(key, value)` to change the behaviour of the Source without altering its implementation.
     #[serde(default)]
     pub configuration: Configuration,
@@ -124,14 +138,23 @@ impl FlattenedSourceDescriptor {
                 description: custom_source.description,
                 source: SourceVariant::Library(custom_source.library),
                 outputs: custom_source.outputs,
+                period: custom_source.period,
+                restart: custom_source.restart,
                 configuration: overwritting_configuration
                     .merge_overwrite(custom_source.configuration),
             }),
             LocalSourceVariants::Zenoh(zenoh_desc) => Ok(Self {
                 id: source_desc.id,
                 description: zenoh_desc.description,
-                outputs: zenoh_desc.subscribers.keys().cloned().collect(),
+                outputs: zenoh_desc
+                    .subscribers
+                    .keys()
+                    .cloned()
+                    .map(PortDescriptor::from)
+                    .collect(),
                 source: SourceVariant::Zenoh(zenoh_desc.subscribers),
+                period: None,
+                restart: RestartPolicy::default(),
                 configuration: Configuration::default(),
             }),
         }
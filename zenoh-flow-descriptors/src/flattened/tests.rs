@@ -20,14 +20,14 @@ use std::{
 use serde_json::json;
 use url::Url;
 use uuid::Uuid;
-use zenoh_flow_commons::{NodeId, RuntimeId, Vars};
+use zenoh_flow_commons::{Labels, NodeId, RestartPolicy, RuntimeId, Vars};
 
 use crate::{
     flattened::nodes::{sink::SinkVariant, source::SourceVariant},
     uri::try_load_descriptor,
-    DataFlowDescriptor, FlattenedDataFlowDescriptor, FlattenedOperatorDescriptor,
+    DataFlowDescriptor, FailoverPolicy, FlattenedDataFlowDescriptor, FlattenedOperatorDescriptor,
     FlattenedSinkDescriptor, FlattenedSourceDescriptor, InputDescriptor, LinkDescriptor,
-    OutputDescriptor,
+    OutputDescriptor, RuntimeCandidate,
 };
 
 const BASE_DIR: &str = "./tests/descriptors";
@@ -71,6 +71,8 @@ fn test_flatten_descriptor() {
             description: Some("source".into()),
             outputs: vec!["source-out".into()],
             source: SourceVariant::Library(Url::parse("file://source.so").unwrap()),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer" }).into(),
         },
         FlattenedSourceDescriptor {
@@ -78,6 +80,8 @@ fn test_flatten_descriptor() {
             description: Some("source".into()),
             outputs: vec!["source-out".into()],
             source: SourceVariant::Library(Url::parse("file://source.so").unwrap()),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer" }).into(),
         },
         FlattenedSourceDescriptor {
@@ -88,6 +92,8 @@ fn test_flatten_descriptor() {
                 "source-composite-out-2".into(),
             ],
             source: SourceVariant::Library(Url::parse("file://source-composite.so").unwrap()),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer", "bar": "re-reverse" }).into(),
         },
     ];
@@ -112,6 +118,8 @@ fn test_flatten_descriptor() {
             inputs: vec!["operator-in".into()],
             outputs: vec!["operator-out".into()],
             library: Url::parse("file://operator.so").unwrap(),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer" }).into(),
         },
         FlattenedOperatorDescriptor {
@@ -120,6 +128,8 @@ fn test_flatten_descriptor() {
             inputs: vec!["operator-in".into()],
             outputs: vec!["operator-out".into()],
             library: Url::parse("file://operator.so").unwrap(),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer" }).into(),
         },
         /*
@@ -137,6 +147,8 @@ fn test_flatten_descriptor() {
             inputs: vec!["sub-operator-1-in-1".into(), "sub-operator-1-in-2".into()],
             outputs: vec!["sub-operator-1-out".into()],
             library: Url::parse("file://sub-operator-1.so").unwrap(),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration:
                 json!({ "foo": "global-outer", "quux": "global-inner", "bar": "composite-outer" })
                     .into(),
@@ -153,6 +165,8 @@ fn test_flatten_descriptor() {
             inputs: vec!["sub-sub-operator-1-in".into()],
             outputs: vec!["sub-sub-operator-1-out".into()],
             library: Url::parse("file://sub-sub-operator-1.so").unwrap(),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration:
                 json!({ "foo": "global-outer", "quux": "global-inner", "bar": "composite-outer", "buzz": "composite-inner", "baz": "leaf" }).into(),
         },
@@ -165,6 +179,8 @@ fn test_flatten_descriptor() {
             inputs: vec!["sub-sub-operator-2-in".into()],
             outputs: vec!["sub-sub-operator-2-out".into()],
             library: Url::parse("file://sub-sub-operator-2.so").unwrap(),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration:
                 json!({ "foo": "global-outer", "quux": "global-inner", "bar": "composite-outer", "buzz": "composite-inner" }).into(),
         },
@@ -177,6 +193,8 @@ fn test_flatten_descriptor() {
             inputs: vec!["sub-operator-2-in".into()],
             outputs: vec!["sub-operator-2-out-1".into(), "sub-operator-2-out-2".into()],
             library: Url::parse("file://sub-operator-2.so").unwrap(),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration:
                 json!({ "foo": "global-outer", "quux": "global-inner", "bar": "composite-outer" }).into(),
         },
@@ -202,6 +220,8 @@ fn test_flatten_descriptor() {
             description: Some("sink".into()),
             inputs: vec!["sink-in".into()],
             sink: SinkVariant::Library(Url::parse("file://sink.so").unwrap()),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer" }).into(),
         },
         FlattenedSinkDescriptor {
@@ -209,6 +229,8 @@ fn test_flatten_descriptor() {
             description: Some("sink".into()),
             inputs: vec!["sink-in".into()],
             sink: SinkVariant::Library(Url::parse("file://sink.so").unwrap()),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer" }).into(),
         },
         FlattenedSinkDescriptor {
@@ -216,6 +238,8 @@ fn test_flatten_descriptor() {
             description: Some("composite-sink".into()),
             inputs: vec!["sink-composite-in-1".into(), "sink-composite-in-2".into()],
             sink: SinkVariant::Library(Url::parse("file://sink-composite.so").unwrap()),
+            period: None,
+            restart: RestartPolicy::default(),
             configuration: json!({ "foo": "global-outer", "bar": "reverse" }).into(),
         },
     ];
@@ -333,6 +357,28 @@ fn test_flatten_descriptor() {
     ]);
 
     assert_eq!(expected_mapping, flatten.mapping);
+
+    // The failover policy of a Composite Operator is propagated to the Operators it contains.
+    let expected_failover: HashMap<NodeId, FailoverPolicy> = HashMap::from([
+        (
+            "operator-composite>sub-operator-1".into(),
+            FailoverPolicy::Any,
+        ),
+        (
+            "operator-composite>sub-operator-composite>sub-sub-operator-1".into(),
+            FailoverPolicy::Any,
+        ),
+        (
+            "operator-composite>sub-operator-composite>sub-sub-operator-2".into(),
+            FailoverPolicy::Any,
+        ),
+        (
+            "operator-composite>sub-operator-2".into(),
+            FailoverPolicy::Any,
+        ),
+    ]);
+
+    assert_eq!(expected_failover, flatten.failover);
 }
 
 #[test]
@@ -486,3 +532,181 @@ links:
     );
     assert!(flat_flow_yaml.mapping.is_empty());
 }
+
+#[test]
+fn test_try_place() {
+    let flow_yaml = r#"
+name: test-placement
+
+sources:
+  - id: source-0
+    library: file:///home/zenoh-flow/libsource.so
+    outputs:
+      - out-0
+
+operators:
+  - id: operator-1
+    library: file:///home/zenoh-flow/liboperator.so
+    inputs:
+      - in-1
+    outputs:
+      - out-1
+
+sinks:
+  - id: sink-2
+    library: file:///home/zenoh-flow/libsink.so
+    inputs:
+      - in-2
+
+links:
+  - from:
+      node: source-0
+      output: out-0
+    to:
+      node: operator-1
+      input: in-1
+  - from:
+      node: operator-1
+      output: out-1
+    to:
+      node: sink-2
+      input: in-2
+
+placement:
+  source-0:
+    labels:
+      camera: true
+  operator-1:
+    labels:
+      site: lab
+    affinity:
+      - sink-2
+  sink-2:
+    anti-affinity:
+      - source-0
+"#;
+
+    let flat_flow = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str::<DataFlowDescriptor>(flow_yaml).unwrap(),
+        Vars::default(),
+    )
+    .unwrap();
+
+    let camera = RuntimeCandidate {
+        id: RuntimeId::rand(),
+        labels: Labels::from([("site", "lab"), ("camera", "true")]),
+        available: Some(2),
+    };
+    let lab = RuntimeCandidate {
+        id: RuntimeId::rand(),
+        labels: Labels::from([("site", "lab")]),
+        available: None,
+    };
+    let field = RuntimeCandidate {
+        id: RuntimeId::rand(),
+        labels: Labels::from([("site", "field")]),
+        available: None,
+    };
+
+    // The Source can only run on `camera`, the Operator and the Sink run together on a "lab" runtime that is not the
+    // one of the Source.
+    let mut placed = flat_flow.clone();
+    placed
+        .try_place(&[field.clone(), camera.clone(), lab.clone()], &field.id)
+        .expect("Failed to place the nodes");
+    let expected_mapping: HashMap<RuntimeId, HashSet<NodeId>> = HashMap::from([
+        (camera.id.clone(), HashSet::from(["source-0".into()])),
+        (
+            lab.id.clone(),
+            HashSet::from(["operator-1".into(), "sink-2".into()]),
+        ),
+    ]);
+    assert_eq!(expected_mapping, placed.mapping);
+
+    // Without the "lab" runtime, the anti-affinity cannot be satisfied.
+    let mut placed = flat_flow.clone();
+    assert!(placed
+        .try_place(&[field.clone(), camera.clone()], &camera.id)
+        .is_err());
+
+    // An explicit mapping that violates the constraints is refused.
+    let mut mapped = flat_flow.clone();
+    mapped
+        .mapping
+        .insert(field.id.clone(), HashSet::from(["sink-2".into()]));
+    assert!(mapped
+        .try_place(&[field.clone(), camera.clone(), lab.clone()], &lab.id)
+        .is_err());
+
+    // The capacity of the runtimes is taken into account.
+    let mut full = flat_flow.clone();
+    let camera_full = RuntimeCandidate {
+        available: Some(0),
+        ..camera
+    };
+    assert!(full.try_place(&[camera_full, lab], &field.id).is_err());
+}
+
+#[test]
+fn test_try_place_unconstrained_anti_affinity() {
+    let flow_yaml = r#"
+name: test-placement
+
+sources:
+  - id: source-0
+    library: file:///home/zenoh-flow/libsource.so
+    outputs:
+      - out-0
+
+sinks:
+  - id: sink-1
+    library: file:///home/zenoh-flow/libsink.so
+    inputs:
+      - in-1
+
+links:
+  - from:
+      node: source-0
+      output: out-0
+    to:
+      node: sink-1
+      input: in-1
+
+placement:
+  source-0:
+    anti-affinity:
+      - sink-1
+"#;
+
+    let flat_flow = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str::<DataFlowDescriptor>(flow_yaml).unwrap(),
+        Vars::default(),
+    )
+    .unwrap();
+
+    let default = RuntimeCandidate {
+        id: RuntimeId::rand(),
+        labels: Labels::default(),
+        available: None,
+    };
+    let other = RuntimeCandidate {
+        id: RuntimeId::rand(),
+        labels: Labels::default(),
+        available: None,
+    };
+
+    // The Sink, neither mapped nor constrained, runs on the default runtime: the Source cannot run there.
+    let mut placed = flat_flow.clone();
+    placed
+        .try_place(&[default.clone(), other.clone()], &default.id)
+        .expect("Failed to place the nodes");
+    let expected_mapping: HashMap<RuntimeId, HashSet<NodeId>> = HashMap::from([
+        (default.id.clone(), HashSet::from(["sink-1".into()])),
+        (other.id.clone(), HashSet::from(["source-0".into()])),
+    ]);
+    assert_eq!(expected_mapping, placed.mapping);
+
+    // With the default runtime as the only candidate, the anti-affinity cannot be satisfied.
+    let mut placed = flat_flow.clone();
+    assert!(placed.try_place(&[default.clone()], &default.id).is_err());
+}
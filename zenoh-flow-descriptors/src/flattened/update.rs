@@ -0,0 +1,109 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{NodeId, Result, RuntimeId, Vars};
+
+use super::dataflow::{try_flatten_nodes, NodeTables};
+use crate::{
+    mapping::split_mapping, DataFlowUpdateDescriptor, FailoverPolicy, FlattenedOperatorDescriptor,
+    FlattenedSinkDescriptor, FlattenedSourceDescriptor, LinkDescriptor,
+};
+
+/// A `FlattenedDataFlowUpdate` is the self-contained description of the changes to apply to the graph of a data flow
+/// instance.
+///
+/// It is obtained by [flattening](FlattenedDataFlowUpdate::try_flatten()) a [DataFlowUpdateDescriptor] and is applied
+/// on a [FlattenedDataFlowDescriptor](crate::FlattenedDataFlowDescriptor) through its
+/// [try_update](crate::FlattenedDataFlowDescriptor::try_update()) method.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct FlattenedDataFlowUpdate {
+    /// The Sources to add or replace.
+    #[serde(default)]
+    pub sources: Vec<FlattenedSourceDescriptor>,
+    /// The Operators to add or replace.
+    #[serde(default)]
+    pub operators: Vec<FlattenedOperatorDescriptor>,
+    /// The Sinks to add or replace.
+    #[serde(default)]
+    pub sinks: Vec<FlattenedSinkDescriptor>,
+    /// The links to add.
+    #[serde(default)]
+    pub links: Vec<LinkDescriptor>,
+    /// On which Zenoh-Flow runtime the nodes added or replaced should run.
+    #[serde(default)]
+    pub mapping: HashMap<RuntimeId, HashSet<NodeId>>,
+    /// The failover policies of the nodes mapped by this update.
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub failover: HashMap<NodeId, FailoverPolicy>,
+    /// The nodes to remove, alongside all their links.
+    #[serde(default)]
+    pub removed_nodes: HashSet<NodeId>,
+    /// The links to remove, identified by their `from` and `to` sections.
+    #[serde(default)]
+    pub removed_links: Vec<LinkDescriptor>,
+}
+
+impl FlattenedDataFlowUpdate {
+    /// Flattens the provided [DataFlowUpdateDescriptor]: the nodes to add are flattened exactly as the nodes of a
+    /// [DataFlowDescriptor](crate::DataFlowDescriptor) are.
+    ///
+    /// Contrary to the flattening of a data flow, no validation is performed: an update can only be validated once
+    /// applied on the data flow it modifies.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the flattening of a Source, an Operator or a Sink failed.
+    pub fn try_flatten(mut update: DataFlowUpdateDescriptor, vars: Vars) -> Result<Self> {
+        let (mapping, failover) = split_mapping(update.mapping);
+        let mut tables = NodeTables {
+            mapping,
+            failover,
+            placement: HashMap::default(),
+        };
+        let (sources, operators, sinks) = try_flatten_nodes(
+            &update.configuration,
+            update.sources,
+            update.operators,
+            update.sinks,
+            &mut update.links,
+            &mut tables,
+            &vars,
+        )?;
+
+        Ok(Self {
+            sources,
+            operators,
+            sinks,
+            links: update.links,
+            mapping: tables.mapping,
+            failover: tables.failover,
+            removed_nodes: update.remove.nodes.into_iter().collect(),
+            removed_links: update.remove.links,
+        })
+    }
+
+    /// Returns `true` if this update does not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+            && self.operators.is_empty()
+            && self.sinks.is_empty()
+            && self.links.is_empty()
+            && self.mapping.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.removed_links.is_empty()
+    }
+}
@@ -12,18 +12,18 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::bail;
 use zenoh_flow_commons::{NodeId, PortId, Result};
 
-use crate::FlattenedDataFlowDescriptor;
+use crate::{FlattenedDataFlowDescriptor, PortDescriptor};
 
 #[derive(Default)]
 pub(crate) struct Validator<'a> {
     node_ids: HashSet<&'a NodeId>,
-    outputs: HashSet<(&'a NodeId, &'a PortId)>,
-    inputs: HashSet<(&'a NodeId, &'a PortId)>,
+    outputs: HashMap<(&'a NodeId, &'a PortId), &'a PortDescriptor>,
+    inputs: HashMap<(&'a NodeId, &'a PortId), &'a PortDescriptor>,
 }
 
 impl<'a> Validator<'a> {
@@ -38,12 +38,16 @@ impl<'a> Validator<'a> {
         Ok(())
     }
 
-    pub(crate) fn validate_input(&mut self, node_id: &'a NodeId, input: &'a PortId) -> Result<()> {
-        if !self.inputs.insert((node_id, input)) {
+    pub(crate) fn validate_input(
+        &mut self,
+        node_id: &'a NodeId,
+        input: &'a PortDescriptor,
+    ) -> Result<()> {
+        if self.inputs.insert((node_id, &input.id), input).is_some() {
             bail!(
                 "Node < {} > declares the following input (at least) twice: < {} >",
                 node_id,
-                input
+                input.id
             );
         }
 
@@ -53,13 +57,21 @@ impl<'a> Validator<'a> {
     pub(crate) fn validate_output(
         &mut self,
         node_id: &'a NodeId,
-        output: &'a PortId,
+        output: &'a PortDescriptor,
     ) -> Result<()> {
-        if !self.outputs.insert((node_id, output)) {
+        if self.outputs.insert((node_id, &output.id), output).is_some() {
             bail!(
                 "Node < {} > declares the following output (at least) twice: < {} >",
                 node_id,
-                output
+                output.id
+            );
+        }
+
+        if output.injectable {
+            bail!(
+                "Node < {} > declares the output < {} > as injectable, only inputs can be",
+                node_id,
+                output.id
             );
         }
 
@@ -105,11 +117,11 @@ impl<'a> Validator<'a> {
             }
         }
 
-        let mut unused_inputs = this.inputs.clone();
-        let mut unused_outputs = this.outputs.clone();
+        let mut unused_inputs = this.inputs.keys().copied().collect::<HashSet<_>>();
+        let mut unused_outputs = this.outputs.keys().copied().collect::<HashSet<_>>();
 
         for link in data_flow.links.iter() {
-            if !this.outputs.contains(&(&link.from.node, &link.from.output)) {
+            let Some(output) = this.outputs.get(&(&link.from.node, &link.from.output)) else {
                 bail!(
                     r#"
 The following `from` section of this link does not exist:
@@ -122,10 +134,10 @@ Does it declare an output named < {} >?
                     link.from.node,
                     link.from.output
                 );
-            }
+            };
             unused_outputs.remove(&(&link.from.node, &link.from.output));
 
-            if !this.inputs.contains(&(&link.to.node, &link.to.input)) {
+            let Some(input) = this.inputs.get(&(&link.to.node, &link.to.input)) else {
                 bail!(
                     r#"
 The following `to` section of this link does not exist:
@@ -138,6 +150,22 @@ Does it declare an input named < {} >?
                     link.to.node,
                     link.to.input
                 );
+            };
+
+            if !output.is_compatible_with(input) {
+                bail!(
+                    r#"
+The following link connects two incompatible ports:
+{}
+
+The output < {}.{} > and the input < {}.{} > must declare the same schema and encoding.
+"#,
+                    link,
+                    link.from.node,
+                    output,
+                    link.to.node,
+                    input
+                );
             }
 
             // Contrary to outputs, there cannot be multiple incoming links pointing to a single input.
@@ -179,6 +207,21 @@ We have detected several links that point the same Input < {} >:
             bail!(error_message);
         }
 
+        for (node_id, placement) in data_flow.placement.iter() {
+            for id in std::iter::once(node_id)
+                .chain(placement.affinity.iter())
+                .chain(placement.anti_affinity.iter())
+            {
+                if !this.node_ids.contains(id) {
+                    bail!(
+                        "The placement of node < {} > references the node < {} >, which does not exist",
+                        node_id,
+                        id
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 }
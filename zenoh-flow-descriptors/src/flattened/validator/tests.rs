@@ -930,3 +930,138 @@ links:
     assert!(format!("{:?}", res)
         .contains("We have detected several links that point the same Input < sink-0.in >:"));
 }
+
+#[test]
+fn test_incompatible_ports() {
+    let yaml_incompatible = r#"
+name: incompatible ports
+
+sources:
+  - id: source-0
+    description: my source
+    library: file:///home/zenoh-flow/source.so
+    outputs:
+      - id: out-0
+        schema: geometry_msgs/Point
+        encoding: cbor
+
+operators:
+  - id: operator-0
+    description: my operator
+    library: file:///home/zenoh-flow/operator.so
+    inputs:
+      - id: in-0
+        schema: geometry_msgs/Point
+    outputs:
+      - id: out-0
+        encoding: json
+
+sinks:
+  - id: sink-0
+    description: my sink
+    library: file:///home/zenoh-flow/sink.so
+    inputs:
+      - id: in-0
+        encoding: json
+
+links:
+  - from:
+      node: source-0
+      output: out-0
+    to:
+      node: operator-0
+      input: in-0
+  - from:
+      node: operator-0
+      output: out-0
+    to:
+      node: sink-0
+      input: in-0
+"#;
+
+    // A schema or an encoding declared on one side only is not checked.
+    assert!(FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str(yaml_incompatible).unwrap(),
+        Vars::default(),
+    )
+    .is_ok());
+
+    let yaml_schema_mismatch = yaml_incompatible.replacen(
+        "schema: geometry_msgs/Point",
+        "schema: geometry_msgs/Pose",
+        1,
+    );
+    let res = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str(&yaml_schema_mismatch).unwrap(),
+        Vars::default(),
+    );
+
+    assert!(res.is_err());
+    assert!(format!("{:?}", res).contains("The following link connects two incompatible ports:"));
+    assert!(format!("{:?}", res).contains("source-0.out-0: geometry_msgs/Pose (cbor)"));
+    assert!(format!("{:?}", res).contains("operator-0.in-0: geometry_msgs/Point"));
+
+    let yaml_encoding_mismatch = yaml_incompatible.replace(
+        "      - id: in-0\n        encoding: json",
+        "      - id: in-0\n        encoding: msgpack",
+    );
+    let res = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str(&yaml_encoding_mismatch).unwrap(),
+        Vars::default(),
+    );
+
+    assert!(res.is_err());
+    assert!(format!("{:?}", res).contains("operator-0.out-0 (json)"));
+    assert!(format!("{:?}", res).contains("sink-0.in-0 (msgpack)"));
+}
+
+#[test]
+fn test_injectable_ports() {
+    let yaml_injectable = r#"
+name: injectable ports
+
+sources:
+  - id: source-0
+    description: my source
+    library: file:///home/zenoh-flow/source.so
+    outputs:
+      - out-0
+
+sinks:
+  - id: sink-0
+    description: my sink
+    library: file:///home/zenoh-flow/sink.so
+    inputs:
+      - id: in-0
+        injectable: true
+
+links:
+  - from:
+      node: source-0
+      output: out-0
+    to:
+      node: sink-0
+      input: in-0
+"#;
+
+    let flat_flow = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str(yaml_injectable).unwrap(),
+        Vars::default(),
+    )
+    .expect("An Input can be injectable");
+    assert!(flat_flow.sinks[0].inputs[0].injectable);
+
+    let yaml_injectable_output = yaml_injectable.replace(
+        "    outputs:\n      - out-0",
+        "    outputs:\n      - id: out-0\n        injectable: true",
+    );
+    let res = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str(&yaml_injectable_output).unwrap(),
+        Vars::default(),
+    );
+
+    assert!(res.is_err());
+    assert!(format!("{:?}", res).contains(
+        "Node < source-0 > declares the output < out-0 > as injectable, only inputs can be"
+    ));
+}
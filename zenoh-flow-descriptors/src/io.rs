@@ -12,12 +12,158 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::fmt;
+use std::{fmt, str::FromStr, sync::Arc};
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{self, value::MapAccessDeserializer, IntoDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 #[cfg(feature = "shared-memory")]
 use zenoh_flow_commons::SharedMemoryConfiguration;
-use zenoh_flow_commons::{NodeId, PortId};
+use zenoh_flow_commons::{deserialize_id, Encoding, NodeId, PortId, QueueConfiguration};
+
+/// A `PortDescriptor` declares an Input or an Output of a Zenoh-Flow node.
+///
+/// A port can be declared with its identifier only or, to describe the data going through it, with its identifier, the
+/// identifier of their type or schema and their [Encoding]. Both are optional and only serve to verify, when the data
+/// flow is validated, that the ports connected by a link agree on what they exchange.
+///
+/// The `schema` is an opaque identifier: two schemas are compatible if, and only if, they are equal. It can also be
+/// declared with the `type` key.
+///
+/// An Input can also be declared `injectable`: messages can then be injected into it, from outside of the data flow,
+/// while the data flow instance runs (for manual testing or incident recovery). Outputs cannot be injectable.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_descriptors::PortDescriptor;
+/// # let ports = r#"
+/// - out-raw
+/// - id: out-json
+///   encoding: json
+/// - id: out-position
+///   schema: geometry_msgs/Point
+///   encoding: cbor
+/// - id: in-commands
+///   injectable: true
+/// # "#;
+/// # serde_yaml::from_str::<Vec<PortDescriptor>>(ports).unwrap();
+/// ```
+#[derive(Debug, Clone, Hash, Serialize, PartialEq, Eq)]
+pub struct PortDescriptor {
+    pub id: PortId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<Encoding>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub injectable: bool,
+}
+
+impl fmt::Display for PortDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)?;
+        if let Some(schema) = &self.schema {
+            write!(f, ": {}", schema)?;
+        }
+        if let Some(encoding) = &self.encoding {
+            write!(f, " ({})", encoding)?;
+        }
+        if self.injectable {
+            write!(f, " [injectable]")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PortDescriptor {
+    /// Returns `true` if the data produced by this port can be received by the `other` port.
+    ///
+    /// A schema or an encoding that is not declared on both ports is not checked.
+    pub fn is_compatible_with(&self, other: &PortDescriptor) -> bool {
+        fn agree<T: PartialEq>(left: &Option<T>, right: &Option<T>) -> bool {
+            match (left, right) {
+                (Some(left), Some(right)) => left == right,
+                _ => true,
+            }
+        }
+
+        agree(&self.schema, &other.schema) && agree(&self.encoding, &other.encoding)
+    }
+}
+
+impl From<PortId> for PortDescriptor {
+    fn from(id: PortId) -> Self {
+        Self {
+            id,
+            schema: None,
+            encoding: None,
+            injectable: false,
+        }
+    }
+}
+
+impl From<&str> for PortDescriptor {
+    fn from(id: &str) -> Self {
+        Self::from(PortId::from(id))
+    }
+}
+
+// A port is either declared by its identifier alone or as a map. Deriving `Deserialize` on an untagged enum would hide
+// the reason why an identifier was rejected, hence this manual implementation.
+impl<'de> Deserialize<'de> for PortDescriptor {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Declaration {
+            id: PortId,
+            #[serde(default, alias = "type")]
+            schema: Option<Arc<str>>,
+            #[serde(default)]
+            encoding: Option<Encoding>,
+            #[serde(default)]
+            injectable: bool,
+        }
+
+        struct PortVisitor;
+
+        impl<'de> Visitor<'de> for PortVisitor {
+            type Value = PortDescriptor;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(
+                    "a port identifier or a port declaration (`id` and, optionally, `schema`, `encoding` and \
+                     `injectable`)",
+                )
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<Self::Value, E> {
+                let id: PortId = deserialize_id(value.into_deserializer())?.as_ref().into();
+                Ok(id.into())
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let declaration = Declaration::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(PortDescriptor {
+                    id: declaration.id,
+                    schema: declaration.schema,
+                    encoding: declaration.encoding,
+                    injectable: declaration.injectable,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(PortVisitor)
+    }
+}
 
 /// An `InputDescriptor` uniquely describes an Input port of a Zenoh-Flow node.
 ///
@@ -52,6 +198,30 @@ impl InputDescriptor {
     }
 }
 
+/// Parses an `InputDescriptor` from its textual representation: `<node>.<input>`.
+///
+/// As the identifier of a node can contain dots, the *last* dot separates the node from the input.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_descriptors::InputDescriptor;
+/// let input: InputDescriptor = "greetings-maker.name".parse().unwrap();
+/// assert_eq!(InputDescriptor::new("greetings-maker", "name"), input);
+/// ```
+impl FromStr for InputDescriptor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('.') {
+            Some((node, input)) if !node.is_empty() && !input.is_empty() => {
+                Ok(Self::new(node, input))
+            }
+            _ => anyhow::bail!("Expected < node.input >, found < {s} >"),
+        }
+    }
+}
+
 /// An `OutputDescriptor` uniquely describes an Output port of a Zenoh-Flow node.
 ///
 /// # Example
@@ -85,11 +255,36 @@ impl OutputDescriptor {
     }
 }
 
+/// Parses an `OutputDescriptor` from its textual representation: `<node>.<output>`.
+///
+/// As the identifier of a node can contain dots, the *last* dot separates the node from the output.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_descriptors::OutputDescriptor;
+/// let output: OutputDescriptor = "greetings-maker.greeting".parse().unwrap();
+/// assert_eq!(OutputDescriptor::new("greetings-maker", "greeting"), output);
+/// ```
+impl FromStr for OutputDescriptor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('.') {
+            Some((node, output)) if !node.is_empty() && !output.is_empty() => {
+                Ok(Self::new(node, output))
+            }
+            _ => anyhow::bail!("Expected < node.output >, found < {s} >"),
+        }
+    }
+}
+
 /// A `LinkDescriptor` describes a link in Zenoh-Flow: a connection from an Output to an Input.
 ///
 /// A link is composed of:
 /// - an [OutputDescriptor],
 /// - an [InputDescriptor],
+/// - *(optional, unbounded by default)* the configuration of its queue,
 /// - *(optional, disabled by default)* Zenoh shared-memory parameters.
 ///
 /// # Example
@@ -107,10 +302,30 @@ impl OutputDescriptor {
 /// # "#;
 /// # serde_yaml::from_str::<LinkDescriptor>(link_desc).unwrap();
 /// ```
+///
+/// A link can be given a bounded queue. When the queue is full, its `policy` dictates what happens to a new message:
+/// `block` (the default), `drop-oldest` or `drop-newest`.
+/// ```
+/// # use zenoh_flow_descriptors::LinkDescriptor;
+/// # let link_desc = r#"
+/// from:
+///   node : Operator
+///   output : o-operator
+/// to:
+///   node : Sink
+///   input : i-sink
+/// queue:
+///   capacity: 64
+///   policy: drop-oldest
+/// # "#;
+/// # serde_yaml::from_str::<LinkDescriptor>(link_desc).unwrap();
+/// ```
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct LinkDescriptor {
     pub from: OutputDescriptor,
     pub to: InputDescriptor,
+    #[serde(default)]
+    pub queue: Option<QueueConfiguration>,
     #[cfg(feature = "shared-memory")]
     #[serde(default, alias = "shm", alias = "shared-memory")]
     pub shared_memory: Option<SharedMemoryConfiguration>,
@@ -127,11 +342,17 @@ impl LinkDescriptor {
         Self {
             from,
             to,
+            queue: None,
             #[cfg(feature = "shared-memory")]
             shared_memory: None,
         }
     }
 
+    pub fn set_queue(mut self, queue: QueueConfiguration) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
     #[cfg(feature = "shared-memory")]
     pub fn set_shared_memory(mut self, shm: SharedMemoryConfiguration) -> Self {
         self.shared_memory = Some(shm);
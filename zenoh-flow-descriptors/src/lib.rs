@@ -37,7 +37,10 @@
 pub(crate) mod dataflow;
 pub(crate) mod flattened;
 pub(crate) mod io;
+pub(crate) mod mapping;
 pub(crate) mod nodes;
+pub(crate) mod placement;
+pub(crate) mod update;
 pub(crate) mod uri;
 
 pub use self::{
@@ -49,6 +52,10 @@ pub use self::{
             sink::{FlattenedSinkDescriptor, SinkVariant},
             source::{FlattenedSourceDescriptor, SourceVariant},
         },
+        update::FlattenedDataFlowUpdate,
     },
-    io::{InputDescriptor, LinkDescriptor, OutputDescriptor},
+    io::{InputDescriptor, LinkDescriptor, OutputDescriptor, PortDescriptor},
+    mapping::FailoverPolicy,
+    placement::{PlacementDescriptor, RuntimeCandidate},
+    update::{DataFlowUpdateDescriptor, RemovalDescriptor},
 };
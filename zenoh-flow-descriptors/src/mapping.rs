@@ -0,0 +1,232 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{NodeId, RuntimeId};
+
+/// What the orchestrating Zenoh-Flow runtime should do with a node when the runtime it is mapped to is lost.
+///
+/// A node that has a failover policy other than `never` is re-placed on another Zenoh-Flow runtime, its connectors are
+/// regenerated and the data flow instance is resumed. Only the runtimes alive and already involved in the data flow
+/// instance are eligible when the policy is `any`.
+///
+/// In a descriptor, the `runtimes` variant is written as a map with a single key: the fields holding a
+/// `FailoverPolicy` are (de)serialised with [serde_yaml::with::singleton_map].
+///
+/// # Examples
+///
+/// ```
+/// # use zenoh_flow_descriptors::FailoverPolicy;
+/// # let failover = r#"
+/// any
+/// # "#;
+/// # let deserializer = serde_yaml::Deserializer::from_str(failover);
+/// # assert_eq!(
+/// #     FailoverPolicy::Any,
+/// #     serde_yaml::with::singleton_map::deserialize::<FailoverPolicy, _>(deserializer).unwrap()
+/// # );
+/// ```
+///
+/// ```
+/// # use zenoh_flow_descriptors::FailoverPolicy;
+/// # let failover = r#"
+/// runtimes:
+///   - a100ae41d10b4ec58dccba4cfa30d732
+///   - d8c50f6160154e409c77b61866c5cb47
+/// # "#;
+/// # let deserializer = serde_yaml::Deserializer::from_str(failover);
+/// # serde_yaml::with::singleton_map::deserialize::<FailoverPolicy, _>(deserializer).unwrap();
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailoverPolicy {
+    /// The node is not re-placed: the data flow instance stays disconnected until the runtime comes back.
+    #[default]
+    Never,
+    /// The node is re-placed on any Zenoh-Flow runtime alive that is involved in the data flow instance.
+    Any,
+    /// The node is re-placed on the first runtime of the list that is alive.
+    Runtimes(Vec<RuntimeId>),
+}
+
+impl Display for FailoverPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailoverPolicy::Never => write!(f, "never"),
+            FailoverPolicy::Any => write!(f, "any"),
+            FailoverPolicy::Runtimes(runtimes) => write!(
+                f,
+                "runtimes ({})",
+                runtimes
+                    .iter()
+                    .map(|runtime_id| runtime_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// An entry of the `mapping` section: either the identifier of a node or the identifier of a node alongside its
+/// [FailoverPolicy].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub(crate) enum MappingEntry {
+    Node(NodeId),
+    WithFailover {
+        node: NodeId,
+        #[serde(default, with = "serde_yaml::with::singleton_map")]
+        failover: FailoverPolicy,
+    },
+}
+
+/// Splits the `mapping` section of a descriptor into the mapping of the nodes and their failover policies.
+///
+/// Nodes whose policy is [Never](FailoverPolicy::Never) do not appear in the failover policies.
+pub(crate) fn split_mapping(
+    entries: HashMap<RuntimeId, HashSet<MappingEntry>>,
+) -> (
+    HashMap<RuntimeId, HashSet<NodeId>>,
+    HashMap<NodeId, FailoverPolicy>,
+) {
+    let mut mapping = HashMap::with_capacity(entries.len());
+    let mut failover = HashMap::default();
+
+    for (runtime_id, entries) in entries {
+        let nodes = entries
+            .into_iter()
+            .map(|entry| match entry {
+                MappingEntry::Node(node) => node,
+                MappingEntry::WithFailover {
+                    node,
+                    failover: policy,
+                } => {
+                    if policy != FailoverPolicy::Never {
+                        failover.insert(node.clone(), policy);
+                    }
+                    node
+                }
+            })
+            .collect::<HashSet<_>>();
+        mapping.insert(runtime_id, nodes);
+    }
+
+    (mapping, failover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_failover_policy() {
+        let failover = |entry: &str| match serde_yaml::from_str::<MappingEntry>(entry)
+            .expect("Failed to deserialise a mapping entry")
+        {
+            MappingEntry::Node(_) => panic!("Expected a mapping entry with a failover policy"),
+            MappingEntry::WithFailover { failover, .. } => failover,
+        };
+
+        assert_eq!(FailoverPolicy::Never, failover("node: source"));
+        assert_eq!(
+            FailoverPolicy::Never,
+            failover("{ node: source, failover: never }")
+        );
+        assert_eq!(
+            FailoverPolicy::Any,
+            failover("{ node: source, failover: any }")
+        );
+
+        let runtime_a = RuntimeId::rand();
+        let runtime_b = RuntimeId::rand();
+        let entry = serde_yaml::from_str::<MappingEntry>(&format!(
+            r#"
+node: source
+failover:
+  runtimes:
+    - {runtime_a}
+    - {runtime_b}
+"#
+        ))
+        .expect("Failed to deserialise a failover policy");
+        assert_eq!(
+            MappingEntry::WithFailover {
+                node: "source".into(),
+                failover: FailoverPolicy::Runtimes(vec![runtime_a.clone(), runtime_b.clone()]),
+            },
+            entry
+        );
+
+        let serialized = serde_yaml::to_string(&entry).expect("Failed to serialise");
+        assert_eq!(
+            entry,
+            serde_yaml::from_str::<MappingEntry>(&serialized).expect("Failed to deserialise")
+        );
+        let serialized = serde_json::to_string(&entry).expect("Failed to serialise");
+        assert_eq!(
+            entry,
+            serde_json::from_str::<MappingEntry>(&serialized).expect("Failed to deserialise")
+        );
+
+        assert!(
+            serde_yaml::from_str::<MappingEntry>("{ node: source, failover: anywhere }").is_err()
+        );
+        assert!(serde_yaml::from_str::<MappingEntry>(
+            "{ node: source, failover: { runtimes: any } }"
+        )
+        .is_err());
+        assert!(
+            serde_yaml::from_str::<MappingEntry>("{ node: source, failover: { any: [] } }")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_split_mapping() {
+        let runtime_id = RuntimeId::rand();
+        let entries = serde_yaml::from_str::<HashMap<RuntimeId, HashSet<MappingEntry>>>(&format!(
+            r#"
+{runtime_id}:
+  - source
+  - node: operator
+    failover: any
+  - node: sink
+    failover:
+      runtimes: [{runtime_id}]
+"#
+        ))
+        .expect("Failed to deserialise a mapping");
+
+        let (mapping, failover) = split_mapping(entries);
+        assert_eq!(
+            HashSet::from(["source".into(), "operator".into(), "sink".into()]),
+            mapping[&runtime_id]
+        );
+        assert_eq!(
+            HashMap::from([
+                (NodeId::from("operator"), FailoverPolicy::Any),
+                (
+                    NodeId::from("sink"),
+                    FailoverPolicy::Runtimes(vec![runtime_id.clone()])
+                ),
+            ]),
+            failover
+        );
+    }
+}
@@ -14,13 +14,16 @@
 
 pub(crate) mod composite;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use url::Url;
-use zenoh_flow_commons::{Configuration, NodeId, PortId};
+use zenoh_flow_commons::{
+    deserialize_period, serialize_period, Configuration, NodeId, RestartPolicy,
+};
 
 use super::RemoteNodeDescriptor;
+use crate::PortDescriptor;
 
 /// An `OperatorDescriptor` uniquely identifies and configures an Operator.
 ///
@@ -82,6 +85,24 @@ use super::RemoteNodeDescriptor;
 /// configuration:
 ///   answer: 1
 /// ```
+///
+/// ## Describing the data going through a port
+///
+/// A port can also record the schema and the [Encoding](zenoh_flow_commons::Encoding) of the data going through it,
+/// the latter typically being the codec passed to `typed_with`. Links between ports that declare different schemas or
+/// encodings are rejected when the data flow is validated.
+///
+/// ```yaml
+/// id: my-operator-1
+/// library: file:///home/zenoh-flow/libmy_operator.so
+/// inputs:
+///   - id: in-1
+///     schema: sensor_msgs/Image
+///     encoding: json
+/// outputs:
+///   - id: out-1
+///     encoding: msgpack
+/// ```
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OperatorDescriptor {
     pub id: NodeId,
@@ -100,8 +121,17 @@ pub(crate) enum OperatorVariants {
 pub(crate) struct CustomOperatorDescriptor {
     pub description: Option<Arc<str>>,
     pub library: Url,
-    pub inputs: Vec<PortId>,
-    pub outputs: Vec<PortId>,
+    pub inputs: Vec<PortDescriptor>,
+    pub outputs: Vec<PortDescriptor>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_period",
+        serialize_with = "serialize_period",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub period: Option<Duration>,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub restart: RestartPolicy,
     #[serde(default)]
     pub configuration: Configuration,
 }
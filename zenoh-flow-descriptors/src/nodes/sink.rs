@@ -12,14 +12,16 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use url::Url;
-use zenoh_flow_commons::{Configuration, NodeId, PortId};
+use zenoh_flow_commons::{
+    deserialize_period, serialize_period, Configuration, NodeId, RestartPolicy,
+};
 
 use super::RemoteNodeDescriptor;
-use crate::nodes::builtin::zenoh::ZenohSinkDescriptor;
+use crate::{nodes::builtin::zenoh::ZenohSinkDescriptor, PortDescriptor};
 
 /// A `SinkDescriptor` uniquely identifies a Sink.
 ///
@@ -85,7 +87,16 @@ pub(crate) enum SinkVariants {
 pub(crate) struct CustomSinkDescriptor {
     pub description: Option<Arc<str>>,
     pub library: Url,
-    pub inputs: Vec<PortId>,
+    pub inputs: Vec<PortDescriptor>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_period",
+        serialize_with = "serialize_period",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub period: Option<Duration>,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub restart: RestartPolicy,
     #[serde(default)]
     pub configuration: Configuration,
 }
@@ -12,14 +12,16 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use url::Url;
-use zenoh_flow_commons::{Configuration, NodeId, PortId};
+use zenoh_flow_commons::{
+    deserialize_period, serialize_period, Configuration, NodeId, RestartPolicy,
+};
 
 use super::RemoteNodeDescriptor;
-use crate::nodes::builtin::zenoh::ZenohSourceDescriptor;
+use crate::{nodes::builtin::zenoh::ZenohSourceDescriptor, PortDescriptor};
 
 /// A `SourceDescriptor` uniquely identifies a Source.
 ///
@@ -46,6 +48,12 @@ use crate::nodes::builtin::zenoh::ZenohSourceDescriptor;
 /// ## Inline declaration
 /// ### Custom source
 ///
+/// The optional `period` makes the Zenoh-Flow runtime call the `iteration` of the Source at a fixed rate instead of in
+/// a loop. It accepts any duration understood by [humantime](https://docs.rs/humantime), e.g. `100ms` or `1s`.
+///
+/// The optional `restart` policy tells the Zenoh-Flow runtime what to do when the `iteration` of the Source fails. See
+/// [RestartPolicy](zenoh_flow_commons::RestartPolicy) for the possible values.
+///
 /// ```yaml
 /// id: my-source-0
 /// description: This is my Source
@@ -53,6 +61,11 @@ use crate::nodes::builtin::zenoh::ZenohSourceDescriptor;
 /// outputs:
 ///   - out-0
 ///   - out-1
+/// period: 100ms
+/// restart:
+///   on-failure:
+///     max-retries: 5
+///     backoff: 100ms
 /// configuration:
 ///   answer: 42
 /// ```
@@ -85,7 +98,16 @@ pub(crate) enum SourceVariants {
 pub(crate) struct CustomSourceDescriptor {
     pub description: Option<Arc<str>>,
     pub library: Url,
-    pub outputs: Vec<PortId>,
+    pub outputs: Vec<PortDescriptor>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_period",
+        serialize_with = "serialize_period",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub period: Option<Duration>,
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    pub restart: RestartPolicy,
     #[serde(default)]
     pub configuration: Configuration,
 }
@@ -0,0 +1,274 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{Labels, NodeId, Result, RuntimeId};
+
+use crate::FlattenedDataFlowDescriptor;
+
+/// A `PlacementDescriptor` describes, through constraints, the Zenoh-Flow runtimes on which a node can run.
+///
+/// Contrary to the `mapping` section, the constraints do not name a runtime: they are resolved against the Zenoh-Flow
+/// runtimes that are reachable when the data flow is instantiated (see
+/// [try_place](FlattenedDataFlowDescriptor::try_place())).
+///
+/// All the sections are *optional*:
+/// - `labels`: the [Labels] the runtime must have, with the same value.
+/// - `affinity`: the nodes that must run on the same runtime as this node.
+/// - `anti-affinity`: the nodes that must *not* run on the same runtime as this node.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_descriptors::PlacementDescriptor;
+/// # let yaml = r#"
+/// labels:
+///   arch: aarch64
+///   camera: true
+/// affinity:
+///   - Operator
+/// anti-affinity:
+///   - Sink
+/// # "#;
+/// # serde_yaml::from_str::<PlacementDescriptor>(yaml).unwrap();
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct PlacementDescriptor {
+    /// The labels the Zenoh-Flow runtime must have.
+    #[serde(default)]
+    pub labels: Labels,
+    /// The nodes that must run on the same Zenoh-Flow runtime.
+    #[serde(default)]
+    pub affinity: Vec<NodeId>,
+    /// The nodes that must not run on the same Zenoh-Flow runtime.
+    #[serde(default)]
+    pub anti_affinity: Vec<NodeId>,
+}
+
+/// A `RuntimeCandidate` is a Zenoh-Flow runtime on which nodes can be placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeCandidate {
+    /// The unique identifier of the Zenoh-Flow runtime.
+    pub id: RuntimeId,
+    /// The labels describing the Zenoh-Flow runtime.
+    pub labels: Labels,
+    /// How many additional nodes the Zenoh-Flow runtime can manage. `None` means that it has no limit.
+    pub available: Option<usize>,
+}
+
+impl FlattenedDataFlowDescriptor {
+    /// Resolves the [placement](PlacementDescriptor) constraints of the nodes against the provided Zenoh-Flow
+    /// `runtimes`, adding the nodes to the `mapping` of this data flow.
+    ///
+    /// The nodes linked through an `affinity` are placed together. A group of nodes where one node is already mapped is
+    /// placed on that runtime, provided that it satisfies the constraints. Otherwise, the group is placed on the runtime
+    /// that satisfies the constraints and that has the most nodes available, the `default_runtime` being preferred in
+    /// case of a tie. The nodes without constraints that are not involved in an `affinity` are left untouched, except
+    /// the ones referenced in an `anti-affinity` that are not mapped: they are mapped on the `default_runtime`.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if:
+    /// - the nodes that must run together are mapped on different runtimes, require different values for the same
+    ///   label or are also required to run on different runtimes,
+    /// - a group of nodes is mapped on a runtime that does not satisfy its constraints,
+    /// - no runtime satisfies the constraints of a group of nodes.
+    pub fn try_place(
+        &mut self,
+        runtimes: &[RuntimeCandidate],
+        default_runtime: &RuntimeId,
+    ) -> Result<()> {
+        if self.placement.is_empty() {
+            return Ok(());
+        }
+
+        let mut assignments = HashMap::<NodeId, RuntimeId>::default();
+        for (runtime_id, nodes) in self.mapping.iter() {
+            for node_id in nodes.iter() {
+                assignments.insert(node_id.clone(), runtime_id.clone());
+            }
+        }
+
+        let mut available = runtimes
+            .iter()
+            .map(|runtime| (runtime.id.clone(), runtime.available))
+            .collect::<HashMap<_, _>>();
+
+        let groups = self.affinity_groups();
+
+        // The nodes referenced in an `anti-affinity` that are neither mapped nor part of a group would only be assigned
+        // to the `default_runtime` when the record is created: they are assigned to it now, such that the nodes that
+        // must run apart from them are not placed there as well.
+        let mut unconstrained = self
+            .placement
+            .values()
+            .flat_map(|placement| placement.anti_affinity.iter())
+            .filter(|&node_id| {
+                !assignments.contains_key(node_id)
+                    && !groups.iter().any(|group| group.contains(node_id))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        unconstrained.sort_by_key(|node_id| node_id.to_string());
+        unconstrained.dedup();
+        if let Some(Some(count)) = available.get_mut(default_runtime) {
+            *count = count.saturating_sub(unconstrained.len());
+        }
+        for node_id in unconstrained {
+            assignments.insert(node_id, default_runtime.clone());
+        }
+
+        for group in groups {
+            let mut selector = Labels::default();
+            let mut anti_affinity = HashSet::<&NodeId>::default();
+            for node_id in group.iter() {
+                if let Some(placement) = self.placement.get(node_id) {
+                    for (key, value) in placement.labels.iter() {
+                        if let Some(previous) = selector.insert(key.clone(), value.clone()) {
+                            if previous != *value {
+                                bail!(
+                                    "The nodes {:?} must run together but require different values for label < {} >",
+                                    group,
+                                    key
+                                );
+                            }
+                        }
+                    }
+                    anti_affinity.extend(placement.anti_affinity.iter());
+                }
+            }
+            for (node_id, placement) in self.placement.iter() {
+                if placement.anti_affinity.iter().any(|id| group.contains(id)) {
+                    anti_affinity.insert(node_id);
+                }
+            }
+
+            if let Some(node_id) = group.iter().find(|&id| anti_affinity.contains(id)) {
+                bail!(
+                    "Node < {} > must run together with, and apart from, some of the nodes {:?}",
+                    node_id,
+                    group
+                );
+            }
+
+            let forbidden = anti_affinity
+                .iter()
+                .filter_map(|&node_id| assignments.get(node_id))
+                .collect::<HashSet<_>>();
+            let unassigned = group
+                .iter()
+                .filter(|&node_id| !assignments.contains_key(node_id))
+                .count();
+            let satisfies = |runtime: &RuntimeCandidate| {
+                runtime.labels.matches(&selector) && !forbidden.contains(&runtime.id)
+            };
+
+            let mapped = group
+                .iter()
+                .filter_map(|node_id| assignments.get(node_id))
+                .collect::<HashSet<_>>();
+            let runtime_id = match mapped.len() {
+                0 => runtimes
+                    .iter()
+                    .filter(|&runtime| {
+                        satisfies(runtime)
+                            && available[&runtime.id].map_or(true, |count| count >= unassigned)
+                    })
+                    .min_by_key(|&runtime| {
+                        (
+                            std::cmp::Reverse(available[&runtime.id].unwrap_or(usize::MAX)),
+                            runtime.id != *default_runtime,
+                            runtime.id.to_string(),
+                        )
+                    })
+                    .map(|runtime| runtime.id.clone())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Found no runtime satisfying the placement of nodes {:?} (labels: {})",
+                            group,
+                            selector
+                        )
+                    })?,
+                1 => {
+                    let runtime_id = mapped.into_iter().next().unwrap().clone();
+                    let satisfied = match runtimes.iter().find(|runtime| runtime.id == runtime_id) {
+                        Some(runtime) => satisfies(runtime),
+                        None => selector.is_empty() && !forbidden.contains(&runtime_id),
+                    };
+                    if !satisfied {
+                        bail!(
+                            "The nodes {:?} are mapped on runtime < {} >, which violates their placement",
+                            group,
+                            runtime_id
+                        );
+                    }
+                    runtime_id
+                }
+                _ => bail!(
+                    "The nodes {:?} must run together but are mapped on different runtimes",
+                    group
+                ),
+            };
+
+            if let Some(Some(count)) = available.get_mut(&runtime_id) {
+                *count = count.saturating_sub(unassigned);
+            }
+            for node_id in group {
+                assignments
+                    .entry(node_id)
+                    .or_insert_with(|| runtime_id.clone());
+            }
+        }
+
+        self.mapping.clear();
+        for (node_id, runtime_id) in assignments {
+            self.mapping.entry(runtime_id).or_default().insert(node_id);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the groups of nodes that must run together, i.e. the nodes linked through an `affinity`, restricted to
+    /// the groups where at least one node has a placement. The groups, and the nodes they contain, are sorted.
+    fn affinity_groups(&self) -> Vec<Vec<NodeId>> {
+        let mut groups: Vec<Vec<NodeId>> = Vec::default();
+        let mut sorted_placement = self.placement.iter().collect::<Vec<_>>();
+        sorted_placement.sort_by_key(|(node_id, _)| node_id.to_string());
+
+        for (node_id, placement) in sorted_placement {
+            let members = std::iter::once(node_id)
+                .chain(placement.affinity.iter())
+                .collect::<HashSet<_>>();
+
+            let mut merged = members.into_iter().cloned().collect::<Vec<_>>();
+            groups.retain(|group| {
+                if group.iter().any(|id| merged.contains(id)) {
+                    merged.extend(group.iter().cloned());
+                    return false;
+                }
+                true
+            });
+            merged.sort_by_key(|id| id.to_string());
+            merged.dedup();
+            groups.push(merged);
+        }
+
+        groups.sort_by_key(|group| group[0].to_string());
+        groups
+    }
+}
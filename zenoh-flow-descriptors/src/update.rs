@@ -0,0 +1,107 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{Configuration, NodeId, RuntimeId};
+
+use crate::{
+    mapping::MappingEntry,
+    nodes::{operator::OperatorDescriptor, sink::SinkDescriptor, source::SourceDescriptor},
+    LinkDescriptor,
+};
+
+/// A `DataFlowUpdateDescriptor` describes the changes to apply to the graph of a data flow instance that is already
+/// loaded: the nodes and links to add and the ones to remove.
+///
+/// Like a [DataFlowDescriptor](crate::DataFlowDescriptor), it is meant to be parsed from a textual representation and
+/// then *[flattened](crate::FlattenedDataFlowUpdate::try_flatten())*.
+///
+/// # Structure
+///
+/// All the sections are *optional*:
+/// - `configuration`, `sources`, `operators`, `sinks` and `links`: the nodes and links to add, described exactly as in
+///   a data flow descriptor. A node whose identifier is already used by the data flow *replaces* the existing node,
+///   keeping its links.
+/// - `mapping`: where the nodes should run. The nodes added without a mapping are assigned to the Zenoh-Flow runtime
+///   that orchestrates the data flow instance, the nodes replaced without a mapping keep theirs. As in a data flow
+///   descriptor, a node can be mapped alongside its [failover](crate::FailoverPolicy) policy.
+/// - `remove`: the identifiers of the `nodes` to remove (their links are removed as well) and the `links` to remove.
+///   A link is identified by its `from` and `to` sections.
+///
+/// The removals are processed before the additions.
+///
+/// # Example
+///
+/// The update below attaches a debug Sink to the output of an existing Operator and removes a Sink.
+///
+/// ```
+/// # use zenoh_flow_descriptors::DataFlowUpdateDescriptor;
+/// # let yaml = r#"
+/// sinks:
+///   - id: Debug
+///     library: "file:///home/zenoh-flow/nodes/libdebug.so"
+///     inputs:
+///       - i-debug
+///
+/// links:
+///   - from:
+///       node: Operator
+///       output: o-operator
+///     to:
+///       node: Debug
+///       input: i-debug
+///
+/// remove:
+///   nodes:
+///     - Remote-Sink
+/// # "#;
+/// # let update = serde_yaml::from_str::<DataFlowUpdateDescriptor>(yaml).unwrap();
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DataFlowUpdateDescriptor {
+    /// *(optional)* Pairs of `(key, value)` that are transmitted to the nodes added at their creation.
+    #[serde(default)]
+    pub(crate) configuration: Configuration,
+    /// *(optional)* The Source(s) to add or replace.
+    #[serde(default)]
+    pub(crate) sources: Vec<SourceDescriptor>,
+    /// *(optional)* The Operator(s) to add or replace.
+    #[serde(default)]
+    pub(crate) operators: Vec<OperatorDescriptor>,
+    /// *(optional)* The Sink(s) to add or replace.
+    #[serde(default)]
+    pub(crate) sinks: Vec<SinkDescriptor>,
+    /// *(optional)* The links to add.
+    #[serde(default)]
+    pub(crate) links: Vec<LinkDescriptor>,
+    /// *(optional)* On which Zenoh-Flow runtime the nodes added or replaced should run.
+    #[serde(default)]
+    pub(crate) mapping: HashMap<RuntimeId, HashSet<MappingEntry>>,
+    /// *(optional)* The nodes and links to remove.
+    #[serde(default)]
+    pub(crate) remove: RemovalDescriptor,
+}
+
+/// The nodes and links to remove from a data flow instance.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct RemovalDescriptor {
+    /// The identifiers of the nodes to remove, alongside all their links.
+    #[serde(default)]
+    pub nodes: Vec<NodeId>,
+    /// The links to remove, identified by their `from` and `to` sections.
+    #[serde(default)]
+    pub links: Vec<LinkDescriptor>,
+}
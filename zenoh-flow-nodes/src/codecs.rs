@@ -0,0 +1,155 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Ready-to-use codecs for typed [Inputs](crate::prelude::Input) and [Outputs](crate::prelude::Output).
+//!
+//! Instead of providing serialisation and deserialisation closures, a node can rely on one of the codecs exposed in
+//! this module through the `typed_with` methods of the [InputBuilder](crate::InputBuilder::typed_with()) and
+//! [OutputBuilder](crate::OutputBuilder::typed_with()).
+//!
+//! Except for [Bincode], which Zenoh-Flow already uses internally, each codec is gated behind a feature of the same
+//! name: `json`, `cbor`, `msgpack` and `protobuf`.
+//!
+//! The codec used by a port can be recorded in its declaration, see the [Encoding] of each codec.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use zenoh_flow_nodes::prelude::*;
+//! # let mut inputs = Inputs::default();
+//! # let mut outputs = Outputs::default();
+//! let input = inputs
+//!     .take("in")
+//!     .expect("No input called 'in' found")
+//!     .typed_with::<u64, codecs::Bincode>();
+//!
+//! let output = outputs
+//!     .take("out")
+//!     .expect("No output called 'out' found")
+//!     .typed_with::<u64, codecs::Bincode>();
+//! ```
+
+use anyhow::Context;
+use zenoh_flow_commons::{Encoding, Result};
+
+/// A `Codec` encodes instances of `T` into bytes and decodes them back.
+pub trait Codec<T>: Send + Sync + 'static {
+    /// The [Encoding] produced by this codec.
+    const ENCODING: Encoding;
+
+    /// Encodes the `data` into the `buffer`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the data could not be encoded.
+    fn encode(buffer: &mut Vec<u8>, data: &T) -> Result<()>;
+
+    /// Decodes an instance of `T` from the `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the bytes are not a valid encoding of `T`.
+    fn decode(bytes: &[u8]) -> Result<T>;
+}
+
+/// A [Codec] relying on [bincode].
+pub struct Bincode;
+
+impl<T: serde::Serialize + serde::de::DeserializeOwned> Codec<T> for Bincode {
+    const ENCODING: Encoding = Encoding::Bincode;
+
+    fn encode(buffer: &mut Vec<u8>, data: &T) -> Result<()> {
+        bincode::serialize_into(buffer, data).context("Failed to encode data with bincode")
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T> {
+        bincode::deserialize(bytes).context("Failed to decode data with bincode")
+    }
+}
+
+/// A [Codec] relying on `serde_json`.
+#[cfg(feature = "json")]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned> Codec<T> for Json {
+    const ENCODING: Encoding = Encoding::Json;
+
+    fn encode(buffer: &mut Vec<u8>, data: &T) -> Result<()> {
+        serde_json::to_writer(buffer, data).context("Failed to encode data in JSON")
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T> {
+        serde_json::from_slice(bytes).context("Failed to decode JSON data")
+    }
+}
+
+/// A [Codec] relying on `serde_cbor`.
+#[cfg(feature = "cbor")]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned> Codec<T> for Cbor {
+    const ENCODING: Encoding = Encoding::Cbor;
+
+    fn encode(buffer: &mut Vec<u8>, data: &T) -> Result<()> {
+        serde_cbor::to_writer(buffer, data).context("Failed to encode data in CBOR")
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T> {
+        serde_cbor::from_slice(bytes).context("Failed to decode CBOR data")
+    }
+}
+
+/// A [Codec] relying on `rmp-serde`.
+///
+/// Structures are encoded as maps (i.e. with the name of their fields) such that nodes written in other programming
+/// languages can decode them.
+#[cfg(feature = "msgpack")]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned> Codec<T> for MessagePack {
+    const ENCODING: Encoding = Encoding::MessagePack;
+
+    fn encode(buffer: &mut Vec<u8>, data: &T) -> Result<()> {
+        rmp_serde::encode::write_named(buffer, data).context("Failed to encode data in MessagePack")
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T> {
+        rmp_serde::from_slice(bytes).context("Failed to decode MessagePack data")
+    }
+}
+
+/// A [Codec] relying on `prost`, for structures generated from Protocol Buffers definitions.
+#[cfg(feature = "protobuf")]
+pub struct Protobuf;
+
+#[cfg(feature = "protobuf")]
+impl<T: prost::Message + Default> Codec<T> for Protobuf {
+    const ENCODING: Encoding = Encoding::Protobuf;
+
+    fn encode(buffer: &mut Vec<u8>, data: &T) -> Result<()> {
+        data.encode(buffer)
+            .context("Failed to encode data with Protocol Buffers")
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T> {
+        T::decode(bytes).context("Failed to decode Protocol Buffers data")
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/codecs-tests.rs"]
+mod tests;
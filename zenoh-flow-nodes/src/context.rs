@@ -12,23 +12,46 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
+use uhlc::HLC;
 use zenoh_flow_commons::{InstanceId, NodeId, RuntimeId};
 
+use crate::{
+    io::Statistics,
+    timer::{Timer, TimerMode},
+};
+
 /// The `Context` structure provides information about the data flow and the Zenoh-Flow runtime.
 ///
 /// In particular, it allows accessing:
 /// - the [name](Context::name()) of the data flow,
 /// - the [instance id](Context::instance_id()) of this instance of the data flow,
-/// - the [runtime id](Context::runtime_id()) of the Zenoh-Flow runtime managing the **node**.
-#[derive(Clone, Debug)]
+/// - the [runtime id](Context::runtime_id()) of the Zenoh-Flow runtime managing the **node**,
+/// - the [statistics](Context::statistics()) of the ports of the **node**,
+/// - [timers](Context::fixed_rate_timer()) synchronised with the [HLC](uhlc::HLC) of the Zenoh-Flow runtime.
+#[derive(Clone)]
 pub struct Context {
     pub(crate) node_id: NodeId,
     pub(crate) flow_name: Arc<str>,
     pub(crate) instance_id: InstanceId,
     pub(crate) runtime_id: RuntimeId,
     pub(crate) library_path: Arc<PathBuf>,
+    pub(crate) statistics: Statistics,
+    pub(crate) hlc: Arc<HLC>,
+}
+
+impl std::fmt::Debug for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("node_id", &self.node_id)
+            .field("flow_name", &self.flow_name)
+            .field("instance_id", &self.instance_id)
+            .field("runtime_id", &self.runtime_id)
+            .field("library_path", &self.library_path)
+            .field("statistics", &self.statistics)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Context {
@@ -46,9 +69,25 @@ impl Context {
             runtime_id,
             library_path,
             node_id,
+            statistics: Statistics::default(),
+            hlc: Arc::new(HLC::default()),
         }
     }
 
+    /// Sets the [Statistics] of the ports of the node.
+    pub fn with_statistics(mut self, statistics: Statistics) -> Self {
+        self.statistics = statistics;
+        self
+    }
+
+    /// Sets the [HLC](uhlc::HLC) used to timestamp the ticks of the [Timer]s.
+    ///
+    /// This should be the same HLC as the one used by the Zenoh-Flow runtime.
+    pub fn with_hlc(mut self, hlc: Arc<HLC>) -> Self {
+        self.hlc = hlc;
+        self
+    }
+
     /// Returns the name of the data flow.
     ///
     /// Note all instances of the same data flow will share the same `name`.
@@ -80,4 +119,33 @@ impl Context {
     pub fn node_id(&self) -> &NodeId {
         &self.node_id
     }
+
+    /// Returns the statistics of the Inputs and Outputs of the node.
+    ///
+    /// They notably expose, per Input, the number of messages that were lost or duplicated on the way.
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// Returns a [Timer] that ticks every `period`, compensating for the time spent between two ticks.
+    ///
+    /// See [TimerMode::FixedRate].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the `period` is zero.
+    pub fn fixed_rate_timer(&self, period: Duration) -> Timer {
+        Timer::new(period, TimerMode::FixedRate, self.hlc.clone())
+    }
+
+    /// Returns a [Timer] that ticks `delay` after it was last polled.
+    ///
+    /// See [TimerMode::FixedDelay].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the `delay` is zero.
+    pub fn fixed_delay_timer(&self, delay: Duration) -> Timer {
+        Timer::new(delay, TimerMode::FixedDelay, self.hlc.clone())
+    }
 }
@@ -16,10 +16,14 @@ use std::{collections::HashMap, ops::Deref, sync::Arc};
 
 use anyhow::{anyhow, bail};
 use flume::TryRecvError;
-use uhlc::Timestamp;
 use zenoh_flow_commons::{PortId, Result};
 
-use crate::messages::{Data, DeserializerFn, LinkMessage};
+use super::statistics::{InputCounters, InputStatistics};
+use crate::{
+    codecs::Codec,
+    messages::{Data, DeserializerFn, LinkMessage, Message, TypedMessage},
+    trace::Tracer,
+};
 
 /// The `Inputs` structure contains all the inputs created for a [Sink](crate::prelude::Sink) or an
 /// [Operator](crate::prelude::Operator).
@@ -51,12 +55,14 @@ use crate::messages::{Data, DeserializerFn, LinkMessage};
 /// ```
 #[derive(Default)]
 pub struct Inputs {
-    pub(crate) hmap: HashMap<PortId, flume::Receiver<LinkMessage>>,
+    pub(crate) hmap: HashMap<PortId, flume::Receiver<Message>>,
+    pub(crate) counters: HashMap<PortId, Arc<InputCounters>>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
 }
 
 // Dereferencing on the internal `HashMap` allows users to call all the methods implemented on it: `keys()` for one.
 impl Deref for Inputs {
-    type Target = HashMap<PortId, flume::Receiver<LinkMessage>>;
+    type Target = HashMap<PortId, flume::Receiver<Message>>;
 
     fn deref(&self) -> &Self::Target {
         &self.hmap
@@ -64,8 +70,23 @@ impl Deref for Inputs {
 }
 
 impl Inputs {
+    /// Sets the [Tracer] of the node, shared with its [Outputs](crate::prelude::Outputs), through which the trace
+    /// context of the messages received is followed.
+    #[doc(hidden)]
+    pub fn with_tracer(mut self, tracer: Arc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Returns the [Tracer] of the node, if one was set.
+    #[doc(hidden)]
+    pub fn tracer(&self) -> Option<Arc<Tracer>> {
+        self.tracer.clone()
+    }
+
     /// Insert the `flume::Receiver` in the [Inputs], creating the entry if needed in the internal `HashMap`.
-    pub fn insert(&mut self, port_id: PortId, rx: flume::Receiver<LinkMessage>) {
+    pub fn insert(&mut self, port_id: PortId, rx: flume::Receiver<Message>) {
+        self.counters.entry(port_id.clone()).or_default();
         self.hmap.entry(port_id).or_insert(rx);
     }
 
@@ -76,7 +97,7 @@ impl Inputs {
     ///
     /// This builder can either produce a, typed, [`Input<T>`](Input) or an [InputRaw]. The main difference between both
     /// is the type of data they expose: an [`Input<T>`](Input) automatically tries to downcast or deserialize the data
-    /// contained in the message to expose `&T`, while an [InputRaw] simply exposes a [LinkMessage].
+    /// contained in the message to expose `&T`, while an [InputRaw] simply exposes a [Message].
     ///
     /// ## Typed
     ///
@@ -110,6 +131,12 @@ impl Inputs {
             .map(|receiver| InputBuilder {
                 port_id: port_id.as_ref().into(),
                 receiver,
+                counters: self
+                    .counters
+                    .get(&port_id.as_ref().into())
+                    .cloned()
+                    .unwrap_or_default(),
+                tracer: self.tracer.clone(),
             })
     }
 }
@@ -118,22 +145,23 @@ impl Inputs {
 ///
 /// The main difference between both is the type of data they expose: an [`Input<T>`] automatically
 /// tries to downcast or deserialize the data contained in the message to expose `&T`, while an
-/// [InputRaw] simply exposes a [LinkMessage].
+/// [InputRaw] simply exposes a [Message].
 ///
-/// # Planned evolution
+/// # Queues
 ///
-/// Zenoh-Flow will allow tweaking the behaviour of the underlying channels. For now, the
-/// `receivers` channels are _unbounded_ and do not implement a dropping policy, which could lead to
-/// issues.
+/// The behaviour of the underlying channel is dictated by the `queue` section of the link in the descriptor of the
+/// data flow. By default, the channel is _unbounded_.
 pub struct InputBuilder {
     pub(crate) port_id: PortId,
-    pub(crate) receiver: flume::Receiver<LinkMessage>,
+    pub(crate) receiver: flume::Receiver<Message>,
+    pub(crate) counters: Arc<InputCounters>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
 }
 
 impl InputBuilder {
     /// Consume the `InputBuilder` to produce an [InputRaw].
     ///
-    /// An [InputRaw] exposes the [LinkMessage] it receives, without trying to perform any
+    /// An [InputRaw] exposes the [Message] it receives, without trying to perform any
     /// conversion on the data.
     ///
     /// The [InputRaw] was designed for use cases such as load-balancing or rate-limiting. In these
@@ -158,6 +186,8 @@ impl InputBuilder {
         InputRaw {
             port_id: self.port_id,
             receiver: self.receiver,
+            counters: self.counters,
+            tracer: self.tracer,
         }
     }
 
@@ -191,18 +221,44 @@ impl InputBuilder {
             deserializer: Arc::new(deserializer),
         }
     }
+
+    /// Consume the `InputBuilder` to produce an [`Input<T>`] that deserialises the data it receives with the [Codec]
+    /// `C`.
+    ///
+    /// See the [codecs](crate::codecs) module for the codecs provided by Zenoh-Flow. The encoding of the codec can be
+    /// recorded in the declaration of the port, in the descriptor of the node.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use zenoh_flow_nodes::prelude::*;
+    /// # let mut inputs = Inputs::default();
+    /// let input: Input<u64> = inputs
+    ///     .take("test typed")
+    ///     .expect("No input name 'test typed' found")
+    ///     .typed_with::<u64, codecs::Bincode>();
+    /// ```
+    pub fn typed_with<T: Send + Sync + 'static, C: Codec<T>>(self) -> Input<T> {
+        self.typed(C::decode)
+    }
 }
 
-/// An `InputRaw` receives "raw" [LinkMessage].
+/// An `InputRaw` receives "raw" [Message].
 ///
 /// As opposed to a typed [`Input<T>`](Input), an `InputRaw` will not perform any operation on the data it receives.
 /// This behaviour is useful when access to the underlying data is either irrelevant (e.g. for rate-limiting purposes)
 /// or when Zenoh-Flow should not attempt to interpret the contained [Payload](crate::prelude::Payload) (e.g. for
 /// bindings).
+///
+/// # Loss detection
+///
+/// The sequence numbers of the messages received are checked, see [statistics](InputRaw::statistics()).
 #[derive(Clone, Debug)]
 pub struct InputRaw {
     pub(crate) port_id: PortId,
-    pub(crate) receiver: flume::Receiver<LinkMessage>,
+    pub(crate) receiver: flume::Receiver<Message>,
+    pub(crate) counters: Arc<InputCounters>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
 }
 
 impl InputRaw {
@@ -215,7 +271,34 @@ impl InputRaw {
         self.receiver.len()
     }
 
-    /// Returns the first queued [LinkMessage] or [None] if there is no queued message.
+    /// Returns the capacity of the queue of this Input or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.receiver.capacity()
+    }
+
+    /// Returns the number of messages received, lost and duplicated since the creation of this Input.
+    ///
+    /// Lost and duplicated messages are detected through the sequence number each Output stamps on the messages it
+    /// sends. Watermarks are not accounted for.
+    pub fn statistics(&self) -> InputStatistics {
+        self.counters.snapshot(self.receiver.len())
+    }
+
+    // Account for the reception of the message, checking its sequence number and following its trace context.
+    fn account(&self, message: Message) -> Message {
+        self.counters.activity.consume();
+        if let Message::Data(ref data) = message {
+            self.counters
+                .record(&self.port_id, data.sequence(), data.payload().bytes_len());
+            if let Some(tracer) = &self.tracer {
+                tracer.enter(&self.port_id, data.headers());
+            }
+        }
+
+        message
+    }
+
+    /// Returns the first queued [Message] or [None] if there is no queued message.
     ///
     /// # Asynchronous alternative: `recv`
     ///
@@ -225,9 +308,9 @@ impl InputRaw {
     /// # Errors
     ///
     /// An error is returned if the associated channel is disconnected.
-    pub fn try_recv(&self) -> Result<Option<LinkMessage>> {
+    pub fn try_recv(&self) -> Result<Option<Message>> {
         match self.receiver.try_recv() {
-            Ok(message) => Ok(Some(message)),
+            Ok(message) => Ok(Some(self.account(message))),
             Err(e) => match e {
                 TryRecvError::Empty => Ok(None),
                 TryRecvError::Disconnected => {
@@ -238,19 +321,24 @@ impl InputRaw {
         }
     }
 
-    /// Returns the first [LinkMessage] that was received, *asynchronously*, on any of the channels associated with this
+    /// Returns the first [Message] that was received, *asynchronously*, on any of the channels associated with this
     /// Input.
     ///
-    /// If several [LinkMessage] are received at the same time, one is *randomly* selected.
+    /// If several [Message] are received at the same time, one is *randomly* selected.
     ///
     /// # Errors
     ///
     /// An error is returned if a channel was disconnected.
-    pub async fn recv(&self) -> Result<LinkMessage> {
-        self.receiver.recv_async().await.map_err(|_| {
-            tracing::error!("Link disconnected: {}", self.port_id);
-            anyhow!("Disconnected")
-        })
+    pub async fn recv(&self) -> Result<Message> {
+        let _waiting = self.counters.activity.wait();
+        self.receiver
+            .recv_async()
+            .await
+            .map(|message| self.account(message))
+            .map_err(|_| {
+                tracing::error!("Link disconnected: {}", self.port_id);
+                anyhow!("Disconnected")
+            })
     }
 }
 
@@ -280,12 +368,13 @@ impl<T: Send + Sync + 'static> Deref for Input<T> {
 }
 
 impl<T: Send + Sync + 'static> Input<T> {
-    /// Returns the first [`Data<T>`](Data) that was received, *asynchronously*, on any of the channels
+    /// Returns the first [`TypedMessage<T>`](TypedMessage) that was received, *asynchronously*, on any of the channels
     /// associated with this Input.
     ///
-    /// If several [`Data<T>`](Data) are received at the same time, one is *randomly* selected.
+    /// If several messages are received at the same time, one is *randomly* selected.
     ///
-    /// This method interprets the data to the type associated with this [`Input<T>`](Input).
+    /// This method interprets the data to the type associated with this [`Input<T>`](Input). Watermarks, that carry no
+    /// data, are exposed as the distinct [Watermark](TypedMessage::Watermark) variant.
     ///
     /// # Performance
     ///
@@ -302,16 +391,13 @@ impl<T: Send + Sync + 'static> Input<T> {
     /// Several errors can occur:
     /// - a channel was disconnected,
     /// - Zenoh-Flow failed at interpreting the received data as an instance of `T`.
-    pub async fn recv(&self) -> Result<(Data<T>, Timestamp)> {
-        let LinkMessage { payload, timestamp } = self.input_raw.recv().await?;
-        Ok((
-            Data::try_from_payload(payload, self.deserializer.clone())?,
-            timestamp,
-        ))
+    pub async fn recv(&self) -> Result<TypedMessage<T>> {
+        let message = self.input_raw.recv().await?;
+        self.try_interpret(message)
     }
 
-    /// Returns the first [`Data<T>`](Data) that was received on any of the channels associated with this Input,
-    /// or [None] if all the channels are empty.
+    /// Returns the first [`TypedMessage<T>`](TypedMessage) that was received on any of the channels associated with
+    /// this Input, or [None] if all the channels are empty.
     ///
     /// # Performance
     ///
@@ -331,15 +417,27 @@ impl<T: Send + Sync + 'static> Input<T> {
     /// - Zenoh-Flow failed at interpreting the received data as an instance of `T`.
     ///
     /// Note that if some channels are disconnected, for each of such channel an error is logged.
-    pub fn try_recv(&self) -> Result<Option<(Data<T>, Timestamp)>> {
-        if let Some(LinkMessage { payload, timestamp }) = self.input_raw.try_recv()? {
-            return Ok(Some((
-                Data::try_from_payload(payload, self.deserializer.clone())?,
+    pub fn try_recv(&self) -> Result<Option<TypedMessage<T>>> {
+        self.input_raw
+            .try_recv()?
+            .map(|message| self.try_interpret(message))
+            .transpose()
+    }
+
+    // Converts a raw [Message] into a [TypedMessage], downcasting or deserialising its payload.
+    pub(crate) fn try_interpret(&self, message: Message) -> Result<TypedMessage<T>> {
+        match message {
+            Message::Data(LinkMessage {
+                payload,
                 timestamp,
-            )));
+                headers,
+                ..
+            }) => Ok(TypedMessage::Data(
+                Data::try_from_payload(payload, headers, self.deserializer.clone())?,
+                timestamp,
+            )),
+            Message::Watermark(timestamp) => Ok(TypedMessage::Watermark(timestamp)),
         }
-
-        Ok(None)
     }
 }
 
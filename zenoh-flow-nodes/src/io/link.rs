@@ -0,0 +1,166 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use flume::{Receiver, SendError, Sender, TrySendError};
+use zenoh_flow_commons::{QueueConfiguration, QueuePolicy};
+
+use crate::messages::Message;
+
+/// (⚙️ *internal)* Creates the channel backing a link, honouring its (optional) [QueueConfiguration].
+///
+/// If no queue configuration is provided, the channel is *unbounded*.
+pub fn link_channel(queue: Option<&QueueConfiguration>) -> (LinkSender, Receiver<Message>) {
+    let Some(queue) = queue else {
+        let (tx, rx) = flume::unbounded();
+        return (tx.into(), rx);
+    };
+
+    let (tx, rx) = flume::bounded(queue.capacity);
+    let overflow = match queue.policy {
+        QueuePolicy::Block => Overflow::Block,
+        QueuePolicy::DropNewest => Overflow::DropNewest,
+        QueuePolicy::DropOldest => Overflow::DropOldest(rx.clone()),
+    };
+
+    (
+        LinkSender {
+            sender: tx,
+            overflow,
+        },
+        rx,
+    )
+}
+
+/// (⚙️ *internal)* The sending half of a link: a channel and what to do when it is full.
+#[derive(Clone, Debug)]
+pub struct LinkSender {
+    pub(crate) sender: Sender<Message>,
+    pub(crate) overflow: Overflow,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Overflow {
+    Block,
+    DropNewest,
+    // A `flume::Sender` cannot remove messages from its channel. To evict the oldest message we thus keep a clone of
+    // the `Receiver`.
+    //
+    // A side effect is that the channel is never seen as disconnected by the sender.
+    DropOldest(Receiver<Message>),
+}
+
+/// What happened to a message sent on a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Delivery {
+    Delivered,
+    /// The message was delivered but, the queue being full, the oldest message it contained was discarded.
+    DroppedOldest,
+    /// The queue being full, the message was discarded.
+    DroppedNewest,
+}
+
+// A link created from a bare `flume::Sender` blocks when its channel is full, which is how links behaved before queues
+// could be configured.
+impl From<Sender<Message>> for LinkSender {
+    fn from(sender: Sender<Message>) -> Self {
+        Self {
+            sender,
+            overflow: Overflow::Block,
+        }
+    }
+}
+
+impl LinkSender {
+    /// Returns the [QueuePolicy] of this link.
+    pub fn policy(&self) -> QueuePolicy {
+        match self.overflow {
+            Overflow::Block => QueuePolicy::Block,
+            Overflow::DropNewest => QueuePolicy::DropNewest,
+            Overflow::DropOldest(_) => QueuePolicy::DropOldest,
+        }
+    }
+
+    /// Returns the capacity of the queue of this link or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+
+    /// Returns the number of messages waiting in the queue of this link.
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// Returns `true` if no message is waiting in the queue of this link.
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+
+    /// Attempts to send, *synchronously*, the message on the link, applying its policy if the queue is full.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the channel is disconnected or if the queue is full and the policy is
+    /// [Block](QueuePolicy::Block).
+    pub(crate) fn try_send(
+        &self,
+        message: Message,
+    ) -> std::result::Result<Delivery, TrySendError<Message>> {
+        let message = match self.sender.try_send(message) {
+            Ok(()) => return Ok(Delivery::Delivered),
+            Err(TrySendError::Full(message)) => message,
+            Err(e) => return Err(e),
+        };
+
+        match &self.overflow {
+            Overflow::Block => Err(TrySendError::Full(message)),
+            Overflow::DropNewest => Ok(Delivery::DroppedNewest),
+            Overflow::DropOldest(evictor) => {
+                let mut message = message;
+                // NOTE: The receiving end can only free space in the queue. Hence, unless several clones of the same
+                // Output are sending concurrently, a single eviction is enough.
+                loop {
+                    let _ = evictor.try_recv();
+                    match self.sender.try_send(message) {
+                        Ok(()) => return Ok(Delivery::DroppedOldest),
+                        Err(TrySendError::Full(returned)) => message = returned,
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends, *asynchronously*, the message on the link, applying its policy if the queue is full.
+    ///
+    /// Only a link with the [Block](QueuePolicy::Block) policy will wait for space to be available in its queue.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the channel is disconnected.
+    pub(crate) async fn send_async(
+        &self,
+        message: Message,
+    ) -> std::result::Result<Delivery, SendError<Message>> {
+        match self.overflow {
+            Overflow::Block => self
+                .sender
+                .send_async(message)
+                .await
+                .map(|_| Delivery::Delivered),
+            Overflow::DropNewest | Overflow::DropOldest(_) => self
+                .try_send(message)
+                .map_err(|e| SendError(e.into_inner())),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use crate::messages::Message;
+
+/// The `Mirrors` of an Output are additional channels, attached and detached while the node runs, that receive a copy
+/// of every message sent on it.
+///
+/// They are shared by all the clones of the [OutputRaw](crate::prelude::OutputRaw) and by the Zenoh-Flow runtime,
+/// which uses them to observe the traffic of a link without modifying the data flow.
+///
+/// Mirroring never slows down the node: a message that does not fit in the channel of a mirror is discarded for that
+/// mirror only. A mirror is detached as soon as its receiving half is dropped.
+#[derive(Debug, Default)]
+pub struct Mirrors {
+    count: AtomicUsize,
+    senders: Mutex<Vec<flume::Sender<Message>>>,
+}
+
+impl Mirrors {
+    /// Attaches a new mirror, returning the receiving half of its channel.
+    ///
+    /// The channel is bounded by `capacity`.
+    pub fn attach(&self, capacity: usize) -> flume::Receiver<Message> {
+        let (tx, rx) = flume::bounded(capacity);
+        if let Ok(mut senders) = self.senders.lock() {
+            senders.push(tx);
+            self.count.store(senders.len(), Ordering::Relaxed);
+        }
+
+        rx
+    }
+
+    /// Returns the number of mirrors attached.
+    ///
+    /// Mirrors whose receiving half was dropped are only detached when the next message is sent.
+    pub fn len(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if no mirror is attached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends a copy of the `message` to all the mirrors, detaching the ones whose receiving half was dropped.
+    pub(crate) fn mirror(&self, message: &Message) {
+        if self.is_empty() {
+            return;
+        }
+
+        if let Ok(mut senders) = self.senders.lock() {
+            senders.retain(|sender| {
+                !matches!(
+                    sender.try_send(message.clone()),
+                    Err(flume::TrySendError::Disconnected(_))
+                )
+            });
+            self.count.store(senders.len(), Ordering::Relaxed);
+        }
+    }
+}
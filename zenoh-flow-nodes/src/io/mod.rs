@@ -13,9 +13,17 @@
 //
 
 mod inputs;
+mod link;
+mod mirror;
 mod outputs;
+mod statistics;
+mod sync;
 
 pub use self::{
     inputs::{Input, InputBuilder, InputRaw, Inputs},
+    link::{link_channel, LinkSender},
+    mirror::Mirrors,
     outputs::{Output, OutputBuilder, OutputRaw, Outputs},
+    statistics::{InputStatistics, NodeStatistics, OutputStatistics, Statistics},
+    sync::{InputTuple, Latest, Select, Zip},
 };
@@ -12,14 +12,27 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{collections::HashMap, marker::PhantomData, ops::Deref, sync::Arc};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    ops::Deref,
+    sync::{atomic::Ordering, Arc},
+};
 
 use anyhow::bail;
-use flume::Sender;
 use uhlc::{Timestamp, HLC};
-use zenoh_flow_commons::{PortId, Result};
-
-use crate::messages::{Data, LinkMessage, Payload, SerializerFn};
+use zenoh_flow_commons::{PortId, QueuePolicy, Result};
+
+use super::{
+    link::{Delivery, LinkSender},
+    mirror::Mirrors,
+    statistics::{OutputCounters, OutputStatistics},
+};
+use crate::{
+    codecs::Codec,
+    messages::{Data, Headers, LinkMessage, Message, Payload, SerializerFn},
+    trace::Tracer,
+};
 
 /// The [Outputs] structure contains all the outputs created for a [Source](crate::prelude::Source) or an
 /// [Operator](crate::prelude::Operator).
@@ -32,13 +45,16 @@ use crate::messages::{Data, LinkMessage, Payload, SerializerFn};
 /// contains.
 #[derive(Default)]
 pub struct Outputs {
-    pub(crate) hmap: HashMap<PortId, Vec<flume::Sender<LinkMessage>>>,
+    pub(crate) hmap: HashMap<PortId, Vec<LinkSender>>,
+    pub(crate) counters: HashMap<PortId, Arc<OutputCounters>>,
+    pub(crate) mirrors: HashMap<PortId, Arc<Mirrors>>,
     pub(crate) hlc: Arc<HLC>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
 }
 
 // Dereferencing on the internal [HashMap] allows users to call all the methods implemented on it: `keys()` for one.
 impl Deref for Outputs {
-    type Target = HashMap<PortId, Vec<flume::Sender<LinkMessage>>>;
+    type Target = HashMap<PortId, Vec<LinkSender>>;
 
     fn deref(&self) -> &Self::Target {
         &self.hmap
@@ -49,14 +65,60 @@ impl Outputs {
     pub fn new(hlc: Arc<HLC>) -> Self {
         Self {
             hmap: HashMap::default(),
+            counters: HashMap::default(),
+            mirrors: HashMap::default(),
             hlc,
+            tracer: None,
         }
     }
 
-    /// Insert the `flume::Sender` in the [Outputs], creating the entry if needed in the internal
+    /// Sets the [Tracer] of the node, shared with its [Inputs](crate::prelude::Inputs), through which the trace context
+    /// is propagated to the messages sent.
+    #[doc(hidden)]
+    pub fn with_tracer(mut self, tracer: Arc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Returns the [Tracer] of the node, if one was set.
+    #[doc(hidden)]
+    pub fn tracer(&self) -> Option<Arc<Tracer>> {
+        self.tracer.clone()
+    }
+
+    /// Returns the [Mirrors] of the Outputs, indexed by their port identifier.
+    #[doc(hidden)]
+    pub fn mirrors(&self) -> HashMap<PortId, Arc<Mirrors>> {
+        self.mirrors.clone()
+    }
+
+    /// Returns an [OutputRaw] sending on the channels of the Output `port_id`, without taking it.
+    ///
+    /// The messages sent through it are accounted for in the statistics of the Output. This allows the Zenoh-Flow
+    /// runtime to send messages in place of the node, when replaying a recording for instance.
+    #[doc(hidden)]
+    pub fn handle(&self, port_id: &PortId) -> Option<OutputRaw> {
+        self.hmap.get(port_id).map(|senders| {
+            OutputBuilder {
+                port_id: port_id.clone(),
+                senders: senders.clone(),
+                counters: self.counters.get(port_id).cloned().unwrap_or_default(),
+                mirrors: self.mirrors.get(port_id).cloned().unwrap_or_default(),
+                hlc: Arc::clone(&self.hlc),
+                tracer: None,
+            }
+            .raw()
+        })
+    }
+
+    /// Insert the sending half of a link in the [Outputs], creating the entry if needed in the internal
     /// `HashMap`.
-    pub fn insert(&mut self, port_id: PortId, tx: Sender<LinkMessage>) {
-        self.hmap.entry(port_id).or_default().push(tx)
+    ///
+    /// A bare `flume::Sender` is accepted, in which case the link will block when its channel is full.
+    pub fn insert(&mut self, port_id: PortId, tx: impl Into<LinkSender>) {
+        self.counters.entry(port_id.clone()).or_default();
+        self.mirrors.entry(port_id.clone()).or_default();
+        self.hmap.entry(port_id).or_default().push(tx.into())
     }
 
     /// Returns an Output builder for the provided `port_id`, if an output was declared with this exact name in the
@@ -104,7 +166,18 @@ impl Outputs {
             .map(|senders| OutputBuilder {
                 port_id: port_id.as_ref().into(),
                 senders,
+                counters: self
+                    .counters
+                    .get(&port_id.as_ref().into())
+                    .cloned()
+                    .unwrap_or_default(),
+                mirrors: self
+                    .mirrors
+                    .get(&port_id.as_ref().into())
+                    .cloned()
+                    .unwrap_or_default(),
                 hlc: Arc::clone(&self.hlc),
+                tracer: self.tracer.clone(),
             })
     }
 }
@@ -114,20 +187,23 @@ impl Outputs {
 /// The main difference between both is the type of data they accept: an [Output] accepts anything that is `Into<T>`
 /// while an [OutputRaw] accepts a [LinkMessage] or anything that is `Into<Payload>`.
 ///
-/// # Planned evolution
+/// # Queues
 ///
-/// Zenoh-Flow will allow tweaking the behaviour of the underlying channels. For now, the `senders` channels are
-/// _unbounded_ and do not implement a dropping policy, which could lead to issues.
+/// The behaviour of the underlying channels is dictated by the `queue` section of each link in the descriptor of the
+/// data flow. By default, channels are _unbounded_. See the [QueuePolicy] for the behaviour of a bounded channel.
 pub struct OutputBuilder {
     pub(crate) port_id: PortId,
-    pub(crate) senders: Vec<flume::Sender<LinkMessage>>,
+    pub(crate) senders: Vec<LinkSender>,
+    pub(crate) counters: Arc<OutputCounters>,
+    pub(crate) mirrors: Arc<Mirrors>,
     pub(crate) hlc: Arc<HLC>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
 }
 
 impl OutputBuilder {
     /// Consume this `OutputBuilder` to produce an [OutputRaw].
     ///
-    /// An [OutputRaw] sends [LinkMessage]s (through `forward`) or anything that is `Into<Payload>` (through `send` and
+    /// An [OutputRaw] sends [Message]s (through `forward`) or anything that is `Into<Payload>` (through `send` and
     /// `try_send`) to downstream nodes.
     ///
     /// The [OutputRaw] was designed for use cases such as load-balancing or rate-limiting. In this scenarios, the node
@@ -154,6 +230,23 @@ impl OutputBuilder {
             port_id: self.port_id,
             senders: self.senders,
             hlc: self.hlc,
+            counters: self.counters,
+            mirrors: self.mirrors,
+            tracer: self.tracer,
+            relay: false,
+        }
+    }
+
+    /// Consume this `OutputBuilder` to produce an [OutputRaw] that does not stamp its own sequence numbers on the
+    /// messages it forwards.
+    ///
+    /// This is used by the connectors such that the sequence numbers stamped by the Output of the upstream node, on
+    /// another Zenoh-Flow runtime, reach the downstream node. Losses on the network can then be detected.
+    #[doc(hidden)]
+    pub fn relay(self) -> OutputRaw {
+        OutputRaw {
+            relay: true,
+            ..self.raw()
         }
     }
 
@@ -203,18 +296,58 @@ impl OutputBuilder {
             }),
         }
     }
+
+    /// Consume this `OutputBuilder` to produce an [`Output<T>`](Output) that serialises the data it sends, when needed,
+    /// with the [Codec] `C`.
+    ///
+    /// See the [codecs](crate::codecs) module for the codecs provided by Zenoh-Flow. The encoding of the codec can be
+    /// recorded in the declaration of the port, in the descriptor of the node.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use zenoh_flow_nodes::prelude::*;
+    /// # let mut outputs = Outputs::default();
+    /// let output: Output<u64> = outputs
+    ///     .take("test")
+    ///     .expect("No key named 'test' found")
+    ///     .typed_with::<u64, codecs::Bincode>();
+    /// ```
+    pub fn typed_with<T: Send + Sync + 'static, C: Codec<T>>(self) -> Output<T> {
+        self.typed(C::encode)
+    }
 }
 
-/// An [OutputRaw] sends [LinkMessage] or [`Into<Payload>`](crate::prelude::Payload) to downstream nodes.
+/// An [OutputRaw] sends [Message] or [`Into<Payload>`](crate::prelude::Payload) to downstream nodes.
 ///
 /// Its primary purpose is to ensure optimal performance: any message received on an input can
 /// transparently be sent downstream, without requiring (a potentially expensive) access to the data
 /// it contained.
+///
+/// # Watermarks
+///
+/// Besides data, an [OutputRaw] (and thus an [`Output<T>`](Output)) can send watermarks: timestamp-only markers telling
+/// the downstream nodes that no data older than this timestamp will follow. See
+/// [send_watermark](OutputRaw::send_watermark()).
+///
+/// # Queues
+///
+/// If a link has a bounded queue, the [QueuePolicy] decides what happens when it is full. Messages discarded by the
+/// `drop-oldest` and `drop-newest` policies are not errors: they are counted, see [dropped](OutputRaw::dropped()).
+///
+/// # Sequence numbers
+///
+/// Each message (watermarks excluded) sent is stamped with a monotonically increasing sequence number, allowing the
+/// downstream Inputs to detect lost or duplicated messages.
 #[derive(Clone)]
 pub struct OutputRaw {
     pub(crate) port_id: PortId,
-    pub(crate) senders: Vec<flume::Sender<LinkMessage>>,
+    pub(crate) senders: Vec<LinkSender>,
     pub(crate) hlc: Arc<HLC>,
+    pub(crate) counters: Arc<OutputCounters>,
+    pub(crate) mirrors: Arc<Mirrors>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
+    pub(crate) relay: bool,
 }
 
 impl OutputRaw {
@@ -224,6 +357,53 @@ impl OutputRaw {
             .unwrap_or_else(|| self.hlc.new_timestamp())
     }
 
+    // Keep track of the messages that were discarded because of the policy of a link.
+    fn account(&self, delivery: Delivery) {
+        let discarded = match delivery {
+            Delivery::Delivered => return,
+            Delivery::DroppedOldest => "oldest",
+            Delivery::DroppedNewest => "newest",
+        };
+
+        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        tracing::debug!(
+            "[Output: {}] Queue is full, discarded the {} message",
+            self.port_id,
+            discarded
+        );
+    }
+
+    /// Returns the number of messages discarded, since the creation of this Output, because a queue was full.
+    ///
+    /// Only the links with a `drop-oldest` or `drop-newest` [QueuePolicy] discard messages.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages sent and discarded since the creation of this Output.
+    pub fn statistics(&self) -> OutputStatistics {
+        self.counters.snapshot()
+    }
+
+    // Stamp the sequence number of this Output on the message, unless it is relayed, account for its size and
+    // propagate the trace context of the node.
+    fn stamp(&self, message: impl Into<Message>) -> Message {
+        match message.into() {
+            Message::Data(mut message) => {
+                let sequence = self.counters.next_sequence();
+                self.counters.add_bytes(message.payload.bytes_len());
+                if !self.relay {
+                    message.sequence = sequence;
+                }
+                if let Some(tracer) = &self.tracer {
+                    tracer.inject(&mut message.headers);
+                }
+                Message::Data(message)
+            }
+            watermark => watermark,
+        }
+    }
+
     /// Returns the port id associated with this Output.
     pub fn port_id(&self) -> &PortId {
         &self.port_id
@@ -242,25 +422,37 @@ impl OutputRaw {
     /// `forward`. Hence, although synchronous, this method will not block the thread on which it is
     /// executed.
     ///
+    /// # Queues
+    ///
+    /// When the queue of a link is full, its [QueuePolicy] applies: a `block` link fails (as this method cannot wait)
+    /// while `drop-oldest` and `drop-newest` links discard a message and account for it.
+    ///
     /// # Errors
     ///
     /// If an error occurs while sending the message on a channel, Zenoh-Flow still tries to send it
     /// on the remaining channels. For each failing channel, an error is logged.
-    pub(crate) fn try_forward(&self, message: LinkMessage) -> Result<()> {
+    pub(crate) fn try_forward(&self, message: impl Into<Message>) -> Result<()> {
+        let message = self.stamp(message);
+        self.mirrors.mirror(&message);
         let mut err_count = 0;
-        self.senders.iter().for_each(|sender| {
-            if let Err(e) = sender.try_send(message.clone()) {
-                err_count += 1;
-                match e {
-                    flume::TrySendError::Full(_) => {
-                        tracing::error!("[Output: {}] Channel is full", self.port_id)
-                    }
-                    flume::TrySendError::Disconnected(_) => {
-                        tracing::error!("[Output: {}] Channel disconnected", self.port_id)
+        self.senders
+            .iter()
+            .for_each(|sender| match sender.try_send(message.clone()) {
+                Ok(delivery) => self.account(delivery),
+                Err(e) => {
+                    err_count += 1;
+                    match e {
+                        flume::TrySendError::Full(_) => tracing::error!(
+                            "[Output: {}] Channel is full (policy: {})",
+                            self.port_id,
+                            QueuePolicy::Block
+                        ),
+                        flume::TrySendError::Disconnected(_) => {
+                            tracing::error!("[Output: {}] Channel disconnected", self.port_id)
+                        }
                     }
                 }
-            }
-        });
+            });
 
         if err_count > 0 {
             bail!(
@@ -288,21 +480,39 @@ impl OutputRaw {
     /// If an error occurs while sending the watermark on a channel, Zenoh-Flow still tries to send
     /// it on the remaining channels. For each failing channel, an error is logged and counted for.
     pub fn try_send(&self, payload: impl Into<Payload>, timestamp: Option<u64>) -> Result<()> {
+        self.try_send_with_headers(payload, timestamp, Headers::default())
+    }
+
+    /// Attempt to send, *synchronously*, the `data` and its [Headers] on all channels to the downstream Nodes.
+    ///
+    /// See [try_send](OutputRaw::try_send()) for the other parameters and the errors.
+    pub fn try_send_with_headers(
+        &self,
+        payload: impl Into<Payload>,
+        timestamp: Option<u64>,
+        headers: Headers,
+    ) -> Result<()> {
         let message = LinkMessage {
             payload: payload.into(),
             timestamp: self.make_timestamp(timestamp),
+            headers,
+            sequence: 0,
         };
 
         self.try_forward(message)
     }
 
-    /// Forward, *asynchronously*, the [LinkMessage] on all channels to the downstream Nodes.
+    /// Forward, *asynchronously*, the [Message] on all channels to the downstream Nodes.
+    ///
+    /// Only the links with a `block` [QueuePolicy] will wait for their queue to have enough space.
     ///
     /// # Errors
     ///
     /// If an error occurs while sending the message on a channel, Zenoh-Flow still tries to send it on the remaining
     /// channels. For each failing channel, an error is logged and counted for.
-    pub async fn forward(&self, message: LinkMessage) -> Result<()> {
+    pub async fn forward(&self, message: impl Into<Message>) -> Result<()> {
+        let message = self.stamp(message);
+        self.mirrors.mirror(&message);
         // FIXME Feels like a cheap hack counting the number of errors. To improve.
         let mut err = 0;
         let fut_senders = self
@@ -312,8 +522,9 @@ impl OutputRaw {
         // `join_all` executes all futures concurrently.
         let res = futures::future::join_all(fut_senders).await;
 
-        res.iter().for_each(|res| {
-            if let Err(e) = res {
+        res.into_iter().for_each(|res| match res {
+            Ok(delivery) => self.account(delivery),
+            Err(e) => {
                 tracing::error!(
                     "[Output: {}] Error occurred while sending to downstream node(s): {:?}",
                     self.port_id(),
@@ -344,13 +555,64 @@ impl OutputRaw {
     /// If an error occurs while sending the watermark on a channel, Zenoh-Flow still tries to send
     /// it on the remaining channels. For each failing channel, an error is logged and counted for.
     pub async fn send(&self, payload: impl Into<Payload>, timestamp: Option<u64>) -> Result<()> {
+        self.send_with_headers(payload, timestamp, Headers::default())
+            .await
+    }
+
+    /// Send, *asynchronously*, the `data` and its [Headers] on all channels to the downstream Nodes.
+    ///
+    /// See [send](OutputRaw::send()) for the other parameters and the errors.
+    pub async fn send_with_headers(
+        &self,
+        payload: impl Into<Payload>,
+        timestamp: Option<u64>,
+        headers: Headers,
+    ) -> Result<()> {
         let message = LinkMessage {
             payload: payload.into(),
             timestamp: self.make_timestamp(timestamp),
+            headers,
+            sequence: 0,
         };
 
         self.forward(message).await
     }
+
+    /// Attempt to send, *synchronously*, a watermark on all channels to the downstream Nodes.
+    ///
+    /// A watermark tells the downstream Nodes that no data older than its timestamp will be sent on this Output.
+    ///
+    /// If no `timestamp` is provided, the current timestamp (as per the [HLC](uhlc::HLC) used by
+    /// the Zenoh-Flow daemon running this Node) is taken.
+    ///
+    /// # Asynchronous alternative: `send_watermark`
+    ///
+    /// This method is a synchronous fail-fast alternative to its asynchronous counterpart: `send_watermark`.
+    /// Hence, although synchronous, this method will not block the thread on which it is executed.
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs while sending the watermark on a channel, Zenoh-Flow still tries to send
+    /// it on the remaining channels. For each failing channel, an error is logged and counted for.
+    pub fn try_send_watermark(&self, timestamp: Option<u64>) -> Result<()> {
+        self.try_forward(Message::Watermark(self.make_timestamp(timestamp)))
+    }
+
+    /// Send, *asynchronously*, a watermark on all channels to the downstream Nodes.
+    ///
+    /// A watermark tells the downstream Nodes that no data older than its timestamp will be sent on this Output.
+    ///
+    /// If no `timestamp` is provided, the current timestamp — as per the [HLC](uhlc::HLC) used by
+    /// the Zenoh-Flow daemon running this Node — is taken.
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs while sending the watermark on a channel, Zenoh-Flow still tries to send
+    /// it on the remaining channels. For each failing channel, an error is logged and counted for.
+    pub async fn send_watermark(&self, timestamp: Option<u64>) -> Result<()> {
+        self.forward(Message::Watermark(self.make_timestamp(timestamp)))
+            .await
+    }
 }
 
 /// An `Output<T>` (only) sends instances of `T` to downstream nodes.
@@ -379,11 +641,14 @@ impl<T: Send + Sync + 'static> Output<T> {
         &self,
         data: impl Into<Data<T>>,
         timestamp: Option<u64>,
+        headers: Headers,
     ) -> Result<LinkMessage> {
         let payload = Payload::from_data(data.into(), Arc::clone(&self.serializer));
         Ok(LinkMessage {
             payload,
             timestamp: self.make_timestamp(timestamp),
+            headers,
+            sequence: 0,
         })
     }
 
@@ -400,8 +665,23 @@ impl<T: Send + Sync + 'static> Output<T> {
     ///
     /// An error is returned if the send operation failed.
     pub async fn send(&self, data: impl Into<Data<T>>, timestamp: Option<u64>) -> Result<()> {
+        self.send_with_headers(data, timestamp, Headers::default())
+            .await
+    }
+
+    /// Send, *asynchronously*, the provided `data` and its [Headers] to downstream node(s).
+    ///
+    /// The headers of a received [`Data<T>`](Data) are not propagated: they have to be explicitly provided.
+    ///
+    /// See [send](Output::send()) for the other parameters and the errors.
+    pub async fn send_with_headers(
+        &self,
+        data: impl Into<Data<T>>,
+        timestamp: Option<u64>,
+        headers: Headers,
+    ) -> Result<()> {
         self.output_raw
-            .forward(self.construct_message(data, timestamp)?)
+            .forward(self.construct_message(data, timestamp, headers)?)
             .await
     }
 
@@ -418,8 +698,22 @@ impl<T: Send + Sync + 'static> Output<T> {
     ///
     /// An error is returned if sending on a channel failed.
     pub fn try_send(&self, data: impl Into<Data<T>>, timestamp: Option<u64>) -> Result<()> {
+        self.try_send_with_headers(data, timestamp, Headers::default())
+    }
+
+    /// Send, *synchronously*, the provided `data` and its [Headers] to downstream node(s).
+    ///
+    /// The headers of a received [`Data<T>`](Data) are not propagated: they have to be explicitly provided.
+    ///
+    /// See [try_send](Output::try_send()) for the other parameters and the errors.
+    pub fn try_send_with_headers(
+        &self,
+        data: impl Into<Data<T>>,
+        timestamp: Option<u64>,
+        headers: Headers,
+    ) -> Result<()> {
         self.output_raw
-            .try_forward(self.construct_message(data, timestamp)?)
+            .try_forward(self.construct_message(data, timestamp, headers)?)
     }
 }
 
@@ -0,0 +1,351 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::PortId;
+
+use super::{Inputs, Outputs};
+use crate::messages::Message;
+
+/// The counters of an Output, shared by all the clones of the [OutputRaw](crate::prelude::OutputRaw).
+#[derive(Debug, Default)]
+pub(crate) struct OutputCounters {
+    sent: AtomicU64,
+    bytes: AtomicU64,
+    typed: AtomicBool,
+    pub(crate) dropped: AtomicU64,
+}
+
+impl OutputCounters {
+    /// Accounts for a message sent on this Output, returning its sequence number.
+    ///
+    /// Sequence numbers start at 1: 0 indicates a message that was not sent through an Output.
+    pub(crate) fn next_sequence(&self) -> u64 {
+        self.sent.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Accounts for the size of a payload sent on this Output: `None` indicates a typed payload, whose size is unknown.
+    pub(crate) fn add_bytes(&self, bytes: Option<usize>) {
+        add_bytes(&self.bytes, &self.typed, bytes);
+    }
+
+    pub(crate) fn snapshot(&self) -> OutputStatistics {
+        OutputStatistics {
+            sent: self.sent.load(Ordering::Relaxed),
+            bytes: load_bytes(&self.bytes, &self.typed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Keeps track of whether a node is waiting for a message and of how many messages it consumed.
+///
+/// The Zenoh-Flow runtime relies on it to stop a node without interrupting the processing of a message.
+#[derive(Debug, Default)]
+pub(crate) struct Activity {
+    waiting: AtomicUsize,
+    consumed: AtomicU64,
+}
+
+impl Activity {
+    /// Marks the node as waiting for a message until the returned guard is dropped.
+    pub(crate) fn wait(&self) -> Waiting<'_> {
+        self.waiting.fetch_add(1, Ordering::AcqRel);
+        Waiting(&self.waiting)
+    }
+
+    /// Accounts for a message consumed by the node.
+    pub(crate) fn consume(&self) {
+        self.consumed.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.waiting.load(Ordering::Acquire) > 0
+    }
+
+    fn consumed(&self) -> u64 {
+        self.consumed.load(Ordering::Acquire)
+    }
+}
+
+/// A guard marking a node as waiting for a message, see [Activity::wait].
+pub(crate) struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The counters of an Input, shared by all the clones of the [InputRaw](crate::prelude::InputRaw).
+#[derive(Debug, Default)]
+pub(crate) struct InputCounters {
+    pub(crate) activity: Activity,
+    last_sequence: AtomicU64,
+    received: AtomicU64,
+    bytes: AtomicU64,
+    typed: AtomicBool,
+    lost: AtomicU64,
+    duplicates: AtomicU64,
+}
+
+impl InputCounters {
+    /// Accounts for the reception of the message with the provided `sequence` number and the size of its payload in
+    /// `bytes` (`None` if the payload is typed).
+    ///
+    /// As a link connects a single Output to an Input, the sequence numbers received should be contiguous: a gap
+    /// indicates lost messages while a sequence number that is not greater than the last one indicates a duplicate (or
+    /// a message received out of order). The gaps are only computed after the first message is received.
+    ///
+    /// The sequence number 1 indicates that the upstream Output restarted (e.g. its node was re-created by an update or
+    /// re-placed on another runtime, or its runtime restarted): the tracking starts over.
+    pub(crate) fn record(&self, port_id: &PortId, sequence: u64, bytes: Option<usize>) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        add_bytes(&self.bytes, &self.typed, bytes);
+
+        // Messages that were not sent through an Output (e.g. created by hand) carry no sequence number.
+        if sequence == 0 {
+            return;
+        }
+
+        if sequence == 1 {
+            let last = self.last_sequence.swap(sequence, Ordering::Relaxed);
+            if last != 0 {
+                tracing::debug!(
+                    "[Input: {}] The upstream Output restarted (last sequence number: {})",
+                    port_id,
+                    last
+                );
+            }
+            return;
+        }
+
+        let last = self.last_sequence.fetch_max(sequence, Ordering::Relaxed);
+        if last == 0 {
+            return;
+        }
+
+        if sequence <= last {
+            self.duplicates.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(
+                "[Input: {}] Received a duplicate: sequence number {} (last: {})",
+                port_id,
+                sequence,
+                last
+            );
+        } else if sequence > last + 1 {
+            self.lost.fetch_add(sequence - last - 1, Ordering::Relaxed);
+            tracing::debug!(
+                "[Input: {}] Lost {} message(s) between sequence numbers {} and {}",
+                port_id,
+                sequence - last - 1,
+                last,
+                sequence
+            );
+        }
+    }
+
+    pub(crate) fn snapshot(&self, pending: usize) -> InputStatistics {
+        InputStatistics {
+            pending: pending as u64,
+            received: self.received.load(Ordering::Relaxed),
+            bytes: load_bytes(&self.bytes, &self.typed),
+            lost: self.lost.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Typed payloads are only serialised when they cross a runtime: their size is unknown to the ports. Once a port
+// carried one, its total size is not reported rather than under-estimated.
+fn add_bytes(total: &AtomicU64, typed: &AtomicBool, bytes: Option<usize>) {
+    match bytes {
+        Some(bytes) => {
+            total.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+        None => typed.store(true, Ordering::Relaxed),
+    }
+}
+
+fn load_bytes(total: &AtomicU64, typed: &AtomicBool) -> Option<u64> {
+    (!typed.load(Ordering::Relaxed)).then(|| total.load(Ordering::Relaxed))
+}
+
+/// The statistics of an Input: how many messages it received and, based on their sequence numbers, how many were lost
+/// or duplicated on the way.
+///
+/// Messages can be lost when a queue with a `drop-oldest` or `drop-newest` policy is full or, for a link that crosses
+/// Zenoh-Flow runtimes, on the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputStatistics {
+    /// The number of messages (watermarks excluded) received.
+    pub received: u64,
+    /// The number of messages that never reached this Input.
+    pub lost: u64,
+    /// The number of messages received more than once or out of order.
+    pub duplicates: u64,
+    /// The number of messages (watermarks included) waiting in the queue of this Input.
+    #[serde(default)]
+    pub pending: u64,
+    /// The number of bytes received, `None` if at least one payload received was typed.
+    ///
+    /// Typed payloads, exchanged between nodes of the same runtime, are not serialised: their size is unknown.
+    #[serde(default)]
+    pub bytes: Option<u64>,
+}
+
+/// The statistics of an Output: how many messages it sent and how many were discarded because a queue was full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputStatistics {
+    /// The number of messages (watermarks excluded) sent.
+    pub sent: u64,
+    /// The number of messages discarded by a `drop-oldest` or `drop-newest` queue.
+    pub dropped: u64,
+    /// The number of bytes sent, `None` if at least one payload sent was typed.
+    ///
+    /// Typed payloads, exchanged between nodes of the same runtime, are not serialised: their size is unknown.
+    #[serde(default)]
+    pub bytes: Option<u64>,
+}
+
+/// A snapshot of the [Statistics] of all the ports of a node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeStatistics {
+    pub inputs: HashMap<PortId, InputStatistics>,
+    pub outputs: HashMap<PortId, OutputStatistics>,
+}
+
+/// The `Statistics` give access, at any time, to the counters of all the ports of a node.
+///
+/// They are accessible to the node through its [Context](crate::prelude::Context).
+///
+/// # Sequence numbers
+///
+/// Each Output stamps the messages it sends with a monotonically increasing sequence number. Each Input then checks
+/// that the sequence numbers it receives are contiguous, accounting for the messages that were lost or duplicated.
+#[derive(Clone, Debug, Default)]
+pub struct Statistics {
+    inputs: HashMap<PortId, Arc<InputCounters>>,
+    outputs: HashMap<PortId, Arc<OutputCounters>>,
+    receivers: HashMap<PortId, flume::Receiver<Message>>,
+    // The activity of the node outside of its Inputs, see `wait_for`.
+    activity: Arc<Activity>,
+}
+
+impl Statistics {
+    /// Creates the `Statistics` of the node that will receive the provided [Inputs] and [Outputs].
+    ///
+    /// This method must be called before the ports are taken by the node.
+    pub fn new(inputs: &Inputs, outputs: &Outputs) -> Self {
+        Self {
+            inputs: inputs.counters.clone(),
+            outputs: outputs.counters.clone(),
+            receivers: inputs.hmap.clone(),
+            activity: Arc::default(),
+        }
+    }
+
+    /// Returns the statistics of the Input `port_id`, if the node has such an Input.
+    pub fn input(&self, port_id: impl AsRef<str>) -> Option<InputStatistics> {
+        let port_id: PortId = port_id.as_ref().into();
+        self.inputs
+            .get(&port_id)
+            .map(|counters| counters.snapshot(self.pending_on(&port_id)))
+    }
+
+    /// Returns the statistics of the Output `port_id`, if the node has such an Output.
+    pub fn output(&self, port_id: impl AsRef<str>) -> Option<OutputStatistics> {
+        self.outputs
+            .get(&port_id.as_ref().into())
+            .map(|counters| counters.snapshot())
+    }
+
+    /// Returns the statistics of all the ports of the node.
+    pub fn snapshot(&self) -> NodeStatistics {
+        NodeStatistics {
+            inputs: self
+                .inputs
+                .iter()
+                .map(|(port_id, counters)| {
+                    (port_id.clone(), counters.snapshot(self.pending_on(port_id)))
+                })
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|(port_id, counters)| (port_id.clone(), counters.snapshot()))
+                .collect(),
+        }
+    }
+
+    /// Returns the total number of messages waiting in the queues of the Inputs of the node.
+    ///
+    /// The Zenoh-Flow runtime relies on this number to know when the messages in flight were drained.
+    pub fn pending(&self) -> usize {
+        self.receivers.values().map(|receiver| receiver.len()).sum()
+    }
+
+    /// Returns `true` if the node is waiting for a message: on one of its Inputs or, through
+    /// [wait_for](Statistics::wait_for()), from outside of the data flow.
+    ///
+    /// The Zenoh-Flow runtime relies on it to stop a node without interrupting the processing of a message.
+    pub fn is_waiting(&self) -> bool {
+        self.activity.is_waiting()
+            || self
+                .inputs
+                .values()
+                .any(|counters| counters.activity.is_waiting())
+    }
+
+    /// Returns the total number of messages, watermarks included, consumed by the node: received on its Inputs or,
+    /// through [wait_for](Statistics::wait_for()), from outside of the data flow.
+    pub fn consumed(&self) -> u64 {
+        self.activity.consumed()
+            + self
+                .inputs
+                .values()
+                .map(|counters| counters.activity.consumed())
+                .sum::<u64>()
+    }
+
+    /// Awaits the `future`, marking the node as waiting for a message until it completes.
+    ///
+    /// A Source that waits for data coming from outside of the data flow should wrap that wait with this method: the
+    /// Zenoh-Flow runtime can then stop (or checkpoint) it without interrupting the processing of a message. The
+    /// `future` can thus be dropped before it completes, it must be cancel-safe.
+    pub async fn wait_for<F: Future>(&self, future: F) -> F::Output {
+        let output = {
+            let _waiting = self.activity.wait();
+            future.await
+        };
+        self.activity.consume();
+        output
+    }
+
+    fn pending_on(&self, port_id: &PortId) -> usize {
+        self.receivers
+            .get(port_id)
+            .map(|receiver| receiver.len())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,510 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Helpers to synchronise the messages received on several inputs.
+//!
+//! Zenoh-Flow provides three combinators, all built from a tuple of (up to four) typed [Input]s:
+//! - a [Zip], that matches the messages of all its inputs based on their [Timestamp],
+//! - a [Latest], that returns the most recent message of each of its inputs,
+//! - a [Select], that returns the first message received on any of its inputs.
+//!
+//! All of them serve their inputs in a round-robin fashion: an input receiving messages at a high rate cannot starve
+//! the others.
+
+use std::{any::Any, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail};
+use futures::lock::Mutex;
+use uhlc::{Timestamp, NTP64};
+use zenoh_flow_commons::{PortId, Result};
+
+use super::{Input, InputRaw};
+use crate::messages::{Data, LinkMessage, Message, TypedMessage};
+
+type ErasedData = Arc<dyn Any + Send + Sync>;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A tuple of typed [Input]s that can be synchronised.
+///
+/// This trait is implemented for tuples of two, three and four [Input]s. It cannot be implemented outside of
+/// Zenoh-Flow.
+pub trait InputTuple: sealed::Sealed {
+    /// The tuple returned by a [Zip]: a `(Data<T>, Timestamp)` per input.
+    type Zipped;
+    /// The tuple returned by a [Latest]: a `(Arc<Data<T>>, Timestamp)` per input.
+    type Latest;
+    /// The tuple returned by a [Select]: an `Option<TypedMessage<T>>` per input, only one of which is `Some`.
+    type Selected;
+
+    #[doc(hidden)]
+    fn raw_inputs(&self) -> Vec<InputRaw>;
+
+    #[doc(hidden)]
+    fn try_zip(&self, messages: Vec<LinkMessage>) -> Result<Self::Zipped>;
+
+    #[doc(hidden)]
+    fn try_erase(&self, index: usize, message: LinkMessage) -> Result<ErasedData>;
+
+    #[doc(hidden)]
+    fn latest(values: &[(ErasedData, Timestamp)]) -> Result<Self::Latest>;
+
+    #[doc(hidden)]
+    fn try_select(&self, index: usize, message: Message) -> Result<Self::Selected>;
+}
+
+macro_rules! impl_input_tuple {
+    ($($index:tt $T:ident),+) => {
+        impl<$($T: Send + Sync + 'static),+> sealed::Sealed for ($(Input<$T>,)+) {}
+
+        impl<$($T: Send + Sync + 'static),+> InputTuple for ($(Input<$T>,)+) {
+            type Zipped = ($((Data<$T>, Timestamp),)+);
+            type Latest = ($((Arc<Data<$T>>, Timestamp),)+);
+            type Selected = ($(Option<TypedMessage<$T>>,)+);
+
+            fn raw_inputs(&self) -> Vec<InputRaw> {
+                vec![$(self.$index.input_raw.clone()),+]
+            }
+
+            fn try_zip(&self, messages: Vec<LinkMessage>) -> Result<Self::Zipped> {
+                let mut messages = messages.into_iter();
+                Ok(($({
+                    let LinkMessage { payload, timestamp, headers, .. } = messages
+                        .next()
+                        .ok_or_else(|| anyhow!("Missing message for input < {} >", self.$index.port_id()))?;
+                    (
+                        Data::try_from_payload(payload, headers, self.$index.deserializer.clone())?,
+                        timestamp,
+                    )
+                },)+))
+            }
+
+            fn try_erase(&self, index: usize, message: LinkMessage) -> Result<ErasedData> {
+                match index {
+                    $($index => Ok(Arc::new(Data::try_from_payload(
+                        message.payload,
+                        message.headers,
+                        self.$index.deserializer.clone(),
+                    )?) as ErasedData),)+
+                    _ => bail!("No input at index {}", index),
+                }
+            }
+
+            fn latest(values: &[(ErasedData, Timestamp)]) -> Result<Self::Latest> {
+                Ok(($({
+                    let (data, timestamp) = values
+                        .get($index)
+                        .ok_or_else(|| anyhow!("No value for the input at index {}", $index))?;
+                    let data = data
+                        .clone()
+                        .downcast::<Data<$T>>()
+                        .map_err(|_| anyhow!("Failed to downcast the value of the input at index {}", $index))?;
+                    (data, *timestamp)
+                },)+))
+            }
+
+            fn try_select(&self, index: usize, message: Message) -> Result<Self::Selected> {
+                let mut selected: Self::Selected = ($(None::<TypedMessage<$T>>,)+);
+                match index {
+                    $($index => selected.$index = Some(self.$index.try_interpret(message)?),)+
+                    _ => bail!("No input at index {}", index),
+                }
+
+                Ok(selected)
+            }
+        }
+    };
+}
+
+impl_input_tuple!(0 A, 1 B);
+impl_input_tuple!(0 A, 1 B, 2 C);
+impl_input_tuple!(0 A, 1 B, 2 C, 3 D);
+
+// Returns `true` if `timestamp` is older than `reference` by more than `tolerance`.
+fn is_older(timestamp: &Timestamp, reference: &Timestamp, tolerance: NTP64) -> bool {
+    timestamp
+        .get_time()
+        .as_u64()
+        .saturating_add(tolerance.as_u64())
+        < reference.get_time().as_u64()
+}
+
+// Receives the first message available on one of the `candidates` inputs.
+//
+// The candidates are polled starting from the one following the input that was last served (as indicated by the
+// `cursor`) such that an input receiving messages at a high rate cannot starve the others.
+async fn recv_fair(
+    inputs: &[InputRaw],
+    candidates: &[usize],
+    cursor: &mut usize,
+) -> Result<(usize, Message)> {
+    if candidates.is_empty() {
+        bail!("No input to receive from");
+    }
+
+    let mut ordered = candidates.to_vec();
+    ordered.sort_by_key(|index| (*index < *cursor, *index));
+
+    for &index in ordered.iter() {
+        if let Some(message) = inputs[index].try_recv()? {
+            *cursor = index + 1;
+            return Ok((index, message));
+        }
+    }
+
+    let futures = ordered.iter().map(|&index| {
+        let input = &inputs[index];
+        Box::pin(async move { (index, input.recv().await) })
+    });
+    let ((index, message), _, _) = futures::future::select_all(futures).await;
+    *cursor = index + 1;
+
+    Ok((index, message?))
+}
+
+/// A `Zip` matches the messages received on several [Input]s by their [Timestamp].
+///
+/// A tuple is returned when every input has a message and all these messages are within the configured `tolerance`
+/// of each other.
+///
+/// # Late and missing messages
+///
+/// A message is discarded — i.e. it will never be part of a tuple — when it can no longer be matched:
+/// - it is older, by more than the tolerance, than the most recent message waiting on another input (its counterpart is
+///   missing),
+/// - it is older, by more than the tolerance, than a watermark received on an input that has no message waiting (the
+///   counterpart will never come),
+/// - it is older, by more than the tolerance, than the last tuple returned (it arrived late).
+///
+/// Discarded messages are logged (at the `debug` level) and counted, see [discarded](Zip::discarded()).
+///
+/// Watermarks are only used to discard messages, they are not returned.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use zenoh_flow_nodes::prelude::*;
+/// # async fn zip(left: Input<u64>, right: Input<String>) -> Result<()> {
+/// let zip = Zip::new((left, right), Duration::from_millis(10));
+/// let ((number, _), (text, _)) = zip.recv().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Zip<I: InputTuple> {
+    inputs: I,
+    raw: Vec<InputRaw>,
+    tolerance: NTP64,
+    state: Mutex<ZipState>,
+}
+
+struct ZipState {
+    slots: Vec<Option<LinkMessage>>,
+    watermarks: Vec<Option<Timestamp>>,
+    last: Option<Timestamp>,
+    cursor: usize,
+    discarded: u64,
+}
+
+impl ZipState {
+    fn discard(&mut self, port_id: &PortId, timestamp: &Timestamp, reason: &str) {
+        self.discarded += 1;
+        tracing::debug!(
+            "[Input: {}] Discarded message ({}): {}",
+            port_id,
+            timestamp,
+            reason
+        );
+    }
+
+    // Discards the waiting messages that can no longer be matched.
+    fn prune(&mut self, raw: &[InputRaw], tolerance: NTP64) {
+        let reference = self
+            .slots
+            .iter()
+            .zip(self.watermarks.iter())
+            .filter_map(|(slot, watermark)| match slot {
+                Some(message) => Some(*message.timestamp()),
+                None => *watermark,
+            })
+            .max();
+
+        let Some(reference) = reference else {
+            return;
+        };
+
+        let mut pruned = Vec::new();
+        for (slot, input) in self.slots.iter_mut().zip(raw) {
+            let timestamp = match slot {
+                Some(message) if is_older(message.timestamp(), &reference, tolerance) => {
+                    *message.timestamp()
+                }
+                _ => continue,
+            };
+
+            *slot = None;
+            pruned.push((input.port_id(), timestamp));
+        }
+
+        for (port_id, timestamp) in pruned {
+            self.discard(port_id, &timestamp, "no matching message");
+        }
+    }
+}
+
+impl<I: InputTuple> Zip<I> {
+    /// Creates a new `Zip` matching the messages of the `inputs` whose timestamps are within `tolerance`.
+    pub fn new(inputs: I, tolerance: Duration) -> Self {
+        let raw = inputs.raw_inputs();
+        Self {
+            state: Mutex::new(ZipState {
+                slots: vec![None; raw.len()],
+                watermarks: vec![None; raw.len()],
+                last: None,
+                cursor: 0,
+                discarded: 0,
+            }),
+            inputs,
+            raw,
+            tolerance: NTP64::from(tolerance),
+        }
+    }
+
+    /// Returns the number of messages discarded, since the creation of this `Zip`, because they could not be matched.
+    pub async fn discarded(&self) -> u64 {
+        self.state.lock().await.discarded
+    }
+
+    /// Returns, *asynchronously*, the next tuple of matching messages.
+    ///
+    /// # Errors
+    ///
+    /// Several errors can occur:
+    /// - a channel was disconnected,
+    /// - Zenoh-Flow failed at interpreting the received data as an instance of the type of its input.
+    pub async fn recv(&self) -> Result<I::Zipped> {
+        let mut state = self.state.lock().await;
+
+        loop {
+            state.prune(&self.raw, self.tolerance);
+
+            if state.slots.iter().all(Option::is_some) {
+                let messages: Vec<_> = state.slots.iter_mut().filter_map(Option::take).collect();
+                state.last = messages.iter().map(|message| *message.timestamp()).max();
+                return self.inputs.try_zip(messages);
+            }
+
+            let empty: Vec<usize> = state
+                .slots
+                .iter()
+                .enumerate()
+                .filter_map(|(index, slot)| slot.is_none().then_some(index))
+                .collect();
+
+            let (index, message) = recv_fair(&self.raw, &empty, &mut state.cursor).await?;
+            match message {
+                Message::Data(message) => {
+                    let timestamp = *message.timestamp();
+                    if state
+                        .last
+                        .is_some_and(|last| is_older(&timestamp, &last, self.tolerance))
+                    {
+                        state.discard(self.raw[index].port_id(), &timestamp, "late");
+                    } else {
+                        state.slots[index] = Some(message);
+                    }
+                }
+                Message::Watermark(timestamp) => {
+                    state.watermarks[index] = state.watermarks[index].max(Some(timestamp));
+                }
+            }
+        }
+    }
+}
+
+/// A `Latest` returns the most recent message received on each of its [Input]s.
+///
+/// Each call to [recv](Latest::recv()) waits for a new message on any of the inputs and then returns the latest message
+/// of every input. The same message can thus be returned several times.
+///
+/// # Late and missing messages
+///
+/// - Nothing is returned until every input has received at least one message.
+/// - A message older than the latest message of its input is discarded. Discarded messages are logged (at the `debug`
+///   level) and counted, see [discarded](Latest::discarded()).
+/// - Watermarks are ignored.
+///
+/// # Example
+///
+/// ```no_run
+/// # use zenoh_flow_nodes::prelude::*;
+/// # async fn latest(speed: Input<f64>, heading: Input<f64>) -> Result<()> {
+/// let latest = Latest::new((speed, heading));
+/// let ((speed, _), (heading, _)) = latest.recv().await?;
+/// println!("{} - {}", **speed, **heading);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Latest<I: InputTuple> {
+    inputs: I,
+    raw: Vec<InputRaw>,
+    state: Mutex<LatestState>,
+}
+
+struct LatestState {
+    values: Vec<Option<(ErasedData, Timestamp)>>,
+    cursor: usize,
+    discarded: u64,
+}
+
+impl<I: InputTuple> Latest<I> {
+    /// Creates a new `Latest` over the `inputs`.
+    pub fn new(inputs: I) -> Self {
+        let raw = inputs.raw_inputs();
+        Self {
+            state: Mutex::new(LatestState {
+                values: vec![None; raw.len()],
+                cursor: 0,
+                discarded: 0,
+            }),
+            inputs,
+            raw,
+        }
+    }
+
+    /// Returns the number of messages discarded, since the creation of this `Latest`, because they arrived late.
+    pub async fn discarded(&self) -> u64 {
+        self.state.lock().await.discarded
+    }
+
+    /// Returns, *asynchronously*, the latest message of every input once a new message was received on any of them.
+    ///
+    /// # Errors
+    ///
+    /// Several errors can occur:
+    /// - a channel was disconnected,
+    /// - Zenoh-Flow failed at interpreting the received data as an instance of the type of its input.
+    pub async fn recv(&self) -> Result<I::Latest> {
+        let mut state = self.state.lock().await;
+        let candidates: Vec<usize> = (0..self.raw.len()).collect();
+
+        loop {
+            let (index, message) = recv_fair(&self.raw, &candidates, &mut state.cursor).await?;
+            let Message::Data(message) = message else {
+                continue;
+            };
+
+            let timestamp = *message.timestamp();
+            if state.values[index]
+                .as_ref()
+                .is_some_and(|(_, latest)| timestamp < *latest)
+            {
+                state.discarded += 1;
+                tracing::debug!(
+                    "[Input: {}] Discarded message ({}): late",
+                    self.raw[index].port_id(),
+                    timestamp
+                );
+                continue;
+            }
+
+            state.values[index] = Some((self.inputs.try_erase(index, message)?, timestamp));
+
+            if let Some(values) = state.values.iter().cloned().collect::<Option<Vec<_>>>() {
+                return I::latest(&values);
+            }
+        }
+    }
+}
+
+/// A `Select` returns the first message received on any of its [Input]s.
+///
+/// The message is returned in a tuple of [Option]s (one per input) where only the entry of the input that received it is
+/// `Some`. Watermarks are returned as any other message.
+///
+/// # Example
+///
+/// ```no_run
+/// # use zenoh_flow_nodes::prelude::*;
+/// # async fn select(numbers: Input<u64>, commands: Input<String>) -> Result<()> {
+/// let select = Select::new((numbers, commands));
+/// match select.recv().await? {
+///     (Some(TypedMessage::Data(number, _)), _) => println!("Number: {}", *number),
+///     (_, Some(TypedMessage::Data(command, _))) => println!("Command: {}", *command),
+///     _ => (),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Select<I: InputTuple> {
+    inputs: I,
+    raw: Vec<InputRaw>,
+    cursor: Mutex<usize>,
+}
+
+impl<I: InputTuple> Select<I> {
+    /// Creates a new `Select` over the `inputs`.
+    pub fn new(inputs: I) -> Self {
+        Self {
+            raw: inputs.raw_inputs(),
+            inputs,
+            cursor: Mutex::new(0),
+        }
+    }
+
+    /// Returns, *asynchronously*, the first message received on any of the inputs.
+    ///
+    /// # Errors
+    ///
+    /// Several errors can occur:
+    /// - a channel was disconnected,
+    /// - Zenoh-Flow failed at interpreting the received data as an instance of the type of its input.
+    pub async fn recv(&self) -> Result<I::Selected> {
+        let mut cursor = self.cursor.lock().await;
+        let candidates: Vec<usize> = (0..self.raw.len()).collect();
+        let (index, message) = recv_fair(&self.raw, &candidates, &mut cursor).await?;
+
+        self.inputs.try_select(index, message)
+    }
+
+    /// Returns the first message queued on any of the inputs or [None] if they are all empty.
+    ///
+    /// # Errors
+    ///
+    /// Several errors can occur:
+    /// - a channel was disconnected,
+    /// - Zenoh-Flow failed at interpreting the received data as an instance of the type of its input.
+    pub fn try_recv(&self) -> Result<Option<I::Selected>> {
+        let Some(mut cursor) = self.cursor.try_lock() else {
+            return Ok(None);
+        };
+
+        let count = self.raw.len();
+        for offset in 0..count {
+            let index = (*cursor + offset) % count;
+            if let Some(message) = self.raw[index].try_recv()? {
+                *cursor = index + 1;
+                return self.inputs.try_select(index, message).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/sync-tests.rs"]
+mod tests;
@@ -14,12 +14,14 @@
 
 use std::sync::Arc;
 
+use futures::FutureExt;
 use prost::Message as pMessage;
 use serde::{Deserialize, Serialize};
 
-use super::{Input, InputRaw};
+use super::{Input, InputRaw, Inputs};
 use crate::{
-    messages::{LinkMessage, Payload},
+    io::{Outputs, Statistics},
+    messages::{LinkMessage, Message, Payload, TypedMessage},
     traits::SendSyncAny,
 };
 
@@ -45,11 +47,13 @@ fn test_typed_input<T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'stat
     deserializer: impl Fn(&[u8]) -> anyhow::Result<T> + Send + Sync + 'static,
 ) {
     let hlc = uhlc::HLC::default();
-    let (tx, rx) = flume::unbounded::<LinkMessage>();
+    let (tx, rx) = flume::unbounded::<Message>();
 
     let input_raw = InputRaw {
         port_id: "test-id".into(),
         receiver: rx,
+        counters: Arc::default(),
+        tracer: None,
     };
 
     let input = Input {
@@ -61,12 +65,15 @@ fn test_typed_input<T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'stat
         Payload::Bytes(Arc::new(expected_serialized)),
         hlc.new_timestamp(),
     );
-    tx.send(message).expect("Failed to send message");
+    tx.send(message.into()).expect("Failed to send message");
 
-    let (data, _) = input
+    let TypedMessage::Data(data, _) = input
         .try_recv()
         .expect("Message (serialised) was not sent")
-        .expect("No message was received");
+        .expect("No message was received")
+    else {
+        panic!("Unexpected watermark");
+    };
 
     assert_eq!(expected_data, *data);
 
@@ -78,12 +85,15 @@ fn test_typed_input<T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'stat
         )),
         hlc.new_timestamp(),
     );
-    tx.send(message).expect("Failed to send message");
+    tx.send(message.into()).expect("Failed to send message");
 
-    let (data, _) = input
+    let TypedMessage::Data(data, _) = input
         .try_recv()
         .expect("Message (dyn SendSyncAny) was not sent")
-        .expect("No message was received");
+        .expect("No message was received")
+    else {
+        panic!("Unexpected watermark");
+    };
     assert_eq!(expected_data, *data);
 }
 
@@ -145,3 +155,78 @@ fn test_protobuf_prost() {
         <TestProto>::decode(bytes).map_err(|e| anyhow::anyhow!(e))
     })
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// WATERMARK
+
+#[test]
+fn test_watermark() {
+    let hlc = uhlc::HLC::default();
+    let (tx, rx) = flume::unbounded::<Message>();
+
+    let input: Input<u64> = Input {
+        input_raw: InputRaw {
+            port_id: "test-id".into(),
+            receiver: rx,
+            counters: Arc::default(),
+            tracer: None,
+        },
+        // The deserialiser should never be called, hence the panic.
+        deserializer: Arc::new(|_bytes| panic!("Unexpected call to deserialise a watermark")),
+    };
+
+    let timestamp = hlc.new_timestamp();
+    tx.send(Message::Watermark(timestamp))
+        .expect("Failed to send watermark");
+
+    match input
+        .try_recv()
+        .expect("Watermark was not sent")
+        .expect("No message was received")
+    {
+        TypedMessage::Watermark(received) => assert_eq!(timestamp, received),
+        TypedMessage::Data(_, _) => panic!("Expected a watermark, received data"),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// ACTIVITY
+
+#[test]
+fn test_waiting() {
+    let hlc = uhlc::HLC::default();
+    let (tx, rx) = flume::unbounded::<Message>();
+
+    let mut inputs = Inputs::default();
+    inputs.insert("test-id".into(), rx);
+    let statistics = Statistics::new(&inputs, &Outputs::new(Arc::new(uhlc::HLC::default())));
+    let input = inputs.take("test-id").expect("No input 'test-id'").raw();
+
+    assert!(!statistics.is_waiting());
+    assert_eq!(0, statistics.consumed());
+
+    // A node is waiting as long as a call to `recv` is pending, even if the future is dropped before completing.
+    let mut recv = Box::pin(input.recv());
+    assert!(recv.as_mut().now_or_never().is_none());
+    assert!(statistics.is_waiting());
+    drop(recv);
+    assert!(!statistics.is_waiting());
+    assert_eq!(0, statistics.consumed());
+
+    // Watermarks are consumed as well.
+    tx.send(Message::Watermark(hlc.new_timestamp()))
+        .expect("Failed to send watermark");
+    futures::executor::block_on(input.recv()).expect("Failed to receive the watermark");
+    assert!(!statistics.is_waiting());
+    assert_eq!(1, statistics.consumed());
+
+    // Sources mark themselves as waiting through `wait_for`.
+    let mut wait = Box::pin(statistics.wait_for(futures::future::pending::<()>()));
+    assert!(wait.as_mut().now_or_never().is_none());
+    assert!(statistics.is_waiting());
+    drop(wait);
+    assert!(!statistics.is_waiting());
+
+    futures::executor::block_on(statistics.wait_for(async {}));
+    assert_eq!(2, statistics.consumed());
+}
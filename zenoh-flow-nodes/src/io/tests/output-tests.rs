@@ -14,12 +14,15 @@
 
 use std::{collections::HashMap, sync::Arc};
 
-use prost::Message;
+use prost::Message as pMessage;
 use serde::{Deserialize, Serialize};
-use zenoh_flow_commons::PortId;
+use zenoh_flow_commons::{PortId, QueueConfiguration, QueuePolicy};
 
-use super::Outputs;
-use crate::messages::{LinkMessage, Payload};
+use super::{OutputRaw, Outputs};
+use crate::{
+    io::{link_channel, Input, InputRaw, InputStatistics, OutputStatistics},
+    messages::{Headers, Message, Payload, TypedMessage},
+};
 
 /// Test that the Output behaves as expected for the provided data and serialiser:
 /// 1. the `serialiser` is correctly type-erased yet still produces the correct output,
@@ -52,11 +55,14 @@ fn test_typed_output<T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'sta
     let hlc = uhlc::HLC::default();
     let key: PortId = "test".into();
 
-    let (tx, rx) = flume::unbounded::<LinkMessage>();
+    let (tx, rx) = flume::unbounded::<Message>();
 
     let mut outputs = Outputs {
-        hmap: HashMap::from([(key.clone(), vec![tx])]),
+        hmap: HashMap::from([(key.clone(), vec![tx.into()])]),
+        counters: HashMap::default(),
+        mirrors: HashMap::default(),
         hlc: Arc::new(hlc),
+        tracer: None,
     };
 
     let output = outputs
@@ -68,7 +74,9 @@ fn test_typed_output<T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'sta
         .try_send(expected_data.clone(), None)
         .expect("Failed to send the message");
 
-    let message = rx.recv().expect("Received no message");
+    let Message::Data(message) = rx.recv().expect("Received no message") else {
+        panic!("Unexpected watermark");
+    };
     match message.payload {
         Payload::Bytes(_) => panic!("Unexpected bytes payload"),
         Payload::Typed((dyn_data, serializer)) => {
@@ -83,6 +91,16 @@ fn test_typed_output<T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'sta
             assert_eq!(expected_data, *data);
         }
     }
+
+    // The size of a typed payload is unknown: it is not reported.
+    assert_eq!(
+        OutputStatistics {
+            sent: 1,
+            dropped: 0,
+            bytes: None
+        },
+        output.statistics()
+    );
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -146,3 +164,328 @@ fn test_protobuf_prost() {
 
     test_typed_output(expected_data, expected_serialized, serializer)
 }
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// QUEUES
+
+/// Sends three messages on an Output whose (single) link has a queue of capacity 2 and the provided policy, returns
+/// the raw Output and the receiving end of the link.
+fn fill_queue(policy: QueuePolicy) -> (OutputRaw, flume::Receiver<Message>) {
+    let key: PortId = "test".into();
+    let (tx, rx) = link_channel(Some(&QueueConfiguration {
+        capacity: 2,
+        policy,
+    }));
+
+    let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+    outputs.insert(key.clone(), tx);
+
+    let output = outputs
+        .take(key.as_ref())
+        .expect("Wrong key provided")
+        .raw();
+
+    output.try_send(vec![1u8], None).expect("Failed to send 1");
+    output.try_send(vec![2u8], None).expect("Failed to send 2");
+    let res = output.try_send(vec![3u8], None);
+    if policy == QueuePolicy::Block {
+        assert!(res.is_err());
+    } else {
+        assert!(res.is_ok());
+    }
+
+    (output, rx)
+}
+
+fn drain(rx: &flume::Receiver<Message>) -> Vec<u8> {
+    rx.drain()
+        .map(|message| match message {
+            Message::Data(message) => match message.payload {
+                Payload::Bytes(bytes) => bytes[0],
+                Payload::Typed(_) => panic!("Unexpected typed payload"),
+            },
+            Message::Watermark(_) => panic!("Unexpected watermark"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_queue_block() {
+    let (output, rx) = fill_queue(QueuePolicy::Block);
+    assert_eq!(0, output.dropped());
+    assert_eq!(vec![1, 2], drain(&rx));
+}
+
+#[test]
+fn test_queue_drop_oldest() {
+    let (output, rx) = fill_queue(QueuePolicy::DropOldest);
+    assert_eq!(1, output.dropped());
+    assert_eq!(vec![2, 3], drain(&rx));
+}
+
+#[test]
+fn test_queue_drop_newest() {
+    let (output, rx) = fill_queue(QueuePolicy::DropNewest);
+    assert_eq!(1, output.dropped());
+    assert_eq!(vec![1, 2], drain(&rx));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// SEQUENCE NUMBERS
+
+#[test]
+fn test_sequence_numbers() {
+    let (output, rx) = fill_queue(QueuePolicy::DropNewest);
+    let input = InputRaw {
+        port_id: "test".into(),
+        receiver: rx,
+        counters: Arc::default(),
+        tracer: None,
+    };
+
+    for expected_sequence in [1, 2] {
+        match input.try_recv().expect("Failed to receive") {
+            Some(Message::Data(message)) => assert_eq!(expected_sequence, message.sequence()),
+            _ => panic!("Expected a message"),
+        }
+    }
+
+    // The third message was discarded by the queue: the Input detects it upon receiving the fourth.
+    output.try_send(vec![4u8], None).expect("Failed to send 4");
+    let Some(Message::Data(fourth)) = input.try_recv().expect("Failed to receive") else {
+        panic!("Expected the fourth message");
+    };
+    assert_eq!(4, fourth.sequence());
+    assert_eq!(
+        OutputStatistics {
+            sent: 4,
+            dropped: 1,
+            bytes: Some(4)
+        },
+        output.statistics()
+    );
+    assert_eq!(
+        InputStatistics {
+            received: 3,
+            lost: 1,
+            duplicates: 0,
+            pending: 0,
+            bytes: Some(3)
+        },
+        input.statistics()
+    );
+
+    // A relay, as used by the connectors, preserves the sequence numbers of the messages it forwards.
+    let key: PortId = "relay".into();
+    let (tx, rx) = flume::unbounded::<Message>();
+    let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+    outputs.insert(key.clone(), tx);
+
+    let relay = outputs
+        .take(key.as_ref())
+        .expect("Wrong key provided")
+        .relay();
+    relay
+        .try_forward(fourth.clone())
+        .expect("Failed to relay the message");
+    relay
+        .try_forward(fourth)
+        .expect("Failed to relay the message");
+
+    let relayed = InputRaw {
+        port_id: key,
+        receiver: rx,
+        counters: Arc::default(),
+        tracer: None,
+    };
+    while let Some(message) = relayed.try_recv().expect("Failed to receive") {
+        let Message::Data(message) = message else {
+            panic!("Unexpected watermark");
+        };
+        assert_eq!(4, message.sequence());
+    }
+    assert_eq!(
+        InputStatistics {
+            received: 2,
+            lost: 0,
+            duplicates: 1,
+            pending: 0,
+            bytes: Some(2)
+        },
+        relayed.statistics()
+    );
+}
+
+#[test]
+fn test_sequence_numbers_restart() {
+    let key: PortId = "test".into();
+    let (tx, rx) = flume::unbounded::<Message>();
+    let input = InputRaw {
+        port_id: key.clone(),
+        receiver: rx,
+        counters: Arc::default(),
+        tracer: None,
+    };
+
+    // The upstream Output is re-created, e.g. after its node was updated: its sequence numbers restart at 1.
+    for count in [3, 2] {
+        let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+        outputs.insert(key.clone(), tx.clone());
+        let output = outputs
+            .take(key.as_ref())
+            .expect("Wrong key provided")
+            .raw();
+        for i in 0..count {
+            output.try_send(vec![i], None).expect("Failed to send");
+        }
+    }
+    while input.try_recv().expect("Failed to receive").is_some() {}
+
+    assert_eq!(
+        InputStatistics {
+            received: 5,
+            lost: 0,
+            duplicates: 0,
+            pending: 0,
+            bytes: Some(5)
+        },
+        input.statistics()
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// WATERMARK
+
+#[test]
+fn test_watermark() {
+    let key: PortId = "test".into();
+    let (tx, rx) = flume::unbounded::<Message>();
+
+    let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+    outputs.insert(key.clone(), tx);
+
+    let output = outputs
+        .take(key.as_ref())
+        .expect("Wrong key provided")
+        .typed(|buffer: &mut Vec<u8>, data: &u64| {
+            serde_json::ser::to_writer(buffer, data).map_err(|e| anyhow::anyhow!(e))
+        });
+
+    output
+        .try_send_watermark(Some(42))
+        .expect("Failed to send the watermark");
+
+    match rx.recv().expect("Received no message") {
+        Message::Watermark(timestamp) => assert_eq!(42, timestamp.get_time().as_u64()),
+        Message::Data(_) => panic!("Expected a watermark, received data"),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// HEADERS
+
+#[test]
+fn test_headers() {
+    let key: PortId = "test".into();
+    let (tx, rx) = flume::unbounded::<Message>();
+
+    let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+    outputs.insert(key.clone(), tx);
+
+    let output = outputs
+        .take(key.as_ref())
+        .expect("Wrong key provided")
+        .typed(|buffer: &mut Vec<u8>, data: &u64| {
+            serde_json::ser::to_writer(buffer, data).map_err(|e| anyhow::anyhow!(e))
+        });
+
+    let headers = Headers::from_iter([
+        ("correlation-id", "42"),
+        ("content-type", "application/json"),
+    ]);
+    output
+        .try_send_with_headers(1u64, None, headers.clone())
+        .expect("Failed to send the message");
+
+    // The message goes through the same serialisation as when it is sent to another Zenoh-Flow runtime.
+    let (mut message_buffer, mut payload_buffer) = (Vec::new(), Vec::new());
+    rx.recv()
+        .expect("Received no message")
+        .serialize_bincode_into(&mut message_buffer, &mut payload_buffer)
+        .expect("Failed to serialise the message");
+    let message: Message =
+        bincode::deserialize(&message_buffer).expect("Failed to deserialise the message");
+
+    let (tx, rx) = flume::unbounded::<Message>();
+    let input: Input<u64> = Input {
+        input_raw: InputRaw {
+            port_id: key,
+            receiver: rx,
+            counters: Arc::default(),
+            tracer: None,
+        },
+        deserializer: Arc::new(|bytes| {
+            serde_json::from_slice(bytes).map_err(|e| anyhow::anyhow!(e))
+        }),
+    };
+    tx.send(message).expect("Failed to send the message");
+
+    let TypedMessage::Data(data, _) = input
+        .try_recv()
+        .expect("Failed to receive the message")
+        .expect("No message was received")
+    else {
+        panic!("Unexpected watermark");
+    };
+    assert_eq!(1, *data);
+    assert_eq!(&headers, data.headers());
+    assert_eq!(Some("42"), data.headers().get_str("correlation-id"));
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+/// MIRRORS
+
+#[test]
+fn test_mirrors() {
+    let key: PortId = "test".into();
+    let (tx, rx) = flume::unbounded::<Message>();
+
+    let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+    outputs.insert(key.clone(), tx);
+
+    let mirrors = outputs
+        .mirrors()
+        .remove(&key)
+        .expect("No mirrors for the Output");
+    let handle = outputs.handle(&key).expect("Wrong key provided");
+    let output = outputs
+        .take(key.as_ref())
+        .expect("Wrong key provided")
+        .raw();
+
+    // Nothing is mirrored while no mirror is attached.
+    output.try_send(vec![1u8], None).unwrap();
+    let mirror = mirrors.attach(1);
+    assert!(mirror.is_empty());
+
+    // A mirror receives a copy of the messages, a full mirror does not prevent the message from being sent.
+    output.try_send(vec![2u8], None).unwrap();
+    output.try_send(vec![3u8], None).unwrap();
+    let Ok(Message::Data(message)) = mirror.try_recv() else {
+        panic!("Expected a mirrored message");
+    };
+    assert_eq!(2, message.sequence());
+    assert!(mirror.is_empty());
+
+    // The handle sends on the same channels, its messages are accounted for in the statistics of the Output.
+    handle.try_send(vec![4u8], None).unwrap();
+    assert!(mirror.try_recv().is_ok());
+    assert_eq!(4, output.statistics().sent);
+    assert_eq!(4, rx.len());
+
+    // Dropping the receiving half detaches the mirror.
+    assert_eq!(1, mirrors.len());
+    drop(mirror);
+    output.try_send(vec![5u8], None).unwrap();
+    assert!(mirrors.is_empty());
+}
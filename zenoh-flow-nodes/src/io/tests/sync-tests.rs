@@ -0,0 +1,178 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{sync::Arc, time::Duration};
+
+use flume::Sender;
+use futures::executor::block_on;
+use uhlc::{Timestamp, HLC, NTP64};
+
+use super::{Latest, Select, Zip};
+use crate::{
+    io::{Input, InputRaw},
+    messages::{LinkMessage, Message, Payload, TypedMessage},
+    traits::SendSyncAny,
+};
+
+/// Creates a typed input and the sender feeding it.
+///
+/// The data are sent typed, the deserialiser should thus never be called.
+fn input<T: Send + Sync + 'static>(port: &str) -> (Input<T>, Sender<Message>) {
+    let (tx, rx) = flume::unbounded();
+    let input = Input {
+        input_raw: InputRaw {
+            port_id: port.into(),
+            receiver: rx,
+            counters: Arc::default(),
+            tracer: None,
+        },
+        deserializer: Arc::new(|_bytes| panic!("Unexpected call to deserialise the data")),
+    };
+
+    (input, tx)
+}
+
+fn timestamp(hlc: &HLC, time: u64) -> Timestamp {
+    Timestamp::new(NTP64::from(Duration::from_millis(time)), *hlc.get_id())
+}
+
+fn send<T: Send + Sync + 'static>(tx: &Sender<Message>, data: T, timestamp: Timestamp) {
+    let payload = Payload::Typed((
+        Arc::new(data) as Arc<dyn SendSyncAny>,
+        Arc::new(|_buffer, _data| panic!("Unexpected call to serialise the data")),
+    ));
+    tx.send(LinkMessage::new(payload, timestamp).into())
+        .expect("Failed to send message");
+}
+
+#[test]
+fn test_zip_tolerance() {
+    let hlc = HLC::default();
+    let (left, tx_left) = input::<u64>("left");
+    let (right, tx_right) = input::<String>("right");
+    let zip = Zip::new((left, right), Duration::from_millis(5));
+
+    // The `right` counterpart of the message at 10 is missing: it should be discarded.
+    send(&tx_left, 10u64, timestamp(&hlc, 10));
+    send(&tx_left, 20u64, timestamp(&hlc, 20));
+    send(&tx_right, "22".to_string(), timestamp(&hlc, 22));
+
+    let ((number, number_ts), (text, text_ts)) = block_on(zip.recv()).expect("Failed to zip");
+    assert_eq!(20, *number);
+    assert_eq!("22", *text);
+    assert_eq!(timestamp(&hlc, 20), number_ts);
+    assert_eq!(timestamp(&hlc, 22), text_ts);
+    assert_eq!(1, block_on(zip.discarded()));
+
+    // Arrives after a more recent tuple was returned: it should be discarded.
+    send(&tx_right, "12".to_string(), timestamp(&hlc, 12));
+    send(&tx_left, 30u64, timestamp(&hlc, 30));
+    send(&tx_right, "30".to_string(), timestamp(&hlc, 30));
+
+    let ((number, _), (text, _)) = block_on(zip.recv()).expect("Failed to zip");
+    assert_eq!(30, *number);
+    assert_eq!("30", *text);
+    assert_eq!(2, block_on(zip.discarded()));
+}
+
+#[test]
+fn test_zip_watermark() {
+    let hlc = HLC::default();
+    let (left, tx_left) = input::<u64>("left");
+    let (right, tx_right) = input::<u64>("right");
+    let zip = Zip::new((left, right), Duration::from_millis(5));
+
+    // The watermark indicates that `right` will never send the counterpart of the message at 10.
+    send(&tx_left, 10u64, timestamp(&hlc, 10));
+    tx_right
+        .send(Message::Watermark(timestamp(&hlc, 20)))
+        .expect("Failed to send watermark");
+    send(&tx_right, 20u64, timestamp(&hlc, 20));
+    send(&tx_left, 21u64, timestamp(&hlc, 21));
+
+    let ((left, _), (right, _)) = block_on(zip.recv()).expect("Failed to zip");
+    assert_eq!(21, *left);
+    assert_eq!(20, *right);
+    assert_eq!(1, block_on(zip.discarded()));
+}
+
+#[test]
+fn test_latest() {
+    let hlc = HLC::default();
+    let (speed, tx_speed) = input::<u64>("speed");
+    let (heading, tx_heading) = input::<String>("heading");
+    let latest = Latest::new((speed, heading));
+
+    send(&tx_speed, 10u64, timestamp(&hlc, 10));
+    send(&tx_speed, 20u64, timestamp(&hlc, 20));
+    send(&tx_heading, "north".to_string(), timestamp(&hlc, 15));
+
+    // Nothing is returned until both inputs have received a message. As the inputs are served in a round-robin
+    // fashion, the first message of `heading` is processed before the second of `speed`.
+    let ((speed, _), (heading, _)) = block_on(latest.recv()).expect("Failed to receive");
+    assert_eq!(10, **speed);
+    assert_eq!("north", **heading);
+
+    let ((speed, _), (heading, _)) = block_on(latest.recv()).expect("Failed to receive");
+    assert_eq!(20, **speed);
+    assert_eq!("north", **heading);
+
+    // The first message is older than the latest `speed`: it should be discarded.
+    send(&tx_speed, 5u64, timestamp(&hlc, 5));
+    send(&tx_speed, 30u64, timestamp(&hlc, 30));
+    let ((speed, _), (heading, _)) = block_on(latest.recv()).expect("Failed to receive");
+    assert_eq!(30, **speed);
+    assert_eq!("north", **heading);
+    assert_eq!(1, block_on(latest.discarded()));
+}
+
+#[test]
+fn test_select_fairness() {
+    let hlc = HLC::default();
+    let (numbers, tx_numbers) = input::<u64>("numbers");
+    let (texts, tx_texts) = input::<String>("texts");
+    let select = Select::new((numbers, texts));
+
+    send(&tx_numbers, 1u64, timestamp(&hlc, 1));
+    send(&tx_numbers, 2u64, timestamp(&hlc, 2));
+    send(&tx_texts, "3".to_string(), timestamp(&hlc, 3));
+    tx_texts
+        .send(Message::Watermark(timestamp(&hlc, 4)))
+        .expect("Failed to send watermark");
+
+    // Although `numbers` has two queued messages, `texts` must be served in between.
+    match select.try_recv().expect("Failed to receive") {
+        Some((Some(TypedMessage::Data(number, _)), None)) => assert_eq!(1, *number),
+        _ => panic!("Expected data on `numbers`"),
+    }
+
+    match block_on(select.recv()).expect("Failed to receive") {
+        (None, Some(TypedMessage::Data(text, _))) => assert_eq!("3", *text),
+        _ => panic!("Expected data on `texts`"),
+    }
+
+    match block_on(select.recv()).expect("Failed to receive") {
+        (Some(TypedMessage::Data(number, _)), None) => assert_eq!(2, *number),
+        _ => panic!("Expected data on `numbers`"),
+    }
+
+    match select.try_recv().expect("Failed to receive") {
+        Some((None, Some(TypedMessage::Watermark(watermark)))) => {
+            assert_eq!(timestamp(&hlc, 4), watermark)
+        }
+        _ => panic!("Expected a watermark on `texts`"),
+    }
+
+    assert!(select.try_recv().expect("Failed to receive").is_none());
+}
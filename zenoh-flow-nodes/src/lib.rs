@@ -29,15 +29,20 @@
 //! [Sink](crate::prelude::Sink) and possibly some [Operators](crate::prelude::Operator). See their respective
 //! documentation for examples.
 
+pub mod codecs;
 pub(crate) mod context;
 pub(crate) mod declaration;
 pub(crate) mod io;
 pub(crate) mod messages;
+pub(crate) mod timer;
+pub(crate) mod trace;
 pub(crate) mod traits;
 
 pub use self::{
     declaration::{NodeDeclaration, OperatorFn, SinkFn, SourceFn, CORE_VERSION, RUSTC_VERSION},
-    io::{InputBuilder, OutputBuilder},
+    io::{link_channel, InputBuilder, LinkSender, Mirrors, OutputBuilder},
+    timer::next_deadline,
+    trace::{Hop, Tracer},
 };
 
 /// This module expose all the structures required to implement a Zenoh-Flow node.
@@ -50,9 +55,15 @@ pub mod prelude {
     pub use zenoh_flow_derive::{export_operator, export_sink, export_source};
 
     pub use crate::{
+        codecs::{self, Codec},
         context::Context,
-        io::{Input, InputRaw, Inputs, Output, OutputRaw, Outputs},
-        messages::{Data, LinkMessage, Payload},
+        io::{
+            Input, InputRaw, InputStatistics, InputTuple, Inputs, Latest, NodeStatistics, Output,
+            OutputRaw, OutputStatistics, Outputs, Select, Statistics, Zip,
+        },
+        messages::{Data, Headers, LinkMessage, Message, Payload, TypedMessage},
+        timer::{Timer, TimerMode},
+        trace::{TraceContext, TRACEPARENT},
         traits::{Node, Operator, SendSyncAny, Sink, Source},
     };
 }
@@ -12,7 +12,13 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{cmp::Ordering, fmt::Debug, ops::Deref, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
 use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
@@ -96,6 +102,16 @@ impl Payload {
         }
     }
 
+    /// Returns the number of bytes of the [Payload] if it is already serialised, [None] if it is typed.
+    ///
+    /// A typed [Payload] is never serialised just to compute its size: it only is when it crosses a runtime.
+    pub(crate) fn bytes_len(&self) -> Option<usize> {
+        match self {
+            Payload::Bytes(bytes) => Some(bytes.len()),
+            Payload::Typed(_) => None,
+        }
+    }
+
     /// Return an [Arc] containing the bytes representation of the [Payload].
     ///
     /// # Performance
@@ -138,11 +154,74 @@ impl From<LinkMessage> for Payload {
     }
 }
 
-/// A message send on a Zenoh-Flow link: a [Payload] and a [Timestamp].
+/// The `Headers` are metadata attached to a [LinkMessage], alongside its [Payload].
+///
+/// They map a (string) key to an array of bytes and are transmitted as-is, whether the downstream node resides on the
+/// same Zenoh-Flow runtime or not. Typical uses are correlation identifiers, trace context, provenance or the
+/// content-type of the payload.
+///
+/// `Headers` dereference to a [BTreeMap], giving access to all its methods.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_nodes::prelude::Headers;
+/// let mut headers = Headers::default();
+/// headers.insert("content-type", "application/json");
+/// assert_eq!(Some("application/json"), headers.get_str("content-type"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Headers(BTreeMap<String, Vec<u8>>);
+
+impl Deref for Headers {
+    type Target = BTreeMap<String, Vec<u8>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Headers {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<K: Into<String>, V: Into<Vec<u8>>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Headers {
+    /// Insert the `value` associated with the `key`, returning the previous value if there was one.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Option<Vec<u8>> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    /// Return the value associated with the `key` as a string slice, if there is one and it is valid UTF-8.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.0
+            .get(key)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+}
+
+/// A message send on a Zenoh-Flow link: a [Payload], a [Timestamp] and its [Headers].
+///
+/// The message also carries the sequence number stamped by the Output that sent it, see
+/// [sequence](LinkMessage::sequence()).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LinkMessage {
     pub(crate) payload: Payload,
     pub(crate) timestamp: Timestamp,
+    pub(crate) headers: Headers,
+    pub(crate) sequence: u64,
 }
 
 impl Ord for LinkMessage {
@@ -167,7 +246,18 @@ impl Eq for LinkMessage {}
 
 impl LinkMessage {
     pub fn new(payload: Payload, timestamp: Timestamp) -> Self {
-        Self { payload, timestamp }
+        Self {
+            payload,
+            timestamp,
+            headers: Headers::default(),
+            sequence: 0,
+        }
+    }
+
+    /// Replace the [Headers] of this message.
+    pub fn with_headers(mut self, headers: Headers) -> Self {
+        self.headers = headers;
+        self
     }
 
     /// Creates a new message from serialised data.
@@ -177,6 +267,8 @@ impl LinkMessage {
         Self {
             payload: Payload::Bytes(Arc::new(data)),
             timestamp,
+            headers: Headers::default(),
+            sequence: 0,
         }
     }
 
@@ -203,6 +295,24 @@ impl LinkMessage {
         &self.timestamp
     }
 
+    /// Return the [Headers] associated with this message.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Return a mutable reference to the [Headers] associated with this message.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
+    /// Return the sequence number of this message.
+    ///
+    /// Each Output stamps the messages it sends with a monotonically increasing sequence number, starting at 1. A
+    /// message that was not (yet) sent by an Output has the sequence number 0.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
     /// Serialises the [LinkMessage] using [bincode] into the given `buffer`.
     ///
     /// The `inner_buffer` is used to serialise (if need be) the [Payload] contained inside the
@@ -233,6 +343,8 @@ impl LinkMessage {
                 let serialized_message = Self {
                     payload: Payload::Bytes(Arc::new(payload_buffer.clone())),
                     timestamp: self.timestamp,
+                    headers: self.headers.clone(),
+                    sequence: self.sequence,
                 };
 
                 bincode::serialize_into(message_buffer, &serialized_message)
@@ -242,6 +354,109 @@ impl LinkMessage {
     }
 }
 
+/// A message sent on a Zenoh-Flow link: either data or a watermark.
+///
+/// A watermark is a timestamp-only marker. By sending a watermark, a node indicates to the downstream nodes that it will
+/// no longer send data with an older [Timestamp]. Event-time operators can thus rely on watermarks to know when a time
+/// window is complete.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Message {
+    /// A [LinkMessage]: a [Payload] and its [Timestamp].
+    Data(LinkMessage),
+    /// A watermark: no data older than this [Timestamp] will follow.
+    Watermark(Timestamp),
+}
+
+impl From<LinkMessage> for Message {
+    fn from(message: LinkMessage) -> Self {
+        Self::Data(message)
+    }
+}
+
+impl Message {
+    /// Return the [Timestamp] associated with this message.
+    pub fn timestamp(&self) -> &Timestamp {
+        match self {
+            Message::Data(message) => message.timestamp(),
+            Message::Watermark(timestamp) => timestamp,
+        }
+    }
+
+    /// Return `true` if this message is a watermark.
+    pub fn is_watermark(&self) -> bool {
+        matches!(self, Message::Watermark(_))
+    }
+
+    /// Serialises the [Message] using [bincode] into the given `buffer`.
+    ///
+    /// The `inner_buffer` is used to serialise (if need be) the [Payload] contained inside the
+    /// [LinkMessage].
+    ///
+    /// # Performance
+    ///
+    /// The provided `buffer` and `inner_buffer` are reused and cleared between calls, so once their
+    /// capacity stabilises no (re)allocation is performed.
+    ///
+    /// # Errors
+    ///
+    /// An error variant is returned in case of:
+    /// - fails to serialise
+    pub fn serialize_bincode_into(
+        &self,
+        message_buffer: &mut Vec<u8>,
+        payload_buffer: &mut Vec<u8>,
+    ) -> Result<()> {
+        payload_buffer.clear(); // empty the buffers but keep their allocated capacity
+        message_buffer.clear();
+
+        match self {
+            Message::Watermark(_) => bincode::serialize_into(message_buffer, &self)
+                .context("Failed to serialise `Message::Watermark`"),
+            Message::Data(message) => match &message.payload {
+                Payload::Bytes(_) => bincode::serialize_into(message_buffer, &self)
+                    .context("Failed to serialise `Payload::Bytes``"),
+                Payload::Typed((data, serializer)) => {
+                    (serializer)(payload_buffer, Arc::clone(data))?;
+                    let serialized_message = Message::Data(LinkMessage {
+                        payload: Payload::Bytes(Arc::new(payload_buffer.clone())),
+                        timestamp: message.timestamp,
+                        headers: message.headers.clone(),
+                        sequence: message.sequence,
+                    });
+
+                    bincode::serialize_into(message_buffer, &serialized_message)
+                        .context("Failed to serialise `Payload::Typed`")
+                }
+            },
+        }
+    }
+}
+
+/// A message received by a typed [`Input<T>`](crate::prelude::Input): either a [`Data<T>`](Data) or a watermark.
+///
+/// See [Message] for the meaning of a watermark.
+#[derive(Debug)]
+pub enum TypedMessage<T> {
+    /// A [`Data<T>`](Data) and its [Timestamp].
+    Data(Data<T>, Timestamp),
+    /// A watermark: no data older than this [Timestamp] will follow.
+    Watermark(Timestamp),
+}
+
+impl<T> TypedMessage<T> {
+    /// Return the [Timestamp] associated with this message.
+    pub fn timestamp(&self) -> &Timestamp {
+        match self {
+            TypedMessage::Data(_, timestamp) | TypedMessage::Watermark(timestamp) => timestamp,
+        }
+    }
+
+    /// Return `true` if this message is a watermark.
+    pub fn is_watermark(&self) -> bool {
+        matches!(self, TypedMessage::Watermark(_))
+    }
+}
+
 /// A `Data<T>` is a wrapper around `T` given by a typed [`Input<T>`](crate::prelude::Input).
 ///
 /// A `Data<T>` automatically dereferences to a `&T`.
@@ -253,9 +468,15 @@ impl LinkMessage {
 ///
 /// To perform the deserialisation, the [deserialiser](crate::io::InputBuilder::typed()) function passed to the
 /// [`Input<T>`](crate::prelude::Input) will be called.
+///
+/// # Headers
+///
+/// The [Headers] of the message that contained the data are accessible through [headers](Data::headers()). They are
+/// _not_ propagated when a `Data<T>` is sent on an [`Output<T>`](crate::prelude::Output).
 #[derive(Debug)]
 pub struct Data<T> {
     inner: DataInner<T>,
+    headers: Headers,
 }
 
 /// The `DataInner` enum represents the two ways to send data in an [`Output<T>`](`Output`).
@@ -285,6 +506,7 @@ impl<T: Send + Sync + 'static> From<T> for Data<T> {
     fn from(value: T) -> Self {
         Self {
             inner: DataInner::Data(value),
+            headers: Headers::default(),
         }
     }
 }
@@ -355,6 +577,15 @@ Feel free to contact us at < zenoh@zettascale.tech >.
     }
 }
 
+impl<T> Data<T> {
+    /// Return the [Headers] of the message that contained this data.
+    ///
+    /// A `Data<T>` created from an instance of `T` has no headers.
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+}
+
 impl<T: 'static> Data<T> {
     /// Try to create a new [`Data<T>`](`Data`) based on a [`Payload`](`Payload`).
     ///
@@ -370,6 +601,7 @@ impl<T: 'static> Data<T> {
     /// the downcast failed.
     pub(crate) fn try_from_payload(
         payload: Payload,
+        headers: Headers,
         deserializer: Arc<DeserializerFn<T>>,
     ) -> Result<Self> {
         let mut typed = None;
@@ -388,6 +620,7 @@ impl<T: 'static> Data<T> {
                 payload,
                 data: typed,
             },
+            headers,
         })
     }
 }
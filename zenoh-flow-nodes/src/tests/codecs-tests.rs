@@ -0,0 +1,127 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::PortId;
+
+use super::*;
+use crate::{
+    io::{InputBuilder, Outputs},
+    messages::{LinkMessage, Message, Payload, TypedMessage},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct TestData {
+    pub field1: u8,
+    pub field2: String,
+    pub field3: f64,
+}
+
+fn test_data() -> TestData {
+    TestData {
+        field1: 1u8,
+        field2: "two".into(),
+        field3: 0.3f64,
+    }
+}
+
+/// Sends `expected_data` on an Output built with the codec `C`, serialises it as it would be before going through
+/// Zenoh and ensures that an Input built with the same codec decodes it.
+fn test_codec<T, C>(expected_data: T)
+where
+    T: Send + Sync + Clone + std::fmt::Debug + PartialEq + 'static,
+    C: Codec<T>,
+{
+    let key: PortId = "test".into();
+    let (tx, rx) = flume::unbounded::<Message>();
+
+    let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+    outputs.insert(key.clone(), tx);
+    let output = outputs
+        .take(key.as_ref())
+        .expect("Wrong key provided")
+        .typed_with::<T, C>();
+
+    output
+        .try_send(expected_data.clone(), None)
+        .expect("Failed to send the message");
+
+    let Message::Data(message) = rx.recv().expect("Received no message") else {
+        panic!("Unexpected watermark");
+    };
+    let bytes = message
+        .payload()
+        .try_as_bytes()
+        .expect("Failed to serialise");
+
+    let (tx, rx) = flume::unbounded::<Message>();
+    let input = InputBuilder {
+        port_id: key,
+        receiver: rx,
+        counters: Arc::default(),
+        tracer: None,
+    }
+    .typed_with::<T, C>();
+
+    tx.send(LinkMessage::new(Payload::Bytes(bytes), *message.timestamp()).into())
+        .expect("Failed to send the message");
+
+    match input.try_recv().expect("Failed to receive") {
+        Some(TypedMessage::Data(data, _)) => assert_eq!(expected_data, *data),
+        _ => panic!("Expected data"),
+    }
+}
+
+#[test]
+fn test_bincode() {
+    test_codec::<TestData, Bincode>(test_data());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json() {
+    test_codec::<TestData, Json>(test_data());
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor() {
+    test_codec::<TestData, Cbor>(test_data());
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack() {
+    test_codec::<TestData, MessagePack>(test_data());
+}
+
+#[cfg(feature = "protobuf")]
+#[test]
+fn test_protobuf() {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TestProto {
+        #[prost(int64, tag = "1")]
+        pub field1: i64,
+        #[prost(string, tag = "2")]
+        pub field2: ::prost::alloc::string::String,
+    }
+
+    test_codec::<TestProto, Protobuf>(TestProto {
+        field1: 1,
+        field2: "two".into(),
+    });
+}
@@ -0,0 +1,91 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use uhlc::HLC;
+
+use super::*;
+
+#[test]
+fn test_next_deadline() {
+    let period = Duration::from_millis(10);
+    let deadline = Instant::now();
+
+    // On time: the next deadline is exactly one period after the previous one.
+    assert_eq!(
+        (deadline + period, 0),
+        next_deadline(deadline, period, deadline + Duration::from_millis(2))
+    );
+
+    // Late by 2.5 periods: the ticks at +10ms and +20ms are skipped, the next one is at +30ms.
+    assert_eq!(
+        (deadline + period * 3, 2),
+        next_deadline(deadline, period, deadline + Duration::from_millis(25))
+    );
+
+    // Exactly on the following deadline: it is considered missed.
+    assert_eq!(
+        (deadline + period * 2, 1),
+        next_deadline(deadline, period, deadline + period)
+    );
+}
+
+#[test]
+fn test_fixed_rate_timer() {
+    let hlc = Arc::new(HLC::default());
+    let period = Duration::from_millis(5);
+    let mut timer = Timer::new(period, TimerMode::FixedRate, hlc);
+
+    let start = Instant::now();
+    let first = futures::executor::block_on(timer.tick());
+    let second = futures::executor::block_on(timer.tick());
+
+    assert!(start.elapsed() >= period);
+    assert!(first < second);
+
+    // Falling behind: the ticks that were missed are skipped and accounted for.
+    std::thread::sleep(period * 3);
+    futures::executor::block_on(timer.tick());
+    assert!(timer.missed() >= 2);
+}
+
+#[test]
+fn test_fixed_delay_timer() {
+    let hlc = Arc::new(HLC::default());
+    let delay = Duration::from_millis(5);
+    let mut timer = Timer::new(delay, TimerMode::FixedDelay, hlc);
+
+    futures::executor::block_on(timer.tick());
+    // Whatever the time spent between two ticks, there is always at least `delay` between them.
+    std::thread::sleep(delay * 2);
+    let instant = Instant::now();
+    futures::executor::block_on(timer.tick());
+
+    assert!(instant.elapsed() >= delay);
+    assert_eq!(0, timer.missed());
+}
+
+#[test]
+#[should_panic]
+fn test_zero_period() {
+    Timer::new(
+        Duration::ZERO,
+        TimerMode::FixedRate,
+        Arc::new(HLC::default()),
+    );
+}
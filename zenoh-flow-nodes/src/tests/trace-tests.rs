@@ -0,0 +1,108 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::sync::Arc;
+
+use super::*;
+use crate::{
+    io::{Inputs, Outputs},
+    messages::Message,
+};
+
+#[test]
+fn test_traceparent() {
+    let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    let context: TraceContext = traceparent.parse().expect("Failed to parse traceparent");
+    assert_eq!(0x4bf92f3577b34da6a3ce929d0e0e4736, context.trace_id());
+    assert_eq!(0x00f067aa0ba902b7, context.span_id());
+    assert!(context.is_sampled());
+    assert_eq!(traceparent, context.to_string());
+
+    let child = context.child();
+    assert_eq!(context.trace_id(), child.trace_id());
+    assert_ne!(context.span_id(), child.span_id());
+
+    for invalid in [
+        "",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e47zz-00f067aa0ba902b7-01",
+    ] {
+        assert!(
+            invalid.parse::<TraceContext>().is_err(),
+            "< {invalid} > should be invalid"
+        );
+    }
+}
+
+#[test]
+fn test_propagation() {
+    let hlc = Arc::new(uhlc::HLC::default());
+    let (hops_tx, hops_rx) = flume::unbounded();
+
+    // Source --"out"/"in"--> Operator --"out"--> ...
+    let source_tracer = Arc::new(Tracer::new("source".into()).with_start_traces(true));
+    let operator_tracer = Arc::new(Tracer::new("operator".into()).with_sink(Some(hops_tx)));
+
+    let (tx, rx) = flume::unbounded::<Message>();
+    let mut source_outputs = Outputs::new(hlc.clone()).with_tracer(source_tracer);
+    source_outputs.insert("out".into(), tx);
+    let mut operator_inputs = Inputs::default().with_tracer(operator_tracer.clone());
+    operator_inputs.insert("in".into(), rx);
+
+    let (tx, downstream) = flume::unbounded::<Message>();
+    let mut operator_outputs = Outputs::new(hlc).with_tracer(operator_tracer.clone());
+    operator_outputs.insert("out".into(), tx);
+
+    let source_output = source_outputs.take("out").unwrap().raw();
+    let operator_input = operator_inputs.take("in").unwrap().raw();
+    let operator_output = operator_outputs.take("out").unwrap().raw();
+
+    // The Source starts a new trace.
+    source_output.try_send(vec![1u8], None).unwrap();
+    let Some(Message::Data(message)) = operator_input.try_recv().unwrap() else {
+        panic!("Expected a message");
+    };
+    let root = TraceContext::from_headers(message.headers()).expect("Expected a trace context");
+
+    // The Operator propagates the context of its hop, child of the context of the Source.
+    operator_output.try_send(vec![2u8], None).unwrap();
+    let Ok(Message::Data(message)) = downstream.try_recv() else {
+        panic!("Expected a message");
+    };
+    let hop_context =
+        TraceContext::from_headers(message.headers()).expect("Expected a trace context");
+    assert_eq!(root.trace_id(), hop_context.trace_id());
+    assert_ne!(root.span_id(), hop_context.span_id());
+
+    // The hop is exported once finished (i.e. at the end of the iteration).
+    assert!(hops_rx.is_empty());
+    operator_tracer.finish();
+    let hop = hops_rx.try_recv().expect("Expected a hop");
+    assert_eq!(hop_context, hop.context);
+    assert_eq!(root.span_id(), hop.parent_span_id);
+    assert_eq!(NodeId::from("operator"), hop.node);
+    assert_eq!(PortId::from("in"), hop.port);
+    assert!(hop.start <= hop.end);
+
+    // Outside of a hop, and without starting traces, a message sent carries no context.
+    operator_output.try_send(vec![3u8], None).unwrap();
+    let Ok(Message::Data(message)) = downstream.try_recv() else {
+        panic!("Expected a message");
+    };
+    assert!(TraceContext::from_headers(message.headers()).is_none());
+}
@@ -0,0 +1,160 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use uhlc::{Timestamp, HLC};
+
+/// How a [Timer] computes its next deadline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerMode {
+    /// The deadlines are computed from the *previous deadline*: the time spent between two ticks does not shift the
+    /// following ones. If the node falls behind, the missed ticks are skipped (and counted) rather than fired in a
+    /// burst.
+    FixedRate,
+    /// The deadlines are computed from the moment [tick](Timer::tick()) is called: there is always *at least* the
+    /// configured delay between the end of a processing and the next tick.
+    FixedDelay,
+}
+
+/// A `Timer` allows a node to perform an action at regular intervals.
+///
+/// A `Timer` is obtained through the [Context](crate::prelude::Context) of a node, either with
+/// [fixed_rate_timer](crate::prelude::Context::fixed_rate_timer()) or with
+/// [fixed_delay_timer](crate::prelude::Context::fixed_delay_timer()). Each tick is timestamped with the
+/// [HLC](uhlc::HLC) of the Zenoh-Flow runtime, such that the timestamps of the ticks and of the messages sent by the
+/// node are comparable.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use zenoh_flow_nodes::prelude::*;
+/// # async fn example(context: Context, output: Output<u64>) -> Result<()> {
+/// let mut timer = context.fixed_rate_timer(Duration::from_millis(100));
+/// loop {
+///     let timestamp = timer.tick().await;
+///     output.send(42, Some(timestamp.get_time().as_u64())).await?;
+/// }
+/// # }
+/// ```
+pub struct Timer {
+    period: Duration,
+    mode: TimerMode,
+    deadline: Instant,
+    missed: u64,
+    hlc: Arc<HLC>,
+}
+
+impl std::fmt::Debug for Timer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timer")
+            .field("period", &self.period)
+            .field("mode", &self.mode)
+            .field("deadline", &self.deadline)
+            .field("missed", &self.missed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Timer {
+    /// Creates a new `Timer` whose first tick will happen after one `period`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the `period` is zero.
+    pub(crate) fn new(period: Duration, mode: TimerMode, hlc: Arc<HLC>) -> Self {
+        assert!(!period.is_zero(), "The period of a Timer cannot be zero");
+
+        Self {
+            period,
+            mode,
+            deadline: Instant::now() + period,
+            missed: 0,
+            hlc,
+        }
+    }
+
+    /// Returns the period (or delay) of this `Timer`.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the [TimerMode] of this `Timer`.
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    /// Returns the number of ticks that were skipped because the node was not calling [tick](Timer::tick()) often
+    /// enough.
+    ///
+    /// Only a [fixed-rate](TimerMode::FixedRate) `Timer` can miss ticks.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    /// Waits until the next deadline of this `Timer` and returns the [Timestamp] at which it was reached.
+    ///
+    /// If the deadline is already past, this method returns immediately.
+    pub async fn tick(&mut self) -> Timestamp {
+        if self.mode == TimerMode::FixedDelay {
+            self.deadline = Instant::now() + self.period;
+        }
+
+        let now = Instant::now();
+        if self.deadline > now {
+            async_std::task::sleep(self.deadline - now).await;
+        }
+        let timestamp = self.hlc.new_timestamp();
+
+        if self.mode == TimerMode::FixedRate {
+            let (deadline, missed) = next_deadline(self.deadline, self.period, Instant::now());
+            self.deadline = deadline;
+            self.missed += missed;
+        }
+
+        timestamp
+    }
+}
+
+/// Returns the first deadline, following `deadline` by a multiple of `period`, that is after `now` as well as the
+/// number of deadlines that were skipped to reach it.
+///
+/// # Panics
+///
+/// This function will panic if the `period` is zero.
+pub fn next_deadline(deadline: Instant, period: Duration, now: Instant) -> (Instant, u64) {
+    let next = deadline + period;
+    if next > now {
+        return (next, 0);
+    }
+
+    let late = now.duration_since(next).as_nanos();
+    let skipped = u64::try_from(late / period.as_nanos())
+        .map_or(u64::MAX, |skipped| skipped.saturating_add(1));
+    // The remainder is smaller than `period`: it fits in a `Duration` and can be subtracted from it.
+    let remainder = late % period.as_nanos();
+    let remainder = Duration::new(
+        (remainder / 1_000_000_000) as u64,
+        (remainder % 1_000_000_000) as u32,
+    );
+    (now + (period - remainder), skipped)
+}
+
+#[cfg(test)]
+#[path = "./tests/timer-tests.rs"]
+mod tests;
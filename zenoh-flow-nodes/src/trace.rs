@@ -0,0 +1,302 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{fmt::Display, str::FromStr, sync::Mutex, time::SystemTime};
+
+use anyhow::{anyhow, bail};
+use zenoh_flow_commons::{NodeId, PortId};
+
+use crate::messages::Headers;
+
+/// The key of the [Headers] in which the [TraceContext] of a message is carried, as defined by the W3C Trace Context
+/// recommendation.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// The sampled flag of the `trace-flags` of a [TraceContext].
+const FLAG_SAMPLED: u8 = 0x01;
+
+/// A `TraceContext` identifies the span, of a distributed trace, that produced a message.
+///
+/// It is carried in the [Headers] of the messages, under the key [TRACEPARENT], following the format of the W3C Trace
+/// Context recommendation: `00-<trace-id>-<parent-id>-<trace-flags>`. As the [Headers] are part of the bincode frame
+/// exchanged between Zenoh-Flow runtimes, the context follows a message across all the nodes and runtimes it
+/// traverses.
+///
+/// # Example
+///
+/// ```
+/// # use zenoh_flow_nodes::prelude::*;
+/// let context: TraceContext = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(0x00f067aa0ba902b7, context.span_id());
+/// assert!(context.is_sampled());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    flags: u8,
+}
+
+impl TraceContext {
+    /// Creates the context of the first span of a new (sampled) trace.
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: uuid::Uuid::new_v4().as_u128(),
+            span_id: new_span_id(),
+            flags: FLAG_SAMPLED,
+        }
+    }
+
+    /// Creates the context of a new span, child of this one: it belongs to the same trace.
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id,
+            span_id: new_span_id(),
+            flags: self.flags,
+        }
+    }
+
+    /// Returns the identifier of the trace.
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// Returns the identifier of the span.
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Returns `true` if the trace was sampled, i.e. if its spans should be recorded.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & FLAG_SAMPLED != 0
+    }
+
+    /// Returns the `TraceContext` carried in the [Headers], if there is a valid one.
+    pub fn from_headers(headers: &Headers) -> Option<Self> {
+        headers
+            .get_str(TRACEPARENT)
+            .and_then(|traceparent| traceparent.parse().ok())
+    }
+
+    /// Inserts this `TraceContext` in the [Headers], replacing the previous one if there was one.
+    pub fn inject(&self, headers: &mut Headers) {
+        headers.insert(TRACEPARENT, self.to_string());
+    }
+}
+
+/// Returns a random, non-zero, span identifier.
+fn new_span_id() -> u64 {
+    (uuid::Uuid::new_v4().as_u128() as u64).max(1)
+}
+
+impl Display for TraceContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.span_id, self.flags
+        )
+    }
+}
+
+impl FromStr for TraceContext {
+    type Err = anyhow::Error;
+
+    fn from_str(traceparent: &str) -> Result<Self, Self::Err> {
+        let parts = traceparent.trim().split('-').collect::<Vec<_>>();
+        let [version, trace_id, span_id, flags] = parts[..] else {
+            bail!("Expected 4 fields in < {traceparent} >");
+        };
+
+        let hex = |field: &str, length: usize| {
+            if field.len() != length || !field.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(anyhow!("Invalid field < {field} > in < {traceparent} >"));
+            }
+            Ok(())
+        };
+
+        hex(version, 2)?;
+        hex(trace_id, 32)?;
+        hex(span_id, 16)?;
+        hex(flags, 2)?;
+
+        // NOTE: Later versions of the recommendation may append fields, the version "ff" is forbidden.
+        if version == "ff" {
+            bail!("Invalid version in < {traceparent} >");
+        }
+
+        let context = Self {
+            trace_id: u128::from_str_radix(trace_id, 16)?,
+            span_id: u64::from_str_radix(span_id, 16)?,
+            flags: u8::from_str_radix(flags, 16)?,
+        };
+
+        if context.trace_id == 0 || context.span_id == 0 {
+            bail!("Trace and span identifiers cannot be zero in < {traceparent} >");
+        }
+
+        Ok(context)
+    }
+}
+
+/// A `Hop` is the span covering the processing of a message by a node: from its reception on an Input until the end of
+/// the `iteration` (or the reception of the next message).
+#[derive(Clone, Debug)]
+pub struct Hop {
+    /// The context of the span of this hop.
+    pub context: TraceContext,
+    /// The identifier of the span that sent the message.
+    pub parent_span_id: u64,
+    /// The node that processed the message.
+    pub node: NodeId,
+    /// The port on which the message was received.
+    pub port: PortId,
+    /// When the message was received.
+    pub start: SystemTime,
+    /// When the processing of the message ended.
+    pub end: SystemTime,
+}
+
+/// The hop currently being processed by a node.
+#[derive(Debug)]
+struct ActiveHop {
+    context: TraceContext,
+    parent_span_id: u64,
+    port: PortId,
+    start: SystemTime,
+    // Dropping the span closes it.
+    _span: tracing::Span,
+}
+
+/// The `Tracer` of a node keeps track of the [Hop] it is processing and propagates its [TraceContext] to the messages
+/// it sends.
+///
+/// It is shared by the Inputs and Outputs of a node, and by the Zenoh-Flow runtime that runs it:
+/// - when a message carrying a [TraceContext] is received, a new hop, child of that context, starts,
+/// - when a message is sent, the context of the current hop replaces the one it carries (if any),
+/// - when the `iteration` of the node ends, the current hop ends as well and it is exported.
+///
+/// A message sent while no hop is in progress keeps its context or, if it has none and the `Tracer` was configured to
+/// do so, starts a new trace.
+#[derive(Debug)]
+pub struct Tracer {
+    node_id: NodeId,
+    start_traces: bool,
+    sink: Option<flume::Sender<Hop>>,
+    current: Mutex<Option<ActiveHop>>,
+}
+
+impl Tracer {
+    /// Creates the `Tracer` of the node `node_id`.
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            start_traces: false,
+            sink: None,
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Sets whether the messages sent without a [TraceContext], outside of a hop, start a new trace.
+    pub fn with_start_traces(mut self, start_traces: bool) -> Self {
+        self.start_traces = start_traces;
+        self
+    }
+
+    /// Sets the channel on which the finished (and sampled) [Hop]s are sent.
+    ///
+    /// If the channel is full, the hops are discarded.
+    pub fn with_sink(mut self, sink: Option<flume::Sender<Hop>>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Finishes the current hop and, if the `headers` of the message received on the Input `port` carry a
+    /// [TraceContext], starts a new one.
+    pub fn enter(&self, port: &PortId, headers: &Headers) {
+        self.finish();
+
+        let Some(parent) = TraceContext::from_headers(headers) else {
+            return;
+        };
+
+        let context = parent.child();
+        let span = tracing::debug_span!(
+            "hop",
+            node = %self.node_id,
+            port = %port,
+            trace_id = %format!("{:032x}", context.trace_id),
+            span_id = %format!("{:016x}", context.span_id),
+            parent_span_id = %format!("{:016x}", parent.span_id),
+        );
+
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(ActiveHop {
+                context,
+                parent_span_id: parent.span_id,
+                port: port.clone(),
+                start: SystemTime::now(),
+                _span: span,
+            });
+        }
+    }
+
+    /// Propagates the [TraceContext] of the current hop in the `headers` of a message being sent.
+    pub fn inject(&self, headers: &mut Headers) {
+        let current = self
+            .current
+            .lock()
+            .ok()
+            .and_then(|current| current.as_ref().map(|hop| hop.context));
+
+        match current {
+            Some(context) => context.inject(headers),
+            None if self.start_traces && !headers.contains_key(TRACEPARENT) => {
+                TraceContext::new_root().inject(headers)
+            }
+            None => (),
+        }
+    }
+
+    /// Finishes the current hop, if there is one, sending it to the sink if its trace is sampled.
+    pub fn finish(&self) {
+        let Some(hop) = self
+            .current
+            .lock()
+            .ok()
+            .and_then(|mut current| current.take())
+        else {
+            return;
+        };
+
+        if let Some(sink) = &self.sink {
+            if hop.context.is_sampled() {
+                let _ = sink.try_send(Hop {
+                    context: hop.context,
+                    parent_span_id: hop.parent_span_id,
+                    node: self.node_id.clone(),
+                    port: hop.port,
+                    start: hop.start,
+                    end: SystemTime::now(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/trace-tests.rs"]
+mod tests;
@@ -58,13 +58,31 @@ impl<T: 'static + Send + Sync> SendSyncAny for T {
 /// For usage examples see the [Operator](crate::prelude::Operator), [Source](crate::prelude::Source) or
 /// [Sink](crate::prelude::Sink) traits.
 ///
-/// # Additional hooks: `on_resume`, `on_abort`
+/// # Additional hooks: `on_resume`, `on_abort`, `on_stop`
 ///
 /// It is possible to define specific code that the Zenoh-Flow runtime should run *before* the node is aborted and
 /// *before* it is resumed.
 ///
 /// Note that the `on_resume` hook is only run once the node has been aborted. It is not run when it is created.
 ///
+/// The `on_stop` hook is run, instead of `on_abort`, when the node was *gracefully* stopped: its `iteration` was not
+/// cancelled while it was being executed.
+///
+/// # Live reconfiguration: `on_configure`
+///
+/// The [Configuration] of a node is given to its constructor. A node that can adapt its behaviour while it runs (e.g.
+/// a threshold or a rate) can implement [on_configure](Node::on_configure()): it receives the new configuration
+/// whenever it is changed on the Zenoh-Flow runtime. By default, a node refuses to be reconfigured.
+///
+/// # Checkpoints: `snapshot`, `restore`
+///
+/// A node that keeps a state (e.g. a window, a counter or a model) can have it saved in the checkpoints of the data
+/// flow instance by implementing [snapshot](Node::snapshot()). When the same data flow instance is loaded again, the
+/// last saved state is given to [restore](Node::restore()), before the node is started.
+///
+/// The Zenoh-Flow runtime only takes a snapshot once the messages in flight were processed, such that the states of
+/// all the nodes of a data flow instance are consistent with each other.
+///
 /// A default blank implementation is provided.
 #[async_trait]
 pub trait Node: Send + Sync {
@@ -91,6 +109,52 @@ pub trait Node: Send + Sync {
     }
 
     async fn on_abort(&self) {}
+
+    /// Custom code that Zenoh-Flow will run after the node was gracefully stopped.
+    ///
+    /// When a data flow is gracefully stopped, the Sources are stopped first, once their current `iteration` is over.
+    /// The messages still in flight are then processed by the Operators and Sinks. Once they are all processed (or
+    /// once the deadline is reached), the nodes are stopped and this hook is called --- the nodes whose `iteration`
+    /// had to be cancelled after the deadline have their `on_abort` hook called instead.
+    ///
+    /// The node is therefore in a consistent state: this is where resources should be flushed or released.
+    ///
+    /// The blanket implementation does nothing.
+    async fn on_stop(&self) {}
+
+    /// Custom code that Zenoh-Flow will run when the [Configuration] of the node is changed while it is loaded.
+    ///
+    /// The `configuration` is the complete, updated, configuration: the changes requested were already merged with
+    /// the previous configuration. If an error is returned, the change is refused and the previous configuration is
+    /// kept.
+    ///
+    /// This hook is called concurrently with the `iteration` of the node, which is not interrupted.
+    ///
+    /// The blanket implementation refuses the change, returning an error.
+    async fn on_configure(&self, _configuration: Configuration) -> Result<()> {
+        anyhow::bail!("This node does not support being reconfigured while it is loaded")
+    }
+
+    /// Returns the state of the node, serialised, to be saved in a checkpoint.
+    ///
+    /// An empty state is not saved. The blanket implementation returns an empty state.
+    ///
+    /// # Performance
+    ///
+    /// This method is only called when a checkpoint is taken: the Sources are paused in the meantime.
+    async fn snapshot(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    /// Restores the state of the node from the last checkpoint in which it was saved.
+    ///
+    /// This method is called after the node is created and before it is started, only if the node had a non-empty
+    /// state in the checkpoint.
+    ///
+    /// The blanket implementation defaults to returning `Ok(())`.
+    async fn restore(&self, _state: &[u8]) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// A `Source` feeds data into a data flow.
@@ -201,8 +265,12 @@ pub trait Source: Node + Send + Sync {
 /// #[async_trait]
 /// impl Node for NoOp {
 ///     async fn iteration(&self) -> Result<()> {
-///         let (message, _timestamp) = self.input.recv().await?;
-///         self.output.send(*message, None).await
+///         match self.input.recv().await? {
+///             TypedMessage::Data(message, _timestamp) => self.output.send(*message, None).await,
+///             TypedMessage::Watermark(timestamp) => {
+///                 self.output.send_watermark(Some(timestamp.get_time().as_u64())).await
+///             }
+///         }
 ///     }
 /// }
 /// ```
@@ -261,8 +329,9 @@ pub trait Operator: Node + Send + Sync {
 /// #[async_trait]
 /// impl Node for GenericSink {
 ///     async fn iteration(&self) -> Result<()> {
-///         let (message, _timestamp) = self.input.recv().await?;
-///         println!("{}", *message);
+///         if let TypedMessage::Data(message, _timestamp) = self.input.recv().await? {
+///             println!("{}", *message);
+///         }
 ///
 ///         Ok(())
 ///     }
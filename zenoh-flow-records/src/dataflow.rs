@@ -20,10 +20,11 @@ use std::{
 use anyhow::{anyhow, bail, Context};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use zenoh_flow_commons::{InstanceId, NodeId, Result, RuntimeId};
+use zenoh_flow_commons::{Configuration, InstanceId, NodeId, Result, RuntimeId};
 use zenoh_flow_descriptors::{
-    FlattenedDataFlowDescriptor, FlattenedOperatorDescriptor, FlattenedSinkDescriptor,
-    FlattenedSourceDescriptor, InputDescriptor, LinkDescriptor, OutputDescriptor,
+    FailoverPolicy, FlattenedDataFlowDescriptor, FlattenedDataFlowUpdate,
+    FlattenedOperatorDescriptor, FlattenedSinkDescriptor, FlattenedSourceDescriptor,
+    InputDescriptor, LinkDescriptor, OutputDescriptor,
 };
 use zenoh_keyexpr::OwnedKeyExpr;
 
@@ -54,6 +55,8 @@ pub struct DataFlowRecord {
     pub(crate) receivers: HashMap<NodeId, ReceiverRecord>,
     pub(crate) links: Vec<LinkDescriptor>,
     pub(crate) mapping: HashMap<RuntimeId, HashSet<NodeId>>,
+    #[serde(default)]
+    pub(crate) failover: HashMap<NodeId, FailoverPolicy>,
 }
 
 impl DataFlowRecord {
@@ -75,6 +78,10 @@ impl DataFlowRecord {
     ///
     /// Node that this should not happen if the [FlattenedDataFlowDescriptor] was obtained by parsing and flattening a
     /// [DataFlowDescriptor](zenoh_flow_descriptors::DataFlowDescriptor).
+    ///
+    /// The creation will also fail if a node has [placement](zenoh_flow_descriptors::PlacementDescriptor) constraints
+    /// but no mapping: the constraints must first be resolved through
+    /// [try_place](FlattenedDataFlowDescriptor::try_place()).
     pub fn try_new(
         data_flow: &FlattenedDataFlowDescriptor,
         default_runtime: &RuntimeId,
@@ -87,8 +94,25 @@ impl DataFlowRecord {
             sinks,
             mut links,
             mut mapping,
+            failover,
+            placement,
         } = data_flow.clone();
 
+        // NOTE: The placement constraints are not resolved here as it requires knowing the Zenoh-Flow runtimes that are
+        // reachable, see `FlattenedDataFlowDescriptor::try_place`.
+        let mut unplaced = placement
+            .keys()
+            .filter(|&node_id| !mapping.values().any(|nodes| nodes.contains(node_id)))
+            .map(|node_id| node_id.to_string())
+            .collect::<Vec<_>>();
+        if !unplaced.is_empty() {
+            unplaced.sort();
+            bail!(
+                "The placement of the following nodes was not resolved: {}",
+                unplaced.join(", ")
+            );
+        }
+
         let id = id.unwrap_or_else(|| Uuid::new_v4().into());
 
         // Nodes that are not running on the same runtime need to be connected.
@@ -195,6 +219,7 @@ Caused by:
                 additional_links.push(LinkDescriptor {
                     from: output,
                     to: input,
+                    queue: link.queue,
                     #[cfg(feature = "shared-memory")]
                     shared_memory: link.shared_memory,
                 });
@@ -245,9 +270,174 @@ Caused by:
             receivers,
             links,
             mapping,
+            failover,
         })
     }
 
+    /// Attempts to apply the [update](FlattenedDataFlowUpdate) to the graph of this [DataFlowRecord], returning the
+    /// record of the updated data flow instance.
+    ///
+    /// The updated record keeps the same instance identifier and name. The nodes that were not touched by the update
+    /// keep their mapping, the nodes added without a mapping are assigned to the default [runtime](RuntimeId). The
+    /// connectors are generated anew: as their identifiers are derived from the nodes and ports they connect, the ones
+    /// that are still needed are identical.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the update could not be applied (see
+    /// [try_update](FlattenedDataFlowDescriptor::try_update())) or if the record of the updated data flow could not be
+    /// created.
+    pub fn try_update(
+        &self,
+        update: &FlattenedDataFlowUpdate,
+        default_runtime: &RuntimeId,
+    ) -> Result<Self> {
+        let data_flow = self.flattened().try_update(update).with_context(|| {
+            format!(
+                "Failed to update the graph of data flow < {} > ({})",
+                self.id, self.name
+            )
+        })?;
+
+        Self::try_new(&data_flow, default_runtime)
+    }
+
+    /// Returns the [update](FlattenedDataFlowUpdate) that re-places, on another Zenoh-Flow runtime, the nodes of the
+    /// `lost_runtime` that have a [FailoverPolicy].
+    ///
+    /// A node is re-placed on the first runtime that is eligible and that appears in `alive_runtimes`:
+    /// - with the [Any](FailoverPolicy::Any) policy, the eligible runtimes are the ones already involved in this data
+    ///   flow instance, in the order of `alive_runtimes`,
+    /// - with the [Runtimes](FailoverPolicy::Runtimes) policy, the eligible runtimes are the ones listed, in that order.
+    ///
+    /// The nodes without a failover policy are left on the `lost_runtime`. Applying the update through
+    /// [try_update](DataFlowRecord::try_update()) regenerates the connectors of the links that moved.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if a node to re-place has no eligible runtime that is alive.
+    pub fn try_failover(
+        &self,
+        lost_runtime: &RuntimeId,
+        alive_runtimes: &[RuntimeId],
+    ) -> Result<FlattenedDataFlowUpdate> {
+        let mut update = FlattenedDataFlowUpdate::default();
+        let Some(nodes) = self.mapping.get(lost_runtime) else {
+            return Ok(update);
+        };
+
+        let alive_runtimes = alive_runtimes
+            .iter()
+            .filter(|&runtime_id| runtime_id != lost_runtime)
+            .collect::<Vec<_>>();
+
+        for node_id in nodes.iter() {
+            let Some(policy) = self.failover.get(node_id) else {
+                continue;
+            };
+
+            let runtime_id = match policy {
+                FailoverPolicy::Never => continue,
+                FailoverPolicy::Any => alive_runtimes
+                    .iter()
+                    .find(|&&runtime_id| self.mapping.contains_key(runtime_id))
+                    .copied(),
+                FailoverPolicy::Runtimes(runtimes) => runtimes
+                    .iter()
+                    .find(|&runtime_id| alive_runtimes.contains(&runtime_id)),
+            }
+            .ok_or_else(|| {
+                anyhow!(
+                    "Found no runtime alive to re-place node < {} > (failover: {})",
+                    node_id,
+                    policy
+                )
+            })?;
+
+            update
+                .mapping
+                .entry(runtime_id.clone())
+                .or_default()
+                .insert(node_id.clone());
+            update.failover.insert(node_id.clone(), policy.clone());
+        }
+
+        Ok(update)
+    }
+
+    /// Returns the [FlattenedDataFlowDescriptor] from which this record could have been created: the connectors are
+    /// removed and the links they were part of are restored.
+    fn flattened(&self) -> FlattenedDataFlowDescriptor {
+        let is_connector = |node_id: &NodeId| {
+            self.senders.contains_key(node_id) || self.receivers.contains_key(node_id)
+        };
+
+        let mut links = self
+            .links
+            .iter()
+            .filter(|link| !is_connector(&link.from.node) && !is_connector(&link.to.node))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // A link between two runtimes was split in two: `from -> Sender` and `Receiver -> to`, the input of the Sender
+        // and the output of the Receiver being the key expression of the link.
+        for to_sender in self
+            .links
+            .iter()
+            .filter(|link| self.senders.contains_key(&link.to.node))
+        {
+            for from_receiver in self.links.iter().filter(|link| {
+                self.receivers.contains_key(&link.from.node)
+                    && link.from.output == to_sender.to.input
+            }) {
+                // NOTE: All the links starting from the same output share the same key expression, they thus go
+                // through the same Sender.
+                if links
+                    .iter()
+                    .any(|link| link.from == to_sender.from && link.to == from_receiver.to)
+                {
+                    continue;
+                }
+
+                links.push(LinkDescriptor {
+                    from: to_sender.from.clone(),
+                    to: from_receiver.to.clone(),
+                    queue: to_sender.queue,
+                    #[cfg(feature = "shared-memory")]
+                    shared_memory: to_sender.shared_memory,
+                });
+            }
+        }
+
+        let mapping = self
+            .mapping
+            .iter()
+            .map(|(runtime_id, nodes)| {
+                (
+                    runtime_id.clone(),
+                    nodes
+                        .iter()
+                        .filter(|&node_id| !is_connector(node_id))
+                        .cloned()
+                        .collect::<HashSet<_>>(),
+                )
+            })
+            .filter(|(_, nodes)| !nodes.is_empty())
+            .collect();
+
+        FlattenedDataFlowDescriptor {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            sources: self.sources.values().cloned().collect(),
+            operators: self.operators.values().cloned().collect(),
+            sinks: self.sinks.values().cloned().collect(),
+            links,
+            mapping,
+            failover: self.failover.clone(),
+            placement: HashMap::default(),
+        }
+    }
+
     /// Returns the unique identifier of this [`DataFlowRecord`].
     ///
     /// # Performance
@@ -267,6 +457,12 @@ Caused by:
         &self.mapping
     }
 
+    /// Returns the [FailoverPolicy] of the nodes that should be re-placed if the Zenoh-Flow runtime they run on is
+    /// lost.
+    pub fn failover(&self) -> &HashMap<NodeId, FailoverPolicy> {
+        &self.failover
+    }
+
     /// Returns the set of [Senders](SenderRecord) of the data flow.
     ///
     /// A [Sender](SenderRecord) sends data, through a publication on Zenoh, to [Receiver(s)](ReceiverRecord).
@@ -310,6 +506,37 @@ Caused by:
     pub fn sinks(&self) -> &HashMap<NodeId, FlattenedSinkDescriptor> {
         &self.sinks
     }
+
+    /// Returns the [Configuration] of the Source, Operator or Sink identified by `node_id`, if there is one.
+    pub fn configuration(&self, node_id: &NodeId) -> Option<&Configuration> {
+        self.sources
+            .get(node_id)
+            .map(|source| &source.configuration)
+            .or_else(|| {
+                self.operators
+                    .get(node_id)
+                    .map(|operator| &operator.configuration)
+            })
+            .or_else(|| self.sinks.get(node_id).map(|sink| &sink.configuration))
+    }
+
+    /// Returns a mutable reference to the [Configuration] of the Source, Operator or Sink identified by `node_id`, if
+    /// there is one.
+    ///
+    /// This is used to keep the record up to date when a node is reconfigured while the data flow instance runs.
+    pub fn configuration_mut(&mut self, node_id: &NodeId) -> Option<&mut Configuration> {
+        if let Some(source) = self.sources.get_mut(node_id) {
+            return Some(&mut source.configuration);
+        }
+
+        if let Some(operator) = self.operators.get_mut(node_id) {
+            return Some(&mut operator.configuration);
+        }
+
+        self.sinks
+            .get_mut(node_id)
+            .map(|sink| &mut sink.configuration)
+    }
 }
 
 #[cfg(test)]
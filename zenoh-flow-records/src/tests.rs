@@ -14,10 +14,10 @@
 
 use std::collections::{HashMap, HashSet};
 
-use zenoh_flow_commons::{NodeId, RuntimeId, Vars};
+use zenoh_flow_commons::{Configuration, NodeId, RuntimeId, Vars};
 use zenoh_flow_descriptors::{
-    DataFlowDescriptor, FlattenedDataFlowDescriptor, InputDescriptor, LinkDescriptor,
-    OutputDescriptor,
+    DataFlowDescriptor, DataFlowUpdateDescriptor, FlattenedDataFlowDescriptor,
+    FlattenedDataFlowUpdate, InputDescriptor, LinkDescriptor, OutputDescriptor,
 };
 use zenoh_keyexpr::OwnedKeyExpr;
 
@@ -268,6 +268,7 @@ mapping:
             node: sender_thing_edge.clone(),
             input: key_expr_thing_edge.to_string().into(),
         },
+        queue: None,
         #[cfg(feature = "shared-memory")]
         shared_memory: None,
     };
@@ -282,6 +283,7 @@ mapping:
             node: "operator-1".into(),
             input: "in-1".into(),
         },
+        queue: None,
         #[cfg(feature = "shared-memory")]
         shared_memory: None,
     };
@@ -296,6 +298,7 @@ mapping:
             node: sender_edge_default.clone(),
             input: key_expr_edge_default.to_string().into(),
         },
+        queue: None,
         #[cfg(feature = "shared-memory")]
         shared_memory: None,
     };
@@ -310,6 +313,7 @@ mapping:
             node: "sink-2".into(),
             input: "in-2".into(),
         },
+        queue: None,
         #[cfg(feature = "shared-memory")]
         shared_memory: None,
     };
@@ -395,3 +399,264 @@ links:
     let _string = serde_yaml::to_string(&record).expect("Failed to serialize to yaml");
     println!("{_string}");
 }
+
+#[test]
+fn test_configuration() {
+    let flow_yaml = r#"
+name: base test flow
+
+sources:
+  - id: source-0
+    description: test source
+    library: file:///home/zenoh-flow/libsource.so
+    outputs:
+      - out-0
+
+sinks:
+  - id: sink-1
+    description: test sink
+    library: file:///home/zenoh-flow/libsink.so
+    inputs:
+      - in-1
+    configuration:
+      threshold: 10
+
+links:
+  - from:
+     node: source-0
+     output: out-0
+    to:
+     node: sink-1
+     input: in-1
+    "#;
+
+    let flat_desc = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str::<DataFlowDescriptor>(flow_yaml).unwrap(),
+        Vars::default(),
+    )
+    .unwrap();
+
+    let mut record = DataFlowRecord::try_new(&flat_desc, &RuntimeId::rand()).unwrap();
+    let sink_id: NodeId = "sink-1".into();
+
+    assert_eq!(
+        Some(&Configuration::from(serde_json::json!({ "threshold": 10 }))),
+        record.configuration(&sink_id)
+    );
+    assert_eq!(
+        Some(&Configuration::default()),
+        record.configuration(&"source-0".into())
+    );
+    assert!(record.configuration(&"operator-2".into()).is_none());
+
+    *record.configuration_mut(&sink_id).unwrap() =
+        Configuration::from(serde_json::json!({ "threshold": 20 }));
+    assert_eq!(
+        Some(&Configuration::from(serde_json::json!({ "threshold": 20 }))),
+        record.configuration(&sink_id)
+    );
+}
+
+#[test]
+fn test_update() {
+    let runtime_source = RuntimeId::rand();
+    let runtime_sink = RuntimeId::rand();
+    let flow_yaml = format!(
+        r#"
+name: base test flow
+
+sources:
+  - id: source-0
+    library: file:///home/zenoh-flow/libsource.so
+    outputs:
+      - out-0
+
+sinks:
+  - id: sink-1
+    library: file:///home/zenoh-flow/libsink.so
+    inputs:
+      - in-1
+
+links:
+  - from:
+     node: source-0
+     output: out-0
+    to:
+     node: sink-1
+     input: in-1
+
+mapping:
+  {runtime_source}:
+    - source-0
+  {runtime_sink}:
+    - sink-1
+    "#
+    );
+
+    let flat_desc = FlattenedDataFlowDescriptor::try_flatten(
+        serde_yaml::from_str::<DataFlowDescriptor>(&flow_yaml).unwrap(),
+        Vars::default(),
+    )
+    .unwrap();
+    let record = DataFlowRecord::try_new(&flat_desc, &runtime_source).unwrap();
+
+    let try_update = |record: &DataFlowRecord, update_yaml: &str| {
+        let update = FlattenedDataFlowUpdate::try_flatten(
+            serde_yaml::from_str::<DataFlowUpdateDescriptor>(update_yaml).unwrap(),
+            Vars::default(),
+        )
+        .unwrap();
+        record.try_update(&update, &runtime_source)
+    };
+
+    // Attach a debug Sink, without mapping, to the Source.
+    let debug_yaml = r#"
+sinks:
+  - id: debug
+    library: file:///home/zenoh-flow/libdebug.so
+    inputs:
+      - in-debug
+
+links:
+  - from:
+     node: source-0
+     output: out-0
+    to:
+     node: debug
+     input: in-debug
+"#;
+    let updated = try_update(&record, debug_yaml).expect("Failed to attach the debug Sink");
+    assert_eq!(record.instance_id(), updated.instance_id());
+    assert_eq!(record.name(), updated.name());
+    assert_eq!(record.senders(), updated.senders());
+    assert_eq!(record.receivers(), updated.receivers());
+    assert_eq!(3, updated.links().len());
+    assert!(updated.mapping()[&runtime_source].contains(&NodeId::from("debug")));
+    assert_eq!(
+        record.mapping()[&runtime_sink],
+        updated.mapping()[&runtime_sink]
+    );
+
+    // Removing it restores the initial record.
+    let removed = try_update(&updated, "remove:\n  nodes:\n    - debug\n")
+        .expect("Failed to remove the debug Sink");
+    assert_eq!(record.mapping(), removed.mapping());
+    assert_eq!(record.links().len(), removed.links().len());
+    assert!(record
+        .links()
+        .iter()
+        .all(|link| removed.links().contains(link)));
+
+    // Replacing a node keeps its links and its mapping.
+    let replace_yaml = r#"
+sinks:
+  - id: sink-1
+    library: file:///home/zenoh-flow/libsink-v2.so
+    inputs:
+      - in-1
+"#;
+    let replaced = try_update(&record, replace_yaml).expect("Failed to replace the Sink");
+    assert_eq!(record.links().len(), replaced.links().len());
+    assert_eq!(record.mapping(), replaced.mapping());
+    assert_ne!(record.sinks(), replaced.sinks());
+
+    // An update that leaves the data flow invalid is refused.
+    assert!(try_update(&record, "remove:\n  nodes:\n    - sink-1\n").is_err());
+    assert!(try_update(&record, "remove:\n  nodes:\n    - unknown\n").is_err());
+}
+
+#[test]
+fn test_failover() {
+    let runtime_source = RuntimeId::rand();
+    let runtime_sink = RuntimeId::rand();
+    let runtime_backup = RuntimeId::rand();
+    let flow_yaml = |failover: &str| {
+        format!(
+            r#"
+name: base test flow
+
+sources:
+  - id: source-0
+    library: file:///home/zenoh-flow/libsource.so
+    outputs:
+      - out-0
+
+sinks:
+  - id: sink-1
+    library: file:///home/zenoh-flow/libsink.so
+    inputs:
+      - in-1
+
+links:
+  - from:
+     node: source-0
+     output: out-0
+    to:
+     node: sink-1
+     input: in-1
+
+mapping:
+  {runtime_source}:
+    - source-0
+  {runtime_sink}:
+    - node: sink-1
+      failover: {failover}
+    "#
+        )
+    };
+
+    let try_record = |failover: &str| {
+        let flat_desc = FlattenedDataFlowDescriptor::try_flatten(
+            serde_yaml::from_str::<DataFlowDescriptor>(&flow_yaml(failover)).unwrap(),
+            Vars::default(),
+        )
+        .unwrap();
+        DataFlowRecord::try_new(&flat_desc, &runtime_source).unwrap()
+    };
+
+    // With the `any` policy, the Sink is re-placed on a runtime already involved: the connectors are no longer needed.
+    let record = try_record("any");
+    assert_eq!(1, record.senders().len());
+    let update = record
+        .try_failover(
+            &runtime_sink,
+            &[runtime_backup.clone(), runtime_source.clone()],
+        )
+        .expect("Failed to re-place the Sink");
+    let failed_over = record
+        .try_update(&update, &runtime_source)
+        .expect("Failed to apply the failover");
+    assert_eq!(1, failed_over.mapping().len());
+    assert!(failed_over.mapping()[&runtime_source].contains(&NodeId::from("sink-1")));
+    assert!(failed_over.senders().is_empty());
+    assert!(failed_over.receivers().is_empty());
+    assert_eq!(1, failed_over.links().len());
+    assert_eq!(record.failover(), failed_over.failover());
+
+    // With a list of runtimes, the first one alive is selected, even if it is not yet involved.
+    let record = try_record(&format!(
+        "{{ runtimes: [ {runtime_sink}, {runtime_backup} ] }}"
+    ));
+    let update = record
+        .try_failover(
+            &runtime_sink,
+            &[runtime_source.clone(), runtime_backup.clone()],
+        )
+        .expect("Failed to re-place the Sink");
+    let failed_over = record.try_update(&update, &runtime_source).unwrap();
+    assert!(failed_over.mapping()[&runtime_backup].contains(&NodeId::from("sink-1")));
+    assert!(!failed_over.mapping().contains_key(&runtime_sink));
+    assert_eq!(1, failed_over.senders().len());
+    assert_eq!(1, failed_over.receivers().len());
+
+    assert!(record
+        .try_failover(&runtime_sink, &[runtime_source.clone()])
+        .is_err());
+
+    // Without a failover policy, nothing is re-placed.
+    let record = try_record("never");
+    assert!(record
+        .try_failover(&runtime_sink, &[runtime_source.clone()])
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,243 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use uhlc::Timestamp;
+use zenoh_flow_commons::{InstanceId, NodeId, Result};
+
+use crate::runners::Runner;
+
+const CHECKPOINT_EXTENSION: &str = "checkpoint";
+
+/// A `Checkpoint` holds the states of the nodes of a data flow instance, managed by a Zenoh-Flow runtime, at a given
+/// point in time.
+///
+/// The states are obtained by calling the `snapshot` method of each node once the messages in flight were processed.
+/// Only the nodes that returned a non-empty state are part of a `Checkpoint`.
+///
+/// # Storage
+///
+/// Checkpoints are stored in the directory provided to the [RuntimeBuilder](crate::RuntimeBuilder), in a
+/// sub-directory named after the instance identifier. Their name is derived from their timestamp such that they are
+/// sorted chronologically.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Checkpoint {
+    instance_id: InstanceId,
+    timestamp: Timestamp,
+    states: HashMap<NodeId, Vec<u8>>,
+}
+
+impl Checkpoint {
+    pub(crate) fn new(
+        instance_id: InstanceId,
+        timestamp: Timestamp,
+        states: HashMap<NodeId, Vec<u8>>,
+    ) -> Self {
+        Self {
+            instance_id,
+            timestamp,
+            states,
+        }
+    }
+
+    /// Returns the identifier of the data flow instance this `Checkpoint` was taken from.
+    pub fn instance_id(&self) -> &InstanceId {
+        &self.instance_id
+    }
+
+    /// Returns the [Timestamp] at which this `Checkpoint` was taken.
+    pub fn timestamp(&self) -> &Timestamp {
+        &self.timestamp
+    }
+
+    /// Returns the identifiers of the nodes whose state is saved in this `Checkpoint`.
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeId> {
+        self.states.keys()
+    }
+
+    /// Returns the saved state of the node `node_id`, if it is part of this `Checkpoint`.
+    pub fn state(&self, node_id: &NodeId) -> Option<&[u8]> {
+        self.states.get(node_id).map(|state| state.as_slice())
+    }
+
+    /// Attempts to save this `Checkpoint` in the provided `directory`, returning the path of the file.
+    ///
+    /// The file is first written under a temporary name and then renamed such that a partially written checkpoint is
+    /// never restored.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the directory of the instance could not be created or if the file could not be
+    /// written.
+    pub(crate) async fn try_save(&self, directory: &Path) -> Result<PathBuf> {
+        let instance_directory = directory.join(self.instance_id.to_string());
+        async_std::fs::create_dir_all(&instance_directory)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to create the checkpoints directory < {} >",
+                    instance_directory.display()
+                )
+            })?;
+
+        let path = instance_directory.join(format!(
+            "{:020}.{CHECKPOINT_EXTENSION}",
+            self.timestamp.get_time().as_u64()
+        ));
+        let tmp_path = path.with_extension("tmp");
+
+        let bytes = bincode::serialize(self).context("Failed to serialize the checkpoint")?;
+        async_std::fs::write(&tmp_path, bytes)
+            .await
+            .with_context(|| format!("Failed to write < {} >", tmp_path.display()))?;
+        async_std::fs::rename(&tmp_path, &path)
+            .await
+            .with_context(|| format!("Failed to rename < {} >", tmp_path.display()))?;
+
+        Ok(path)
+    }
+
+    /// Attempts to load the `Checkpoint` stored at `path`.
+    pub(crate) async fn try_load(path: &Path) -> Result<Self> {
+        let bytes = async_std::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read < {} >", path.display()))?;
+        bincode::deserialize(&bytes)
+            .with_context(|| format!("Failed to deserialize checkpoint < {} >", path.display()))
+    }
+}
+
+/// Returns the paths of the checkpoints of the instance `instance_id` stored in `directory`, sorted chronologically.
+///
+/// # Errors
+///
+/// This method will fail if the directory of the instance exists but could not be read.
+pub(crate) async fn try_list(directory: &Path, instance_id: &InstanceId) -> Result<Vec<PathBuf>> {
+    let instance_directory = directory.join(instance_id.to_string());
+    let mut entries = match async_std::fs::read_dir(&instance_directory).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::default()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read < {} >", instance_directory.display()))
+        }
+    };
+
+    let mut paths = Vec::default();
+    while let Some(entry) = entries.next().await {
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        let path: PathBuf = entry.path().into();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == CHECKPOINT_EXTENSION)
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Restores, in the provided `runners`, the states saved in the last checkpoint of the instance `instance_id`, if
+/// there is one.
+///
+/// # Errors
+///
+/// This method will fail if the last checkpoint could not be loaded or if the `restore` method of a node failed.
+pub(crate) async fn try_restore_latest(
+    directory: &Path,
+    instance_id: &InstanceId,
+    runners: &HashMap<NodeId, Runner>,
+) -> Result<()> {
+    let Some(path) = try_list(directory, instance_id).await?.pop() else {
+        return Ok(());
+    };
+
+    let checkpoint = Checkpoint::try_load(&path).await?;
+    for (node_id, state) in checkpoint.states.iter() {
+        match runners.get(node_id) {
+            Some(runner) => runner.restore(state).await?,
+            None => tracing::warn!(
+                "Checkpoint < {} > contains the state of node < {} > which is not managed by this runtime",
+                path.display(),
+                node_id
+            ),
+        }
+    }
+
+    tracing::info!("Restored checkpoint < {} >", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use uhlc::HLC;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[async_std::test]
+    async fn test_save_list_load() {
+        let directory = std::env::temp_dir().join(format!("zenoh-flow-{}", Uuid::new_v4()));
+        let instance_id: InstanceId = Uuid::new_v4().into();
+        let hlc = HLC::default();
+
+        assert!(try_list(&directory, &instance_id)
+            .await
+            .expect("Failed to list checkpoints")
+            .is_empty());
+
+        let first = Checkpoint::new(
+            instance_id.clone(),
+            hlc.new_timestamp(),
+            HashMap::from([(NodeId::from("counter"), vec![1, 2, 3])]),
+        );
+        let second = Checkpoint::new(
+            instance_id.clone(),
+            hlc.new_timestamp(),
+            HashMap::from([(NodeId::from("counter"), vec![4, 5, 6])]),
+        );
+
+        // Saved out of order on purpose: the listing must still be chronological.
+        let second_path = second.try_save(&directory).await.expect("Failed to save");
+        let first_path = first.try_save(&directory).await.expect("Failed to save");
+
+        assert_eq!(
+            vec![first_path, second_path.clone()],
+            try_list(&directory, &instance_id)
+                .await
+                .expect("Failed to list checkpoints")
+        );
+
+        let loaded = Checkpoint::try_load(&second_path)
+            .await
+            .expect("Failed to load");
+        assert_eq!(second, loaded);
+        assert_eq!(Some([4, 5, 6].as_slice()), loaded.state(&"counter".into()));
+
+        std::fs::remove_dir_all(directory).expect("Failed to clean up");
+    }
+}
@@ -12,14 +12,46 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{collections::HashMap, fmt::Display, ops::Deref};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    ops::Deref,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::{anyhow, bail};
+use async_std::task::JoinHandle;
 use serde::{Deserialize, Serialize};
 use uhlc::{Timestamp, HLC};
-use zenoh_flow_commons::{NodeId, Result, RuntimeId};
+use zenoh_flow_commons::{Configuration, IMergeOverwrite, NodeId, Result, RuntimeId};
+use zenoh_flow_descriptors::{InputDescriptor, LinkDescriptor, OutputDescriptor};
+use zenoh_flow_nodes::{
+    prelude::{Message, NodeStatistics, OutputRaw},
+    LinkSender, Mirrors,
+};
 use zenoh_flow_records::DataFlowRecord;
 
-use crate::runners::Runner;
+use crate::{
+    checkpoint::Checkpoint,
+    metrics::InstanceMetrics,
+    recording::{Recorder, Recording, RecordingSummary, ReplayMode},
+    runners::{Halt, Runner},
+};
+
+/// The interval at which the nodes of a data flow that is being gracefully stopped, or checkpointed, are checked for
+/// being idle.
+const HALT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The channels of the links between the nodes managed by a runtime, indexed by the ports they connect.
+///
+/// They are kept for as long as the data flow instance is loaded such that, when its graph is updated, the nodes that
+/// are re-created reuse the channels of the links that did not change.
+pub(crate) type LinkChannels = HashMap<
+    (OutputDescriptor, InputDescriptor),
+    (LinkDescriptor, LinkSender, flume::Receiver<Message>),
+>;
 
 /// A `DataFlowInstance` keeps track of the parts of a data flow managed by the Zenoh-Flow runtime.
 ///
@@ -31,6 +63,12 @@ pub struct DataFlowInstance {
     pub(crate) state: InstanceState,
     pub(crate) record: DataFlowRecord,
     pub(crate) runners: HashMap<NodeId, Runner>,
+    pub(crate) injectors: HashMap<InputDescriptor, OutputRaw>,
+    pub(crate) links: LinkChannels,
+    pub(crate) hlc: Arc<HLC>,
+    pub(crate) recorder: Option<Recorder>,
+    pub(crate) replay: Option<JoinHandle<()>>,
+    pub(crate) lost_runtimes: HashMap<RuntimeId, Timestamp>,
 }
 
 /// The different states of a [DataFlowInstance].
@@ -59,6 +97,23 @@ pub enum InstanceState {
     ///
     /// [runtime]: crate::Runtime
     Running(Timestamp),
+    /// A [runtime] listing a [DataFlowInstance] in the `Degraded` state has (re)started all the nodes it manages but
+    /// at least one of them was stopped after exhausting its [restart policy]. The reason of the failure(s) is
+    /// recorded, alongside the time of the last one.
+    ///
+    /// A `Degraded` data flow can be restarted (which restarts the stopped nodes), aborted or deleted.
+    ///
+    /// [runtime]: crate::Runtime
+    /// [restart policy]: zenoh_flow_commons::RestartPolicy
+    Degraded((Timestamp, String)),
+    /// A [runtime] listing a [DataFlowInstance] in the `Disconnected` state has (re)started all the nodes it manages
+    /// but lost the connection with at least one of the other runtimes involved, which are listed alongside the time
+    /// the last one was lost: the nodes they manage no longer exchange messages with the nodes of this runtime.
+    ///
+    /// A `Disconnected` data flow can be aborted or deleted. It is `Running` again once the lost runtimes are back.
+    ///
+    /// [runtime]: crate::Runtime
+    Disconnected((Timestamp, Vec<RuntimeId>)),
     /// A [runtime] listing a [DataFlowInstance] in the `Aborted` state has abruptly stopped all the nodes it manages.
     ///
     /// An `Aborted` data flow can be restarted or deleted.
@@ -80,6 +135,19 @@ impl Display for InstanceState {
             InstanceState::Creating(ts) => write!(f, "Creation started on {}", ts.get_time()),
             InstanceState::Loaded(ts) => write!(f, "Loaded on {}", ts.get_time()),
             InstanceState::Running(ts) => write!(f, "Running since {}", ts.get_time()),
+            InstanceState::Degraded((ts, reason)) => {
+                write!(f, "Degraded on {} with:\n{}", ts.get_time(), reason)
+            }
+            InstanceState::Disconnected((ts, runtimes)) => write!(
+                f,
+                "Disconnected on {} from:\n{}",
+                ts.get_time(),
+                runtimes
+                    .iter()
+                    .map(|runtime_id| format!("- < {runtime_id} >"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
             InstanceState::Aborted(ts) => write!(f, "Aborted on {}", ts.get_time()),
             InstanceState::Failed((ts, reason)) => {
                 write!(f, "Failed on {} with:\n{}", ts.get_time(), reason)
@@ -94,7 +162,8 @@ impl Display for InstanceState {
 /// - from which runtime this information comes from (through its [identifier](RuntimeId)),
 /// - the [state](InstanceState) of the data flow instance,
 /// - the list of nodes (through their [identifier](NodeId)) the runtime manages --- and thus for which the state
-///   applies.
+///   applies,
+/// - the [statistics](NodeStatistics) of the ports of these nodes, notably the number of messages lost or duplicated.
 ///
 /// This information is what is displayed by the `zfctl` tool when requesting the status of a data flow instance.
 #[derive(Deserialize, Serialize, Debug)]
@@ -105,6 +174,9 @@ pub struct InstanceStatus {
     pub state: InstanceState,
     /// The nodes managed by this runtime, for which the state applies.
     pub nodes: Vec<NodeId>,
+    /// The statistics of the ports of the nodes managed by this runtime.
+    #[serde(default)]
+    pub statistics: HashMap<NodeId, NodeStatistics>,
 }
 
 impl Deref for DataFlowInstance {
@@ -117,11 +189,17 @@ impl Deref for DataFlowInstance {
 
 impl DataFlowInstance {
     /// Creates a new `DataFlowInstance`, setting its state to [Creating](InstanceState::Creating).
-    pub(crate) fn new(record: DataFlowRecord, hlc: &HLC) -> Self {
+    pub(crate) fn new(record: DataFlowRecord, hlc: Arc<HLC>) -> Self {
         Self {
             state: InstanceState::Creating(hlc.new_timestamp()),
             record,
             runners: HashMap::default(),
+            injectors: HashMap::default(),
+            links: LinkChannels::default(),
+            hlc,
+            recorder: None,
+            replay: None,
+            lost_runtimes: HashMap::default(),
         }
     }
 
@@ -137,7 +215,7 @@ impl DataFlowInstance {
     /// [on_resume]: zenoh_flow_nodes::prelude::Node::on_resume()
     pub async fn start(&mut self, hlc: &HLC) -> Result<()> {
         for (node_id, runner) in self.runners.iter_mut() {
-            runner.start().await?;
+            runner.start(self.hlc.clone()).await?;
             tracing::trace!("Started node < {} >", node_id);
         }
 
@@ -149,6 +227,10 @@ impl DataFlowInstance {
     ///
     /// The [hlc](HLC) is required to keep track of when this call was made.
     pub async fn abort(&mut self, hlc: &HLC) {
+        if let Some(replay) = self.replay.take() {
+            replay.cancel().await;
+        }
+
         for (node_id, runner) in self.runners.iter_mut() {
             runner.abort().await;
             tracing::trace!("Aborted node < {} >", node_id);
@@ -157,9 +239,414 @@ impl DataFlowInstance {
         self.state = InstanceState::Aborted(hlc.new_timestamp());
     }
 
+    /// Halts, until the `deadline`, the runners of the nodes of this `DataFlowInstance`, following the data flow.
+    ///
+    /// A node is halted once it is [idle](Runner::try_halt()), all the nodes sending it messages (and managed by this
+    /// runtime) were halted and it processed the messages pending in its Inputs. The nodes through which messages enter
+    /// this part of the data flow, i.e. the Sources and the receivers of messages from other runtimes, are thus halted
+    /// first and no message is in flight once all nodes are halted.
+    ///
+    /// Returns how each node that was halted before the `deadline` was halted. The nodes that are not running are not
+    /// halted.
+    async fn halt(&mut self, deadline: Instant) -> HashMap<NodeId, Halt> {
+        let mut upstream: HashMap<NodeId, HashSet<NodeId>> = HashMap::default();
+        for link in self.record.links() {
+            if self.runners.contains_key(&link.from.node) {
+                upstream
+                    .entry(link.to.node.clone())
+                    .or_default()
+                    .insert(link.from.node.clone());
+            }
+        }
+
+        let running = self
+            .runners
+            .iter()
+            .filter(|(_, runner)| runner.is_running())
+            .map(|(node_id, _)| node_id.clone())
+            .collect::<HashSet<_>>();
+
+        let mut halted = HashMap::with_capacity(running.len());
+        loop {
+            for (node_id, runner) in self.runners.iter_mut() {
+                if !running.contains(node_id)
+                    || halted.contains_key(node_id)
+                    || runner.statistics().pending() > 0
+                {
+                    continue;
+                }
+
+                let upstream_halted = upstream.get(node_id).map_or(true, |nodes| {
+                    nodes
+                        .iter()
+                        .all(|node| !running.contains(node) || halted.contains_key(node))
+                });
+                if !upstream_halted {
+                    continue;
+                }
+
+                match runner.try_halt().await {
+                    Halt::Busy => (),
+                    halt => {
+                        halted.insert(node_id.clone(), halt);
+                    }
+                }
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if halted.len() == running.len() || remaining.is_zero() {
+                return halted;
+            }
+
+            async_std::task::sleep(HALT_POLL_INTERVAL.min(remaining)).await;
+        }
+    }
+
+    /// Gracefully stops the `DataFlowInstance`, waiting at most `timeout`.
+    ///
+    /// The nodes are stopped following the data flow, starting with the Sources: a node is stopped once the nodes
+    /// sending it messages were, it processed all the messages pending in its Inputs and it is idle, i.e. waiting for a
+    /// message or between two `iteration`s. Its `on_stop` hook is then called.
+    ///
+    /// After the `timeout`, the nodes that are idle and have no pending message are still stopped gracefully. The other
+    /// nodes are aborted: their `on_abort` hook is called.
+    ///
+    /// Note that the messages are only drained *locally*: the nodes running on other runtimes are stopped by their
+    /// own runtime.
+    ///
+    /// The [hlc](HLC) is required to keep track of when this call was made.
+    pub async fn stop(&mut self, hlc: &HLC, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+
+        if let Some(replay) = self.replay.take() {
+            replay.cancel().await;
+        }
+
+        let mut halted = self.halt(deadline).await;
+
+        let mut aborted = 0;
+        for (node_id, runner) in self.runners.iter_mut() {
+            let halt = match halted.remove(node_id) {
+                Some(halt) => halt,
+                None if runner.statistics().pending() == 0 => runner.try_halt().await,
+                None => Halt::Busy,
+            };
+
+            match halt {
+                Halt::Busy => {
+                    runner.abort().await;
+                    aborted += 1;
+                    tracing::trace!("Aborted node < {} >", node_id);
+                }
+                halt => {
+                    runner.stop_halted(halt).await;
+                    tracing::trace!("Stopped node < {} >", node_id);
+                }
+            }
+        }
+
+        if aborted > 0 {
+            tracing::warn!(
+                "{} node(s) were still processing messages after {}ms and were aborted",
+                aborted,
+                timeout.as_millis()
+            );
+        }
+
+        self.state = InstanceState::Aborted(hlc.new_timestamp());
+    }
+
+    /// Takes a consistent [Checkpoint] of the nodes of this `DataFlowInstance`, waiting at most `timeout`.
+    ///
+    /// The nodes are paused following the data flow, as they would be [stopped](DataFlowInstance::stop()), starting
+    /// with the Sources and the receivers of messages from other runtimes. A snapshot of each node is taken once all of
+    /// them are paused, i.e. once no message is in flight. The nodes are then resumed.
+    ///
+    /// Note that the checkpoint is only consistent *locally*: the messages in transit between runtimes are not part of
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if:
+    /// - a node was still processing messages after the `timeout`,
+    /// - a node consumed a message while it was being paused,
+    /// - the `snapshot` method of a node failed.
+    pub(crate) async fn checkpoint(&mut self, hlc: &HLC, timeout: Duration) -> Result<Checkpoint> {
+        let deadline = Instant::now() + timeout;
+
+        // 1. Pause the nodes.
+        let halted = self.halt(deadline).await;
+        let running = self
+            .runners
+            .values()
+            .filter(|runner| runner.is_running())
+            .count();
+
+        // 2. Take the snapshots.
+        let checkpoint = if running > 0 {
+            Err(anyhow!(
+                "Messages were still in flight after {}ms",
+                timeout.as_millis()
+            ))
+        } else if let Some((node_id, _)) =
+            halted.iter().find(|(_, halt)| **halt == Halt::Interrupted)
+        {
+            Err(anyhow!(
+                "Node < {} > consumed a message while it was being paused",
+                node_id
+            ))
+        } else {
+            self.snapshot(hlc).await
+        };
+
+        // 3. Resume the nodes.
+        for node_id in halted.keys() {
+            if let Some(runner) = self.runners.get_mut(node_id) {
+                runner.respawn(self.hlc.clone());
+            }
+        }
+
+        checkpoint
+    }
+
+    // Returns a Checkpoint holding the states of the nodes.
+    async fn snapshot(&self, hlc: &HLC) -> Result<Checkpoint> {
+        let timestamp = hlc.new_timestamp();
+        let mut states = HashMap::default();
+        for (node_id, runner) in self.runners.iter() {
+            let state = runner.snapshot().await?;
+            if !state.is_empty() {
+                states.insert(node_id.clone(), state);
+            }
+        }
+
+        Ok(Checkpoint::new(
+            self.record.instance_id().clone(),
+            timestamp,
+            states,
+        ))
+    }
+
+    /// Merges the `patch` into the [Configuration] of the node `node_id`, the keys of the patch overwriting the existing
+    /// ones, and returns the updated configuration.
+    ///
+    /// If the node is managed by this runtime, its `on_configure` hook is called first: if it refuses the new
+    /// configuration, the record is left untouched. Otherwise only the record is updated, keeping it consistent with
+    /// the ones held by the other runtimes.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if:
+    /// - the patch is not a set of `(key, value)` pairs,
+    /// - the data flow has no Source, Operator or Sink `node_id`,
+    /// - the node refused the new configuration.
+    pub(crate) async fn reconfigure(
+        &mut self,
+        node_id: &NodeId,
+        patch: Configuration,
+    ) -> Result<Configuration> {
+        if !patch.is_object() && !patch.is_null() {
+            bail!(
+                "A configuration must be a set of (key, value) pairs, found: {}",
+                *patch
+            );
+        }
+
+        let Some(current) = self.record.configuration(node_id) else {
+            bail!(
+                "Found no Source, Operator or Sink < {} > in data flow < {} >",
+                node_id,
+                self.instance_id()
+            );
+        };
+        let configuration = patch.merge_overwrite(current.clone());
+
+        if let Some(runner) = self.runners.get(node_id) {
+            runner.configure(configuration.clone()).await?;
+        }
+
+        if let Some(current) = self.record.configuration_mut(node_id) {
+            *current = configuration.clone();
+        }
+
+        Ok(configuration)
+    }
+
+    /// Returns the handle through which messages are injected into the `input`, or [None] if its node is not managed by
+    /// this runtime.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the instance is not running or if the `input` was not declared injectable.
+    pub(crate) fn injector(&self, input: &InputDescriptor) -> Result<Option<OutputRaw>> {
+        if !self.runners.contains_key(&input.node) {
+            return Ok(None);
+        }
+
+        if !matches!(
+            self.state,
+            InstanceState::Running(_) | InstanceState::Degraded(_)
+        ) {
+            bail!(
+                "Messages can only be injected into a running data flow instance, < {} > is: {}",
+                self.instance_id(),
+                self.state
+            );
+        }
+
+        match self.injectors.get(input) {
+            Some(injector) => Ok(Some(injector.clone())),
+            None => bail!(
+                "The input < {input} > is not injectable: it must be declared with `injectable: true` in the \
+                 descriptor of its node"
+            ),
+        }
+    }
+
+    /// Returns the [Mirrors] of the `output`, if its node is managed by this runtime.
+    pub(crate) fn mirrors(&self, output: &OutputDescriptor) -> Option<Arc<Mirrors>> {
+        self.runners
+            .get(&output.node)
+            .and_then(|runner| runner.outputs().mirrors.get(&output.output))
+            .cloned()
+    }
+
+    /// Starts recording, in the file at `path`, the messages sent on the `outputs` of the nodes managed by this
+    /// runtime, returning the number of Outputs recorded.
+    ///
+    /// The Outputs of nodes managed by other runtimes are ignored. If none of the `outputs` is managed by this runtime,
+    /// nothing is recorded and no file is created.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if a recording is already in progress or if the file could not be created.
+    pub(crate) async fn start_recording(
+        &mut self,
+        path: PathBuf,
+        outputs: &[OutputDescriptor],
+    ) -> Result<usize> {
+        if self.recorder.is_some() {
+            bail!("A recording is already in progress");
+        }
+
+        let mirrors = outputs
+            .iter()
+            .filter_map(|output| {
+                self.mirrors(output)
+                    .map(|mirrors| (output.clone(), mirrors))
+            })
+            .collect::<Vec<_>>();
+
+        if mirrors.is_empty() {
+            return Ok(0);
+        }
+
+        let count = mirrors.len();
+        self.recorder =
+            Some(Recorder::try_spawn(path, self.record.instance_id().clone(), mirrors).await?);
+
+        Ok(count)
+    }
+
+    /// Stops the recording in progress, if there is one, and returns its [summary](RecordingSummary).
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the messages recorded could not be written.
+    pub(crate) async fn stop_recording(
+        &mut self,
+        runtime_id: &RuntimeId,
+    ) -> Result<Option<RecordingSummary>> {
+        match self.recorder.take() {
+            Some(recorder) => Ok(Some(recorder.stop(runtime_id.clone()).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Starts the `DataFlowInstance`, replacing its Sources by the [Recording]: the nodes that are not Sources are
+    /// started while the messages recorded on the Outputs of the Sources are sent in their place, following the
+    /// [ReplayMode].
+    ///
+    /// Returns the number of messages that will be replayed.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the instance is running or if the [on_resume] method of a node failed.
+    ///
+    /// [on_resume]: zenoh_flow_nodes::prelude::Node::on_resume()
+    pub(crate) async fn replay(
+        &mut self,
+        hlc: &HLC,
+        recording: Recording,
+        mode: ReplayMode,
+    ) -> Result<u64> {
+        if matches!(
+            self.state,
+            InstanceState::Creating(_) | InstanceState::Running(_) | InstanceState::Failed(_)
+        ) {
+            bail!("Only a loaded or aborted data flow instance can replay a recording");
+        }
+
+        let mut outputs = HashMap::default();
+        for (node_id, runner) in self.runners.iter_mut() {
+            if self.record.sources().contains_key(node_id) {
+                outputs.extend(runner.outputs().handles.iter().map(|(port_id, handle)| {
+                    (
+                        OutputDescriptor {
+                            node: node_id.clone(),
+                            output: port_id.clone(),
+                        },
+                        handle.clone(),
+                    )
+                }));
+                continue;
+            }
+
+            runner.start(self.hlc.clone()).await?;
+            tracing::trace!("Started node < {} >", node_id);
+        }
+
+        let (count, handle) = recording.replay(outputs, mode);
+        self.replay = Some(handle);
+        self.state = InstanceState::Running(hlc.new_timestamp());
+
+        Ok(count)
+    }
+
     /// Returns the [state](InstanceState) of this `DataFlowInstance`.
-    pub fn state(&self) -> &InstanceState {
-        &self.state
+    ///
+    /// A `Running` instance is reported as [Disconnected](InstanceState::Disconnected) if at least one of the other
+    /// runtimes involved was lost and, otherwise, as [Degraded](InstanceState::Degraded) if at least one of its nodes
+    /// was stopped after exhausting its restart policy.
+    pub fn state(&self) -> InstanceState {
+        if !matches!(self.state, InstanceState::Running(_)) {
+            return self.state.clone();
+        }
+
+        if let Some(timestamp) = self.lost_runtimes.values().max() {
+            let mut runtimes = self.lost_runtimes.keys().cloned().collect::<Vec<_>>();
+            runtimes.sort_by_key(|runtime_id| runtime_id.to_string());
+            return InstanceState::Disconnected((*timestamp, runtimes));
+        }
+
+        let mut failures = self
+            .runners
+            .iter()
+            .filter_map(|(node_id, runner)| runner.failure().map(|failure| (node_id, failure)))
+            .collect::<Vec<_>>();
+        failures.sort_by_key(|(_, (timestamp, _))| *timestamp);
+
+        match failures.last() {
+            Some((_, (timestamp, _))) => InstanceState::Degraded((
+                *timestamp,
+                failures
+                    .iter()
+                    .map(|(node_id, (_, reason))| format!("- < {node_id} >: {reason}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+            None => self.state.clone(),
+        }
     }
 
     /// Returns the [status](InstanceStatus) of this `DataFlowInstance`.
@@ -167,18 +654,405 @@ impl DataFlowInstance {
     /// This structure was intended as a way to retrieve and display information about the instance. This is what the
     /// `zfctl` tool leverages for its `instance status` command.
     pub fn status(&self, runtime_id: &RuntimeId) -> InstanceStatus {
+        let runners = self
+            .runners
+            .iter()
+            .filter(|(node_id, _)| {
+                !(self.senders().contains_key(*node_id) || self.receivers().contains_key(*node_id))
+            })
+            .collect::<Vec<_>>();
+
         InstanceStatus {
             runtime_id: runtime_id.clone(),
-            state: self.state.clone(),
+            state: self.state(),
+            nodes: runners
+                .iter()
+                .map(|(node_id, _)| (*node_id).clone())
+                .collect(),
+            statistics: runners
+                .iter()
+                .map(|(node_id, runner)| ((*node_id).clone(), runner.statistics().snapshot()))
+                .collect(),
+        }
+    }
+
+    /// Returns the [metrics](InstanceMetrics) of the nodes of this `DataFlowInstance`, connectors included.
+    ///
+    /// This is what the `zfctl` tool leverages for its `instance metrics` command.
+    pub fn metrics(&self, runtime_id: &RuntimeId) -> InstanceMetrics {
+        InstanceMetrics {
+            runtime_id: runtime_id.clone(),
             nodes: self
                 .runners
-                .keys()
-                .filter(|&node_id| {
-                    !(self.senders().contains_key(node_id)
-                        || self.receivers().contains_key(node_id))
-                })
-                .cloned()
+                .iter()
+                .map(|(node_id, runner)| (node_id.clone(), runner.metrics()))
                 .collect(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    };
+
+    use async_trait::async_trait;
+    use zenoh_flow_commons::Vars;
+    use zenoh_flow_descriptors::{DataFlowDescriptor, FlattenedDataFlowDescriptor};
+    use zenoh_flow_nodes::prelude::{InputRaw, Inputs, Node, Outputs, Statistics};
+
+    use super::*;
+
+    const FLOW: &str = r#"
+name: test-halt
+
+sources:
+  - id: source
+    library: file:///home/zenoh-flow/libsource.so
+    outputs:
+      - out
+
+operators:
+  - id: operator
+    library: file:///home/zenoh-flow/liboperator.so
+    inputs:
+      - in
+    outputs:
+      - out
+
+sinks:
+  - id: sink
+    library: file:///home/zenoh-flow/libsink.so
+    inputs:
+      - in
+
+links:
+  - from:
+      node: source
+      output: out
+    to:
+      node: operator
+      input: in
+  - from:
+      node: operator
+      output: out
+    to:
+      node: sink
+      input: in
+"#;
+
+    type Hooks = Arc<Mutex<Vec<String>>>;
+
+    // Sends, on its Output, the payloads it is fed from outside of the data flow.
+    struct TestSource {
+        feed: flume::Receiver<Vec<u8>>,
+        output: OutputRaw,
+        statistics: Statistics,
+        hooks: Hooks,
+    }
+
+    #[async_trait]
+    impl Node for TestSource {
+        async fn iteration(&self) -> Result<()> {
+            let payload = self.statistics.wait_for(self.feed.recv_async()).await?;
+            self.output.send(payload, None).await
+        }
+
+        async fn on_abort(&self) {
+            self.hooks.lock().unwrap().push("source: on_abort".into());
+        }
+
+        async fn on_stop(&self) {
+            self.hooks.lock().unwrap().push("source: on_stop".into());
+        }
+    }
+
+    // Forwards the messages it receives, taking some time to process each of them, and blocks on the `gate` while it
+    // is closed.
+    struct TestOperator {
+        input: InputRaw,
+        output: OutputRaw,
+        gate: flume::Receiver<()>,
+        closed: Arc<AtomicBool>,
+        processing: Arc<AtomicBool>,
+        forwarded: AtomicU64,
+        hooks: Hooks,
+    }
+
+    #[async_trait]
+    impl Node for TestOperator {
+        async fn iteration(&self) -> Result<()> {
+            let message = self.input.recv().await?;
+            self.processing.store(true, Ordering::SeqCst);
+            async_std::task::sleep(Duration::from_millis(20)).await;
+            if self.closed.load(Ordering::SeqCst) {
+                self.gate.recv_async().await?;
+            }
+            self.output.forward(message).await?;
+            self.forwarded.fetch_add(1, Ordering::SeqCst);
+            self.processing.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn on_abort(&self) {
+            self.hooks.lock().unwrap().push("operator: on_abort".into());
+        }
+
+        async fn on_stop(&self) {
+            self.hooks.lock().unwrap().push("operator: on_stop".into());
+        }
+
+        async fn snapshot(&self) -> Result<Vec<u8>> {
+            Ok(self.forwarded.load(Ordering::SeqCst).to_le_bytes().to_vec())
+        }
+    }
+
+    // Keeps the payloads it receives.
+    struct TestSink {
+        input: InputRaw,
+        received: Arc<Mutex<Vec<Vec<u8>>>>,
+        hooks: Hooks,
+    }
+
+    #[async_trait]
+    impl Node for TestSink {
+        async fn iteration(&self) -> Result<()> {
+            if let Message::Data(data) = self.input.recv().await? {
+                let payload = data.payload().try_as_bytes()?.to_vec();
+                self.received.lock().unwrap().push(payload);
+            }
+            Ok(())
+        }
+
+        async fn on_abort(&self) {
+            self.hooks.lock().unwrap().push("sink: on_abort".into());
+        }
+
+        async fn on_stop(&self) {
+            self.hooks.lock().unwrap().push("sink: on_stop".into());
+        }
+
+        async fn snapshot(&self) -> Result<Vec<u8>> {
+            Ok((self.received.lock().unwrap().len() as u64)
+                .to_le_bytes()
+                .to_vec())
+        }
+    }
+
+    struct TestFlow {
+        hlc: Arc<HLC>,
+        instance: DataFlowInstance,
+        feed: flume::Sender<Vec<u8>>,
+        gate: flume::Sender<()>,
+        closed: Arc<AtomicBool>,
+        processing: Arc<AtomicBool>,
+        received: Arc<Mutex<Vec<Vec<u8>>>>,
+        hooks: Hooks,
+    }
+
+    impl TestFlow {
+        async fn start() -> Self {
+            let flattened = FlattenedDataFlowDescriptor::try_flatten(
+                serde_yaml::from_str::<DataFlowDescriptor>(FLOW).unwrap(),
+                Vars::default(),
+            )
+            .unwrap();
+            let record = DataFlowRecord::try_new(&flattened, &RuntimeId::rand()).unwrap();
+
+            let hlc = Arc::new(HLC::default());
+            let hooks = Hooks::default();
+            let (feed, feed_rx) = flume::unbounded();
+            let (gate, gate_rx) = flume::unbounded();
+            let closed = Arc::new(AtomicBool::new(false));
+            let processing = Arc::new(AtomicBool::new(false));
+            let received = Arc::new(Mutex::new(Vec::default()));
+
+            let (source_tx, operator_rx) = flume::unbounded::<Message>();
+            let (operator_tx, sink_rx) = flume::unbounded::<Message>();
+
+            let mut outputs = Outputs::new(hlc.clone());
+            outputs.insert("out".into(), source_tx);
+            let statistics = Statistics::new(&Inputs::default(), &outputs);
+            let source = TestSource {
+                feed: feed_rx,
+                output: outputs.take("out").unwrap().raw(),
+                statistics: statistics.clone(),
+                hooks: hooks.clone(),
+            };
+            let source_runner = Runner::new("source".into(), Arc::new(source), None, statistics);
+
+            let mut inputs = Inputs::default();
+            inputs.insert("in".into(), operator_rx);
+            let mut outputs = Outputs::new(hlc.clone());
+            outputs.insert("out".into(), operator_tx);
+            let statistics = Statistics::new(&inputs, &outputs);
+            let operator = TestOperator {
+                input: inputs.take("in").unwrap().raw(),
+                output: outputs.take("out").unwrap().raw(),
+                gate: gate_rx,
+                closed: closed.clone(),
+                processing: processing.clone(),
+                forwarded: AtomicU64::default(),
+                hooks: hooks.clone(),
+            };
+            let operator_runner =
+                Runner::new("operator".into(), Arc::new(operator), None, statistics);
+
+            let mut inputs = Inputs::default();
+            inputs.insert("in".into(), sink_rx);
+            let statistics = Statistics::new(&inputs, &Outputs::new(hlc.clone()));
+            let sink = TestSink {
+                input: inputs.take("in").unwrap().raw(),
+                received: received.clone(),
+                hooks: hooks.clone(),
+            };
+            let sink_runner = Runner::new("sink".into(), Arc::new(sink), None, statistics);
+
+            let mut instance = DataFlowInstance::new(record, hlc.clone());
+            instance.runners.insert("source".into(), source_runner);
+            instance.runners.insert("operator".into(), operator_runner);
+            instance.runners.insert("sink".into(), sink_runner);
+            instance.start(&hlc).await.unwrap();
+
+            Self {
+                hlc,
+                instance,
+                feed,
+                gate,
+                closed,
+                processing,
+                received,
+                hooks,
+            }
+        }
+
+        // Feeds the Source and waits until it consumed all the payloads.
+        async fn feed(&self, payloads: &[&[u8]]) {
+            for payload in payloads {
+                self.feed.send(payload.to_vec()).unwrap();
+            }
+            while !self.feed.is_empty() {
+                async_std::task::sleep(Duration::from_millis(1)).await;
+            }
+        }
+
+        fn received(&self) -> usize {
+            self.received.lock().unwrap().len()
+        }
+
+        fn hooks(&self) -> Vec<String> {
+            let mut hooks = self.hooks.lock().unwrap().clone();
+            hooks.sort();
+            hooks
+        }
+    }
+
+    #[async_std::test]
+    async fn test_stop_drained() {
+        let mut flow = TestFlow::start().await;
+        flow.feed(&[b"one", b"two", b"three"]).await;
+
+        let timeout = Duration::from_secs(5);
+        let instant = Instant::now();
+        flow.instance.stop(&flow.hlc.clone(), timeout).await;
+
+        assert!(instant.elapsed() < timeout);
+        assert_eq!(
+            vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()],
+            *flow.received.lock().unwrap()
+        );
+        assert_eq!(
+            vec!["operator: on_stop", "sink: on_stop", "source: on_stop"],
+            flow.hooks()
+        );
+        assert!(flow.instance.runners.values().all(|r| !r.is_running()));
+    }
+
+    #[async_std::test]
+    async fn test_stop_deadline() {
+        let mut flow = TestFlow::start().await;
+        flow.closed.store(true, Ordering::SeqCst);
+        flow.feed(&[b"one"]).await;
+        while !flow.processing.load(Ordering::SeqCst) {
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        let timeout = Duration::from_millis(200);
+        let instant = Instant::now();
+        flow.instance.stop(&flow.hlc.clone(), timeout).await;
+
+        let elapsed = instant.elapsed();
+        assert!(elapsed >= timeout);
+        assert!(elapsed < Duration::from_secs(2));
+
+        // The Operator was aborted: unblocking it does not deliver the message.
+        flow.gate.send(()).unwrap();
+        async_std::task::sleep(Duration::from_millis(50)).await;
+        assert_eq!(0, flow.received());
+        // The Source and the Sink, idle, are still stopped gracefully.
+        assert_eq!(
+            vec!["operator: on_abort", "sink: on_stop", "source: on_stop"],
+            flow.hooks()
+        );
+        assert!(flow.instance.runners.values().all(|r| !r.is_running()));
+    }
+
+    #[async_std::test]
+    async fn test_checkpoint() {
+        let mut flow = TestFlow::start().await;
+        flow.feed(&[b"one", b"two"]).await;
+
+        let checkpoint = flow
+            .instance
+            .checkpoint(&flow.hlc.clone(), Duration::from_secs(5))
+            .await
+            .expect("Failed to take a checkpoint");
+
+        // No message was in flight: the Operator forwarded everything the Sink received.
+        let two = 2u64.to_le_bytes();
+        assert_eq!(Some(two.as_slice()), checkpoint.state(&"operator".into()));
+        assert_eq!(Some(two.as_slice()), checkpoint.state(&"sink".into()));
+        assert!(flow.hooks().is_empty());
+
+        // The nodes were resumed.
+        flow.feed(&[b"three"]).await;
+        while flow.received() < 3 {
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        flow.instance
+            .stop(&flow.hlc.clone(), Duration::from_secs(5))
+            .await;
+    }
+
+    #[async_std::test]
+    async fn test_checkpoint_timeout() {
+        let mut flow = TestFlow::start().await;
+        flow.closed.store(true, Ordering::SeqCst);
+        flow.feed(&[b"one"]).await;
+        while !flow.processing.load(Ordering::SeqCst) {
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        assert!(flow
+            .instance
+            .checkpoint(&flow.hlc.clone(), Duration::from_millis(100))
+            .await
+            .is_err());
+
+        // The nodes that were paused were resumed: once the Operator is unblocked, the message reaches the Sink.
+        flow.closed.store(false, Ordering::SeqCst);
+        flow.gate.send(()).unwrap();
+        flow.feed(&[b"two"]).await;
+        while flow.received() < 2 {
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(flow.hooks().is_empty());
+
+        flow.instance
+            .stop(&flow.hlc.clone(), Duration::from_secs(5))
+            .await;
+    }
+}
@@ -21,16 +21,41 @@
 //!
 //! Users interested in fetching the state of a data flow instance should look into the [DataFlowInstance],
 //! [InstanceState] and [InstanceStatus] structures. These structures are leveraged by the `zfctl` command line tool.
+//!
+//! Users interested in monitoring the throughput, latency and backlog of the nodes of a data flow instance should look
+//! into the [InstanceMetrics] and [NodeMetrics] structures.
+//!
+//! Users interested in saving and restoring the state of the nodes of a data flow instance should look into the
+//! [Checkpoint] structure.
+//!
+//! Users interested in recording the messages exchanged on the links of a data flow instance, and replaying them,
+//! should look into the [Recording] structure.
+//!
+//! Users interested in tracing the messages exchanged between the nodes should look into the
+//! [RuntimeBuilder::trace_messages] method and, if the feature `otlp` is enabled, the
+//! `RuntimeBuilder::otlp_endpoint` method that exports the traces to an OpenTelemetry collector.
+
+mod checkpoint;
+pub use self::checkpoint::Checkpoint;
 
 mod instance;
 pub use self::instance::{DataFlowInstance, InstanceState, InstanceStatus};
 
+mod metrics;
+pub use self::metrics::{InstanceMetrics, LatencyHistogram, NodeMetrics, LATENCY_BUCKETS_US};
+
 mod loader;
 pub use self::loader::{Extension, Extensions};
 
+#[cfg(feature = "otlp")]
+mod otlp;
+
 #[cfg(feature = "shared-memory")]
 mod shared_memory;
 
+mod recording;
+pub use self::recording::{Recording, RecordingSummary, ReplayMode};
+
 mod runners;
 
 mod runtime;
@@ -0,0 +1,236 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use zenoh_flow_commons::{NodeId, RuntimeId};
+use zenoh_flow_nodes::prelude::NodeStatistics;
+
+/// The upper bounds, in microseconds, of the buckets of a [LatencyHistogram].
+///
+/// An additional bucket, without upper bound, holds the iterations that took longer than the last one.
+pub const LATENCY_BUCKETS_US: [u64; 12] = [
+    10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000, 100_000, 1_000_000,
+];
+
+/// The distribution of the durations of the `iteration` of a node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    /// The upper bounds, in microseconds, of the buckets.
+    pub bounds_us: Vec<u64>,
+    /// The number of iterations in each bucket (*not* cumulative). There is one more bucket than there are bounds: it
+    /// holds the iterations that took longer than the last bound.
+    pub counts: Vec<u64>,
+    /// The sum, in microseconds, of the durations of all the iterations.
+    pub sum_us: u64,
+}
+
+impl LatencyHistogram {
+    /// Returns the number of iterations accounted for in this histogram.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the mean duration of an iteration or [None] if no iteration was accounted for.
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(Duration::from_micros(self.sum_us / count)),
+        }
+    }
+
+    /// Returns an upper bound of the `quantile` (between 0 and 1) of the durations of the iterations, i.e. the upper
+    /// bound of the bucket in which it falls.
+    ///
+    /// [None] is returned if no iteration was accounted for or if the quantile falls beyond the last bound.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((count as f64) * quantile.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut cumulated = 0;
+        for (bucket, bucket_count) in self.counts.iter().enumerate() {
+            cumulated += bucket_count;
+            if cumulated >= rank {
+                return self
+                    .bounds_us
+                    .get(bucket)
+                    .copied()
+                    .map(Duration::from_micros);
+            }
+        }
+
+        None
+    }
+}
+
+/// The metrics of a node, collected by the Zenoh-Flow runtime that runs it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeMetrics {
+    /// The number of times the `iteration` of the node was called.
+    pub iterations: u64,
+    /// The average number of iterations per second since the node was first started.
+    pub iterations_per_second: f64,
+    /// The number of iterations that returned an error.
+    pub errors: u64,
+    /// The number of iterations that panicked.
+    pub panics: u64,
+    /// The distribution of the durations of the iterations.
+    pub latency: LatencyHistogram,
+    /// The statistics of the ports of the node: messages and bytes exchanged, messages lost and backlog.
+    pub ports: NodeStatistics,
+}
+
+/// The `InstanceMetrics` gather the [metrics](NodeMetrics) of the nodes of a data flow instance managed by a Zenoh-Flow
+/// runtime.
+///
+/// The connectors, that send and receive the messages exchanged with other runtimes, are part of the nodes.
+///
+/// This information is what is displayed by the `zfctl` tool when requesting the metrics of a data flow instance.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InstanceMetrics {
+    /// The identifier of the [runtime](crate::Runtime) this information comes from.
+    pub runtime_id: RuntimeId,
+    /// The metrics of the nodes managed by this runtime.
+    pub nodes: HashMap<NodeId, NodeMetrics>,
+}
+
+/// The counters of the iterations of a node, shared by a `Runner` and the task that polls the `iteration`.
+#[derive(Debug, Default)]
+pub(crate) struct IterationCounters {
+    started: OnceLock<Instant>,
+    iterations: AtomicU64,
+    errors: AtomicU64,
+    panics: AtomicU64,
+    latency_sum_us: AtomicU64,
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+}
+
+/// The outcome of an `iteration`, as far as the metrics are concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Success,
+    Error,
+    Panic,
+}
+
+impl IterationCounters {
+    /// Marks the moment the node was first started. Subsequent calls have no effect.
+    pub(crate) fn start(&self) {
+        self.started.get_or_init(Instant::now);
+    }
+
+    /// Accounts for an `iteration` that lasted `duration`.
+    pub(crate) fn record(&self, duration: Duration, outcome: Outcome) {
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+        match outcome {
+            Outcome::Success => (),
+            Outcome::Error => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+            Outcome::Panic => {
+                self.panics.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let micros = duration.as_micros().min(u64::MAX as u128) as u64;
+        self.latency_sum_us.fetch_add(micros, Ordering::Relaxed);
+        let bucket = LATENCY_BUCKETS_US.partition_point(|bound| *bound < micros);
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the [NodeMetrics], combining these counters with the statistics of the `ports` of the node.
+    pub(crate) fn snapshot(&self, ports: NodeStatistics) -> NodeMetrics {
+        let iterations = self.iterations.load(Ordering::Relaxed);
+        let elapsed = self
+            .started
+            .get()
+            .map(|started| started.elapsed().as_secs_f64())
+            .unwrap_or_default();
+
+        NodeMetrics {
+            iterations,
+            iterations_per_second: if elapsed > 0.0 {
+                iterations as f64 / elapsed
+            } else {
+                0.0
+            },
+            errors: self.errors.load(Ordering::Relaxed),
+            panics: self.panics.load(Ordering::Relaxed),
+            latency: LatencyHistogram {
+                bounds_us: LATENCY_BUCKETS_US.to_vec(),
+                counts: self
+                    .latency_buckets
+                    .iter()
+                    .map(|count| count.load(Ordering::Relaxed))
+                    .collect(),
+                sum_us: self.latency_sum_us.load(Ordering::Relaxed),
+            },
+            ports,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_histogram() {
+        let counters = IterationCounters::default();
+        assert_eq!(
+            None,
+            counters.snapshot(NodeStatistics::default()).latency.mean()
+        );
+
+        counters.start();
+        counters.record(Duration::from_micros(5), Outcome::Success);
+        counters.record(Duration::from_micros(10), Outcome::Success);
+        counters.record(Duration::from_micros(70), Outcome::Error);
+        counters.record(Duration::from_secs(2), Outcome::Panic);
+
+        let metrics = counters.snapshot(NodeStatistics::default());
+        assert_eq!(4, metrics.iterations);
+        assert_eq!(1, metrics.errors);
+        assert_eq!(1, metrics.panics);
+        assert!(metrics.iterations_per_second > 0.0);
+
+        // A duration equal to a bound falls in the bucket of that bound.
+        assert_eq!(2, metrics.latency.counts[0]);
+        assert_eq!(1, metrics.latency.counts[2]);
+        assert_eq!(1, *metrics.latency.counts.last().unwrap());
+        assert_eq!(4, metrics.latency.count());
+        assert_eq!(2_000_085, metrics.latency.sum_us);
+
+        assert_eq!(
+            Some(Duration::from_micros(10)),
+            metrics.latency.quantile(0.5)
+        );
+        assert_eq!(
+            Some(Duration::from_micros(100)),
+            metrics.latency.quantile(0.75)
+        );
+        assert_eq!(None, metrics.latency.quantile(0.99));
+    }
+}
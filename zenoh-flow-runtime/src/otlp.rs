@@ -0,0 +1,258 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! A minimal OpenTelemetry exporter sending the [Hop]s of the messages to a collector, using OTLP over HTTP with the
+//! JSON encoding.
+//!
+//! Only plain `http` endpoints are supported: the exporter is meant to target a collector running next to the
+//! Zenoh-Flow runtime (for instance `http://127.0.0.1:4318`), which then forwards the traces where they should go.
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, bail, Context};
+use async_std::{
+    io::{ReadExt, WriteExt},
+    net::TcpStream,
+};
+use serde_json::{json, Value};
+use url::Url;
+use zenoh_flow_commons::{Result, RuntimeId};
+use zenoh_flow_nodes::Hop;
+
+/// The path on which a collector expects the traces, if the endpoint does not specify one.
+const TRACES_PATH: &str = "/v1/traces";
+/// The maximum number of hops waiting to be exported. Hops produced while the queue is full are discarded.
+const QUEUE_CAPACITY: usize = 2048;
+/// The maximum number of hops sent in a single request.
+const MAX_BATCH: usize = 512;
+/// How long the exporter waits, after receiving a hop, for more hops to batch together.
+const EXPORT_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the exporter waits for the collector before giving up on a batch.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The span kind "consumer": a hop starts with the reception of a message.
+const SPAN_KIND_CONSUMER: u8 = 5;
+
+/// Spawns a task exporting, to the collector listening on `endpoint`, the hops sent on the returned channel.
+///
+/// The task stops once all the senders of the channel are dropped.
+///
+/// # Errors
+///
+/// This function will fail if the endpoint is not a valid `http` URL.
+pub(crate) fn spawn(
+    endpoint: Url,
+    service_name: Arc<str>,
+    runtime_id: RuntimeId,
+) -> Result<flume::Sender<Hop>> {
+    if endpoint.scheme() != "http" {
+        bail!("Only `http` OTLP endpoints are supported, found < {endpoint} >");
+    }
+
+    let host = endpoint
+        .host_str()
+        .ok_or_else(|| anyhow!("The OTLP endpoint < {endpoint} > has no host"))?
+        .to_string();
+    let port = endpoint.port_or_known_default().unwrap_or(80);
+    let path = match endpoint.path() {
+        "" | "/" => TRACES_PATH.to_string(),
+        path => path.to_string(),
+    };
+
+    tracing::info!("Exporting traces to http://{host}:{port}{path}");
+
+    let (tx, rx) = flume::bounded::<Hop>(QUEUE_CAPACITY);
+    async_std::task::spawn(async move {
+        while let Ok(hop) = rx.recv_async().await {
+            async_std::task::sleep(EXPORT_INTERVAL).await;
+
+            let mut hops = vec![hop];
+            hops.extend(rx.try_iter().take(MAX_BATCH - 1));
+
+            let body = encode(&service_name, &runtime_id, &hops).to_string();
+            match async_std::future::timeout(TIMEOUT, post(&host, port, &path, &body)).await {
+                Ok(Ok(())) => tracing::trace!("Exported {} hop(s)", hops.len()),
+                Ok(Err(e)) => tracing::warn!("Failed to export {} hop(s): {:?}", hops.len(), e),
+                Err(_) => tracing::warn!(
+                    "Failed to export {} hop(s): the collector did not answer in time",
+                    hops.len()
+                ),
+            }
+        }
+
+        tracing::debug!("Trace exporter stopped");
+    });
+
+    Ok(tx)
+}
+
+/// Sends the `body` to the collector, in a single HTTP request.
+async fn post(host: &str, port: u16, path: &str, body: &str) -> Result<()> {
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .with_context(|| format!("Failed to connect to < {host}:{port} >"))?;
+
+    let request = format!(
+        "POST {path} HTTP/1.1\r\nHost: {host}:{port}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut response = String::default();
+    stream.read_to_string(&mut response).await?;
+
+    let status = response.split_whitespace().nth(1).unwrap_or_default();
+    if !status.starts_with('2') {
+        bail!(
+            "The collector answered: {}",
+            response.lines().next().unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+/// Encodes the `hops` as an OTLP `ExportTraceServiceRequest`, following the JSON mapping of the protobuf messages.
+fn encode(service_name: &str, runtime_id: &RuntimeId, hops: &[Hop]) -> Value {
+    let string_attribute =
+        |key: &str, value: String| json!({ "key": key, "value": { "stringValue": value } });
+    let unix_nanos = |time: &SystemTime| {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            .to_string()
+    };
+
+    let spans = hops
+        .iter()
+        .map(|hop| {
+            json!({
+                "traceId": format!("{:032x}", hop.context.trace_id()),
+                "spanId": format!("{:016x}", hop.context.span_id()),
+                "parentSpanId": format!("{:016x}", hop.parent_span_id),
+                "name": format!("{}/{}", hop.node, hop.port),
+                "kind": SPAN_KIND_CONSUMER,
+                "startTimeUnixNano": unix_nanos(&hop.start),
+                "endTimeUnixNano": unix_nanos(&hop.end),
+                "attributes": [
+                    string_attribute("zenoh_flow.node", hop.node.to_string()),
+                    string_attribute("zenoh_flow.port", hop.port.to_string()),
+                ],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    string_attribute("service.name", service_name.to_string()),
+                    string_attribute("zenoh_flow.runtime_id", runtime_id.to_string()),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "zenoh-flow", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::{
+        io::{prelude::BufReadExt, BufReader},
+        net::TcpListener,
+    };
+    use zenoh_flow_nodes::prelude::TraceContext;
+
+    use super::*;
+
+    #[async_std::test]
+    async fn test_export() {
+        // A fake collector, accepting a single request.
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind the collector");
+        let address = listener.local_addr().unwrap();
+
+        let hops = spawn(
+            format!("http://{address}").parse().unwrap(),
+            "test-otlp".into(),
+            RuntimeId::rand(),
+        )
+        .expect("Failed to spawn the exporter");
+
+        let context = TraceContext::new_root().child();
+        let now = SystemTime::now();
+        hops.send(Hop {
+            context,
+            parent_span_id: 42,
+            node: "operator".into(),
+            port: "in".into(),
+            start: now,
+            end: now + Duration::from_millis(1),
+        })
+        .unwrap();
+
+        let (stream, _) = listener.accept().await.expect("Failed to accept");
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await.unwrap();
+        assert_eq!("POST /v1/traces HTTP/1.1\r\n", request_line);
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).await.unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(length) = header.to_lowercase().strip_prefix("content-length:") {
+                content_length = length.trim().parse().unwrap();
+            }
+        }
+
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).await.unwrap();
+        let mut writer = &stream;
+        writer
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let request: Value = serde_json::from_slice(&body).expect("Invalid JSON");
+        let span = &request["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(
+            format!("{:032x}", context.trace_id()),
+            span["traceId"].as_str().unwrap()
+        );
+        assert_eq!(
+            format!("{:016x}", context.span_id()),
+            span["spanId"].as_str().unwrap()
+        );
+        assert_eq!("000000000000002a", span["parentSpanId"].as_str().unwrap());
+        assert_eq!("operator/in", span["name"].as_str().unwrap());
+        assert_eq!(
+            "test-otlp",
+            request["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"]
+                .as_str()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::{anyhow, bail, Context};
+use async_std::{
+    io::{BufWriter, WriteExt},
+    task::JoinHandle,
+};
+use futures::{stream::PollNext, StreamExt};
+use serde::{Deserialize, Serialize};
+use uhlc::Timestamp;
+use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
+use zenoh_flow_descriptors::OutputDescriptor;
+use zenoh_flow_nodes::{
+    prelude::{Message, OutputRaw},
+    Mirrors,
+};
+
+/// The version of the format of the recordings, it is checked when a recording is loaded.
+const FORMAT_VERSION: u8 = 1;
+/// The number of messages, per Output, waiting to be written. Messages sent while it is full are not recorded.
+const MIRROR_CAPACITY: usize = 1024;
+
+/// How a [Recording] is replayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReplayMode {
+    /// The messages are sent respecting the time that elapsed between them when they were recorded.
+    #[default]
+    Recorded,
+    /// The messages are sent as fast as possible.
+    AsFastAsPossible,
+}
+
+/// The summary of a recording, returned once it is stopped.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordingSummary {
+    /// The identifier of the [runtime](crate::Runtime) that made the recording.
+    pub runtime_id: RuntimeId,
+    /// The path of the file, on the runtime, where the recording was saved.
+    pub path: PathBuf,
+    /// The Outputs whose messages were recorded.
+    pub outputs: Vec<OutputDescriptor>,
+    /// The number of messages recorded.
+    pub messages: u64,
+}
+
+// The first element of a recording file.
+#[derive(Deserialize, Serialize)]
+struct Header {
+    version: u8,
+    instance_id: InstanceId,
+    outputs: Vec<OutputDescriptor>,
+}
+
+// A message recorded: the Output is an index in the list of Outputs of the header.
+#[derive(Deserialize, Serialize)]
+struct Frame {
+    output: u32,
+    timestamp: Timestamp,
+    payload: Vec<u8>,
+}
+
+/// A `Recording` holds the messages sent on a set of Outputs of a data flow instance: their payload, serialised, their
+/// timestamp and the Output they were sent on.
+///
+/// # Format
+///
+/// A recording is a file containing a header, listing the recorded Outputs, followed by the messages in the order
+/// they were received by the recorder. Both are encoded with bincode.
+pub struct Recording {
+    instance_id: InstanceId,
+    outputs: Vec<OutputDescriptor>,
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    /// Returns a `Recording` of the instance `instance_id` that contains no message.
+    ///
+    /// Replaying it only prevents the Sources from running.
+    pub fn empty(instance_id: InstanceId) -> Self {
+        Self {
+            instance_id,
+            outputs: Vec::default(),
+            frames: Vec::default(),
+        }
+    }
+
+    /// Attempts to load the `Recording` stored at `path`.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the file could not be read, if it is not a recording or if it was made by an
+    /// incompatible version of Zenoh-Flow. A recording whose last message was only partially written is accepted,
+    /// that message is ignored.
+    pub async fn try_load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = async_std::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read < {} >", path.display()))?;
+        let mut reader = bytes.as_slice();
+
+        let header: Header = bincode::deserialize_from(&mut reader)
+            .with_context(|| format!("< {} > is not a recording", path.display()))?;
+        if header.version != FORMAT_VERSION {
+            bail!(
+                "< {} > was recorded with an incompatible format (version {}, expected {})",
+                path.display(),
+                header.version,
+                FORMAT_VERSION
+            );
+        }
+
+        let mut frames = Vec::default();
+        loop {
+            match bincode::deserialize_from::<_, Frame>(&mut reader) {
+                Ok(frame) => {
+                    if frame.output as usize >= header.outputs.len() {
+                        bail!("< {} > is corrupted", path.display());
+                    }
+                    frames.push(frame);
+                }
+                Err(e) => match *e {
+                    bincode::ErrorKind::Io(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                    e => {
+                        return Err(anyhow!(e))
+                            .with_context(|| format!("< {} > is corrupted", path.display()))
+                    }
+                },
+            }
+        }
+
+        Ok(Self {
+            instance_id: header.instance_id,
+            outputs: header.outputs,
+            frames,
+        })
+    }
+
+    /// Returns the identifier of the data flow instance this `Recording` was made on.
+    pub fn instance_id(&self) -> &InstanceId {
+        &self.instance_id
+    }
+
+    /// Returns the Outputs whose messages were recorded.
+    pub fn outputs(&self) -> &[OutputDescriptor] {
+        &self.outputs
+    }
+
+    /// Returns the number of messages in this `Recording`.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns `true` if this `Recording` contains no message.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns an iterator over the messages of this `Recording`: the Output they were sent on, their timestamp and
+    /// their payload.
+    pub fn messages(&self) -> impl Iterator<Item = (&OutputDescriptor, &Timestamp, &[u8])> {
+        self.frames.iter().map(|frame| {
+            (
+                &self.outputs[frame.output as usize],
+                &frame.timestamp,
+                frame.payload.as_slice(),
+            )
+        })
+    }
+
+    /// Spawns a task sending, through the provided `outputs`, the messages of this `Recording`.
+    ///
+    /// The messages recorded on other Outputs are skipped. Returns the number of messages that will be sent and the
+    /// handle of the task.
+    pub(crate) fn replay(
+        self,
+        outputs: HashMap<OutputDescriptor, OutputRaw>,
+        mode: ReplayMode,
+    ) -> (u64, JoinHandle<()>) {
+        let frames = self
+            .frames
+            .into_iter()
+            .filter_map(|frame| {
+                outputs
+                    .get(&self.outputs[frame.output as usize])
+                    .map(|output| (output.clone(), frame))
+            })
+            .collect::<Vec<_>>();
+        let count = frames.len() as u64;
+
+        let handle = async_std::task::spawn(async move {
+            let start = Instant::now();
+            let first = frames
+                .first()
+                .map(|(_, frame)| frame.timestamp.get_time().to_duration());
+
+            for (output, frame) in frames {
+                if let (ReplayMode::Recorded, Some(first)) = (mode, first) {
+                    let offset = frame
+                        .timestamp
+                        .get_time()
+                        .to_duration()
+                        .saturating_sub(first);
+                    let elapsed = start.elapsed();
+                    if offset > elapsed {
+                        async_std::task::sleep(offset - elapsed).await;
+                    }
+                }
+
+                if let Err(e) = output.send(frame.payload, None).await {
+                    tracing::error!(
+                        "Failed to replay message on < {} >: {:?}",
+                        output.port_id(),
+                        e
+                    );
+                }
+            }
+
+            tracing::info!("Replay finished");
+        });
+
+        (count, handle)
+    }
+}
+
+/// A `Recorder` writes, in a file, the messages sent on a set of Outputs of a data flow instance.
+pub(crate) struct Recorder {
+    path: PathBuf,
+    outputs: Vec<OutputDescriptor>,
+    stop: flume::Sender<()>,
+    handle: JoinHandle<Result<u64>>,
+}
+
+impl Recorder {
+    /// Creates the file at `path` and spawns a task that writes in it the messages sent on the provided `outputs`.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the file could not be created.
+    pub(crate) async fn try_spawn(
+        path: PathBuf,
+        instance_id: InstanceId,
+        outputs: Vec<(OutputDescriptor, Arc<Mirrors>)>,
+    ) -> Result<Self> {
+        let file = async_std::fs::File::create(&path)
+            .await
+            .with_context(|| format!("Failed to create < {} >", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        let (descriptors, mirrors): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+        let header = bincode::serialize(&Header {
+            version: FORMAT_VERSION,
+            instance_id,
+            outputs: descriptors.clone(),
+        })
+        .context("Failed to serialise the header of the recording")?;
+        writer
+            .write_all(&header)
+            .await
+            .context("Failed to write the header of the recording")?;
+
+        let receivers = mirrors
+            .iter()
+            .map(|mirrors| mirrors.attach(MIRROR_CAPACITY))
+            .collect::<Vec<_>>();
+        let streams = receivers
+            .iter()
+            .enumerate()
+            .map(|(index, receiver)| {
+                receiver
+                    .clone()
+                    .into_stream()
+                    .map(move |message| Some((index as u32, message)))
+            })
+            .collect::<Vec<_>>();
+
+        // The messages are always polled first: the stop signal is only processed when no message is pending.
+        let (stop, stop_rx) = flume::bounded::<()>(1);
+        let mut messages = futures::stream::select_with_strategy(
+            futures::stream::select_all(streams),
+            stop_rx.into_stream().map(|_| None),
+            |_: &mut ()| PollNext::Left,
+        );
+
+        let task_path = path.clone();
+        let handle = async_std::task::spawn(async move {
+            let mut count = 0;
+            while let Some(Some((output, message))) = messages.next().await {
+                count += write_frame(&mut writer, output, message)
+                    .await
+                    .with_context(|| format!("Failed to write in < {} >", task_path.display()))?;
+            }
+
+            // The messages sent before the recording was stopped but not yet received are recorded as well.
+            for (output, receiver) in receivers.iter().enumerate() {
+                for message in receiver.drain() {
+                    count += write_frame(&mut writer, output as u32, message)
+                        .await
+                        .with_context(|| {
+                            format!("Failed to write in < {} >", task_path.display())
+                        })?;
+                }
+            }
+
+            writer
+                .flush()
+                .await
+                .with_context(|| format!("Failed to write in < {} >", task_path.display()))?;
+
+            Ok(count)
+        });
+
+        Ok(Self {
+            path,
+            outputs: descriptors,
+            stop,
+            handle,
+        })
+    }
+
+    /// Stops the recording, waiting for the file to be written.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if the messages could not be written.
+    pub(crate) async fn stop(self, runtime_id: RuntimeId) -> Result<RecordingSummary> {
+        let _ = self.stop.send(());
+        let messages = self.handle.await?;
+
+        Ok(RecordingSummary {
+            runtime_id,
+            path: self.path,
+            outputs: self.outputs,
+            messages,
+        })
+    }
+}
+
+/// Writes the `message`, sent on the Output at index `output`, returning the number of frames written.
+///
+/// Watermarks and messages that could not be serialised are not written.
+async fn write_frame(
+    writer: &mut BufWriter<async_std::fs::File>,
+    output: u32,
+    message: Message,
+) -> Result<u64> {
+    let Message::Data(data) = message else {
+        return Ok(0);
+    };
+
+    let payload = match data.payload().try_as_bytes() {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Failed to serialise a message, skipping it: {:?}", e);
+            return Ok(0);
+        }
+    };
+
+    let frame = bincode::serialize(&Frame {
+        output,
+        timestamp: *data.timestamp(),
+        payload: payload.to_vec(),
+    })?;
+    writer.write_all(&frame).await?;
+
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use zenoh_flow_nodes::prelude::Outputs;
+
+    use super::*;
+
+    #[async_std::test]
+    async fn test_record_replay() {
+        let path = std::env::temp_dir().join(format!("zenoh-flow-{}.recording", Uuid::new_v4()));
+        let instance_id: InstanceId = Uuid::new_v4().into();
+
+        let (tx, _rx) = flume::unbounded::<Message>();
+        let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+        outputs.insert("out".into(), tx);
+        let mirrors = outputs.mirrors().remove(&"out".into()).unwrap();
+        let output = outputs.take("out").unwrap().raw();
+        let descriptor = OutputDescriptor::new("source", "out");
+
+        let recorder = Recorder::try_spawn(
+            path.clone(),
+            instance_id.clone(),
+            vec![(descriptor.clone(), mirrors)],
+        )
+        .await
+        .expect("Failed to spawn the recorder");
+
+        for payload in [b"one".to_vec(), b"two".to_vec(), b"three".to_vec()] {
+            output.send(payload, None).await.unwrap();
+        }
+        // Watermarks are not recorded.
+        output.send_watermark(None).await.unwrap();
+
+        let summary = recorder
+            .stop(RuntimeId::rand())
+            .await
+            .expect("Failed to stop the recorder");
+        assert_eq!(3, summary.messages);
+
+        let recording = Recording::try_load(&path)
+            .await
+            .expect("Failed to load the recording");
+        assert_eq!(&instance_id, recording.instance_id());
+        assert_eq!(&[descriptor.clone()], recording.outputs());
+        assert_eq!(
+            vec![b"one".as_slice(), b"two".as_slice(), b"three".as_slice()],
+            recording
+                .messages()
+                .map(|(_, _, payload)| payload)
+                .collect::<Vec<_>>()
+        );
+
+        // Replay, as fast as possible, on another Output.
+        let (tx, replayed) = flume::unbounded::<Message>();
+        let mut outputs = Outputs::new(Arc::new(uhlc::HLC::default()));
+        outputs.insert("out".into(), tx);
+        let output = outputs.take("out").unwrap().raw();
+
+        let (count, handle) = recording.replay(
+            HashMap::from([(descriptor, output)]),
+            ReplayMode::AsFastAsPossible,
+        );
+        assert_eq!(3, count);
+        handle.await;
+
+        let payloads = replayed
+            .drain()
+            .filter_map(|message| match message {
+                Message::Data(data) => data.payload().try_as_bytes().ok(),
+                Message::Watermark(_) => None,
+            })
+            .map(|payload| payload.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()],
+            payloads
+        );
+
+        std::fs::remove_file(path).expect("Failed to clean up");
+    }
+}
@@ -21,13 +21,13 @@ use zenoh::{key_expr::OwnedKeyExpr, pubsub::Publisher, Session};
 #[cfg(feature = "shared-memory")]
 use zenoh_flow_commons::SharedMemoryConfiguration;
 use zenoh_flow_commons::{NodeId, PortId, Result};
-use zenoh_flow_nodes::prelude::{InputRaw, Inputs, LinkMessage, Node};
+use zenoh_flow_nodes::prelude::{InputRaw, Inputs, Message, Node};
 
 #[cfg(feature = "shared-memory")]
 use crate::shared_memory::SharedMemory;
 
 /// Internal type of pending futures for the ZenohSink
-type ZFInputFut = Pin<Box<dyn Future<Output = (PortId, Result<LinkMessage>)> + Send + Sync>>;
+type ZFInputFut = Pin<Box<dyn Future<Output = (PortId, Result<Message>)> + Send + Sync>>;
 
 fn wait_flow_input(id: PortId, input: &InputRaw) -> ZFInputFut {
     let input = input.clone();
@@ -156,7 +156,7 @@ impl<'a> Node for ZenohSink<'a> {
         let (key_expr, input, publisher) = self.get(&id);
 
         match message {
-            Ok(data) => {
+            Ok(Message::Data(data)) => {
                 // NOTE: In most of cases sending through the shared memory should suffice.
                 //
                 // This holds true EVEN IF THERE IS NO SHARED MEMORY. Zenoh will, by default, automatically fallback to
@@ -211,6 +211,13 @@ Caused by:
                         .map_err(|e| anyhow!("{:?}", e))?
                 }
             }
+            // A watermark carries no data, there is nothing to publish.
+            Ok(Message::Watermark(timestamp)) => tracing::trace!(
+                "[built-in zenoh sink: {}][port: {}] Ignoring watermark: {}",
+                self.id,
+                key_expr,
+                timestamp
+            ),
             Err(e) => tracing::error!(
                 "[built-in zenoh sink: {}][port: {}] Channel returned an error: {e:?}",
                 self.id,
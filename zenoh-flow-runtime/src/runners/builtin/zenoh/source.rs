@@ -22,7 +22,7 @@ use zenoh::{
     Session,
 };
 use zenoh_flow_commons::{NodeId, PortId, Result};
-use zenoh_flow_nodes::prelude::{Node, OutputRaw, Outputs};
+use zenoh_flow_nodes::prelude::{Node, OutputRaw, Outputs, Statistics};
 
 /// Internal type of pending futures for the ZenohSource
 pub(crate) type ZSubFut = Pin<Box<dyn Future<Output = (PortId, Result<Sample>)> + Send + Sync>>;
@@ -39,6 +39,7 @@ pub(crate) struct ZenohSource {
     key_exprs: HashMap<PortId, OwnedKeyExpr>,
     subscribers: Mutex<HashMap<PortId, Subscriber<FifoChannelHandler<Sample>>>>,
     futs: Arc<Mutex<Vec<ZSubFut>>>,
+    statistics: Statistics,
 }
 
 impl ZenohSource {
@@ -47,6 +48,7 @@ impl ZenohSource {
         session: Session,
         key_exprs: &HashMap<PortId, OwnedKeyExpr>,
         mut outputs: Outputs,
+        statistics: Statistics,
     ) -> Result<ZenohSource> {
         let mut raw_outputs = HashMap::with_capacity(key_exprs.len());
 
@@ -73,6 +75,7 @@ impl ZenohSource {
             key_exprs: key_exprs.clone(),
             subscribers: Mutex::new(HashMap::with_capacity(key_exprs.len())),
             futs: Arc::new(Mutex::new(Vec::with_capacity(key_exprs.len()))),
+            statistics,
         };
 
         Ok(zenoh_source)
@@ -81,11 +84,11 @@ impl ZenohSource {
 
 #[async_trait::async_trait]
 impl Node for ZenohSource {
-    // When we resume an aborted Zenoh Source, we have to re-subscribe to the key expressions and, possibly, recreate
-    // the futures awaiting publications.
+    // When we resume a Zenoh Source, we have to re-subscribe to the key expressions (they were dropped if it was
+    // aborted) and thus recreate the futures awaiting publications.
     async fn on_resume(&self) -> Result<()> {
         let mut futures = self.futs.lock().await;
-        let futures_were_empty = futures.is_empty();
+        futures.clear();
 
         let mut subscribers = self.subscribers.lock().await;
         for (port, key_expr) in self.key_exprs.iter() {
@@ -103,13 +106,7 @@ Caused by:
                     )
                 })?;
 
-            // NOTE: Even though it is more likely that the node was aborted while the `futures` were swapped (and thus
-            // empty), there is still a possibility that the `abort` happened outside of this scenario.
-            // In this rare case, we should not push new futures.
-            if futures_were_empty {
-                futures.push(wait_zenoh_sub(port.clone(), &subscriber));
-            }
-
+            futures.push(wait_zenoh_sub(port.clone(), &subscriber));
             subscribers.insert(port.clone(), subscriber);
         }
 
@@ -128,11 +125,24 @@ Caused by:
 
     // The iteration of a Zenoh Source polls, concurrently, the subscribers and forwards the first publication received
     // on the associated port.
+    //
+    // NOTE: The futures are polled in place, without being taken out of the list, such that the runtime can stop the
+    // Zenoh Source while it is waiting for a publication: the futures are then kept for the next iteration.
     async fn iteration(&self) -> Result<()> {
         let mut subscribers_futures = self.futs.lock().await;
-        let subs = std::mem::take(&mut (*subscribers_futures));
+        let ((id, result), index, _) = self
+            .statistics
+            .wait_for(select_all(subscribers_futures.iter_mut()))
+            .await;
 
-        let ((id, result), _index, mut remaining) = select_all(subs).await;
+        // Replace the future that completed before anything else, it cannot be polled again.
+        let subscribers = self.subscribers.lock().await;
+        let sub = subscribers
+            .get(&id)
+            .ok_or_else(|| anyhow!("[{}] Cannot find port < {} >", self.id, id))?;
+        subscribers_futures[index] = wait_zenoh_sub(id.clone(), sub);
+        drop(subscribers);
+        drop(subscribers_futures);
 
         match result {
             Ok(sample) => {
@@ -149,17 +159,6 @@ Caused by:
             Err(e) => tracing::error!("subscriber for output {id} failed with: {e:?}"),
         }
 
-        // Add back the subscriber that got polled
-        let subscribers = self.subscribers.lock().await;
-        let sub = subscribers
-            .get(&id)
-            .ok_or_else(|| anyhow!("[{}] Cannot find port < {} >", self.id, id))?;
-
-        remaining.push(wait_zenoh_sub(id.clone(), sub));
-
-        // Setting back a complete list for the next iteration
-        *subscribers_futures = remaining;
-
         Ok(())
     }
 }
@@ -23,7 +23,10 @@ use zenoh::{
 #[cfg(feature = "shared-memory")]
 use zenoh_flow_commons::SharedMemoryConfiguration;
 use zenoh_flow_commons::{NodeId, Result};
-use zenoh_flow_nodes::prelude::{InputRaw, Inputs, LinkMessage, Node, OutputRaw, Outputs};
+use zenoh_flow_nodes::{
+    prelude::{InputRaw, Inputs, Message, Node, OutputRaw, Outputs, Statistics},
+    Tracer,
+};
 use zenoh_flow_records::{ReceiverRecord, SenderRecord};
 
 #[cfg(feature = "shared-memory")]
@@ -35,6 +38,7 @@ pub(crate) struct ZenohConnectorSender {
     key_expr: OwnedKeyExpr,
     session: Session,
     state: Arc<Mutex<State>>,
+    tracer: Option<Arc<Tracer>>,
 }
 
 struct State {
@@ -51,6 +55,7 @@ impl ZenohConnectorSender {
         record: SenderRecord,
         mut inputs: Inputs,
     ) -> Result<Self> {
+        let tracer = inputs.tracer();
         let input = inputs
             .take(record.resource())
             // TODO@J-Loudet
@@ -68,6 +73,7 @@ impl ZenohConnectorSender {
             })),
             id: record.id(),
             session,
+            tracer,
         })
     }
 }
@@ -76,7 +82,13 @@ impl ZenohConnectorSender {
 impl Node for ZenohConnectorSender {
     async fn iteration(&self) -> Result<()> {
         match self.input.recv().await {
-            Ok(message) => {
+            Ok(mut message) => {
+                // NOTE: The trace context travels inside the bincode frame, in the headers of the message, such that
+                // the hops on the other runtime are part of the same trace.
+                if let (Some(tracer), Message::Data(data)) = (&self.tracer, &mut message) {
+                    tracer.inject(data.headers_mut());
+                }
+
                 let mut state = self.state.lock().await;
 
                 let mut message_buffer = std::mem::take(&mut state.message_buffer);
@@ -178,6 +190,8 @@ pub(crate) struct ZenohConnectorReceiver {
     pub(crate) key_expr: OwnedKeyExpr,
     pub(crate) output_raw: OutputRaw,
     pub(crate) subscriber: Subscriber<FifoChannelHandler<Sample>>,
+    pub(crate) tracer: Option<Arc<Tracer>>,
+    pub(crate) statistics: Statistics,
 }
 
 impl ZenohConnectorReceiver {
@@ -185,6 +199,7 @@ impl ZenohConnectorReceiver {
         session: Session,
         record: ReceiverRecord,
         mut outputs: Outputs,
+        statistics: Statistics,
     ) -> Result<Self> {
         let ke = session
             .declare_keyexpr(record.resource())
@@ -198,17 +213,22 @@ impl ZenohConnectorReceiver {
             // TODO@J-Loudet
             .map_err(|e| anyhow!("{:?}", e))?;
 
+        // NOTE: The connector relays the messages such that their sequence numbers, stamped by the upstream node, reach
+        // the downstream node.
+        let tracer = outputs.tracer();
         let output_raw = outputs
             .take(record.resource())
             // TODO@J-Loudet
             .ok_or_else(|| anyhow!(""))?
-            .raw();
+            .relay();
 
         Ok(Self {
             id: record.id(),
             key_expr: record.resource().clone(),
             output_raw,
             subscriber,
+            tracer,
+            statistics,
         })
     }
 }
@@ -216,9 +236,12 @@ impl ZenohConnectorReceiver {
 #[async_trait::async_trait]
 impl Node for ZenohConnectorReceiver {
     async fn iteration(&self) -> Result<()> {
-        match self.subscriber.recv_async().await {
+        match self.statistics.wait_for(self.subscriber.recv_async()).await {
             Ok(sample) => {
-                let de: LinkMessage = bincode::deserialize_from(sample.payload().reader())?;
+                let de: Message = bincode::deserialize_from(sample.payload().reader())?;
+                if let (Some(tracer), Message::Data(data)) = (&self.tracer, &de) {
+                    tracer.enter(self.output_raw.port_id(), data.headers());
+                }
 
                 self.output_raw.forward(de).await
             }
@@ -17,14 +17,78 @@ pub(crate) mod builtin;
 #[cfg(feature = "zenoh")]
 pub(crate) mod connectors;
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
 use async_std::task::JoinHandle;
+use futures::FutureExt;
 use libloading::Library;
 use tracing::Instrument;
-use zenoh_flow_commons::{NodeId, Result};
-use zenoh_flow_nodes::prelude::Node;
+use uhlc::{Timestamp, HLC};
+use zenoh_flow_commons::{Configuration, NodeId, PortId, RestartPolicy, Result};
+use zenoh_flow_nodes::{
+    next_deadline,
+    prelude::{Node, OutputRaw, Outputs, Statistics},
+    Mirrors, Tracer,
+};
+
+use crate::metrics::{IterationCounters, NodeMetrics, Outcome};
+
+/// The Outputs of a `Node`, as seen by the runtime: their [Mirrors], to observe the messages sent, and handles to send
+/// messages in place of the `Node`.
+#[derive(Default)]
+pub(crate) struct OutputPorts {
+    pub(crate) mirrors: HashMap<PortId, Arc<Mirrors>>,
+    pub(crate) handles: HashMap<PortId, OutputRaw>,
+}
+
+impl OutputPorts {
+    pub(crate) fn new(outputs: &Outputs) -> Self {
+        Self {
+            mirrors: outputs.mirrors(),
+            handles: outputs
+                .keys()
+                .filter_map(|port_id| {
+                    outputs
+                        .handle(port_id)
+                        .map(|handle| (port_id.clone(), handle))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The interval at which a Runner that is being gracefully stopped checks if its `Node` is idle.
+const HALT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The outcome of an attempt to [halt](Runner::try_halt()) a Runner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Halt {
+    /// The Node is processing a message: the Runner was not halted.
+    Busy,
+    /// The Runner was halted between two `iteration`s or while its Node was waiting for a message.
+    Idle,
+    /// The Runner was halted but its Node consumed a message in the meantime: its processing was interrupted.
+    Interrupted,
+}
+
+/// The state of the task of a Runner, shared with the Runner such that it can be halted between two `iteration`s.
+#[derive(Default)]
+struct Control {
+    // `Some` while an `iteration` is executed, holding the number of messages the Node had consumed when it started.
+    iteration: std::sync::Mutex<Option<u64>>,
+    // Once set, the task finishes instead of starting a new `iteration`.
+    halted: AtomicBool,
+}
 
 /// A `Runner` takes care of running a `Node`.
 ///
@@ -33,6 +97,17 @@ pub(crate) struct Runner {
     pub(crate) id: NodeId,
     node: Arc<dyn Node>,
     handle: Option<JoinHandle<()>>,
+    statistics: Statistics,
+    metrics: Arc<IterationCounters>,
+    tracer: Option<Arc<Tracer>>,
+    outputs: OutputPorts,
+    period: Option<Duration>,
+    restart: RestartPolicy,
+    // The reason why the Node was stopped after exhausting its restart policy, if it was.
+    failure: Arc<std::sync::Mutex<Option<(Timestamp, String)>>>,
+    // The `control` tells if an `iteration` of the Node is being executed. It is only locked to start or finish an
+    // `iteration`, never while the Node waits for a message.
+    control: Arc<Control>,
     // The `_library` field is used solely for its `Arc`. We need to keep track of how many `Runners` are using the
     // `Library` such that once that number reaches 0, we drop the library.
     //
@@ -47,17 +122,88 @@ impl Runner {
     //
     // The control logic is relatively simple:
     // 1. When `start` is called, create a task that will poll indefinitely, in a loop, the `iteration` method of the
-    //    Node.
+    //    Node. If the Node has a period, each `iteration` is polled at a deadline computed from the previous one (so
+    //    that the time spent in an `iteration` does not make the Node drift). Deadlines that were missed are skipped.
+    //    An `iteration` that returns an error or panics is a failure: the RestartPolicy of the Node decides if (and
+    //    when) it is called again or if the task should finish, recording the failure. The duration and outcome of
+    //    each `iteration` are accounted for in the metrics of the Node, and the hop (i.e. the processing of a traced
+    //    message) it was processing, if any, is finished.
     // 2. Keep a reference to that task through its JoinHandle so we can call `cancel` to stop it.
-    pub(crate) fn new(id: NodeId, node: Arc<dyn Node>, library: Option<Arc<Library>>) -> Self {
+    // 3. To stop it gracefully (or to pause it), halt it when its Node is idle: either between two `iteration`s or
+    //    while waiting for a message that is not there, without having consumed one since the `iteration` started.
+    //    Cancelling the task then does not interrupt the processing of a message. The `control`, shared with the
+    //    task, prevents it from starting a new `iteration` once halted.
+    pub(crate) fn new(
+        id: NodeId,
+        node: Arc<dyn Node>,
+        library: Option<Arc<Library>>,
+        statistics: Statistics,
+    ) -> Self {
         Self {
             id,
             node,
             handle: None,
+            statistics,
+            metrics: Arc::new(IterationCounters::default()),
+            tracer: None,
+            outputs: OutputPorts::default(),
+            period: None,
+            restart: RestartPolicy::default(),
+            failure: Arc::new(std::sync::Mutex::new(None)),
+            control: Arc::new(Control::default()),
             _library: library,
         }
     }
 
+    /// Sets the `period` at which the `iteration` of the [Node] is polled.
+    ///
+    /// Without a period, the `iteration` is polled in a loop, as soon as the previous one finished.
+    pub(crate) fn with_period(mut self, period: Option<Duration>) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Sets the [Tracer] of the [Node]: the hop it is processing is finished at the end of each `iteration`.
+    pub(crate) fn with_tracer(mut self, tracer: Option<Arc<Tracer>>) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Sets the [OutputPorts] of the [Node].
+    pub(crate) fn with_outputs(mut self, outputs: OutputPorts) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    /// Sets the [RestartPolicy] applied when the `iteration` of the [Node] fails.
+    pub(crate) fn with_restart_policy(mut self, restart: RestartPolicy) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Returns the reason, and when, the [Node] was stopped after exhausting its [RestartPolicy], if it was.
+    pub(crate) fn failure(&self) -> Option<(Timestamp, String)> {
+        self.failure
+            .lock()
+            .map(|failure| failure.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the [Statistics] of the ports of the [Node] this Runner wraps.
+    pub(crate) fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// Returns the [OutputPorts] of the [Node] this Runner wraps.
+    pub(crate) fn outputs(&self) -> &OutputPorts {
+        &self.outputs
+    }
+
+    /// Returns the [NodeMetrics] of the [Node] this Runner wraps.
+    pub(crate) fn metrics(&self) -> NodeMetrics {
+        self.metrics.snapshot(self.statistics.snapshot())
+    }
+
     /// Returns `true` if the Runner is running, i.e. if the `iteration` of the [Node] it wraps is being polled in a
     /// loop.
     pub(crate) fn is_running(&self) -> bool {
@@ -66,10 +212,22 @@ impl Runner {
 
     /// Starts the runner: run the `iteration` method of the [Node] it wraps in a loop.
     ///
-    /// This method is also idempotent: if the runner is already running, nothing will happen.
-    pub(crate) async fn start(&mut self) -> Result<()> {
+    /// The [HLC] is used to timestamp the failure of the [Node], if it exhausts its [RestartPolicy].
+    ///
+    /// This method is also idempotent: if the runner is already running, nothing will happen. A runner whose [Node]
+    /// was stopped after exhausting its [RestartPolicy] is restarted.
+    pub(crate) async fn start(&mut self, hlc: Arc<HLC>) -> Result<()> {
         if self.is_running() {
-            return Ok(());
+            if self.failure().is_none() {
+                return Ok(());
+            }
+
+            // The task of a Node that exhausted its restart policy is already finished.
+            self.handle = None;
+        }
+
+        if let Ok(mut failure) = self.failure.lock() {
+            *failure = None;
         }
 
         self.node
@@ -77,29 +235,135 @@ impl Runner {
             .await
             .with_context(|| format!("{}: call to `on_resume` failed", self.id))?;
 
+        self.spawn(hlc);
+        Ok(())
+    }
+
+    /// Restarts the task of a runner that was [halted](Runner::try_halt()), without calling the `on_resume` hook of its
+    /// [Node].
+    ///
+    /// This method is idempotent: if the runner is running, or was not halted, nothing will happen.
+    pub(crate) fn respawn(&mut self, hlc: Arc<HLC>) {
+        if !self.is_running() && self.control.halted.load(Ordering::Acquire) {
+            self.spawn(hlc);
+        }
+    }
+
+    // Spawns the task polling, in a loop, the `iteration` of the Node.
+    fn spawn(&mut self, hlc: Arc<HLC>) {
+        if let Ok(mut iteration) = self.control.iteration.lock() {
+            *iteration = None;
+        }
+        self.control.halted.store(false, Ordering::Release);
+
         let id = self.id.clone();
         let node = self.node.clone();
+        let period = self.period;
+        let control = self.control.clone();
+        let statistics = self.statistics.clone();
+        let restart = self.restart;
+        let failure = self.failure.clone();
+        let metrics = self.metrics.clone();
+        metrics.start();
+        let tracer = self.tracer.clone();
         let iteration_span = tracing::trace_span!("iteration", node = %id);
 
         self.handle = Some(async_std::task::spawn(
             async move {
                 let mut instant;
                 let mut iteration;
+                let mut deadline = Instant::now();
+                let mut failures = 0u32;
                 loop {
+                    if let Some(period) = period {
+                        let now = Instant::now();
+                        if deadline > now {
+                            async_std::task::sleep(deadline - now).await;
+                        }
+                        let missed;
+                        (deadline, missed) = next_deadline(deadline, period, Instant::now());
+                        if missed > 0 {
+                            // A periodic Node that also waits for messages routinely misses periods: this is not
+                            // worth a warning.
+                            tracing::debug!("missed {} period(s)", missed);
+                        }
+                    }
+
+                    {
+                        let Ok(mut current) = control.iteration.lock() else {
+                            break;
+                        };
+                        if control.halted.load(Ordering::Acquire) {
+                            break;
+                        }
+                        *current = Some(statistics.consumed());
+                    }
+
                     instant = Instant::now();
-                    iteration = node.iteration().await;
-                    tracing::trace!("duration: {}µs", instant.elapsed().as_micros());
-                    if let Err(e) = iteration {
-                        tracing::error!("{:?}", e);
+                    iteration = AssertUnwindSafe(node.iteration()).catch_unwind().await;
+                    let duration = instant.elapsed();
+                    if let Some(tracer) = &tracer {
+                        tracer.finish();
+                    }
+                    if let Ok(mut current) = control.iteration.lock() {
+                        *current = None;
+                    }
+                    tracing::trace!("duration: {}µs", duration.as_micros());
+                    metrics.record(
+                        duration,
+                        match iteration {
+                            Ok(Ok(())) => Outcome::Success,
+                            Ok(Err(_)) => Outcome::Error,
+                            Err(_) => Outcome::Panic,
+                        },
+                    );
+
+                    let error = match iteration {
+                        Ok(Ok(())) => {
+                            failures = 0;
+                            None
+                        }
+                        Ok(Err(e)) => Some(format!("{e:?}")),
+                        Err(panic) => Some(format!(
+                            "`iteration` panicked: {}",
+                            panic_message(panic.as_ref())
+                        )),
+                    };
+
+                    if let Some(error) = error {
+                        tracing::error!("{}", error);
+                        failures = failures.saturating_add(1);
+                        match restart.backoff(failures) {
+                            Some(backoff) => {
+                                if !backoff.is_zero() {
+                                    tracing::warn!(
+                                        "restarting in {}ms (failure #{})",
+                                        backoff.as_millis(),
+                                        failures
+                                    );
+                                    async_std::task::sleep(backoff).await;
+                                }
+                            }
+                            None => {
+                                tracing::error!(
+                                    "restart policy < {} > exhausted, stopping the node",
+                                    restart
+                                );
+                                if let Ok(mut failure) = failure.lock() {
+                                    *failure = Some((hlc.new_timestamp(), error));
+                                }
+                                break;
+                            }
+                        }
                     }
 
-                    async_std::task::yield_now().await;
+                    if period.is_none() {
+                        async_std::task::yield_now().await;
+                    }
                 }
             }
             .instrument(iteration_span),
         ));
-
-        Ok(())
     }
 
     /// Aborts the runner: stop the execution of its `iteration` method at its nearest `await` point.
@@ -120,4 +384,142 @@ impl Runner {
             self.node.on_abort().await;
         }
     }
+
+    /// Halts the runner if its [Node] is idle: between two `iteration`s or waiting for a message, without having
+    /// consumed one since its current `iteration` started.
+    ///
+    /// Once halted, the task of the runner is cancelled: it can be restarted with [respawn](Runner::respawn()). The
+    /// hooks of the Node are *not* called.
+    ///
+    /// A Node can consume a message between the moment it was found idle and the moment its task is cancelled, which
+    /// interrupts the processing of that message: [Halt::Interrupted] is then returned.
+    ///
+    /// This method is idempotent: if the runner is not running, nothing will happen and [Halt::Idle] is returned.
+    pub(crate) async fn try_halt(&mut self) -> Halt {
+        if !self.is_running() {
+            return Halt::Idle;
+        }
+
+        // The task of a Node that exhausted its restart policy is already finished.
+        if self.failure().is_some() {
+            self.handle = None;
+            return Halt::Idle;
+        }
+
+        let consumed = {
+            let Ok(iteration) = self.control.iteration.lock() else {
+                return Halt::Busy;
+            };
+
+            let consumed = self.statistics.consumed();
+            if let Some(started) = *iteration {
+                if !self.statistics.is_waiting() || consumed != started {
+                    return Halt::Busy;
+                }
+            }
+
+            // Holding the lock guarantees that the task does not start a new `iteration` before seeing this flag.
+            self.control.halted.store(true, Ordering::Release);
+            consumed
+        };
+
+        if let Some(handle) = self.handle.take() {
+            handle.cancel().await;
+        }
+
+        if self.statistics.consumed() != consumed {
+            Halt::Interrupted
+        } else {
+            Halt::Idle
+        }
+    }
+
+    /// Calls the hook of the [Node] of a runner that was [halted](Runner::try_halt()): `on_stop` if it was idle,
+    /// `on_abort` if the processing of a message was interrupted.
+    pub(crate) async fn stop_halted(&self, halt: Halt) {
+        match halt {
+            Halt::Busy => (),
+            Halt::Idle => self.node.on_stop().await,
+            Halt::Interrupted => {
+                tracing::warn!(
+                    "{}: a message was consumed while stopping the node, its processing was interrupted",
+                    self.id
+                );
+                self.node.on_abort().await;
+            }
+        }
+    }
+
+    /// Gracefully stops the runner: wait, at most `timeout`, for its [Node] to be idle (see
+    /// [try_halt](Runner::try_halt())) and call its `on_stop` hook.
+    ///
+    /// If the Node is still processing a message after the `timeout`, the runner is [aborted](Runner::abort())
+    /// instead.
+    ///
+    /// Returns `true` if the runner was gracefully stopped.
+    ///
+    /// This method is idempotent: if the runner is not running, nothing will happen.
+    pub(crate) async fn stop(&mut self, timeout: Duration) -> bool {
+        if !self.is_running() {
+            return true;
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.try_halt().await {
+                Halt::Busy => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+                    async_std::task::sleep(HALT_POLL_INTERVAL.min(remaining)).await;
+                }
+                halt => {
+                    self.stop_halted(halt).await;
+                    return halt == Halt::Idle;
+                }
+            }
+        }
+
+        tracing::warn!(
+            "{}: `iteration` did not finish within {}ms, aborting",
+            self.id,
+            timeout.as_millis()
+        );
+        self.abort().await;
+        false
+    }
+
+    /// Returns the state of the [Node] this Runner wraps, as returned by its `snapshot` method.
+    pub(crate) async fn snapshot(&self) -> Result<Vec<u8>> {
+        self.node
+            .snapshot()
+            .await
+            .with_context(|| format!("{}: call to `snapshot` failed", self.id))
+    }
+
+    /// Restores the state of the [Node] this Runner wraps, calling its `restore` method.
+    pub(crate) async fn restore(&self, state: &[u8]) -> Result<()> {
+        self.node
+            .restore(state)
+            .await
+            .with_context(|| format!("{}: call to `restore` failed", self.id))
+    }
+
+    /// Gives the new `configuration` to the [Node] this Runner wraps, calling its `on_configure` method.
+    pub(crate) async fn configure(&self, configuration: Configuration) -> Result<()> {
+        self.node
+            .on_configure(configuration)
+            .await
+            .with_context(|| format!("{}: call to `on_configure` failed", self.id))
+    }
+}
+
+/// Returns the message with which a task panicked, if it is a string.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("no message")
 }
@@ -20,7 +20,8 @@ use zenoh::Session;
 #[cfg(feature = "zenoh")]
 #[cfg(feature = "shared-memory")]
 use zenoh_flow_commons::SharedMemoryConfiguration;
-use zenoh_flow_commons::{Result, RuntimeId};
+use zenoh_flow_commons::{Labels, Result, RuntimeId};
+use zenoh_flow_nodes::Hop;
 
 use crate::{loader::Loader, Extensions, Runtime};
 
@@ -33,11 +34,18 @@ pub struct RuntimeBuilder {
     name: Arc<str>,
     hlc: Option<HLC>,
     runtime_id: Option<RuntimeId>,
+    labels: Labels,
+    capacity: Option<usize>,
     #[cfg(feature = "zenoh")]
     session: Option<Session>,
     #[cfg(feature = "shared-memory")]
     shared_memory: Option<SharedMemoryConfiguration>,
     loader: Loader,
+    checkpoints_directory: Option<PathBuf>,
+    trace_messages: bool,
+    hops: Option<flume::Sender<Hop>>,
+    #[cfg(feature = "otlp")]
+    otlp_endpoint: Option<url::Url>,
 }
 
 impl RuntimeBuilder {
@@ -47,9 +55,16 @@ impl RuntimeBuilder {
             name: name.into().into(),
             hlc: None,
             runtime_id: None,
+            labels: Labels::default(),
+            capacity: None,
             #[cfg(feature = "zenoh")]
             session: None,
             loader: Loader::default(),
+            checkpoints_directory: None,
+            trace_messages: false,
+            hops: None,
+            #[cfg(feature = "otlp")]
+            otlp_endpoint: None,
         }
     }
 
@@ -86,6 +101,33 @@ impl RuntimeBuilder {
         Ok(self)
     }
 
+    /// Sets the [Labels] describing the Runtime.
+    ///
+    /// The labels are advertised to the other Zenoh-Flow runtimes, such that the nodes of a data flow can be placed on
+    /// the runtimes that match their selectors.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zenoh_flow_commons::Labels;
+    /// use zenoh_flow_runtime::Runtime;
+    ///
+    /// let builder = Runtime::builder("demo").labels(Labels::from([("arch", "aarch64"), ("site", "lab")]));
+    /// ```
+    pub fn labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Sets the maximum number of nodes the Runtime should manage, across all the data flow instances.
+    ///
+    /// The capacity is only taken into account when placing the nodes of a data flow: the Runtime does not refuse to
+    /// load nodes beyond it.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
     #[cfg(feature = "shared-memory")]
     pub fn shared_memory(mut self, shm: SharedMemoryConfiguration) -> Self {
         self.shared_memory = Some(shm);
@@ -131,6 +173,74 @@ impl RuntimeBuilder {
         self
     }
 
+    /// Sets the directory in which the [Checkpoint](crate::Checkpoint)s of the data flow instances are saved.
+    ///
+    /// Without a directory, checkpoints cannot be taken and the states of the nodes are not restored when a data flow
+    /// instance is loaded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zenoh_flow_runtime::Runtime;
+    ///
+    /// let builder = Runtime::builder("demo").checkpoints_directory("/var/zenoh-flow/checkpoints");
+    /// ```
+    pub fn checkpoints_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.checkpoints_directory = Some(directory.into());
+        self
+    }
+
+    /// Sets whether the messages sent by the nodes start a new trace when they do not already carry a
+    /// [TraceContext](zenoh_flow_nodes::prelude::TraceContext).
+    ///
+    /// Regardless of this setting, the trace contexts carried by the messages are always propagated: each node that
+    /// receives such a message opens a new span (a [Hop]), child of the span that sent it, and the messages it sends
+    /// during that `iteration` carry the context of this new span. The context is part of the frame exchanged between
+    /// runtimes, a trace thus follows the messages across the runtimes they traverse.
+    ///
+    /// The spans are emitted at the `debug` level through `tracing` and, if a sink was provided, sent to it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zenoh_flow_runtime::Runtime;
+    ///
+    /// let builder = Runtime::builder("demo").trace_messages(true);
+    /// ```
+    pub fn trace_messages(mut self, trace_messages: bool) -> Self {
+        self.trace_messages = trace_messages;
+        self
+    }
+
+    /// Sets the channel on which the [Hop]s of the sampled traces are sent once they are finished.
+    ///
+    /// If the channel is bounded and full, the hops are discarded.
+    pub fn trace_sink(mut self, hops: flume::Sender<Hop>) -> Self {
+        self.hops = Some(hops);
+        self
+    }
+
+    /// Sets the endpoint of the OpenTelemetry collector to which the [Hop]s of the sampled traces are exported, using
+    /// OTLP over HTTP with the JSON encoding.
+    ///
+    /// Only `http` endpoints are supported. If the endpoint has no path, the traces are sent to `/v1/traces`. This
+    /// setting takes precedence over the [trace_sink](RuntimeBuilder::trace_sink).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use zenoh_flow_runtime::Runtime;
+    ///
+    /// let builder = Runtime::builder("demo")
+    ///     .trace_messages(true)
+    ///     .otlp_endpoint("http://127.0.0.1:4318".parse().unwrap());
+    /// ```
+    #[cfg(feature = "otlp")]
+    pub fn otlp_endpoint(mut self, endpoint: url::Url) -> Self {
+        self.otlp_endpoint = Some(endpoint);
+        self
+    }
+
     /// Attempts to add the provided [Extensions] to the list of extensions supported by this Runtime.
     ///
     /// If previous extensions were already declared for the same file extension, the newly added extensions will
@@ -212,8 +322,10 @@ impl RuntimeBuilder {
     ///
     /// # Errors
     ///
-    /// This method can fail if the `zenoh` feature is enabled (it is by default), no [Session] was provided to the
-    /// builder and the creation of a Session failed.
+    /// This method can fail if:
+    /// - the `zenoh` feature is enabled (it is by default), no [Session] was provided to the builder and the creation of
+    ///   a Session failed,
+    /// - the `otlp` feature is enabled and the endpoint of the collector is not a valid `http` URL.
     ///
     /// # Example
     ///
@@ -247,11 +359,25 @@ impl RuntimeBuilder {
         #[cfg(not(feature = "zenoh"))]
         let runtime_id = self.runtime_id.unwrap_or_else(RuntimeId::rand);
         #[cfg(feature = "zenoh")]
-        let runtime_id = session.zid().into();
+        let runtime_id: RuntimeId = session.zid().into();
+
+        #[cfg(feature = "otlp")]
+        let hops = match self.otlp_endpoint {
+            Some(endpoint) => Some(crate::otlp::spawn(
+                endpoint,
+                self.name.clone(),
+                runtime_id.clone(),
+            )?),
+            None => self.hops,
+        };
+        #[cfg(not(feature = "otlp"))]
+        let hops = self.hops;
 
         Ok(Runtime {
             name: self.name,
             runtime_id,
+            labels: self.labels,
+            capacity: self.capacity,
             hlc: self
                 .hlc
                 .map(Arc::new)
@@ -259,6 +385,9 @@ impl RuntimeBuilder {
             #[cfg(feature = "zenoh")]
             session,
             loader: Mutex::new(self.loader),
+            checkpoints_directory: self.checkpoints_directory,
+            trace_messages: self.trace_messages,
+            hops,
             flows: RwLock::new(HashMap::new()),
         })
     }
@@ -23,17 +23,21 @@
 //   - load its library,
 //   - call its constructor with the correct parameters (i.e. only Inputs for a Sink, only Outputs for a Source).
 
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use anyhow::{bail, Context as _};
 use async_std::sync::RwLock;
 use libloading::Library;
 use url::Url;
 use zenoh_flow_commons::{NodeId, Result};
-use zenoh_flow_descriptors::{SinkVariant, SourceVariant};
+use zenoh_flow_descriptors::{InputDescriptor, SinkVariant, SourceVariant};
 use zenoh_flow_nodes::{
-    prelude::{Context, Inputs, Outputs},
-    OperatorFn, SinkFn, SourceFn,
+    prelude::{Context, Inputs, OutputRaw, Outputs, Statistics},
+    OperatorFn, SinkFn, SourceFn, Tracer,
 };
 use zenoh_flow_records::DataFlowRecord;
 
@@ -42,9 +46,17 @@ use super::Runtime;
 use crate::runners::builtin::zenoh::sink::ZenohSink;
 #[cfg(feature = "zenoh")]
 use crate::runners::builtin::zenoh::source::ZenohSource;
-use crate::{instance::DataFlowInstance, loader::NodeSymbol, runners::Runner, InstanceState};
+use crate::{
+    checkpoint,
+    instance::{DataFlowInstance, LinkChannels},
+    loader::NodeSymbol,
+    runners::{OutputPorts, Runner},
+    InstanceState,
+};
 
 pub(crate) type Channels = HashMap<NodeId, (Inputs, Outputs)>;
+/// The handles through which messages are injected into the injectable Inputs of the nodes managed by a runtime.
+pub(crate) type Injectors = HashMap<InputDescriptor, OutputRaw>;
 
 impl Runtime {
     /// Attempts to load the provided [DataFlowRecord], creating a new [DataFlowInstance] in this `Runtime`.
@@ -53,12 +65,16 @@ impl Runtime {
     /// nodes managed by this Runtime have been successfully loaded, the instance will be put in the
     /// [Loaded](InstanceState::Loaded) state.
     ///
+    /// If a [Checkpoint](crate::Checkpoint) of the same data flow instance was saved by this Runtime, the states of
+    /// the nodes are restored from the most recent one before the instance is put in the `Loaded` state.
+    ///
     /// # Errors
     ///
     /// This method can fail for the following reasons:
     /// - the data flow was not valid; more specifically, at least one link was connecting two nodes that are running on
     ///   different runtimes (the current one and another),
     /// - the runtime failed to load: an operator, a source, a sink,
+    /// - the runtime failed to restore the states of the nodes from the last checkpoint,
     /// - the runtime encountered an internal error:
     ///   - a channel was not created for a node,
     ///   - a Zenoh built-in source failed to declare its subscriber.
@@ -75,7 +91,10 @@ impl Runtime {
         // To achieve 2. when we want to load an instance we insert in `self.flows` a **locked** lock of the instance
         // we are trying to create.
         let instance_id = data_flow.instance_id().clone();
-        let instance = Arc::new(RwLock::new(DataFlowInstance::new(data_flow, &self.hlc)));
+        let instance = Arc::new(RwLock::new(DataFlowInstance::new(
+            data_flow,
+            self.hlc.clone(),
+        )));
         let mut instance_guard = instance.write().await;
 
         let mut flows_guard = self.flows.write().await;
@@ -101,82 +120,158 @@ impl Runtime {
         }
         // -----------------------------------
 
-        let mut runners = HashMap::<NodeId, Runner>::default();
         let data_flow = &instance_guard.record;
+        let assigned_nodes = data_flow
+            .mapping()
+            .get(&self.runtime_id)
+            .cloned()
+            .unwrap_or_default();
+        let mut links = LinkChannels::default();
 
         // NOTE: By wrapping all the calls in a named block we avoid having to separately call `map_err` and set the
         // data flow instance in a failed state.
         //
         // We process the `load_result` once at the end of the block.
         let load_result = 'load: {
-            let mut channels = match self.create_channels(data_flow) {
-                Ok(channels) => channels,
+            let (runners, injectors) = match self
+                .try_load_nodes(data_flow, &assigned_nodes, &mut links)
+                .await
+            {
+                Ok(loaded) => loaded,
                 Err(e) => break 'load Err(e),
             };
 
-            runners.extend(
-                match self.try_load_operators(data_flow, &mut channels).await {
-                    Ok(operators) => operators,
-                    Err(e) => break 'load Err(e),
-                },
-            );
-
-            runners.extend(
-                match self.try_load_sources(data_flow, &mut channels).await {
-                    Ok(sources) => sources,
-                    Err(e) => break 'load Err(e),
-                },
-            );
-
-            runners.extend(match self.try_load_sinks(data_flow, &mut channels).await {
-                Ok(sinks) => sinks,
-                Err(e) => break 'load Err(e),
-            });
-
-            #[cfg(feature = "zenoh")]
-            {
-                runners.extend(
-                    match self.try_load_receivers(data_flow, &mut channels).await {
-                        Ok(receivers) => receivers,
-                        Err(e) => break 'load Err(e),
-                    },
-                );
-                runners.extend(match self.try_load_senders(data_flow, &mut channels) {
-                    Ok(senders) => senders,
-                    Err(e) => break 'load Err(e),
-                });
+            if let Some(directory) = &self.checkpoints_directory {
+                if let Err(e) =
+                    checkpoint::try_restore_latest(directory, data_flow.instance_id(), &runners)
+                        .await
+                {
+                    break 'load Err(e);
+                }
             }
 
-            Ok(())
+            Ok((runners, injectors))
         };
 
-        if let Err(e) = load_result {
-            instance_guard.state =
-                InstanceState::Failed((self.hlc.new_timestamp(), format!("{e:?}")));
-            return Err(e);
-        }
+        let (runners, injectors) = match load_result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                instance_guard.state =
+                    InstanceState::Failed((self.hlc.new_timestamp(), format!("{e:?}")));
+                return Err(e);
+            }
+        };
 
         instance_guard.runners = runners;
+        instance_guard.injectors = injectors;
+        instance_guard.links = links;
         instance_guard.state = InstanceState::Loaded(self.hlc.new_timestamp());
 
         Ok(())
     }
 
-    /// Create all the channels for the provided `DataFlowRecord`.
+    /// Attempts to load the `assigned_nodes` of the provided [DataFlowRecord], returning their [Runners] and the
+    /// handles to inject messages into their injectable Inputs.
+    ///
+    /// The channels of the links are taken from, or stored in, `links` (see [create_channels]).
+    ///
+    /// # Errors
+    ///
+    /// This method can fail if the channels could not be created or if a node could not be loaded.
+    ///
+    /// [create_channels]: Runtime::create_channels()
+    pub(crate) async fn try_load_nodes(
+        &self,
+        record: &DataFlowRecord,
+        assigned_nodes: &HashSet<NodeId>,
+        links: &mut LinkChannels,
+    ) -> Result<(HashMap<NodeId, Runner>, Injectors)> {
+        let (mut channels, injectors) = self.create_channels(record, assigned_nodes, links)?;
+
+        let mut runners = HashMap::<NodeId, Runner>::default();
+        runners.extend(
+            self.try_load_operators(record, assigned_nodes, &mut channels)
+                .await?,
+        );
+        runners.extend(
+            self.try_load_sources(record, assigned_nodes, &mut channels)
+                .await?,
+        );
+        runners.extend(
+            self.try_load_sinks(record, assigned_nodes, &mut channels)
+                .await?,
+        );
+
+        #[cfg(feature = "zenoh")]
+        {
+            runners.extend(
+                self.try_load_receivers(record, assigned_nodes, &mut channels)
+                    .await?,
+            );
+            runners.extend(self.try_load_senders(record, assigned_nodes, &mut channels)?);
+        }
+
+        Ok((runners, injectors))
+    }
+
+    /// Create the channels of the `nodes` of the provided `DataFlowRecord`, as well as the handles to inject messages
+    /// into their Inputs declared injectable.
+    ///
+    /// The channels of all the links between the nodes managed by this runtime are kept in `links`: the channel of a
+    /// link that did not change is reused (alongside the messages it holds), the channel of a new or modified link is
+    /// created and the channels of the links that are no longer part of the record are removed.
     ///
     /// # Errors
     ///
     /// The only scenario in which this method fails is if we did not correctly processed the data flow descriptor and
     /// ended up having a link with nodes on two different runtimes.
-    fn create_channels(&self, record: &DataFlowRecord) -> Result<Channels> {
+    fn create_channels(
+        &self,
+        record: &DataFlowRecord,
+        nodes: &HashSet<NodeId>,
+        links: &mut LinkChannels,
+    ) -> Result<(Channels, Injectors)> {
         let nodes_runtime = match record.mapping().get(&self.runtime_id) {
-            Some(nodes) => nodes,
+            Some(nodes_runtime) => nodes_runtime,
             // NOTE: There is a possibility that the runtime that is orchestrating the deployment of the data flow will
             // not have to run any node. In which case, `record.mapping.get` will return nothing.
-            None => return Ok(HashMap::default()),
+            None => {
+                links.clear();
+                return Ok((HashMap::default(), HashMap::default()));
+            }
+        };
+
+        let injectable = record
+            .operators()
+            .values()
+            .map(|operator| (&operator.id, &operator.inputs))
+            .chain(record.sinks().values().map(|sink| (&sink.id, &sink.inputs)))
+            .flat_map(|(node_id, inputs)| {
+                inputs
+                    .iter()
+                    .filter(|input| input.injectable)
+                    .map(move |input| InputDescriptor {
+                        node: node_id.clone(),
+                        input: input.id.clone(),
+                    })
+            })
+            .collect::<HashSet<_>>();
+
+        let new_entry = |node_id: &NodeId| {
+            let tracer = Arc::new(
+                Tracer::new(node_id.clone())
+                    .with_start_traces(self.trace_messages)
+                    .with_sink(self.hops.clone()),
+            );
+            (
+                Inputs::default().with_tracer(tracer.clone()),
+                Outputs::new(self.hlc.clone()).with_tracer(tracer),
+            )
         };
 
         let mut channels = HashMap::default();
+        let mut injectors = HashMap::<NodeId, Outputs>::default();
+        let mut local_links = HashSet::new();
         for link in record.links() {
             if !nodes_runtime.contains(&link.from.node) || !nodes_runtime.contains(&link.to.node) {
                 #[cfg(feature = "zenoh")]
@@ -215,25 +310,59 @@ The problematic link is:
                 }
             }
 
-            let (tx, rx) = flume::unbounded();
-            let (_, outputs) = channels
-                .entry(link.from.node.clone())
-                .or_insert_with(|| (Inputs::default(), Outputs::new(self.hlc.clone())));
-            outputs.insert(link.from.output.clone(), tx);
+            let key = (link.from.clone(), link.to.clone());
+            if !matches!(links.get(&key), Some((channel_link, _, _)) if channel_link == link) {
+                let (tx, rx) = zenoh_flow_nodes::link_channel(link.queue.as_ref());
+                links.insert(key.clone(), (link.clone(), tx, rx));
+            }
+            let (_, tx, rx) = &links[&key];
+            local_links.insert(key);
+
+            if nodes.contains(&link.from.node) {
+                let (_, outputs) = channels
+                    .entry(link.from.node.clone())
+                    .or_insert_with(|| new_entry(&link.from.node));
+                outputs.insert(link.from.output.clone(), tx.clone());
+            }
+
+            if nodes.contains(&link.to.node) {
+                // NOTE: The messages injected into an Input go through the same channel as the ones sent by the
+                // upstream node, they are thus subject to the same queue configuration.
+                if injectable.contains(&link.to) {
+                    injectors
+                        .entry(link.to.node.clone())
+                        .or_insert_with(|| Outputs::new(self.hlc.clone()))
+                        .insert(link.to.input.clone(), tx.clone());
+                }
 
-            let (inputs, _) = channels
-                .entry(link.to.node.clone())
-                .or_insert_with(|| (Inputs::default(), Outputs::new(self.hlc.clone())));
-            inputs.insert(link.to.input.clone(), rx);
+                let (inputs, _) = channels
+                    .entry(link.to.node.clone())
+                    .or_insert_with(|| new_entry(&link.to.node));
+                inputs.insert(link.to.input.clone(), rx.clone());
+            }
         }
 
-        Ok(channels)
+        links.retain(|key, _| local_links.contains(key));
+
+        // The injected messages are relayed: they carry no sequence number, which would otherwise be mistaken for lost
+        // or duplicated messages by the Input.
+        let injectors = injectable
+            .into_iter()
+            .filter_map(|input| {
+                let injector = injectors
+                    .get_mut(&input.node)?
+                    .take(input.input.as_ref())?
+                    .relay();
+                Some((input, injector))
+            })
+            .collect();
+
+        Ok((channels, injectors))
     }
 
     /// Attempts to load the Operators from the provided [DataFlowRecord], returning a list of [Runners].
     ///
-    /// This method will first filter the Operators from the [DataFlowRecord], keeping only those assigned to the
-    /// current runtime.
+    /// This method will first filter the Operators from the [DataFlowRecord], keeping only the `assigned_nodes`.
     ///
     /// # Errors
     ///
@@ -244,13 +373,10 @@ The problematic link is:
     async fn try_load_operators(
         &self,
         record: &DataFlowRecord,
+        assigned_nodes: &HashSet<NodeId>,
         channels: &mut Channels,
     ) -> Result<HashMap<NodeId, Runner>> {
         let mut runners = HashMap::default();
-        let assigned_nodes = match record.mapping().get(&self.runtime_id) {
-            Some(nodes) => nodes,
-            None => return Ok(HashMap::default()),
-        };
 
         for (operator_id, operator) in record
             .operators()
@@ -266,6 +392,9 @@ The channels for the Inputs and Outputs of Operator < {} > were not created.
         "#,
                 &operator_id
             ))?;
+            let statistics = Statistics::new(&inputs, &outputs);
+            let tracer = inputs.tracer();
+            let ports = OutputPorts::new(&outputs);
 
             let (constructor, path, library) = self
                 .try_load_constructor::<OperatorFn>(&operator.library, &NodeSymbol::Operator)
@@ -277,7 +406,9 @@ The channels for the Inputs and Outputs of Operator < {} > were not created.
                 self.runtime_id.clone(),
                 path,
                 operator_id.clone(),
-            );
+            )
+            .with_statistics(statistics.clone())
+            .with_hlc(self.hlc.clone());
 
             let operator_node = (constructor)(
                 context.clone(),
@@ -288,7 +419,16 @@ The channels for the Inputs and Outputs of Operator < {} > were not created.
             .await?;
             runners.insert(
                 operator_id.clone(),
-                Runner::new(operator_id.clone(), operator_node, Some(library)),
+                Runner::new(
+                    operator_id.clone(),
+                    operator_node,
+                    Some(library),
+                    statistics,
+                )
+                .with_period(operator.period)
+                .with_restart_policy(operator.restart)
+                .with_tracer(tracer)
+                .with_outputs(ports),
             );
         }
 
@@ -297,8 +437,7 @@ The channels for the Inputs and Outputs of Operator < {} > were not created.
 
     /// Attempts to load the Sources from the provided [DataFlowRecord], returning a list of [Runners].
     ///
-    /// This method will first filter the Sources from the [DataFlowRecord], keeping only those assigned to the
-    /// current runtime.
+    /// This method will first filter the Sources from the [DataFlowRecord], keeping only the `assigned_nodes`.
     ///
     /// # Errors
     ///
@@ -310,13 +449,10 @@ The channels for the Inputs and Outputs of Operator < {} > were not created.
     async fn try_load_sources(
         &self,
         record: &DataFlowRecord,
+        assigned_nodes: &HashSet<NodeId>,
         channels: &mut Channels,
     ) -> Result<HashMap<NodeId, Runner>> {
         let mut runners = HashMap::default();
-        let assigned_nodes = match record.mapping().get(&self.runtime_id) {
-            Some(nodes) => nodes,
-            None => return Ok(HashMap::default()),
-        };
 
         for (source_id, source) in record
             .sources()
@@ -325,13 +461,16 @@ The channels for the Inputs and Outputs of Operator < {} > were not created.
         {
             tracing::debug!("Loading source: {source_id}");
 
-            let (_, outputs) = channels.remove(source_id).context(format!(
+            let (inputs, outputs) = channels.remove(source_id).context(format!(
                 r#"
 Zenoh-Flow encountered a fatal internal error.
 The channels for the Outputs of Source < {} > were not created.
         "#,
                 &source_id
             ))?;
+            let statistics = Statistics::new(&inputs, &outputs);
+            let tracer = inputs.tracer();
+            let ports = OutputPorts::new(&outputs);
 
             let runner = match &source.source {
                 SourceVariant::Library(uri) => {
@@ -345,13 +484,17 @@ The channels for the Outputs of Source < {} > were not created.
                         self.runtime_id.clone(),
                         path,
                         source_id.clone(),
-                    );
+                    )
+                    .with_statistics(statistics.clone())
+                    .with_hlc(self.hlc.clone());
 
                     let source_node =
                         (constructor)(context.clone(), source.configuration.clone(), outputs)
                             .await?;
 
-                    Runner::new(source.id.clone(), source_node, Some(library))
+                    Runner::new(source.id.clone(), source_node, Some(library), statistics)
+                        .with_period(source.period)
+                        .with_restart_policy(source.restart)
                 }
                 #[cfg(not(feature = "zenoh"))]
                 SourceVariant::Zenoh(_) => {
@@ -364,14 +507,23 @@ Maybe change the features in the Cargo.toml?
                 }
                 #[cfg(feature = "zenoh")]
                 SourceVariant::Zenoh(key_exprs) => {
-                    let dyn_source =
-                        ZenohSource::try_new(&source.id, self.session.clone(), key_exprs, outputs)
-                            .await?;
-                    Runner::new(source.id.clone(), Arc::new(dyn_source), None)
+                    let dyn_source = ZenohSource::try_new(
+                        &source.id,
+                        self.session.clone(),
+                        key_exprs,
+                        outputs,
+                        statistics.clone(),
+                    )
+                    .await?;
+                    Runner::new(source.id.clone(), Arc::new(dyn_source), None, statistics)
+                        .with_restart_policy(source.restart)
                 }
             };
 
-            runners.insert(source_id.clone(), runner);
+            runners.insert(
+                source_id.clone(),
+                runner.with_tracer(tracer).with_outputs(ports),
+            );
         }
 
         Ok(runners)
@@ -379,8 +531,7 @@ Maybe change the features in the Cargo.toml?
 
     /// Attempts to load the Sinks from the provided [DataFlowRecord], returning a list of [Runners].
     ///
-    /// This method will first filter the Sinks from the [DataFlowRecord], keeping only those assigned to the
-    /// current runtime.
+    /// This method will first filter the Sinks from the [DataFlowRecord], keeping only the `assigned_nodes`.
     ///
     /// # Errors
     ///
@@ -392,13 +543,10 @@ Maybe change the features in the Cargo.toml?
     async fn try_load_sinks(
         &self,
         record: &DataFlowRecord,
+        assigned_nodes: &HashSet<NodeId>,
         channels: &mut Channels,
     ) -> Result<HashMap<NodeId, Runner>> {
         let mut runners = HashMap::default();
-        let assigned_nodes = match record.mapping().get(&self.runtime_id) {
-            Some(nodes) => nodes,
-            None => return Ok(HashMap::default()),
-        };
 
         for (sink_id, sink) in record
             .sinks()
@@ -407,13 +555,16 @@ Maybe change the features in the Cargo.toml?
         {
             tracing::debug!("Loading sink: {sink_id}");
 
-            let (inputs, _) = channels.remove(sink_id).context(format!(
+            let (inputs, outputs) = channels.remove(sink_id).context(format!(
                 r#"
 Zenoh-Flow encountered a fatal internal error.
 The channels for the Inputs of Sink < {} > were not created.
         "#,
                 &sink_id
             ))?;
+            let statistics = Statistics::new(&inputs, &outputs);
+            let tracer = inputs.tracer();
+            let ports = OutputPorts::new(&outputs);
 
             let runner = match &sink.sink {
                 SinkVariant::Library(uri) => {
@@ -427,12 +578,16 @@ The channels for the Inputs of Sink < {} > were not created.
                         self.runtime_id.clone(),
                         library_path,
                         sink_id.clone(),
-                    );
+                    )
+                    .with_statistics(statistics.clone())
+                    .with_hlc(self.hlc.clone());
 
                     let sink_node =
                         (constructor)(context.clone(), sink.configuration.clone(), inputs).await?;
 
-                    Runner::new(sink.id.clone(), sink_node, Some(library))
+                    Runner::new(sink.id.clone(), sink_node, Some(library), statistics)
+                        .with_period(sink.period)
+                        .with_restart_policy(sink.restart)
                 }
                 #[cfg(not(feature = "zenoh"))]
                 SinkVariant::Zenoh(_) => {
@@ -455,11 +610,15 @@ Maybe change the features in the Cargo.toml?
                     )
                     .await?;
 
-                    Runner::new(sink_id.clone(), Arc::new(zenoh_sink), None)
+                    Runner::new(sink_id.clone(), Arc::new(zenoh_sink), None, statistics)
+                        .with_restart_policy(sink.restart)
                 }
             };
 
-            runners.insert(sink_id.clone(), runner);
+            runners.insert(
+                sink_id.clone(),
+                runner.with_tracer(tracer).with_outputs(ports),
+            );
         }
 
         Ok(runners)
@@ -467,8 +626,7 @@ Maybe change the features in the Cargo.toml?
 
     /// Attempts to load the Zenoh Receivers from the provided [DataFlowRecord], returning a list of [Runners].
     ///
-    /// This method will first filter the Receivers from the [DataFlowRecord], keeping only those assigned to the
-    /// current runtime.
+    /// This method will first filter the Receivers from the [DataFlowRecord], keeping only the `assigned_nodes`.
     ///
     /// # Errors
     ///
@@ -479,36 +637,42 @@ Maybe change the features in the Cargo.toml?
     async fn try_load_receivers(
         &self,
         record: &DataFlowRecord,
+        assigned_nodes: &HashSet<NodeId>,
         channels: &mut Channels,
     ) -> Result<HashMap<NodeId, Runner>> {
         use crate::runners::connectors::ZenohConnectorReceiver;
 
         let mut runners = HashMap::new();
-        let assigned_nodes = match record.mapping().get(&self.runtime_id) {
-            Some(nodes) => nodes,
-            None => return Ok(HashMap::default()),
-        };
 
         for (receiver_id, receiver) in record
             .receivers()
             .iter()
             .filter(|(_, receiver)| assigned_nodes.contains(&receiver.id()))
         {
-            let (_, outputs) = channels.remove(receiver_id).context(format!(
+            let (inputs, outputs) = channels.remove(receiver_id).context(format!(
                 r#"
 Zenoh-Flow encountered a fatal internal error.
 The channels for the Outputs of Connector Receiver < {} > were not created.
         "#,
                 receiver_id
             ))?;
+            let statistics = Statistics::new(&inputs, &outputs);
+            let tracer = inputs.tracer();
+            let ports = OutputPorts::new(&outputs);
 
-            let runner =
-                ZenohConnectorReceiver::try_new(self.session.clone(), receiver.clone(), outputs)
-                    .await?;
+            let runner = ZenohConnectorReceiver::try_new(
+                self.session.clone(),
+                receiver.clone(),
+                outputs,
+                statistics.clone(),
+            )
+            .await?;
 
             runners.insert(
                 receiver_id.clone(),
-                Runner::new(receiver_id.clone(), Arc::new(runner), None),
+                Runner::new(receiver_id.clone(), Arc::new(runner), None, statistics)
+                    .with_tracer(tracer)
+                    .with_outputs(ports),
             );
         }
 
@@ -517,8 +681,7 @@ The channels for the Outputs of Connector Receiver < {} > were not created.
 
     /// Attempts to load the Zenoh Senders from the provided [DataFlowRecord], returning a list of [Runners].
     ///
-    /// This method will first filter the Senders from the [DataFlowRecord], keeping only those assigned to the
-    /// current runtime.
+    /// This method will first filter the Senders from the [DataFlowRecord], keeping only the `assigned_nodes`.
     ///
     /// # Errors
     ///
@@ -529,28 +692,28 @@ The channels for the Outputs of Connector Receiver < {} > were not created.
     fn try_load_senders(
         &self,
         record: &DataFlowRecord,
+        assigned_nodes: &HashSet<NodeId>,
         channels: &mut Channels,
     ) -> Result<HashMap<NodeId, Runner>> {
         use crate::runners::connectors::ZenohConnectorSender;
 
         let mut runners = HashMap::new();
-        let assigned_nodes = match record.mapping().get(&self.runtime_id) {
-            Some(nodes) => nodes,
-            None => return Ok(HashMap::default()),
-        };
 
         for (sender_id, sender) in record
             .senders()
             .iter()
             .filter(|(_, sender)| assigned_nodes.contains(&sender.id()))
         {
-            let (inputs, _) = channels.remove(sender_id).context(format!(
+            let (inputs, outputs) = channels.remove(sender_id).context(format!(
                 r#"
 Zenoh-Flow encountered a fatal internal error.
 The channels for the Inputs of Connector Sender < {} > were not created.
         "#,
                 sender_id
             ))?;
+            let statistics = Statistics::new(&inputs, &outputs);
+            let tracer = inputs.tracer();
+            let ports = OutputPorts::new(&outputs);
 
             let runner = ZenohConnectorSender::try_new(
                 self.session.clone(),
@@ -562,7 +725,9 @@ The channels for the Inputs of Connector Sender < {} > were not created.
 
             runners.insert(
                 sender_id.clone(),
-                Runner::new(sender_id.clone(), Arc::new(runner), None),
+                Runner::new(sender_id.clone(), Arc::new(runner), None, statistics)
+                    .with_tracer(tracer)
+                    .with_outputs(ports),
             );
         }
 
@@ -16,11 +16,14 @@ mod builder;
 pub use self::builder::RuntimeBuilder;
 
 mod load;
+mod update;
 
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
+    path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail};
@@ -31,12 +34,17 @@ use uhlc::HLC;
 use zenoh::Session;
 #[cfg(feature = "shared-memory")]
 use zenoh_flow_commons::SharedMemoryConfiguration;
-use zenoh_flow_commons::{InstanceId, Result, RuntimeId};
+use zenoh_flow_commons::{Configuration, InstanceId, Labels, NodeId, Result, RuntimeId};
+use zenoh_flow_descriptors::{InputDescriptor, OutputDescriptor};
+use zenoh_flow_nodes::{prelude::Message, Hop};
 use zenoh_flow_records::DataFlowRecord;
 
 use crate::{
+    checkpoint::{self, Checkpoint},
     instance::{DataFlowInstance, InstanceStatus},
     loader::Loader,
+    metrics::InstanceMetrics,
+    recording::{Recording, RecordingSummary, ReplayMode},
     InstanceState,
 };
 
@@ -51,12 +59,17 @@ use crate::{
 pub struct Runtime {
     pub(crate) name: Arc<str>,
     pub(crate) runtime_id: RuntimeId,
+    pub(crate) labels: Labels,
+    pub(crate) capacity: Option<usize>,
     pub(crate) hlc: Arc<HLC>,
     #[cfg(feature = "zenoh")]
     pub(crate) session: Session,
     #[cfg(feature = "shared-memory")]
     pub(crate) shared_memory: SharedMemoryConfiguration,
     pub(crate) loader: Mutex<Loader>,
+    pub(crate) checkpoints_directory: Option<PathBuf>,
+    pub(crate) trace_messages: bool,
+    pub(crate) hops: Option<flume::Sender<Hop>>,
     flows: RwLock<HashMap<InstanceId, Arc<RwLock<DataFlowInstance>>>>,
 }
 
@@ -112,6 +125,38 @@ impl Runtime {
         self.name.clone()
     }
 
+    /// Returns the [Labels] describing this Zenoh-Flow runtime.
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// Returns the maximum number of nodes this Zenoh-Flow runtime should manage, if it was set.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Returns the number of nodes this Zenoh-Flow runtime manages, across all the data flow instances.
+    ///
+    /// The connectors, i.e. the Senders and Receivers generated to connect the runtimes, are not counted.
+    pub async fn assigned_nodes(&self) -> usize {
+        let mut count = 0;
+        for instance in self.flows.read().await.values() {
+            let instance_guard = instance.read().await;
+            let record = &instance_guard.record;
+            if let Some(nodes) = record.mapping().get(&self.runtime_id) {
+                count += nodes
+                    .iter()
+                    .filter(|&node_id| {
+                        !record.senders().contains_key(node_id)
+                            && !record.receivers().contains_key(node_id)
+                    })
+                    .count();
+            }
+        }
+
+        count
+    }
+
     /// Returns a shared pointer over the [HLC] used by this Runtime.
     pub fn hlc(&self) -> Arc<HLC> {
         self.hlc.clone()
@@ -125,13 +170,60 @@ impl Runtime {
             let instance = instance_lck.read().await;
             states.insert(
                 instance_id.clone(),
-                (instance.name().clone(), instance.state().clone()),
+                (instance.name().clone(), instance.state()),
             );
         }
 
         states
     }
 
+    /// Marks the runtime `runtime_id` as lost in all the data flow instances it is involved in, returning their
+    /// identifier.
+    ///
+    /// While a runtime is lost, the running instances it is involved in are reported as
+    /// [Disconnected](InstanceState::Disconnected). Marking an already lost runtime does nothing.
+    pub async fn mark_runtime_lost(&self, runtime_id: &RuntimeId) -> Vec<InstanceId> {
+        let mut instances = Vec::default();
+        for (instance_id, instance) in self.flows.read().await.iter() {
+            let mut instance_guard = instance.write().await;
+            if instance_guard.record.mapping().contains_key(runtime_id) {
+                instance_guard
+                    .lost_runtimes
+                    .entry(runtime_id.clone())
+                    .or_insert_with(|| self.hlc.new_timestamp());
+                instances.push(instance_id.clone());
+            }
+        }
+
+        instances
+    }
+
+    /// Marks the runtime `runtime_id`, previously [lost](Runtime::mark_runtime_lost()), as recovered in all the data
+    /// flow instances, returning the identifier of the instances where it was lost.
+    pub async fn mark_runtime_recovered(&self, runtime_id: &RuntimeId) -> Vec<InstanceId> {
+        let mut instances = Vec::default();
+        for (instance_id, instance) in self.flows.read().await.iter() {
+            if instance
+                .write()
+                .await
+                .lost_runtimes
+                .remove(runtime_id)
+                .is_some()
+            {
+                instances.push(instance_id.clone());
+            }
+        }
+
+        instances
+    }
+
+    /// Returns the number of shared libraries, containing the implementation of nodes, currently loaded by this Runtime.
+    ///
+    /// A library is unloaded once no data flow instance uses any of the nodes it contains.
+    pub async fn loaded_libraries(&self) -> usize {
+        self.loader.lock().await.libraries.len()
+    }
+
     /// Returns a reference over the Zenoh [session](Session) used by this Runtime.
     #[cfg(feature = "zenoh")]
     pub fn session(&self) -> &Session {
@@ -172,6 +264,16 @@ impl Runtime {
         None
     }
 
+    /// Returns the [metrics](InstanceMetrics) of the nodes of the provided data flow instance or [None] if this runtime
+    /// does not manage this instance.
+    pub async fn get_instance_metrics(&self, id: &InstanceId) -> Option<InstanceMetrics> {
+        if let Some(instance) = self.flows.read().await.get(id) {
+            return Some(instance.read().await.metrics(&self.runtime_id));
+        }
+
+        None
+    }
+
     /// Tries to retrieve the [DataFlowInstance] matching the provided [id](InstanceId) from the Zenoh-Flow runtime.
     ///
     /// # Errors
@@ -222,6 +324,10 @@ impl Runtime {
 
     /// Attempts to abort the [DataFlowInstance] identified by the provided `id`.
     ///
+    /// If a `graceful` timeout is provided, the instance is [stopped](DataFlowInstance::stop()) instead: the Sources are
+    /// stopped and the messages in flight are processed by the other nodes, which then have their [on_stop] hook
+    /// called. Only the nodes still processing messages after the timeout are aborted.
+    ///
     /// Note that this method is idempotent: calling it on an already aborted data flow will do nothing.
     ///
     /// # Errors
@@ -229,23 +335,302 @@ impl Runtime {
     /// This method can fail for the following reasons:
     /// - no data flow with the provided id was found,
     /// - the data flow is in a failed state.
+    ///
+    /// [on_stop]: zenoh_flow_nodes::prelude::Node::on_stop()
     #[tracing::instrument(name = "abort", skip(self, id), fields(instance = %id))]
-    pub async fn try_abort_instance(&self, id: &InstanceId) -> Result<()> {
+    pub async fn try_abort_instance(
+        &self,
+        id: &InstanceId,
+        graceful: Option<Duration>,
+    ) -> Result<()> {
         let instance = self.try_get_instance(id).await?;
 
-        if !matches!(instance.read().await.state(), &InstanceState::Running(_)) {
+        if !matches!(
+            instance.read().await.state(),
+            InstanceState::Running(_) | InstanceState::Degraded(_) | InstanceState::Disconnected(_)
+        ) {
             return Ok(());
         }
 
         let mut instance_guard = instance.write().await;
 
-        instance_guard.abort(&self.hlc).await;
-
-        tracing::info!("aborted");
+        match graceful {
+            Some(timeout) => {
+                instance_guard.stop(&self.hlc, timeout).await;
+                tracing::info!("stopped");
+            }
+            None => {
+                instance_guard.abort(&self.hlc).await;
+                tracing::info!("aborted");
+            }
+        }
 
         Ok(())
     }
 
+    /// Attempts to take a [Checkpoint] of the [DataFlowInstance] identified by the provided `id`, waiting at most
+    /// `timeout` for the messages in flight to be processed.
+    ///
+    /// The checkpoint is saved in the directory provided to the [RuntimeBuilder] and will be restored the next time
+    /// the same data flow instance is loaded by this runtime.
+    ///
+    /// Note that the checkpoint only concerns the nodes managed by this runtime.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no directory to store the checkpoints was provided to the [RuntimeBuilder],
+    /// - no data flow with the provided id was found,
+    /// - the data flow is in a failed state,
+    /// - the nodes did not reach a consistent state before the `timeout`,
+    /// - the [snapshot] method of one of the nodes failed,
+    /// - the checkpoint could not be saved.
+    ///
+    /// [snapshot]: zenoh_flow_nodes::prelude::Node::snapshot()
+    #[tracing::instrument(name = "checkpoint", skip(self, id), fields(instance = %id))]
+    pub async fn try_checkpoint_instance(
+        &self,
+        id: &InstanceId,
+        timeout: Duration,
+    ) -> Result<Checkpoint> {
+        let Some(directory) = &self.checkpoints_directory else {
+            bail!("No directory to store the checkpoints was provided to this runtime");
+        };
+
+        let instance = self.try_get_instance(id).await?;
+        let checkpoint = instance
+            .write()
+            .await
+            .checkpoint(&self.hlc, timeout)
+            .await?;
+        let path = checkpoint.try_save(directory).await?;
+
+        tracing::info!("checkpoint saved in < {} >", path.display());
+
+        Ok(checkpoint)
+    }
+
+    /// Returns the [Checkpoint]s of the data flow instance identified by the provided `id`, sorted chronologically.
+    ///
+    /// The data flow instance does not have to be loaded: the checkpoints of deleted instances are also kept.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no directory to store the checkpoints was provided to the [RuntimeBuilder],
+    /// - a checkpoint could not be read.
+    pub async fn try_list_checkpoints(&self, id: &InstanceId) -> Result<Vec<Checkpoint>> {
+        let Some(directory) = &self.checkpoints_directory else {
+            bail!("No directory to store the checkpoints was provided to this runtime");
+        };
+
+        let mut checkpoints = Vec::default();
+        for path in checkpoint::try_list(directory, id).await? {
+            checkpoints.push(Checkpoint::try_load(&path).await?);
+        }
+
+        Ok(checkpoints)
+    }
+
+    /// Attempts to tap the `output` of the [DataFlowInstance] identified by the provided `id`: a copy of every message
+    /// sent on it is sent on the returned channel, bounded by `capacity`.
+    ///
+    /// [None] is returned if the node of the `output` is not managed by this runtime.
+    ///
+    /// The tap does not slow down the node: the messages that do not fit in the channel are discarded. It is removed
+    /// as soon as the returned channel is dropped.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the data flow is in a failed state.
+    pub async fn try_tap_output(
+        &self,
+        id: &InstanceId,
+        output: &OutputDescriptor,
+        capacity: usize,
+    ) -> Result<Option<flume::Receiver<Message>>> {
+        let instance = self.try_get_instance(id).await?;
+        let mirrors = instance.read().await.mirrors(output);
+
+        Ok(mirrors.map(|mirrors| mirrors.attach(capacity)))
+    }
+
+    /// Attempts to reconfigure the node `node_id` of the [DataFlowInstance] identified by the provided `id`, merging the
+    /// `patch` into its [Configuration] (the keys of the patch overwriting the existing ones). Returns the updated
+    /// configuration.
+    ///
+    /// If the node is managed by this runtime, its `on_configure` hook is called with the updated configuration. In all
+    /// cases, the configuration is updated in the [DataFlowRecord] of the instance held by this runtime.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the data flow is in a failed state,
+    /// - the data flow has no Source, Operator or Sink `node_id`,
+    /// - the patch is not a set of `(key, value)` pairs,
+    /// - the node refused the new configuration.
+    #[tracing::instrument(name = "reconfigure", skip(self, id, patch), fields(instance = %id))]
+    pub async fn try_reconfigure_node(
+        &self,
+        id: &InstanceId,
+        node_id: &NodeId,
+        patch: Configuration,
+    ) -> Result<Configuration> {
+        let instance = self.try_get_instance(id).await?;
+        let configuration = instance.write().await.reconfigure(node_id, patch).await?;
+        tracing::info!("reconfigured < {} >", node_id);
+
+        Ok(configuration)
+    }
+
+    /// Attempts to inject, into the `input` of the [DataFlowInstance] identified by the provided `id`, a message carrying
+    /// the `payload` and stamped with a fresh timestamp.
+    ///
+    /// `false` is returned if the node of the `input` is not managed by this runtime.
+    ///
+    /// The message goes through the channel of the link that points to the `input`: if the queue of the link is full,
+    /// its policy applies.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the data flow is not running,
+    /// - the `input` was not declared injectable in the descriptor of its node,
+    /// - the message could not be sent.
+    #[tracing::instrument(name = "inject", skip(self, id, payload), fields(instance = %id))]
+    pub async fn try_inject(
+        &self,
+        id: &InstanceId,
+        input: &InputDescriptor,
+        payload: Vec<u8>,
+    ) -> Result<bool> {
+        let instance = self.try_get_instance(id).await?;
+        // NOTE: The lock on the instance is released before sending, which could block depending on the queue.
+        let injector = instance.read().await.injector(input)?;
+
+        match injector {
+            Some(injector) => {
+                injector.send(payload, None).await?;
+                tracing::debug!("injected a message into < {} >", input);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Attempts to start recording, in the file at `path`, the messages sent on the `outputs` of the [DataFlowInstance]
+    /// identified by the provided `id`. Returns the number of Outputs recorded.
+    ///
+    /// Only the Outputs of the nodes managed by this runtime are recorded: if none of the `outputs` is, nothing is
+    /// recorded and no file is created. The recording lasts until it is [stopped](Runtime::try_stop_recording()).
+    ///
+    /// A message is recorded with its timestamp and its payload, serialised if needed. Watermarks are not recorded.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the data flow is in a failed state,
+    /// - a recording of the data flow is already in progress,
+    /// - the file could not be created.
+    #[tracing::instrument(name = "record", skip(self, id, outputs, path), fields(instance = %id))]
+    pub async fn try_start_recording(
+        &self,
+        id: &InstanceId,
+        outputs: &[OutputDescriptor],
+        path: impl Into<PathBuf>,
+    ) -> Result<usize> {
+        let path = path.into();
+        let instance = self.try_get_instance(id).await?;
+        let count = instance
+            .write()
+            .await
+            .start_recording(path.clone(), outputs)
+            .await?;
+
+        if count > 0 {
+            tracing::info!("recording {} output(s) in < {} >", count, path.display());
+        }
+
+        Ok(count)
+    }
+
+    /// Attempts to stop the recording of the [DataFlowInstance] identified by the provided `id`, returning its
+    /// [summary](RecordingSummary) or [None] if no recording was in progress.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the messages recorded could not be written.
+    #[tracing::instrument(name = "stop-recording", skip(self, id), fields(instance = %id))]
+    pub async fn try_stop_recording(&self, id: &InstanceId) -> Result<Option<RecordingSummary>> {
+        let instance = self
+            .flows
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or(DataFlowErr::NotFound)?;
+
+        let summary = instance
+            .write()
+            .await
+            .stop_recording(&self.runtime_id)
+            .await?;
+
+        if let Some(summary) = &summary {
+            tracing::info!(
+                "recorded {} message(s) in < {} >",
+                summary.messages,
+                summary.path.display()
+            );
+        }
+
+        Ok(summary)
+    }
+
+    /// Attempts to start the [DataFlowInstance] identified by the provided `id`, replacing its Sources by the
+    /// [Recording]. Returns the number of messages that will be replayed.
+    ///
+    /// The nodes that are not Sources are started while the messages recorded on the Outputs of the Sources managed by
+    /// this runtime are sent in their place, with fresh timestamps, following the [ReplayMode]. The messages recorded
+    /// on other Outputs are skipped.
+    ///
+    /// Once the replay is over, the instance keeps running. It can be aborted as usual.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the data flow is in a failed state,
+    /// - the data flow is not loaded or aborted,
+    /// - the [on_resume] method of one of the nodes failed.
+    ///
+    /// [on_resume]: zenoh_flow_nodes::prelude::Node::on_resume()
+    #[tracing::instrument(name = "replay", skip(self, id, recording), fields(instance = %id))]
+    pub async fn try_replay_instance(
+        &self,
+        id: &InstanceId,
+        recording: Recording,
+        mode: ReplayMode,
+    ) -> Result<u64> {
+        let instance = self.try_get_instance(id).await?;
+        let count = instance
+            .write()
+            .await
+            .replay(&self.hlc, recording, mode)
+            .await?;
+
+        tracing::info!("replaying {} message(s)", count);
+
+        Ok(count)
+    }
+
     /// Attempts to delete the [DataFlowInstance] identified by the provided `id`.
     ///
     /// # Errors
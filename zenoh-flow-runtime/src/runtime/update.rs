@@ -0,0 +1,209 @@
+//
+// Copyright © 2021 ZettaScale Technology <contact@zettascale.tech>
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// This file centralizes all the logic regarding the UPDATE of the graph of a data flow instance loaded by a Runtime.
+//
+// The entry point is the method: `try_update_instance`. It computes the updated record and compares, for each node
+// managed by the Runtime, its descriptor and links in both records:
+// - the nodes that are identical are left untouched,
+// - the nodes that were added, or that differ, are loaded -- reusing the channels of the links that did not change,
+// - the nodes that were removed, or that differ, are stopped and dropped.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use zenoh_flow_commons::{InstanceId, NodeId, Result, RuntimeId};
+use zenoh_flow_descriptors::{FlattenedDataFlowUpdate, LinkDescriptor};
+use zenoh_flow_records::DataFlowRecord;
+
+use super::Runtime;
+use crate::InstanceState;
+
+/// How long the nodes that are removed, or re-created, have to finish their current `iteration` before being aborted.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Runtime {
+    /// Attempts to apply the [update](FlattenedDataFlowUpdate) to the graph of the
+    /// [DataFlowInstance](crate::DataFlowInstance) identified by the provided `id`, without tearing it down. Returns the
+    /// updated [DataFlowRecord].
+    ///
+    /// The nodes added without a mapping are assigned to the `default_runtime`. For the records of all the runtimes
+    /// involved to stay identical, they must all be given the same update and default runtime.
+    ///
+    /// Only the nodes managed by this runtime that are affected by the update are processed:
+    /// - the nodes added are loaded,
+    /// - the nodes removed are stopped and dropped,
+    /// - the nodes whose descriptor or links changed are re-created: the new node is loaded, the previous one is
+    ///   stopped and its state, as returned by its `snapshot` method, is restored in the new one,
+    /// - the other nodes are left untouched: they keep running and keep their state.
+    ///
+    /// The channels of the links that did not change are reused, alongside the messages they hold. If the instance is
+    /// running, the nodes loaded are started.
+    ///
+    /// Note that the taps and the recording attached to the Outputs of a re-created node are detached.
+    ///
+    /// # Errors
+    ///
+    /// This method can fail for the following reasons:
+    /// - no data flow with the provided id was found,
+    /// - the data flow is in a failed state,
+    /// - the update could not be applied to the record of the data flow,
+    /// - a node could not be loaded, in which case the instance is left untouched,
+    /// - a node loaded could not be started.
+    #[tracing::instrument(name = "update", skip(self, id, update, default_runtime), fields(instance = %id))]
+    pub async fn try_update_instance(
+        &self,
+        id: &InstanceId,
+        update: &FlattenedDataFlowUpdate,
+        default_runtime: &RuntimeId,
+    ) -> Result<DataFlowRecord> {
+        let instance = self.try_get_instance(id).await?;
+        let mut instance_guard = instance.write().await;
+
+        let record = instance_guard.record.try_update(update, default_runtime)?;
+
+        let previous_nodes = instance_guard
+            .record
+            .mapping()
+            .get(&self.runtime_id)
+            .cloned()
+            .unwrap_or_default();
+        let nodes = record
+            .mapping()
+            .get(&self.runtime_id)
+            .cloned()
+            .unwrap_or_default();
+
+        let loaded = nodes
+            .iter()
+            .filter(|node_id| {
+                !previous_nodes.contains(*node_id)
+                    || has_changed(&instance_guard.record, &record, node_id)
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+        let stopped = previous_nodes
+            .iter()
+            .filter(|node_id| !nodes.contains(*node_id) || loaded.contains(*node_id))
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        // 1. Load the nodes first: if one of them fails to load, the instance is left untouched.
+        let mut links = instance_guard.links.clone();
+        let (mut runners, injectors) = self.try_load_nodes(&record, &loaded, &mut links).await?;
+
+        // 2. Stop the nodes removed or re-created, saving the state of the latter.
+        let mut stopped_runners = stopped
+            .iter()
+            .filter_map(|node_id| {
+                instance_guard
+                    .runners
+                    .remove(node_id)
+                    .map(|runner| (node_id, runner))
+            })
+            .collect::<Vec<_>>();
+        futures::future::join_all(
+            stopped_runners
+                .iter_mut()
+                .map(|(_, runner)| runner.stop(STOP_TIMEOUT)),
+        )
+        .await;
+
+        let mut states = HashMap::new();
+        for (node_id, runner) in stopped_runners.iter() {
+            if !loaded.contains(*node_id) {
+                continue;
+            }
+
+            match runner.snapshot().await {
+                Ok(state) if !state.is_empty() => {
+                    states.insert((*node_id).clone(), state);
+                }
+                Ok(_) => (),
+                Err(e) => tracing::error!("The state of < {} > was lost: {:?}", node_id, e),
+            }
+        }
+        drop(stopped_runners);
+
+        // 3. Replace them with the nodes loaded.
+        for (node_id, runner) in runners.iter() {
+            if let Some(state) = states.get(node_id) {
+                if let Err(e) = runner.restore(state).await {
+                    tracing::error!("The state of < {} > was lost: {:?}", node_id, e);
+                }
+            }
+        }
+
+        instance_guard
+            .injectors
+            .retain(|input, _| !stopped.contains(&input.node));
+        instance_guard.injectors.extend(injectors);
+        instance_guard.links = links;
+        instance_guard
+            .lost_runtimes
+            .retain(|runtime_id, _| record.mapping().contains_key(runtime_id));
+        instance_guard.record = record.clone();
+
+        let result = if matches!(instance_guard.state, InstanceState::Running(_)) {
+            let mut result = Ok(());
+            for runner in runners.values_mut() {
+                if let Err(e) = runner.start(self.hlc.clone()).await {
+                    result = Err(e);
+                    break;
+                }
+            }
+            result
+        } else {
+            Ok(())
+        };
+
+        instance_guard.runners.extend(runners);
+        result?;
+
+        tracing::info!(
+            "updated: {} node(s) loaded, {} node(s) stopped",
+            loaded.len(),
+            stopped.len()
+        );
+
+        Ok(record)
+    }
+}
+
+/// Returns `true` if the descriptor or the links of the node `node_id` differ between the `previous` record and the
+/// `updated` one.
+fn has_changed(previous: &DataFlowRecord, updated: &DataFlowRecord, node_id: &NodeId) -> bool {
+    fn links_of<'a>(record: &'a DataFlowRecord, node_id: &NodeId) -> Vec<&'a LinkDescriptor> {
+        record
+            .links()
+            .iter()
+            .filter(|link| link.from.node == *node_id || link.to.node == *node_id)
+            .collect()
+    }
+
+    let previous_links = links_of(previous, node_id);
+    let updated_links = links_of(updated, node_id);
+
+    previous.sources().get(node_id) != updated.sources().get(node_id)
+        || previous.operators().get(node_id) != updated.operators().get(node_id)
+        || previous.sinks().get(node_id) != updated.sinks().get(node_id)
+        || previous.senders().get(node_id) != updated.senders().get(node_id)
+        || previous.receivers().get(node_id) != updated.receivers().get(node_id)
+        || previous_links.len() != updated_links.len()
+        || previous_links
+            .iter()
+            .any(|link| !updated_links.contains(link))
+}
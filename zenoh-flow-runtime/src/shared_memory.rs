@@ -20,7 +20,7 @@ use zenoh::{
     shm::{SharedMemoryBuf, SharedMemoryManager},
 };
 use zenoh_flow_commons::{NodeId, Result, SharedMemoryConfiguration};
-use zenoh_flow_nodes::prelude::{DataMessage, LinkMessage};
+use zenoh_flow_nodes::prelude::{DataMessage, Message};
 
 pub(crate) struct SharedMemory {
     session: Arc<Session>,
@@ -45,7 +45,7 @@ impl SharedMemory {
         }
     }
 
-    /// This method tries to send the [LinkMessage] via Zenoh's shared memory.
+    /// This method tries to send the [Message] via Zenoh's shared memory.
     ///
     /// # Errors
     ///
@@ -56,7 +56,7 @@ impl SharedMemory {
     pub(crate) async fn try_send_message(
         &mut self,
         key_expr: &str,
-        message: LinkMessage,
+        message: Message,
         message_buffer: &mut Vec<u8>,
         payload_buffer: &mut Vec<u8>,
     ) -> Result<()> {
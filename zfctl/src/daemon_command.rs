@@ -36,7 +36,10 @@ use crate::{
 
 #[derive(Subcommand)]
 pub(crate) enum DaemonCommand {
-    /// List all the Zenoh-Flow daemons reachable on the Zenoh network.
+    /// List all the Zenoh-Flow daemons reachable on the Zenoh network, alongside
+    /// their labels and the number of nodes they manage (out of their capacity,
+    /// if set).
+    #[command(verbatim_doc_comment)]
     List,
     /// Launch a Zenoh-Flow Daemon.
     #[command(verbatim_doc_comment)]
@@ -147,10 +150,19 @@ impl DaemonCommand {
                 let runtimes = get_all_runtimes(&session).await;
 
                 let mut table = Table::new();
-                table.set_width(80);
-                table.set_header(Row::from(vec!["Identifier", "Name"]));
+                table.set_width(120);
+                table.set_header(Row::from(vec!["Identifier", "Name", "Labels", "Nodes"]));
                 runtimes.iter().for_each(|info| {
-                    table.add_row(Row::from(vec![&info.id.to_string(), info.name.as_ref()]));
+                    let nodes = match info.capacity {
+                        Some(capacity) => format!("{} / {}", info.nodes, capacity),
+                        None => info.nodes.to_string(),
+                    };
+                    table.add_row(Row::from(vec![
+                        &info.id.to_string(),
+                        info.name.as_ref(),
+                        &info.labels.to_string(),
+                        &nodes,
+                    ]));
                 });
 
                 println!("{table}");
@@ -12,22 +12,33 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::{anyhow, bail};
-use clap::Subcommand;
+use async_std::stream::StreamExt;
+use clap::{Subcommand, ValueEnum};
 use comfy_table::Table;
 use itertools::Itertools;
+use signal_hook::consts::{SIGINT, SIGQUIT, SIGTERM};
+use signal_hook_async_std::Signals;
 use uuid::Uuid;
-use zenoh::{query::ConsolidationMode, Session};
-use zenoh_flow_commons::{parse_vars, InstanceId, Result, RuntimeId, Vars};
+use zenoh::{query::ConsolidationMode, sample::Sample, Session};
+use zenoh_flow_commons::{parse_vars, Configuration, InstanceId, Result, RuntimeId, Vars};
 use zenoh_flow_daemon::queries::*;
-use zenoh_flow_descriptors::{DataFlowDescriptor, FlattenedDataFlowDescriptor};
+use zenoh_flow_descriptors::{
+    DataFlowDescriptor, DataFlowUpdateDescriptor, FlattenedDataFlowDescriptor,
+    FlattenedDataFlowUpdate, InputDescriptor, OutputDescriptor,
+};
+use zenoh_flow_records::DataFlowRecord;
 use zenoh_flow_runtime::InstanceState;
 
 use super::ZENOH_FLOW_INTERNAL_ERROR;
 use crate::row;
 
+/// How long to wait for the Zenoh-Flow daemon orchestrating an operation that involves the other daemons: it waits for
+/// their acknowledgements, retrying its queries if they do not answer.
+const ORCHESTRATION_TIMEOUT: Duration = Duration::from_secs(60);
+
 #[derive(Subcommand)]
 pub(crate) enum InstanceCommand {
     /// Create an instance of the provided data flow descriptor.
@@ -41,6 +52,9 @@ pub(crate) enum InstanceCommand {
     ///
     ///       zfctl instance status <uuid>
     ///
+    ///   Or, to wait for the creation to complete and obtain the outcome of
+    ///   each involved daemon, provide the `--wait` flag.
+    ///
     /// - This call will **not** start the data flow instance, only load on all
     ///   the involved daemons the nodes composing the data flow.
     ///
@@ -59,24 +73,210 @@ pub(crate) enum InstanceCommand {
         ///     --vars HOME_DIR=/home/zenoh-flow --vars BUILD=debug
         #[arg(long, value_parser = parse_vars::<String, String>, verbatim_doc_comment)]
         vars: Option<Vec<(String, String)>>,
+        /// Wait until all the involved Zenoh-Flow daemons loaded the instance
+        /// (or deleted it, if one of them failed) and display their outcome.
+        #[arg(long)]
+        wait: bool,
     },
     /// To delete (and abort, if required) the data flow instance
     Delete { instance_id: Uuid },
     /// Obtain the status of the data flow instance.
     Status { instance_id: Uuid },
+    /// Obtain the metrics of the nodes of the data flow instance: their
+    /// throughput, the latency of their iterations, their errors and the
+    /// messages and bytes exchanged on their ports.
+    Metrics { instance_id: Uuid },
     /// List all the data flow instances on the contacted Zenoh-Flow daemon
     List,
     /// Start the data flow instance, on all the involved Zenoh-Flow daemons.
     Start { instance_id: Uuid },
     /// Abort the data flow instance, on all the involved Zenoh-Flow daemons.
-    Abort { instance_id: Uuid },
+    Abort {
+        instance_id: Uuid,
+        /// Gracefully stop the data flow instance: stop the Sources and let
+        /// the messages in flight be processed before stopping the other
+        /// nodes. The nodes still processing messages after the provided
+        /// duration (5s by default) are aborted.
+        ///
+        /// Example:
+        ///     --graceful 10s
+        #[arg(
+            long,
+            value_parser = humantime::parse_duration,
+            num_args = 0..=1,
+            default_missing_value = "5s",
+            verbatim_doc_comment
+        )]
+        graceful: Option<Duration>,
+    },
+    /// Record the messages sent on Outputs of the data flow instance.
+    ///
+    /// Each Zenoh-Flow daemon records the Outputs of the nodes it manages in
+    /// a file, on its own filesystem, until the recording is stopped:
+    ///
+    ///     zfctl instance record <uuid> --stop
+    ///
+    /// Example:
+    ///     zfctl instance record <uuid> --path /tmp/greetings.rec \
+    ///         zenoh-sub.out greetings-maker.greeting
+    #[command(verbatim_doc_comment)]
+    Record {
+        instance_id: Uuid,
+        /// The Outputs to record, with the form `<node>.<output>`.
+        #[arg(required_unless_present = "stop")]
+        outputs: Vec<OutputDescriptor>,
+        /// The path of the file, on the Zenoh-Flow daemons, in which the
+        /// messages are recorded.
+        #[arg(long, required_unless_present = "stop")]
+        path: Option<PathBuf>,
+        /// Stop the recording in progress.
+        #[arg(long, conflicts_with_all = ["outputs", "path"])]
+        stop: bool,
+    },
+    /// Start the data flow instance, replacing its Sources by a recording.
+    ///
+    /// The instance must be loaded or aborted. Each Zenoh-Flow daemon replays
+    /// the messages recorded on the Outputs of the Sources it manages, found
+    /// in the file on its own filesystem.
+    #[command(verbatim_doc_comment)]
+    Replay {
+        instance_id: Uuid,
+        /// The path of the recording, on the Zenoh-Flow daemons.
+        path: PathBuf,
+        /// Send the messages as fast as possible instead of respecting the
+        /// time that elapsed between them when they were recorded.
+        #[arg(long)]
+        fast: bool,
+    },
+    /// Print the messages sent on an Output of the data flow instance, until
+    /// interrupted (Ctrl-C).
+    ///
+    /// The Zenoh-Flow daemon managing the node publishes a copy of the
+    /// messages for as long as `zfctl` is connected. If the Output sends
+    /// messages faster than they can be published, some are not printed.
+    ///
+    /// Example:
+    ///     zfctl instance tap <uuid> greetings-maker.greeting --format utf8
+    #[command(verbatim_doc_comment)]
+    Tap {
+        instance_id: Uuid,
+        /// The Output to tap, with the form `<node>.<output>`.
+        output: OutputDescriptor,
+        /// How the payloads of the messages are printed.
+        #[arg(long, value_enum, default_value_t = TapFormat::Auto)]
+        format: TapFormat,
+    },
+    /// Change the configuration of a node of the data flow instance, without
+    /// restarting it.
+    ///
+    /// The keys of the patch overwrite the ones of the current configuration
+    /// of the node, the other keys are kept. The node must support being
+    /// reconfigured.
+    ///
+    /// Example:
+    ///     zfctl instance reconfigure <uuid> threshold-filter '{ threshold: 42 }'
+    #[command(verbatim_doc_comment)]
+    Reconfigure {
+        instance_id: Uuid,
+        /// The identifier of the node.
+        node: String,
+        /// The patch, in JSON or YAML: either the text of the patch or the
+        /// path of a file, prefixed with `@`, that contains it.
+        #[arg(verbatim_doc_comment)]
+        patch: String,
+    },
+    /// Inject a message into an Input of the running data flow instance.
+    ///
+    /// The Input must be declared injectable in the descriptor of its node:
+    ///
+    ///     inputs:
+    ///       - id: name
+    ///         injectable: true
+    ///
+    /// Example:
+    ///     zfctl instance inject <uuid> greetings-maker.name --data @name.txt
+    #[command(verbatim_doc_comment)]
+    Inject {
+        instance_id: Uuid,
+        /// The Input, with the form `<node>.<input>`.
+        input: InputDescriptor,
+        /// The payload of the message: either the path of a file, prefixed
+        /// with `@`, whose content is sent as is, or the text to send.
+        #[arg(long, verbatim_doc_comment)]
+        data: String,
+    },
+    /// Add or remove nodes and links of the data flow instance, without
+    /// tearing it down.
+    ///
+    /// The update descriptor lists the nodes and links to add, described as
+    /// in a data flow descriptor, and the ones to remove:
+    ///
+    ///     sinks:
+    ///       - id: debug
+    ///         descriptor: file:///home/zenoh-flow/nodes/debug.yaml
+    ///     links:
+    ///       - from: { node: greetings-maker, output: greeting }
+    ///         to: { node: debug, input: in }
+    ///     remove:
+    ///       nodes: [ file-writer ]
+    ///
+    /// A node whose identifier is already used replaces the existing node,
+    /// keeping its links. The nodes that are not affected by the update keep
+    /// running and keep their state.
+    ///
+    /// Example:
+    ///     zfctl instance update <uuid> attach-debug.yaml
+    #[command(verbatim_doc_comment)]
+    Update {
+        instance_id: Uuid,
+        /// The path, on your machine, of the update descriptor.
+        update: PathBuf,
+        /// Variables to add / overwrite in the `vars` section of the update,
+        /// with the form `KEY=VALUE`. Can be repeated multiple times.
+        #[arg(long, value_parser = parse_vars::<String, String>, verbatim_doc_comment)]
+        vars: Option<Vec<(String, String)>>,
+    },
+}
+
+/// How the payloads of the tapped messages are printed.
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum TapFormat {
+    /// JSON if the payload is valid JSON, UTF-8 if it is valid UTF-8,
+    /// hexadecimal otherwise.
+    Auto,
+    /// UTF-8, invalid sequences being replaced.
+    Utf8,
+    /// JSON, printed on a single line.
+    Json,
+    /// Hexadecimal.
+    Hex,
+}
+
+impl TapFormat {
+    fn decode(&self, payload: &[u8]) -> String {
+        let hex = || payload.iter().map(|byte| format!("{byte:02x}")).join("");
+        let json = || {
+            serde_json::from_slice::<serde_json::Value>(payload)
+                .ok()
+                .map(|value| value.to_string())
+        };
+
+        match self {
+            TapFormat::Auto => json()
+                .or_else(|| std::str::from_utf8(payload).ok().map(String::from))
+                .unwrap_or_else(hex),
+            TapFormat::Utf8 => String::from_utf8_lossy(payload).into_owned(),
+            TapFormat::Json => json().unwrap_or_else(|| format!("<invalid JSON> {}", hex())),
+            TapFormat::Hex => hex(),
+        }
+    }
 }
 
 impl InstanceCommand {
     pub async fn run(self, session: Session, orchestrator_id: RuntimeId) -> Result<()> {
         let mut selector = selector_instances(&orchestrator_id);
         let query = match self {
-            InstanceCommand::Create { flow, vars } => {
+            InstanceCommand::Create { flow, vars, wait } => {
                 let vars = match vars {
                     Some(v) => Vars::from(v),
                     None => Vars::default(),
@@ -96,7 +296,10 @@ impl InstanceCommand {
                         anyhow!("Failed to flatten data flow < {} >", flow.display())
                     })?;
 
-                InstancesQuery::Create(Box::new(flat_flow))
+                InstancesQuery::Create {
+                    data_flow: Box::new(flat_flow),
+                    wait,
+                }
             }
 
             InstanceCommand::Delete { instance_id } => InstancesQuery::Delete {
@@ -110,15 +313,139 @@ impl InstanceCommand {
                 selector = selector_all_instances();
                 InstancesQuery::Status(instance_id.into())
             }
+            InstanceCommand::Metrics { instance_id } => {
+                selector = selector_all_instances();
+                InstancesQuery::Metrics(instance_id.into())
+            }
             InstanceCommand::Start { instance_id } => InstancesQuery::Start {
                 origin: Origin::Client,
                 instance_id: instance_id.into(),
             },
 
-            InstanceCommand::Abort { instance_id } => InstancesQuery::Abort {
+            InstanceCommand::Abort {
+                instance_id,
+                graceful,
+            } => InstancesQuery::Abort {
                 origin: Origin::Client,
                 instance_id: instance_id.into(),
+                graceful,
             },
+
+            InstanceCommand::Record {
+                instance_id,
+                outputs,
+                path,
+                stop,
+            } => {
+                selector = selector_all_instances();
+                match (stop, path) {
+                    (false, Some(path)) => InstancesQuery::Record {
+                        instance_id: instance_id.into(),
+                        path,
+                        outputs,
+                    },
+                    _ => InstancesQuery::StopRecording(instance_id.into()),
+                }
+            }
+
+            InstanceCommand::Tap {
+                instance_id,
+                output,
+                format,
+            } => return tap(session, instance_id.into(), output, format).await,
+
+            InstanceCommand::Reconfigure {
+                instance_id,
+                node,
+                patch,
+            } => {
+                let patch = match patch.strip_prefix('@') {
+                    Some(path) => std::fs::read_to_string(path)
+                        .map_err(|e| anyhow!("Failed to read < {path} >: {e:?}"))?,
+                    None => patch,
+                };
+                let patch = serde_yaml::from_str::<Configuration>(&patch)
+                    .map_err(|e| anyhow!("Failed to parse the patch: {e:?}"))?;
+
+                InstancesQuery::Reconfigure {
+                    origin: Origin::Client,
+                    instance_id: instance_id.into(),
+                    node_id: node.into(),
+                    patch,
+                }
+            }
+
+            InstanceCommand::Inject {
+                instance_id,
+                input,
+                data,
+            } => {
+                selector = selector_all_instances();
+                let payload = match data.strip_prefix('@') {
+                    Some(path) => std::fs::read(path)
+                        .map_err(|e| anyhow!("Failed to read < {path} >: {e:?}"))?,
+                    None => data.into_bytes(),
+                };
+
+                InstancesQuery::Inject {
+                    instance_id: instance_id.into(),
+                    input,
+                    payload,
+                }
+            }
+
+            InstanceCommand::Update {
+                instance_id,
+                update,
+                vars,
+            } => {
+                let vars = match vars {
+                    Some(v) => Vars::from(v),
+                    None => Vars::default(),
+                };
+
+                let (update_desc, vars) = zenoh_flow_commons::try_parse_from_file::<
+                    DataFlowUpdateDescriptor,
+                >(&update, vars)
+                .map_err(|e| {
+                    tracing::error!("{:?}", e);
+                    anyhow!("Failed to parse update from < {} >", update.display())
+                })?;
+
+                let flat_update =
+                    FlattenedDataFlowUpdate::try_flatten(update_desc, vars).map_err(|e| {
+                        tracing::error!("{:?}", e);
+                        anyhow!("Failed to flatten update < {} >", update.display())
+                    })?;
+
+                if flat_update.is_empty() {
+                    bail!("The update < {} > changes nothing", update.display());
+                }
+
+                InstancesQuery::Update {
+                    origin: Origin::Client,
+                    instance_id: instance_id.into(),
+                    update: Box::new(flat_update),
+                    default_runtime: orchestrator_id.clone(),
+                }
+            }
+
+            InstanceCommand::Replay {
+                instance_id,
+                path,
+                fast,
+            } => {
+                selector = selector_all_instances();
+                InstancesQuery::Replay {
+                    instance_id: instance_id.into(),
+                    path,
+                    mode: if fast {
+                        ReplayMode::AsFastAsPossible
+                    } else {
+                        ReplayMode::Recorded
+                    },
+                }
+            }
         };
 
         let value = serde_json::to_vec(&query).map_err(|e| {
@@ -133,18 +460,73 @@ Caused by:
             anyhow!(ZENOH_FLOW_INTERNAL_ERROR)
         })?;
 
-        let reply = session
+        let mut get = session
             .get(&selector)
             .payload(value)
             // NOTE: We do not want any consolidation, each response, even if identical, is relevant as the origin
             // matters as much as the content.
-            .consolidation(ConsolidationMode::None)
+            .consolidation(ConsolidationMode::None);
+        if matches!(
+            query,
+            InstancesQuery::Create { wait: true, .. }
+                | InstancesQuery::Start { .. }
+                | InstancesQuery::Abort { .. }
+                | InstancesQuery::Delete { .. }
+                | InstancesQuery::Update { .. }
+        ) {
+            get = get.timeout(ORCHESTRATION_TIMEOUT);
+        }
+
+        let reply = get
             .await
             .map_err(|e| anyhow!("Failed to send query on < {} >: {:?}", &selector, e))?;
 
         // Some requests require to process the response.
         match query {
-            InstancesQuery::Create(_) => {
+            InstancesQuery::Create { wait: true, .. } => {
+                let response = reply
+                    .recv_async()
+                    .await
+                    .map_err(|e| anyhow!("Could not create instance: {e:?}"))?;
+
+                let sample = match response.result() {
+                    Ok(sample) => sample,
+                    Err(err) => bail!(
+                        "Failed to create instance: {}",
+                        err.payload()
+                            .try_to_string()
+                            .unwrap_or_else(|e| e.to_string().into())
+                    ),
+                };
+
+                let report = serde_json::from_slice::<CreationReport>(&sample.payload().to_bytes())
+                    .map_err(|e| {
+                        anyhow!(
+                            "Failed to parse 'create' reply from < {:?} >: {:?}",
+                            response.replier_id(),
+                            e
+                        )
+                    })?;
+
+                let mut table = Table::new();
+                table.set_width(80);
+                table.set_header(row!("Runtime", "Outcome", "Errors"));
+                for runtime in report.runtimes.iter() {
+                    table.add_row(row!(
+                        runtime.runtime_id,
+                        runtime.outcome,
+                        runtime.errors.join("\nCaused by: ")
+                    ));
+                }
+                println!("{table}");
+
+                if !report.is_success() {
+                    bail!("Failed to create instance < {} >", report.instance_id);
+                }
+
+                println!("{}", report.instance_id);
+            }
+            InstancesQuery::Create { wait: false, .. } => {
                 let sample = match reply.recv_async().await {
                     Ok(reply) => reply,
                     Err(e) => {
@@ -169,6 +551,17 @@ Caused by:
                 table.set_width(80);
                 table.set_header(row!("Runtime", "Instance State", "Node"));
 
+                let mut statistics_table = Table::new();
+                statistics_table.set_width(80);
+                statistics_table.set_header(row!(
+                    "Node",
+                    "Port",
+                    "Messages",
+                    "Lost",
+                    "Duplicates",
+                    "Dropped"
+                ));
+
                 while let Ok(response) = reply.recv_async().await {
                     match response.result() {
                         Ok(sample) => {
@@ -181,6 +574,42 @@ Caused by:
                                         status.state,
                                         status.nodes.iter().join(", ")
                                     ));
+
+                                    for (node_id, statistics) in status
+                                        .statistics
+                                        .iter()
+                                        .sorted_by_key(|(id, _)| id.to_string())
+                                    {
+                                        for (port_id, input) in statistics
+                                            .inputs
+                                            .iter()
+                                            .sorted_by_key(|(id, _)| id.to_string())
+                                        {
+                                            statistics_table.add_row(row!(
+                                                node_id,
+                                                format!("in: {port_id}"),
+                                                input.received,
+                                                input.lost,
+                                                input.duplicates,
+                                                "-"
+                                            ));
+                                        }
+
+                                        for (port_id, output) in statistics
+                                            .outputs
+                                            .iter()
+                                            .sorted_by_key(|(id, _)| id.to_string())
+                                        {
+                                            statistics_table.add_row(row!(
+                                                node_id,
+                                                format!("out: {port_id}"),
+                                                output.sent,
+                                                "-",
+                                                "-",
+                                                output.dropped
+                                            ));
+                                        }
+                                    }
                                 }
                                 Err(e) => tracing::error!(
                                     "Failed to parse 'status' reply from < {:?} >: {:?}",
@@ -194,6 +623,327 @@ Caused by:
                 }
 
                 println!("{table}");
+                println!("{statistics_table}");
+            }
+            InstancesQuery::Metrics(_) => {
+                let mut table = Table::new();
+                table.set_width(80);
+                table.set_header(row!(
+                    "Runtime",
+                    "Node",
+                    "Iterations",
+                    "Iterations/s",
+                    "Errors",
+                    "Panics",
+                    "Mean",
+                    "p99"
+                ));
+
+                let mut ports_table = Table::new();
+                ports_table.set_width(80);
+                ports_table.set_header(row!("Node", "Port", "Messages", "Bytes", "Backlog"));
+
+                while let Ok(response) = reply.recv_async().await {
+                    match response.result() {
+                        Ok(sample) => {
+                            match serde_json::from_slice::<InstanceMetrics>(
+                                &sample.payload().to_bytes(),
+                            ) {
+                                Ok(metrics) => {
+                                    for (node_id, node) in
+                                        metrics.nodes.iter().sorted_by_key(|(id, _)| id.to_string())
+                                    {
+                                        let latency = &node.latency;
+                                        let mean = latency
+                                            .mean()
+                                            .map(|mean| format!("{mean:?}"))
+                                            .unwrap_or_else(|| "-".into());
+                                        let p99 = match latency.quantile(0.99) {
+                                            Some(p99) => format!("<= {p99:?}"),
+                                            None if latency.count() == 0 => "-".into(),
+                                            None => format!(
+                                                "> {:?}",
+                                                Duration::from_micros(
+                                                    latency
+                                                        .bounds_us
+                                                        .last()
+                                                        .copied()
+                                                        .unwrap_or_default()
+                                                )
+                                            ),
+                                        };
+
+                                        table.add_row(row!(
+                                            metrics.runtime_id,
+                                            node_id,
+                                            node.iterations,
+                                            format!("{:.1}", node.iterations_per_second),
+                                            node.errors,
+                                            node.panics,
+                                            mean,
+                                            p99
+                                        ));
+
+                                        for (port_id, input) in node
+                                            .ports
+                                            .inputs
+                                            .iter()
+                                            .sorted_by_key(|(id, _)| id.to_string())
+                                        {
+                                            ports_table.add_row(row!(
+                                                node_id,
+                                                format!("in: {port_id}"),
+                                                input.received,
+                                                input
+                                                    .bytes
+                                                    .map(|bytes| bytes.to_string())
+                                                    .unwrap_or_else(|| "-".into()),
+                                                input.pending
+                                            ));
+                                        }
+
+                                        for (port_id, output) in node
+                                            .ports
+                                            .outputs
+                                            .iter()
+                                            .sorted_by_key(|(id, _)| id.to_string())
+                                        {
+                                            ports_table.add_row(row!(
+                                                node_id,
+                                                format!("out: {port_id}"),
+                                                output.sent,
+                                                output
+                                                    .bytes
+                                                    .map(|bytes| bytes.to_string())
+                                                    .unwrap_or_else(|| "-".into()),
+                                                "-"
+                                            ));
+                                        }
+                                    }
+                                }
+                                Err(e) => tracing::error!(
+                                    "Failed to parse 'metrics' reply from < {:?} >: {:?}",
+                                    response.replier_id(),
+                                    e
+                                ),
+                            }
+                        }
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                }
+
+                println!("{table}");
+                println!("{ports_table}");
+            }
+            InstancesQuery::Record { .. } => {
+                while let Ok(response) = reply.recv_async().await {
+                    match response.result() {
+                        Ok(sample) => {
+                            match serde_json::from_slice::<usize>(&sample.payload().to_bytes()) {
+                                Ok(0) => {}
+                                Ok(count) => println!(
+                                    "< {:?} > is recording {} output(s)",
+                                    response.replier_id(),
+                                    count
+                                ),
+                                Err(e) => tracing::error!(
+                                    "Failed to parse 'record' reply from < {:?} >: {:?}",
+                                    response.replier_id(),
+                                    e
+                                ),
+                            }
+                        }
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                }
+            }
+            InstancesQuery::StopRecording(_) => {
+                let mut table = Table::new();
+                table.set_width(80);
+                table.set_header(row!("Runtime", "File", "Outputs", "Messages"));
+
+                while let Ok(response) = reply.recv_async().await {
+                    match response.result() {
+                        Ok(sample) => {
+                            match serde_json::from_slice::<Option<RecordingSummary>>(
+                                &sample.payload().to_bytes(),
+                            ) {
+                                Ok(Some(summary)) => {
+                                    table.add_row(row!(
+                                        summary.runtime_id,
+                                        summary.path.display(),
+                                        summary.outputs.iter().join(", "),
+                                        summary.messages
+                                    ));
+                                }
+                                Ok(None) => {}
+                                Err(e) => tracing::error!(
+                                    "Failed to parse 'stop recording' reply from < {:?} >: {:?}",
+                                    response.replier_id(),
+                                    e
+                                ),
+                            }
+                        }
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                }
+
+                println!("{table}");
+            }
+            InstancesQuery::Replay { .. } => {
+                while let Ok(response) = reply.recv_async().await {
+                    match response.result() {
+                        Ok(sample) => {
+                            match serde_json::from_slice::<u64>(&sample.payload().to_bytes()) {
+                                Ok(count) => println!(
+                                    "< {:?} > is replaying {} message(s)",
+                                    response.replier_id(),
+                                    count
+                                ),
+                                Err(e) => tracing::error!(
+                                    "Failed to parse 'replay' reply from < {:?} >: {:?}",
+                                    response.replier_id(),
+                                    e
+                                ),
+                            }
+                        }
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                }
+            }
+            InstancesQuery::Reconfigure { ref node_id, .. } => {
+                let response = reply
+                    .recv_async()
+                    .await
+                    .map_err(|e| anyhow!("Failed to reconfigure < {node_id} >: {e:?}"))?;
+
+                match response.result() {
+                    Ok(sample) => {
+                        match serde_json::from_slice::<Configuration>(&sample.payload().to_bytes())
+                        {
+                            Ok(configuration) => println!(
+                                "< {node_id} > is now configured with:\n{}",
+                                serde_json::to_string_pretty(&configuration)
+                                    .unwrap_or_else(|_| configuration.to_string())
+                            ),
+                            Err(e) => tracing::error!(
+                                "Failed to parse 'reconfigure' reply from < {:?} >: {:?}",
+                                response.replier_id(),
+                                e
+                            ),
+                        }
+                    }
+                    Err(err) => bail!(
+                        "Failed to reconfigure < {node_id} >: {}",
+                        err.payload()
+                            .try_to_string()
+                            .unwrap_or_else(|e| e.to_string().into())
+                    ),
+                }
+            }
+            InstancesQuery::Inject { ref input, .. } => {
+                let mut injected = false;
+                while let Ok(response) = reply.recv_async().await {
+                    match response.result() {
+                        Ok(sample) => {
+                            match serde_json::from_slice::<bool>(&sample.payload().to_bytes()) {
+                                Ok(true) => {
+                                    injected = true;
+                                    println!(
+                                        "< {:?} > injected the message into < {} >",
+                                        response.replier_id(),
+                                        input
+                                    );
+                                }
+                                Ok(false) => {}
+                                Err(e) => tracing::error!(
+                                    "Failed to parse 'inject' reply from < {:?} >: {:?}",
+                                    response.replier_id(),
+                                    e
+                                ),
+                            }
+                        }
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                }
+
+                if !injected {
+                    bail!("The message was not injected into < {input} >");
+                }
+            }
+            InstancesQuery::Update {
+                ref instance_id, ..
+            } => {
+                let response = reply
+                    .recv_async()
+                    .await
+                    .map_err(|e| anyhow!("Failed to update < {instance_id} >: {e:?}"))?;
+
+                match response.result() {
+                    Ok(sample) => {
+                        match serde_json::from_slice::<DataFlowRecord>(&sample.payload().to_bytes())
+                        {
+                            Ok(record) => {
+                                let mut table = Table::new();
+                                table.set_width(80);
+                                table.set_header(row!("Runtime", "Nodes"));
+                                for (runtime_id, nodes) in record
+                                    .mapping()
+                                    .iter()
+                                    .sorted_by_key(|(id, _)| id.to_string())
+                                {
+                                    table.add_row(row!(
+                                        runtime_id,
+                                        nodes
+                                            .iter()
+                                            .filter(|node_id| {
+                                                !record.senders().contains_key(*node_id)
+                                                    && !record.receivers().contains_key(*node_id)
+                                            })
+                                            .sorted_by_key(|node_id| node_id.to_string())
+                                            .join(", ")
+                                    ));
+                                }
+
+                                println!("The graph of < {instance_id} > was updated:\n{table}");
+                            }
+                            Err(e) => tracing::error!(
+                                "Failed to parse 'update' reply from < {:?} >: {:?}",
+                                response.replier_id(),
+                                e
+                            ),
+                        }
+                    }
+                    Err(err) => bail!(
+                        "Failed to update < {instance_id} >: {}",
+                        err.payload()
+                            .try_to_string()
+                            .unwrap_or_else(|e| e.to_string().into())
+                    ),
+                }
+            }
+            InstancesQuery::Start {
+                ref instance_id, ..
+            }
+            | InstancesQuery::Abort {
+                ref instance_id, ..
+            }
+            | InstancesQuery::Delete {
+                ref instance_id, ..
+            } => {
+                let response = reply
+                    .recv_async()
+                    .await
+                    .map_err(|e| anyhow!("Received no reply for < {instance_id} >: {e:?}"))?;
+
+                if let Err(err) = response.result() {
+                    bail!(
+                        "{}",
+                        err.payload()
+                            .try_to_string()
+                            .unwrap_or_else(|e| e.to_string().into())
+                    );
+                }
             }
             InstancesQuery::List => {
                 let mut table = Table::new();
@@ -229,3 +979,98 @@ Caused by:
         Ok(())
     }
 }
+
+/// Taps the `output` of the data flow instance, printing its messages until a termination signal is received.
+///
+/// The liveliness token declared on the key expression of the tap tells the Zenoh-Flow daemon managing the node that
+/// the tap is still in use: once it is undeclared (or once this process is disconnected) the tap is removed.
+async fn tap(
+    session: Session,
+    instance_id: InstanceId,
+    output: OutputDescriptor,
+    format: TapFormat,
+) -> Result<()> {
+    let tap_id = Uuid::new_v4();
+    let key_expr = selector_tap(&instance_id, &tap_id);
+
+    let token = session
+        .liveliness()
+        .declare_token(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to declare liveliness token on < {key_expr} >: {e:?}"))?;
+    let subscriber = session
+        .declare_subscriber(key_expr.clone())
+        .await
+        .map_err(|e| anyhow!("Failed to declare subscriber on < {key_expr} >: {e:?}"))?;
+
+    let query = InstancesQuery::Tap {
+        instance_id: instance_id.clone(),
+        output: output.clone(),
+        tap_id,
+    };
+    let value = serde_json::to_vec(&query).map_err(|e| {
+        tracing::error!("serde_json failed to serialize query: {:?}", e);
+        anyhow!(ZENOH_FLOW_INTERNAL_ERROR)
+    })?;
+
+    let selector = selector_all_instances();
+    let reply = session
+        .get(&selector)
+        .payload(value)
+        .consolidation(ConsolidationMode::None)
+        .await
+        .map_err(|e| anyhow!("Failed to send query on < {} >: {:?}", &selector, e))?;
+
+    let mut tapped = false;
+    while let Ok(response) = reply.recv_async().await {
+        match response.result() {
+            Ok(sample) => match serde_json::from_slice::<bool>(&sample.payload().to_bytes()) {
+                Ok(tapping) => tapped |= tapping,
+                Err(e) => tracing::error!(
+                    "Failed to parse 'tap' reply from < {:?} >: {:?}",
+                    response.replier_id(),
+                    e
+                ),
+            },
+            // NOTE: The Zenoh-Flow daemons not involved in the data flow instance reply with an error.
+            Err(err) => tracing::debug!("{:?}", err),
+        }
+    }
+
+    if !tapped {
+        bail!("No Zenoh-Flow daemon manages the Output < {output} > of instance < {instance_id} >");
+    }
+
+    println!("Tapping < {output} >, press Ctrl-C to stop.");
+
+    let printer = async_std::task::spawn(async move {
+        while let Ok(sample) = subscriber.recv_async().await {
+            print_tapped(&sample, format);
+        }
+    });
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGQUIT]).map_err(|e| {
+        anyhow!("Failed to create SignalsInfo for: [SIGTERM, SIGINT, SIGQUIT]: {e:?}")
+    })?;
+    signals.next().await;
+
+    printer.cancel().await;
+    token
+        .undeclare()
+        .await
+        .map_err(|e| anyhow!("Failed to undeclare liveliness token on < {key_expr} >: {e:?}"))
+}
+
+/// Prints the message, received from a tap, with its timestamp.
+fn print_tapped(sample: &Sample, format: TapFormat) {
+    let time = sample
+        .attachment()
+        .and_then(|attachment| serde_json::from_slice::<TapMetadata>(&attachment.to_bytes()).ok())
+        .map(|metadata| {
+            humantime::format_rfc3339_micros(metadata.timestamp.get_time().to_system_time())
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".into());
+
+    println!("[{time}] {}", format.decode(&sample.payload().to_bytes()));
+}